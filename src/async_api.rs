@@ -0,0 +1,162 @@
+//! Async front-end for running analysis from an async runtime.
+//!
+//! The search itself stays synchronous and runs on a dedicated thread; this
+//! module only bridges its progress and result into channels that async code
+//! can await without blocking the runtime.
+
+use crate::engine::eval::Evaluator;
+use crate::engine::search::{SearchAlgorithm, StopSignal};
+use crate::engine::{Engine, SearchInfo};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use std::thread;
+use tokio::sync::{mpsc, oneshot};
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+/// A running analysis: a stream of per-depth info events and the final best move.
+pub struct Analysis<E: Evaluator, S: SearchAlgorithm> {
+    pub events: UnboundedReceiverStream<SearchInfo>,
+    pub bestmove: BestMove<E, S>,
+}
+
+/// Resolves to the best move and the engine, so the engine can be reused.
+///
+/// Resolves to `None` if the search thread panicked. Dropping the future
+/// before it resolves cancels the search.
+pub struct BestMove<E: Evaluator, S: SearchAlgorithm> {
+    receiver: oneshot::Receiver<(String, Engine<E, S>)>,
+    stop: StopSignal,
+    resolved: bool,
+}
+
+impl<E: Evaluator, S: SearchAlgorithm> BestMove<E, S> {
+    /// Stops the search early; the future still resolves with the best move found so far.
+    pub fn cancel(&self) {
+        self.stop.stop();
+    }
+
+    pub fn stop_handle(&self) -> StopSignal {
+        self.stop.clone()
+    }
+}
+
+impl<E: Evaluator, S: SearchAlgorithm> Future for BestMove<E, S> {
+    type Output = Option<(String, Engine<E, S>)>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let result = ready!(Pin::new(&mut self.receiver).poll(cx));
+        self.resolved = true;
+        // A cancelled search leaves the stop raised; the engine handed back
+        // must be able to search again.
+        Poll::Ready(result.ok().map(|(bestmove, mut engine)| {
+            engine.clear_stop();
+            (bestmove, engine)
+        }))
+    }
+}
+
+impl<E: Evaluator, S: SearchAlgorithm> Drop for BestMove<E, S> {
+    fn drop(&mut self) {
+        if !self.resolved {
+            self.stop.stop();
+        }
+    }
+}
+
+/// Searches the engine's current position to `depth` on a background thread.
+pub fn analyse<E, S>(mut engine: Engine<E, S>, depth: u32) -> Analysis<E, S>
+where
+    E: Evaluator + Send + 'static,
+    S: SearchAlgorithm + Send + 'static,
+{
+    let (info_sender, info_receiver) = mpsc::unbounded_channel();
+    let (best_sender, best_receiver) = oneshot::channel();
+    engine.clear_stop();
+    let stop = engine.stop_handle();
+
+    thread::spawn(move || {
+        let (bestmove, _) = engine.search_iterative_with_info(depth, |info| {
+            let _ = info_sender.send(info.clone());
+        });
+        let _ = best_sender.send((bestmove, engine));
+    });
+
+    Analysis {
+        events: UnboundedReceiverStream::new(info_receiver),
+        bestmove: BestMove {
+            receiver: best_receiver,
+            stop,
+            resolved: false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::eval::MaterialEvaluator;
+    use crate::engine::search::AlphaBetaSearch;
+    use tokio_stream::StreamExt;
+
+    fn runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .expect("runtime")
+    }
+
+    #[test]
+    fn streams_info_and_resolves_bestmove() {
+        let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+        engine.set_position_startpos();
+        let Analysis { events, bestmove } = analyse(engine, 3);
+
+        runtime().block_on(async {
+            let (best, _engine) = bestmove.await.expect("bestmove");
+            assert_ne!(best, "0000");
+            let depths: Vec<u32> = events.map(|info| info.depth).collect().await;
+            assert_eq!(depths, vec![1, 2, 3]);
+        });
+    }
+
+    #[test]
+    fn returned_engine_searches_again() {
+        let depths_of_next_search = |analysis: Analysis<_, _>, cancel: bool| {
+            runtime().block_on(async {
+                if cancel {
+                    analysis.bestmove.cancel();
+                }
+                let (_, mut engine) = analysis.bestmove.await.expect("bestmove");
+                let mut depths = Vec::new();
+                engine.search_iterative_with_info(3, |info| depths.push(info.depth));
+                depths
+            })
+        };
+        let engine = || {
+            let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+            engine.set_position_startpos();
+            engine
+        };
+        assert_eq!(
+            depths_of_next_search(analyse(engine(), 2), false),
+            vec![1, 2, 3]
+        );
+        assert_eq!(
+            depths_of_next_search(analyse(engine(), 64), true),
+            vec![1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn cancel_resolves_early() {
+        let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+        engine.set_position_startpos();
+        let analysis = analyse(engine, 64);
+
+        runtime().block_on(async {
+            analysis.bestmove.cancel();
+            let (best, _engine) = analysis.bestmove.await.expect("bestmove");
+            assert_ne!(best, "0000");
+        });
+    }
+}
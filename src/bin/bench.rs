@@ -1,9 +1,18 @@
-use chess_engine::engine::eval::MaterialEvaluator;
-use chess_engine::engine::search::{AlphaBetaSearch, MinimaxSearch};
-use chess_engine::engine::Engine;
-use std::collections::BTreeMap;
-use std::fs;
-use std::time::Instant;
+use prune::engine::board::Board;
+use prune::engine::eval::MaterialEvaluator;
+use prune::engine::search::mate::{mate_moves_from_score, solve_mate};
+use prune::engine::search::{AlphaBetaSearch, MinimaxSearch, SearchAlgorithm, SearchParams};
+use prune::engine::types::uci_from_move;
+use prune::engine::Engine;
+use prune::json::{self, Json};
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, Clone)]
 struct Puzzle {
@@ -13,8 +22,45 @@ struct Puzzle {
     mate: u8,
 }
 
+struct Args {
+    depth: u32,
+    mate_counts: Vec<u8>,
+    prove_mates: bool,
+    prove_nodes: u64,
+    config_a: Option<String>,
+    config_b: Option<String>,
+    config: Option<String>,
+    history: Option<String>,
+    report: Option<String>,
+    shard_dir: Option<String>,
+    shard_size: usize,
+    threads: usize,
+    seed: u64,
+}
+
 fn main() {
-    let (depth, mate_counts) = parse_args();
+    let Args {
+        depth,
+        mate_counts,
+        prove_mates,
+        prove_nodes,
+        config_a,
+        config_b,
+        config,
+        history,
+        report,
+        shard_dir,
+        shard_size,
+        threads,
+        seed,
+    } = parse_args();
+    if let Some(path) = report {
+        if let Err(err) = print_history_report(&path) {
+            eprintln!("{err}");
+            process::exit(1);
+        }
+        return;
+    }
     let mate_counts = if mate_counts.is_empty() {
         vec![1u8, 2, 3, 4, 5]
     } else {
@@ -32,16 +78,51 @@ fn main() {
             .append(&mut file_puzzles);
     }
 
+    if config_a.is_some() || config_b.is_some() {
+        let params_a = load_params(config_a.as_deref());
+        let params_b = load_params(config_b.as_deref());
+        print_comparison(&puzzles_by_mate, depth, params_a, params_b);
+        return;
+    }
+
+    if let Some(path) = history {
+        let label = config.as_deref().unwrap_or("default");
+        let params = load_params(config.as_deref());
+        record_history(&path, label, params, &puzzles_by_mate, depth);
+        return;
+    }
+
+    if let Some(dir) = shard_dir {
+        let shards = make_shards(&puzzles_by_mate, shard_size);
+        print_sharded_stats(&shards, Path::new(&dir), depth, threads, seed);
+        return;
+    }
+
     let mut alphabeta = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
     print_engine_stats("alphabeta", &mut alphabeta, &puzzles_by_mate, depth);
 
-    let mut minimax = Engine::with_components(MaterialEvaluator, MinimaxSearch);
+    let mut minimax = Engine::with_components(MaterialEvaluator, MinimaxSearch::new());
     print_engine_stats("minimax", &mut minimax, &puzzles_by_mate, depth);
+
+    if prove_mates {
+        print_solver_stats(&puzzles_by_mate, prove_nodes, depth);
+    }
 }
 
-fn parse_args() -> (u32, Vec<u8>) {
+fn parse_args() -> Args {
     let mut depth = 2u32;
     let mut mate_counts = Vec::new();
+    let mut prove_mates = false;
+    let mut prove_nodes = 5_000_000u64;
+    let mut config_a = None;
+    let mut config_b = None;
+    let mut config = None;
+    let mut history = None;
+    let mut report = None;
+    let mut shard_dir = None;
+    let mut shard_size = 100usize;
+    let mut threads = thread::available_parallelism().map_or(1, usize::from);
+    let mut seed = 1u64;
     let mut args = std::env::args().skip(1);
 
     while let Some(arg) = args.next() {
@@ -60,17 +141,93 @@ fn parse_args() -> (u32, Vec<u8>) {
                 },
                 None => eprintln!("missing value for --mate"),
             },
+            "--prove-mates" => prove_mates = true,
+            "--prove-nodes" => match args.next() {
+                Some(value) => match value.parse::<u64>() {
+                    Ok(parsed) => prove_nodes = parsed,
+                    Err(_) => eprintln!("invalid --prove-nodes: {value}"),
+                },
+                None => eprintln!("missing value for --prove-nodes"),
+            },
+            "--config-a" => match args.next() {
+                Some(value) => config_a = Some(value),
+                None => eprintln!("missing value for --config-a"),
+            },
+            "--config-b" => match args.next() {
+                Some(value) => config_b = Some(value),
+                None => eprintln!("missing value for --config-b"),
+            },
+            "--config" => match args.next() {
+                Some(value) => config = Some(value),
+                None => eprintln!("missing value for --config"),
+            },
+            "--history" => match args.next() {
+                Some(value) => history = Some(value),
+                None => eprintln!("missing value for --history"),
+            },
+            "--report" => match args.next() {
+                Some(value) => report = Some(value),
+                None => eprintln!("missing value for --report"),
+            },
+            "--shards" => match args.next() {
+                Some(value) => shard_dir = Some(value),
+                None => eprintln!("missing value for --shards"),
+            },
+            "--shard-size" => match args.next() {
+                Some(value) => match value.parse::<usize>() {
+                    Ok(parsed) if parsed > 0 => shard_size = parsed,
+                    _ => eprintln!("invalid --shard-size: {value}"),
+                },
+                None => eprintln!("missing value for --shard-size"),
+            },
+            "--threads" => match args.next() {
+                Some(value) => match value.parse::<usize>() {
+                    Ok(parsed) if parsed > 0 => threads = parsed,
+                    _ => eprintln!("invalid --threads: {value}"),
+                },
+                None => eprintln!("missing value for --threads"),
+            },
+            "--seed" => match args.next() {
+                Some(value) => match value.parse::<u64>() {
+                    Ok(parsed) => seed = parsed,
+                    Err(_) => eprintln!("invalid --seed: {value}"),
+                },
+                None => eprintln!("missing value for --seed"),
+            },
             _ => eprintln!("unknown argument: {arg}"),
         }
     }
 
-    (depth, mate_counts)
+    Args {
+        depth,
+        mate_counts,
+        prove_mates,
+        prove_nodes,
+        config_a,
+        config_b,
+        config,
+        history,
+        report,
+        shard_dir,
+        shard_size,
+        threads,
+        seed,
+    }
+}
+
+// A missing config means the default parameters.
+fn load_params(path: Option<&str>) -> SearchParams {
+    match path {
+        Some(path) => SearchParams::from_file(path).unwrap_or_else(|err| panic!("{err}")),
+        None => SearchParams::default(),
+    }
 }
 
 fn mate_to_path(mate: u8) -> String {
     format!("bench/puzzles/mateIn{mate}.csv")
 }
 
+#[derive(Default)]
 struct BenchStats {
     solved: usize,
     total: usize,
@@ -78,6 +235,12 @@ struct BenchStats {
 }
 
 impl BenchStats {
+    fn add(&mut self, other: &BenchStats) {
+        self.solved += other.solved;
+        self.total += other.total;
+        self.nodes = self.nodes.saturating_add(other.nodes);
+    }
+
     fn solve_rate(&self) -> f64 {
         if self.total == 0 {
             0.0
@@ -93,8 +256,8 @@ fn print_engine_stats<E, S>(
     puzzles_by_mate: &BTreeMap<u8, Vec<Puzzle>>,
     depth: u32,
 ) where
-    E: chess_engine::engine::eval::Evaluator,
-    S: chess_engine::engine::search::SearchAlgorithm,
+    E: prune::engine::eval::Evaluator,
+    S: prune::engine::search::SearchAlgorithm,
 {
     let mut total_solved = 0usize;
     let mut total_puzzles = 0usize;
@@ -116,17 +279,7 @@ fn print_engine_stats<E, S>(
         total_puzzles += stats.total;
         total_elapsed += elapsed;
         total_nodes += stats.nodes;
-        let nps = nodes_per_second(stats.nodes, elapsed);
-        println!(
-            "{:<6} {:>7} {:>7} {:>7.2}% {:>9.2} {:>10} {:>10}",
-            mate,
-            stats.solved,
-            stats.total,
-            stats.solve_rate(),
-            elapsed,
-            format_nodes(stats.nodes),
-            format_nps(nps)
-        );
+        print_stats_row(&mate.to_string(), &stats, elapsed);
     }
 
     let total_stats = BenchStats {
@@ -134,16 +287,19 @@ fn print_engine_stats<E, S>(
         total: total_puzzles,
         nodes: total_nodes,
     };
-    let total_nps = nodes_per_second(total_stats.nodes, total_elapsed);
+    print_stats_row("total", &total_stats, total_elapsed);
+}
+
+fn print_stats_row(label: &str, stats: &BenchStats, elapsed: f64) {
     println!(
         "{:<6} {:>7} {:>7} {:>7.2}% {:>9.2} {:>10} {:>10}",
-        "total",
-        total_stats.solved,
-        total_stats.total,
-        total_stats.solve_rate(),
-        total_elapsed,
-        format_nodes(total_stats.nodes),
-        format_nps(total_nps)
+        label,
+        stats.solved,
+        stats.total,
+        stats.solve_rate(),
+        elapsed,
+        format_nodes(stats.nodes),
+        format_nps(nodes_per_second(stats.nodes, elapsed))
     );
 }
 
@@ -154,50 +310,832 @@ fn run_engine_on_puzzles<E, S>(
     depth: u32,
 ) -> BenchStats
 where
-    E: chess_engine::engine::eval::Evaluator,
-    S: chess_engine::engine::search::SearchAlgorithm,
+    E: prune::engine::eval::Evaluator,
+    S: prune::engine::search::SearchAlgorithm,
 {
     let mut solved = 0usize;
     let mut nodes = 0u64;
     let total = puzzles.len();
 
     for puzzle in puzzles {
-        if puzzle.moves.is_empty() {
-            continue;
+        let (solved_puzzle, puzzle_nodes) = run_engine_on_puzzle(name, engine, puzzle, depth);
+        nodes = nodes.saturating_add(puzzle_nodes);
+        if solved_puzzle {
+            solved += 1;
         }
+    }
 
-        if let Err(err) = engine.set_position_fen(&puzzle.fen) {
-            eprintln!("{name}: invalid FEN {}: {err}", puzzle.id);
-            continue;
+    BenchStats {
+        solved,
+        total,
+        nodes,
+    }
+}
+
+// Plays the engine's side of one puzzle; returns whether every engine move
+// matched and the nodes searched.
+fn run_engine_on_puzzle<E, S>(
+    name: &'static str,
+    engine: &mut Engine<E, S>,
+    puzzle: &Puzzle,
+    depth: u32,
+) -> (bool, u64)
+where
+    E: prune::engine::eval::Evaluator,
+    S: prune::engine::search::SearchAlgorithm,
+{
+    if puzzle.moves.is_empty() {
+        return (false, 0);
+    }
+    if let Err(err) = engine.set_position_fen(&puzzle.fen) {
+        eprintln!("{name}: invalid FEN {}: {err}", puzzle.id);
+        return (false, 0);
+    }
+
+    let mut nodes = 0u64;
+    engine.apply_move_list(&[puzzle.moves[0].clone()]);
+    for (idx, expected) in puzzle.moves.iter().enumerate().skip(1) {
+        let engine_turn = idx % 2 == 1;
+        if engine_turn {
+            let (best, search_nodes) = engine.search_depth_with_stats(depth);
+            nodes = nodes.saturating_add(search_nodes);
+            if best != *expected {
+                return (false, nodes);
+            }
+        }
+
+        engine.apply_move_list(&[expected.to_string()]);
+    }
+    (true, nodes)
+}
+
+// One configuration's results: per-mate stats and elapsed seconds, plus
+// whether each puzzle was solved, in bench order.
+struct ConfigRun {
+    rows: Vec<(u8, BenchStats, f64)>,
+    solved: Vec<bool>,
+}
+
+impl ConfigRun {
+    fn total(&self) -> (BenchStats, f64) {
+        let mut total = BenchStats {
+            solved: 0,
+            total: 0,
+            nodes: 0,
+        };
+        let mut elapsed = 0.0f64;
+        for (_, stats, row_elapsed) in &self.rows {
+            total.solved += stats.solved;
+            total.total += stats.total;
+            total.nodes = total.nodes.saturating_add(stats.nodes);
+            elapsed += row_elapsed;
+        }
+        (total, elapsed)
+    }
+}
+
+fn run_config(
+    params: SearchParams,
+    puzzles_by_mate: &BTreeMap<u8, Vec<Puzzle>>,
+    depth: u32,
+) -> ConfigRun {
+    let mut engine =
+        Engine::with_components(MaterialEvaluator, AlphaBetaSearch::with_params(params));
+    let mut run = ConfigRun {
+        rows: Vec::new(),
+        solved: Vec::new(),
+    };
+    for (mate, puzzles) in puzzles_by_mate.iter() {
+        let start = Instant::now();
+        let mut stats = BenchStats {
+            solved: 0,
+            total: puzzles.len(),
+            nodes: 0,
+        };
+        for puzzle in puzzles {
+            let (solved, nodes) = run_engine_on_puzzle("alphabeta", &mut engine, puzzle, depth);
+            stats.nodes = stats.nodes.saturating_add(nodes);
+            stats.solved += usize::from(solved);
+            run.solved.push(solved);
+        }
+        run.rows.push((*mate, stats, start.elapsed().as_secs_f64()));
+    }
+    run
+}
+
+// Runs alphabeta with both parameter sets over the same puzzles and prints
+// them side by side, with a McNemar test on the puzzles only one solved.
+fn print_comparison(
+    puzzles_by_mate: &BTreeMap<u8, Vec<Puzzle>>,
+    depth: u32,
+    params_a: SearchParams,
+    params_b: SearchParams,
+) {
+    println!();
+    println!("A: {params_a:?}");
+    println!("B: {params_b:?}");
+    let run_a = run_config(params_a, puzzles_by_mate, depth);
+    let run_b = run_config(params_b, puzzles_by_mate, depth);
+
+    println!(
+        "{:<6} {:>8} {:>8} {:>10} {:>10} {:>9} {:>9}",
+        "mate", "rate(A)", "rate(B)", "nodes(A)", "nodes(B)", "time(A)", "time(B)"
+    );
+    for ((mate, stats_a, elapsed_a), (_, stats_b, elapsed_b)) in
+        run_a.rows.iter().zip(run_b.rows.iter())
+    {
+        print_comparison_row(&mate.to_string(), stats_a, *elapsed_a, stats_b, *elapsed_b);
+    }
+    let (total_a, elapsed_a) = run_a.total();
+    let (total_b, elapsed_b) = run_b.total();
+    print_comparison_row("total", &total_a, elapsed_a, &total_b, elapsed_b);
+
+    let only_a = run_a
+        .solved
+        .iter()
+        .zip(&run_b.solved)
+        .filter(|(a, b)| **a && !**b)
+        .count();
+    let only_b = run_a
+        .solved
+        .iter()
+        .zip(&run_b.solved)
+        .filter(|(a, b)| !**a && **b)
+        .count();
+    println!(
+        "solved only by A: {only_a}, only by B: {only_b}, McNemar p = {:.4}",
+        mcnemar_p_value(only_a, only_b)
+    );
+}
+
+fn print_comparison_row(
+    label: &str,
+    stats_a: &BenchStats,
+    elapsed_a: f64,
+    stats_b: &BenchStats,
+    elapsed_b: f64,
+) {
+    println!(
+        "{:<6} {:>7.2}% {:>7.2}% {:>10} {:>10} {:>9.2} {:>9.2}",
+        label,
+        stats_a.solve_rate(),
+        stats_b.solve_rate(),
+        format_nodes(stats_a.nodes),
+        format_nodes(stats_b.nodes),
+        elapsed_a,
+        elapsed_b
+    );
+}
+
+// Two-sided p-value of McNemar's test with continuity correction, given the
+// counts of discordant pairs. Small p means the solve rates really differ.
+fn mcnemar_p_value(only_a: usize, only_b: usize) -> f64 {
+    let discordant = (only_a + only_b) as f64;
+    if discordant == 0.0 {
+        return 1.0;
+    }
+    let diff = ((only_a as f64) - (only_b as f64)).abs() - 1.0;
+    let chi_squared = diff.max(0.0).powi(2) / discordant;
+    erfc((chi_squared / 2.0).sqrt())
+}
+
+// Abramowitz and Stegun 7.1.26, accurate to about 1e-7 for x >= 0.
+fn erfc(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.327_591_1 * x);
+    let poly = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    poly * (-x * x).exp()
+}
+
+// One `--history` run, appended to the history file as a line of JSON. The
+// object is flat so the bench can read its own history back:
+//
+//     {"date":"2026-10-18","commit":"ec15849","depth":4,"config":"default",
+//      "params":"SearchParams { .. }","mate1_solved":98,"mate1_total":100,
+//      ...,"nodes":1234567,"seconds":12.5}
+#[derive(Debug, Clone, PartialEq)]
+struct HistoryRecord {
+    date: String,
+    commit: String,
+    depth: u32,
+    config: String,
+    params: String,
+    /// Solved and total puzzles per mate file.
+    mates: Vec<(u8, usize, usize)>,
+    nodes: u64,
+    seconds: f64,
+}
+
+impl HistoryRecord {
+    fn stats(&self) -> BenchStats {
+        BenchStats {
+            solved: self.mates.iter().map(|(_, solved, _)| solved).sum(),
+            total: self.mates.iter().map(|(_, _, total)| total).sum(),
+            nodes: self.nodes,
+        }
+    }
+
+    // Runs are only comparable with the same depth, parameters and puzzles.
+    fn series(&self) -> (u32, &str, Vec<u8>) {
+        let mates = self.mates.iter().map(|(mate, _, _)| *mate).collect();
+        (self.depth, &self.params, mates)
+    }
+
+    fn to_json(&self) -> String {
+        let mut json = format!(
+            "{{\"date\":{},\"commit\":{},\"depth\":{},\"config\":{},\"params\":{}",
+            json::quote(&self.date),
+            json::quote(&self.commit),
+            self.depth,
+            json::quote(&self.config),
+            json::quote(&self.params)
+        );
+        for (mate, solved, total) in &self.mates {
+            json.push_str(&format!(
+                ",\"mate{mate}_solved\":{solved},\"mate{mate}_total\":{total}"
+            ));
+        }
+        json.push_str(&format!(
+            ",\"nodes\":{},\"seconds\":{}}}",
+            self.nodes, self.seconds
+        ));
+        json
+    }
+
+    fn parse(line: &str) -> Result<Self, String> {
+        let json = Json::parse(line)?;
+        let fields = json
+            .as_object()
+            .ok_or("history line must be a JSON object")?;
+        let field = |key: &str| fields.get(key).ok_or_else(|| format!("missing {key}"));
+        let text = |key: &str| {
+            field(key)?
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| format!("invalid {key}"))
+        };
+        let number = |key: &str| field(key)?.as_f64().ok_or_else(|| format!("invalid {key}"));
+        let mut mates = Vec::new();
+        for key in fields.keys() {
+            let Some(mate) = key
+                .strip_prefix("mate")
+                .and_then(|rest| rest.strip_suffix("_solved"))
+            else {
+                continue;
+            };
+            let mate = mate.parse().map_err(|_| format!("invalid key {key}"))?;
+            let solved = number(key)? as usize;
+            let total = number(&format!("mate{mate}_total"))? as usize;
+            mates.push((mate, solved, total));
+        }
+        mates.sort_unstable();
+        Ok(Self {
+            date: text("date")?,
+            commit: text("commit")?,
+            depth: number("depth")? as u32,
+            config: text("config")?,
+            params: text("params")?,
+            mates,
+            nodes: number("nodes")? as u64,
+            seconds: number("seconds")?,
+        })
+    }
+}
+
+// Runs alphabeta with `params`, prints the usual table and appends the run
+// to the history file at `path`.
+fn record_history(
+    path: &str,
+    config: &str,
+    params: SearchParams,
+    puzzles_by_mate: &BTreeMap<u8, Vec<Puzzle>>,
+    depth: u32,
+) {
+    let params_text = format!("{params:?}");
+    let run = run_config(params, puzzles_by_mate, depth);
+    println!();
+    println!("engine: alphabeta ({config})");
+    println!(
+        "{:<6} {:>7} {:>7} {:>8} {:>9} {:>10} {:>10}",
+        "mate", "solved", "total", "rate", "time(s)", "nodes", "nps"
+    );
+    for (mate, stats, elapsed) in &run.rows {
+        print_stats_row(&mate.to_string(), stats, *elapsed);
+    }
+    let (total, elapsed) = run.total();
+    print_stats_row("total", &total, elapsed);
+
+    let record = HistoryRecord {
+        date: today(),
+        commit: git_commit(),
+        depth,
+        config: config.to_string(),
+        params: params_text,
+        mates: run
+            .rows
+            .iter()
+            .map(|(mate, stats, _)| (*mate, stats.solved, stats.total))
+            .collect(),
+        nodes: total.nodes,
+        seconds: elapsed,
+    };
+    let appended = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| writeln!(file, "{}", record.to_json()));
+    match appended {
+        Ok(()) => println!("appended to {path}"),
+        Err(err) => {
+            eprintln!("failed to write {path}: {err}");
+            process::exit(1);
+        }
+    }
+}
+
+// Prints every run in the history file with its change in solve rate
+// since the previous comparable run, then each series' trend.
+fn print_history_report(path: &str) -> Result<(), String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("failed to read {path}: {err}"))?;
+    let records = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            HistoryRecord::parse(line).map_err(|err| format!("{path}:{}: {err}", index + 1))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    println!("history: {path} ({} runs)", records.len());
+    println!(
+        "{:<10} {:<14} {:>5} {:<12} {:>7} {:>7} {:>8} {:>7} {:>10}",
+        "date", "commit", "depth", "config", "solved", "total", "rate", "change", "nodes"
+    );
+    for (index, record) in records.iter().enumerate() {
+        let stats = record.stats();
+        let change = records[..index]
+            .iter()
+            .rev()
+            .find(|earlier| earlier.series() == record.series())
+            .map_or("-".to_string(), |earlier| {
+                format!("{:+.2}", stats.solve_rate() - earlier.stats().solve_rate())
+            });
+        println!(
+            "{:<10} {:<14} {:>5} {:<12} {:>7} {:>7} {:>7.2}% {:>7} {:>10}",
+            record.date,
+            record.commit,
+            record.depth,
+            record.config,
+            stats.solved,
+            stats.total,
+            stats.solve_rate(),
+            change,
+            format_nodes(stats.nodes)
+        );
+    }
+
+    println!();
+    for trend in history_trends(&records) {
+        println!("{trend}");
+    }
+    Ok(())
+}
+
+// One line per series of comparable runs: first and latest solve rate and
+// nodes, and the best run.
+fn history_trends(records: &[HistoryRecord]) -> Vec<String> {
+    let mut series: Vec<Vec<&HistoryRecord>> = Vec::new();
+    for record in records {
+        match series
+            .iter_mut()
+            .find(|runs| runs[0].series() == record.series())
+        {
+            Some(runs) => runs.push(record),
+            None => series.push(vec![record]),
         }
+    }
+    series
+        .iter()
+        .map(|runs| {
+            let (first, latest) = (runs[0], runs[runs.len() - 1]);
+            let best = runs
+                .iter()
+                .max_by(|a, b| a.stats().solve_rate().total_cmp(&b.stats().solve_rate()))
+                .expect("series has runs");
+            format!(
+                "depth {} {} ({} runs): rate {:.2}% -> {:.2}%, nodes {} -> {}, best {:.2}% at {}",
+                latest.depth,
+                latest.config,
+                runs.len(),
+                first.stats().solve_rate(),
+                latest.stats().solve_rate(),
+                format_nodes(first.nodes),
+                format_nodes(latest.nodes),
+                best.stats().solve_rate(),
+                best.commit
+            )
+        })
+        .collect()
+}
+
+// Today's UTC date as YYYY-MM-DD.
+fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() / 86_400);
+    let (year, month, day) = civil_from_days(days as i64);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+// The proleptic Gregorian date `days` after 1970-01-01, after Howard
+// Hinnant's `civil_from_days`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
 
-        let mut solved_puzzle = true;
-        engine.apply_move_list(&[puzzle.moves[0].clone()]);
-
-        for (idx, expected) in puzzle.moves.iter().enumerate().skip(1) {
-            let engine_turn = idx % 2 == 1;
-            if engine_turn {
-                let (best, search_nodes) = engine.search_depth_with_stats(depth);
-                nodes = nodes.saturating_add(search_nodes);
-                if best != *expected {
-                    solved_puzzle = false;
-                    break;
+// The checked-out commit, marked `-dirty` with uncommitted changes, or
+// `unknown` outside a git checkout.
+fn git_commit() -> String {
+    process::Command::new("git")
+        .args(["describe", "--always", "--dirty"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|commit| commit.trim().to_string())
+        .filter(|commit| !commit.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+// A run of puzzles from one mate file. Each shard is searched on its own
+// freshly seeded engine and saved to its own file, so results do not depend
+// on which worker ran it and an interrupted run only repeats missing shards.
+struct Shard<'a> {
+    mate: u8,
+    index: usize,
+    puzzles: &'a [Puzzle],
+}
+
+impl Shard<'_> {
+    fn path(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("mate{}-{:05}.txt", self.mate, self.index))
+    }
+
+    fn seed(&self, base: u64) -> u64 {
+        base ^ (u64::from(self.mate) << 32) ^ self.index as u64
+    }
+}
+
+fn make_shards(puzzles_by_mate: &BTreeMap<u8, Vec<Puzzle>>, shard_size: usize) -> Vec<Shard<'_>> {
+    puzzles_by_mate
+        .iter()
+        .flat_map(|(mate, puzzles)| {
+            puzzles
+                .chunks(shard_size)
+                .enumerate()
+                .map(|(index, puzzles)| Shard {
+                    mate: *mate,
+                    index,
+                    puzzles,
+                })
+        })
+        .collect()
+}
+
+// A finished shard, as written to its file:
+//
+//     depth 4
+//     puzzle <id> <solved 0|1> <nodes>
+//     elapsed <seconds>
+#[derive(Debug, Clone, PartialEq)]
+struct ShardResult {
+    depth: u32,
+    puzzles: Vec<(String, bool, u64)>,
+    elapsed: f64,
+}
+
+impl ShardResult {
+    fn stats(&self) -> BenchStats {
+        BenchStats {
+            solved: self.puzzles.iter().filter(|(_, solved, _)| *solved).count(),
+            total: self.puzzles.len(),
+            nodes: self
+                .puzzles
+                .iter()
+                .fold(0u64, |sum, (_, _, nodes)| sum.saturating_add(*nodes)),
+        }
+    }
+
+    fn to_text(&self) -> String {
+        let mut text = format!("depth {}\n", self.depth);
+        for (id, solved, nodes) in &self.puzzles {
+            text.push_str(&format!("puzzle {id} {} {nodes}\n", u8::from(*solved)));
+        }
+        text.push_str(&format!("elapsed {}\n", self.elapsed));
+        text
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut depth = None;
+        let mut elapsed = None;
+        let mut puzzles = Vec::new();
+        for line in text.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                ["depth", value] => depth = value.parse().ok(),
+                ["elapsed", value] => elapsed = value.parse().ok(),
+                ["puzzle", id, solved, nodes] => {
+                    let nodes = nodes
+                        .parse()
+                        .map_err(|_| format!("invalid node count: {line}"))?;
+                    puzzles.push((id.to_string(), *solved == "1", nodes));
                 }
+                _ => return Err(format!("invalid shard line: {line}")),
+            }
+        }
+        // A shard file without `elapsed` was cut off while being written.
+        Ok(Self {
+            depth: depth.ok_or("shard file missing depth")?,
+            puzzles,
+            elapsed: elapsed.ok_or("shard file is incomplete")?,
+        })
+    }
+
+    // A saved result is reused only if it covers the same puzzles at the
+    // same depth.
+    fn load(shard: &Shard, dir: &Path, depth: u32) -> Option<Self> {
+        let result = Self::parse(&fs::read_to_string(shard.path(dir)).ok()?).ok()?;
+        let same_puzzles = result.puzzles.len() == shard.puzzles.len()
+            && result
+                .puzzles
+                .iter()
+                .zip(shard.puzzles)
+                .all(|((id, _, _), puzzle)| *id == puzzle.id);
+        (result.depth == depth && same_puzzles).then_some(result)
+    }
+
+    // Written to a temporary file first so a crash never leaves a shard
+    // file that looks complete.
+    fn save(&self, shard: &Shard, dir: &Path) -> Result<(), String> {
+        let path = shard.path(dir);
+        let partial = path.with_extension("tmp");
+        fs::write(&partial, self.to_text())
+            .and_then(|()| fs::rename(&partial, &path))
+            .map_err(|err| format!("failed to write {}: {err}", path.display()))
+    }
+}
+
+fn run_shard(shard: &Shard, depth: u32, seed: u64) -> ShardResult {
+    let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+    engine.set_rng_seed(shard.seed(seed));
+    let start = Instant::now();
+    let puzzles = shard
+        .puzzles
+        .iter()
+        .map(|puzzle| {
+            let (solved, nodes) = run_engine_on_puzzle("alphabeta", &mut engine, puzzle, depth);
+            (puzzle.id.clone(), solved, nodes)
+        })
+        .collect();
+    ShardResult {
+        depth,
+        puzzles,
+        elapsed: start.elapsed().as_secs_f64(),
+    }
+}
+
+// Takes from the front of the worker's own queue, or steals from the back
+// of another worker's once it runs dry.
+fn next_shard(queues: &[Mutex<VecDeque<usize>>], worker: usize) -> Option<usize> {
+    (0..queues.len())
+        .map(|offset| (worker + offset) % queues.len())
+        .find_map(|victim| {
+            let mut queue = queues[victim].lock().expect("shard queue");
+            if victim == worker {
+                queue.pop_front()
+            } else {
+                queue.pop_back()
             }
+        })
+}
 
-            engine.apply_move_list(&[expected.to_string()]);
+// Runs every shard without a saved result on `threads` workers, then
+// merges all shard files into the usual per-mate table.
+fn print_sharded_stats(shards: &[Shard], dir: &Path, depth: u32, threads: usize, seed: u64) {
+    if let Err(err) = fs::create_dir_all(dir) {
+        panic!("failed to create {}: {err}", dir.display());
+    }
+    let results: Vec<Mutex<Option<ShardResult>>> = shards
+        .iter()
+        .map(|shard| Mutex::new(ShardResult::load(shard, dir, depth)))
+        .collect();
+    let pending: Vec<usize> = (0..shards.len())
+        .filter(|index| results[*index].lock().expect("shard result").is_none())
+        .collect();
+    println!();
+    println!(
+        "engine: alphabeta ({} of {} shards resumed, {threads} threads)",
+        shards.len() - pending.len(),
+        shards.len()
+    );
+
+    let queues: Vec<Mutex<VecDeque<usize>>> = (0..threads)
+        .map(|worker| {
+            Mutex::new(
+                pending
+                    .iter()
+                    .copied()
+                    .skip(worker)
+                    .step_by(threads)
+                    .collect(),
+            )
+        })
+        .collect();
+    thread::scope(|scope| {
+        for worker in 0..threads.min(pending.len()) {
+            let (queues, results) = (&queues, &results);
+            scope.spawn(move || {
+                while let Some(index) = next_shard(queues, worker) {
+                    let result = run_shard(&shards[index], depth, seed);
+                    if let Err(err) = result.save(&shards[index], dir) {
+                        eprintln!("{err}");
+                    }
+                    *results[index].lock().expect("shard result") = Some(result);
+                }
+            });
         }
+    });
 
-        if solved_puzzle {
-            solved += 1;
+    println!(
+        "{:<6} {:>7} {:>7} {:>8} {:>9} {:>10} {:>10}",
+        "mate", "solved", "total", "rate", "time(s)", "nodes", "nps"
+    );
+    // Times are summed over shards, so they measure work rather than the
+    // wall-clock time of the run.
+    let mut rows: BTreeMap<u8, (BenchStats, f64)> = BTreeMap::new();
+    for (shard, result) in shards.iter().zip(results) {
+        let result = result
+            .into_inner()
+            .expect("shard result")
+            .expect("every shard has run");
+        let (stats, elapsed) = rows.entry(shard.mate).or_default();
+        stats.add(&result.stats());
+        *elapsed += result.elapsed;
+    }
+    let mut total = BenchStats::default();
+    let mut total_elapsed = 0.0f64;
+    for (mate, (stats, elapsed)) in &rows {
+        print_stats_row(&mate.to_string(), stats, *elapsed);
+        total.add(stats);
+        total_elapsed += elapsed;
+    }
+    print_stats_row("total", &total, total_elapsed);
+}
+
+// Solves each puzzle with the mate solver bounded by the puzzle's mate length,
+// and cross-checks alphabeta's mate distance at the first engine turn.
+fn print_solver_stats(puzzles_by_mate: &BTreeMap<u8, Vec<Puzzle>>, node_limit: u64, depth: u32) {
+    println!();
+    println!("engine: mate-solver (oracle depth {depth})");
+    println!(
+        "{:<6} {:>7} {:>7} {:>8} {:>9} {:>10} {:>10} {:>9}",
+        "mate", "solved", "total", "rate", "time(s)", "nodes", "nps", "mismatch"
+    );
+
+    let mut oracle = AlphaBetaSearch::new();
+    let mut total = SolverStats::default();
+    let mut total_elapsed = 0.0f64;
+    for (mate, puzzles) in puzzles_by_mate.iter() {
+        let start = Instant::now();
+        let mut stats = SolverStats::default();
+        for puzzle in puzzles {
+            stats.add(&run_solver_on_puzzle(
+                puzzle,
+                node_limit,
+                &mut oracle,
+                depth,
+            ));
         }
+        let elapsed = start.elapsed().as_secs_f64();
+        total.add(&stats);
+        total_elapsed += elapsed;
+        print_solver_row(&mate.to_string(), &stats, elapsed);
     }
+    print_solver_row("total", &total, total_elapsed);
+}
 
-    BenchStats {
-        solved,
-        total,
-        nodes,
+#[derive(Default)]
+struct SolverStats {
+    solved: usize,
+    total: usize,
+    nodes: u64,
+    mismatches: usize,
+}
+
+impl SolverStats {
+    fn add(&mut self, other: &SolverStats) {
+        self.solved += other.solved;
+        self.total += other.total;
+        self.nodes = self.nodes.saturating_add(other.nodes);
+        self.mismatches += other.mismatches;
+    }
+}
+
+fn print_solver_row(label: &str, stats: &SolverStats, elapsed: f64) {
+    let bench = BenchStats {
+        solved: stats.solved,
+        total: stats.total,
+        nodes: stats.nodes,
+    };
+    println!(
+        "{:<6} {:>7} {:>7} {:>7.2}% {:>9.2} {:>10} {:>10} {:>9}",
+        label,
+        bench.solved,
+        bench.total,
+        bench.solve_rate(),
+        elapsed,
+        format_nodes(bench.nodes),
+        format_nps(nodes_per_second(bench.nodes, elapsed)),
+        stats.mismatches
+    );
+}
+
+fn run_solver_on_puzzle(
+    puzzle: &Puzzle,
+    node_limit: u64,
+    oracle: &mut AlphaBetaSearch,
+    depth: u32,
+) -> SolverStats {
+    let mut stats = SolverStats {
+        total: 1,
+        ..SolverStats::default()
+    };
+    let mut board = Board::new();
+    if let Err(err) = board.set_fen(&puzzle.fen) {
+        eprintln!("mate-solver: invalid FEN {}: {err}", puzzle.id);
+        return stats;
     }
+    if board.apply_uci_move_list(&puzzle.moves[..1]).is_err() {
+        return stats;
+    }
+
+    let mut solved = true;
+    for (idx, expected) in puzzle.moves.iter().enumerate().skip(1) {
+        if idx % 2 == 1 {
+            let moves_left = u32::from(puzzle.mate).saturating_sub((idx as u32 - 1) / 2);
+            let search = solve_mate(&mut board, moves_left, node_limit);
+            stats.nodes = stats.nodes.saturating_add(search.nodes);
+            if idx == 1
+                && let Some(proof) = search.proof
+            {
+                let result = oracle.search(&mut board, &MaterialEvaluator, depth);
+                if let Some(claimed) = mate_moves_from_score(result.score, depth)
+                    && claimed != proof.moves as i32
+                {
+                    eprintln!(
+                        "mate-solver: {} alphabeta claims mate in {claimed}, solver proves {}",
+                        puzzle.id, proof.moves
+                    );
+                    stats.mismatches += 1;
+                }
+            }
+            let best = search
+                .proof
+                .and_then(|proof| uci_from_move(proof.best_move));
+            if best.as_deref() != Some(expected.as_str()) {
+                solved = false;
+                break;
+            }
+        }
+        if board
+            .apply_uci_move_list(std::slice::from_ref(expected))
+            .is_err()
+        {
+            solved = false;
+            break;
+        }
+    }
+
+    if solved {
+        stats.solved = 1;
+    }
+    stats
 }
 
 fn parse_puzzles_from_file(path: &str, mate: u8) -> Result<Vec<Puzzle>, String> {
@@ -320,4 +1258,90 @@ mod tests {
         assert_eq!(puzzle.moves, vec!["d4e6".to_string(), "d6h2".to_string()]);
         assert_eq!(puzzle.mate, 1);
     }
+
+    #[test]
+    fn mcnemar_p_value_reflects_discordant_pairs() {
+        assert_eq!(mcnemar_p_value(0, 0), 1.0);
+        assert!((mcnemar_p_value(5, 4) - 1.0).abs() < 1e-6);
+        // chi-squared = (|20 - 5| - 1)^2 / 25 = 7.84, p ~= 0.0051
+        assert!((mcnemar_p_value(20, 5) - 0.0051).abs() < 1e-4);
+    }
+
+    #[test]
+    fn shard_results_round_trip_and_reject_partial_files() {
+        let result = ShardResult {
+            depth: 3,
+            puzzles: vec![
+                ("000rZ".to_string(), true, 812),
+                ("00a1b".to_string(), false, 40),
+            ],
+            elapsed: 0.25,
+        };
+        let text = result.to_text();
+        assert_eq!(ShardResult::parse(&text), Ok(result.clone()));
+        assert_eq!(result.stats().solved, 1);
+        assert_eq!(result.stats().nodes, 852);
+
+        let cut_off = text.trim_end().rsplit_once('\n').expect("lines").0;
+        assert!(ShardResult::parse(cut_off).is_err());
+    }
+
+    fn history_record(commit: &str, depth: u32, solved: usize) -> HistoryRecord {
+        HistoryRecord {
+            date: "2026-10-18".to_string(),
+            commit: commit.to_string(),
+            depth,
+            config: "tuned \"b\".toml".to_string(),
+            params: "SearchParams { contempt: 0 }".to_string(),
+            mates: vec![(1, 10, 10), (3, solved, 20)],
+            nodes: 123_456,
+            seconds: 1.5,
+        }
+    }
+
+    #[test]
+    fn history_records_round_trip() {
+        let record = history_record("ec15849-dirty", 4, 12);
+        let json = record.to_json();
+        assert!(json.starts_with("{\"date\":\"2026-10-18\",\"commit\":\"ec15849-dirty\""));
+        assert!(json.contains(",\"mate3_solved\":12,\"mate3_total\":20,"));
+        assert_eq!(HistoryRecord::parse(&json), Ok(record));
+        assert!(HistoryRecord::parse("{\"date\":\"2026-10-18\"}").is_err());
+    }
+
+    #[test]
+    fn history_trends_compare_runs_of_the_same_series() {
+        let records = [
+            history_record("aaaaaaa", 4, 10),
+            history_record("bbbbbbb", 2, 4),
+            history_record("ccccccc", 4, 16),
+            history_record("ddddddd", 4, 14),
+        ];
+        let trends = history_trends(&records);
+        assert_eq!(trends.len(), 2);
+        assert_eq!(
+            trends[0],
+            "depth 4 tuned \"b\".toml (3 runs): rate 66.67% -> 80.00%, nodes 123.46K -> 123.46K, best 86.67% at ccccccc"
+        );
+        assert!(trends[1].starts_with("depth 2 tuned \"b\".toml (1 runs): rate 46.67%"));
+    }
+
+    #[test]
+    fn civil_from_days_matches_the_calendar() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(20_744), (2026, 10, 18));
+    }
+
+    #[test]
+    fn idle_workers_steal_from_the_back_of_other_queues() {
+        let queues = vec![
+            Mutex::new(VecDeque::from([0, 2, 4])),
+            Mutex::new(VecDeque::new()),
+        ];
+        assert_eq!(next_shard(&queues, 0), Some(0));
+        assert_eq!(next_shard(&queues, 1), Some(4));
+        assert_eq!(next_shard(&queues, 1), Some(2));
+        assert_eq!(next_shard(&queues, 0), None);
+    }
 }
@@ -0,0 +1,206 @@
+use prune::engine::board::Board;
+use prune::engine::composite::Phases;
+use prune::engine::datagen::{label_game, DataGenConfig, DrawLabel};
+use prune::engine::eval::MaterialEvaluator;
+use prune::engine::fen::STARTPOS_FEN;
+use prune::engine::game::{play_game, GameConfig};
+use prune::engine::movegen::generate_legal;
+use prune::engine::search::AlphaBetaSearch;
+use prune::engine::skill::NoisyEvaluator;
+use prune::engine::Engine;
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+use std::fs;
+
+struct Args {
+    games: u32,
+    out: Option<String>,
+    depth: u32,
+    random_plies: u32,
+    seed: u64,
+    /// Centipawns of noise either way in the players' evaluations.
+    eval_noise: i32,
+    config: DataGenConfig,
+}
+
+fn main() {
+    let args = parse_args();
+    let mut rng = SmallRng::seed_from_u64(args.seed);
+    let mut lines = Vec::new();
+
+    for game in 0..args.games {
+        let Some(start_fen) = random_opening(&mut rng, args.random_plies) else {
+            continue;
+        };
+        // Each player's noise is seeded like its move choice, so games differ
+        // from one another but a run repeats. Positions are still scored
+        // without noise.
+        let white_seed = args.seed ^ (u64::from(game) << 1);
+        let black_seed = args.seed ^ ((u64::from(game) << 1) | 1);
+        let mut white = Engine::with_components(
+            NoisyEvaluator::new(&MaterialEvaluator, args.eval_noise, white_seed),
+            AlphaBetaSearch::new(),
+        );
+        let mut black = Engine::with_components(
+            NoisyEvaluator::new(&MaterialEvaluator, args.eval_noise, black_seed),
+            AlphaBetaSearch::new(),
+        );
+        white.set_rng_seed(white_seed);
+        black.set_rng_seed(black_seed);
+        let config = GameConfig {
+            start_fen: Some(start_fen),
+            white_depth: args.depth,
+            black_depth: args.depth,
+            ..GameConfig::default()
+        };
+
+        let positions = play_game(&mut white, &mut black, &config)
+            .and_then(|record| label_game(&record, &MaterialEvaluator, &args.config));
+        match positions {
+            Ok(positions) => {
+                eprintln!("game {}: {} positions", game + 1, positions.len());
+                lines.extend(positions.iter().map(|position| position.to_line()));
+            }
+            Err(err) => eprintln!("game {}: {err}", game + 1),
+        }
+    }
+
+    let output: String = lines.iter().map(|line| format!("{line}\n")).collect();
+    match args.out.as_deref() {
+        Some(path) => {
+            if let Err(err) = fs::write(path, output) {
+                eprintln!("failed to write {path}: {err}");
+                std::process::exit(1);
+            }
+        }
+        None => print!("{output}"),
+    }
+}
+
+fn parse_args() -> Args {
+    let mut parsed = Args {
+        games: 10,
+        out: None,
+        depth: 3,
+        random_plies: 8,
+        seed: 1,
+        eval_noise: 0,
+        config: DataGenConfig::default(),
+    };
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--games" => match args.next() {
+                Some(value) => match value.parse::<u32>() {
+                    Ok(value) => parsed.games = value,
+                    Err(_) => eprintln!("invalid --games: {value}"),
+                },
+                None => eprintln!("missing value for --games"),
+            },
+            "--out" => match args.next() {
+                Some(value) => parsed.out = Some(value),
+                None => eprintln!("missing value for --out"),
+            },
+            "--depth" => match args.next() {
+                Some(value) => match value.parse::<u32>() {
+                    Ok(value) => parsed.depth = value,
+                    Err(_) => eprintln!("invalid --depth: {value}"),
+                },
+                None => eprintln!("missing value for --depth"),
+            },
+            "--score-depth" => match args.next() {
+                Some(value) => match value.parse::<u32>() {
+                    Ok(value) => parsed.config.depth = value,
+                    Err(_) => eprintln!("invalid --score-depth: {value}"),
+                },
+                None => eprintln!("missing value for --score-depth"),
+            },
+            "--random-plies" => match args.next() {
+                Some(value) => match value.parse::<u32>() {
+                    Ok(value) => parsed.random_plies = value,
+                    Err(_) => eprintln!("invalid --random-plies: {value}"),
+                },
+                None => eprintln!("missing value for --random-plies"),
+            },
+            "--seed" => match args.next() {
+                Some(value) => match value.parse::<u64>() {
+                    Ok(value) => parsed.seed = value,
+                    Err(_) => eprintln!("invalid --seed: {value}"),
+                },
+                None => eprintln!("missing value for --seed"),
+            },
+            "--eval-noise" => match args.next() {
+                Some(value) => match value.parse::<i32>() {
+                    Ok(value) if value >= 0 => parsed.eval_noise = value,
+                    _ => eprintln!("invalid --eval-noise: {value}"),
+                },
+                None => eprintln!("missing value for --eval-noise"),
+            },
+            "--draws" => match args.next() {
+                Some(value) => match DrawLabel::parse(&value) {
+                    Ok(draws) => parsed.config.draws = draws,
+                    Err(err) => eprintln!("{err}"),
+                },
+                None => eprintln!("missing value for --draws"),
+            },
+            "--phases" => match args.next() {
+                Some(value) => match parse_phases(&value) {
+                    Ok(phases) => parsed.config.phases = phases,
+                    Err(err) => eprintln!("{err}"),
+                },
+                None => eprintln!("missing value for --phases"),
+            },
+            "--quiet" => parsed.config.quiet_only = true,
+            "--min-eval" => match args.next() {
+                Some(value) => match value.parse::<i32>() {
+                    Ok(value) => parsed.config.min_eval = Some(value),
+                    Err(_) => eprintln!("invalid --min-eval: {value}"),
+                },
+                None => eprintln!("missing value for --min-eval"),
+            },
+            "--max-eval" => match args.next() {
+                Some(value) => match value.parse::<i32>() {
+                    Ok(value) => parsed.config.max_eval = Some(value),
+                    Err(_) => eprintln!("invalid --max-eval: {value}"),
+                },
+                None => eprintln!("missing value for --max-eval"),
+            },
+            _ => eprintln!("unknown argument: {arg}"),
+        }
+    }
+
+    parsed
+}
+
+// A comma-separated list of opening, middlegame and endgame.
+fn parse_phases(text: &str) -> Result<Phases, String> {
+    text.split(',')
+        .map(|name| match name.trim() {
+            "opening" => Ok(Phases::OPENING),
+            "middlegame" => Ok(Phases::MIDDLEGAME),
+            "endgame" => Ok(Phases::ENDGAME),
+            other => Err(format!(
+                "unknown phase: {other} (expected opening, middlegame or endgame)"
+            )),
+        })
+        .reduce(|a, b| Ok(a? | b?))
+        .unwrap_or(Ok(Phases::ALL))
+}
+
+// The start position after `plies` random legal moves, so games differ;
+// `None` if the random moves end the game.
+fn random_opening(rng: &mut SmallRng, plies: u32) -> Option<String> {
+    let mut board = Board::new();
+    board.set_fen(STARTPOS_FEN).ok()?;
+    for _ in 0..plies {
+        let moves = generate_legal(&mut board);
+        if moves.is_empty() {
+            return None;
+        }
+        board
+            .apply_move(moves[rng.gen_range(0..moves.len())])
+            .ok()?;
+    }
+    (!generate_legal(&mut board).is_empty()).then(|| board.to_fen())
+}
@@ -0,0 +1,80 @@
+use prune::engine::eval::MaterialEvaluator;
+use prune::engine::search::AlphaBetaSearch;
+use prune::engine::Engine;
+use prune::lichess::{self, BotConfig, Client};
+use std::time::Duration;
+
+/// The bot account's API token, read from the environment so it stays out
+/// of shell history and process listings.
+const TOKEN_VARIABLE: &str = "LICHESS_BOT_TOKEN";
+
+struct Args {
+    config: BotConfig,
+    /// Another server speaking the Lichess API, for testing.
+    server: Option<String>,
+}
+
+fn parse_args() -> Args {
+    let mut parsed = Args {
+        config: BotConfig::default(),
+        server: None,
+    };
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--max-depth" => match args.next() {
+                Some(value) => match value.parse::<u32>() {
+                    Ok(value) if value > 0 => parsed.config.max_depth = value,
+                    _ => eprintln!("invalid --max-depth: {value}"),
+                },
+                None => eprintln!("missing value for --max-depth"),
+            },
+            "--move-time" => match args.next() {
+                Some(value) => match value.parse::<u64>() {
+                    Ok(value) if value > 0 => {
+                        parsed.config.move_time = Duration::from_millis(value)
+                    }
+                    _ => eprintln!("invalid --move-time: {value}"),
+                },
+                None => eprintln!("missing value for --move-time"),
+            },
+            "--max-games" => match args.next() {
+                Some(value) => match value.parse::<usize>() {
+                    Ok(value) if value > 0 => parsed.config.max_games = value,
+                    _ => eprintln!("invalid --max-games: {value}"),
+                },
+                None => eprintln!("missing value for --max-games"),
+            },
+            "--server" => match args.next() {
+                Some(value) => parsed.server = Some(value),
+                None => eprintln!("missing value for --server"),
+            },
+            _ => eprintln!("unknown argument: {arg}"),
+        }
+    }
+
+    parsed
+}
+
+fn main() {
+    let args = parse_args();
+    let Ok(token) = std::env::var(TOKEN_VARIABLE) else {
+        eprintln!("set {TOKEN_VARIABLE} to the bot account's API token");
+        std::process::exit(1);
+    };
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("failed to start the runtime");
+    let client = match &args.server {
+        Some(server) => Client::with_base_url(server, &token),
+        None => Client::new(&token),
+    };
+    let bot = lichess::run(client, args.config, || {
+        Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new())
+    });
+    if let Err(err) = runtime.block_on(bot) {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+}
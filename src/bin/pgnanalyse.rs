@@ -0,0 +1,363 @@
+use prune::engine::classify::classify;
+use prune::engine::eval::MaterialEvaluator;
+use prune::engine::movegen::generate_legal;
+use prune::engine::notation::{MoveFormat, Notation, PieceLetters};
+use prune::engine::pgn::{parse_pgn_games, write_pgn, PgnGame};
+use prune::engine::search::{AlphaBetaSearch, SearchAlgorithm};
+use prune::engine::types::Color;
+use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+const CSV_HEADER: &str =
+    "Game,White,Black,Result,Plies,WhiteACPL,BlackACPL,WhiteBlunders,BlackBlunders,WhiteQuietBlunders,BlackQuietBlunders";
+// Mate scores would dominate the average; clamp each move's loss.
+const MAX_LOSS: i32 = 1000;
+const INACCURACY: i32 = 50;
+const MISTAKE: i32 = 100;
+const BLUNDER: i32 = 300;
+
+struct Args {
+    pgn: Option<String>,
+    out: Option<String>,
+    csv: Option<String>,
+    depth: u32,
+    threads: usize,
+    format: MoveFormat,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct PlayerStats {
+    moves: u32,
+    total_loss: i64,
+    blunders: u32,
+    /// Blunders in quiet positions, with no tactics on the board to miss.
+    quiet_blunders: u32,
+}
+
+impl PlayerStats {
+    fn acpl(&self) -> f64 {
+        if self.moves == 0 {
+            0.0
+        } else {
+            self.total_loss as f64 / self.moves as f64
+        }
+    }
+}
+
+struct GameReport {
+    game: PgnGame,
+    white: PlayerStats,
+    black: PlayerStats,
+}
+
+fn main() {
+    let args = parse_args();
+    let Some(pgn_path) = args.pgn.as_deref() else {
+        eprintln!("missing --pgn <path>");
+        std::process::exit(1);
+    };
+    let contents = match fs::read_to_string(pgn_path) {
+        Ok(contents) => contents,
+        Err(err) => {
+            eprintln!("failed to read {pgn_path}: {err}");
+            std::process::exit(1);
+        }
+    };
+    // One unreadable game should not cost the rest of the database.
+    let games: Vec<PgnGame> = parse_pgn_games(&contents)
+        .into_iter()
+        .filter_map(|game| game.map_err(|err| eprintln!("skipping {err}")).ok())
+        .collect();
+
+    let reports = analyse_games(games, args.depth, args.threads, args.format);
+
+    let annotated: Vec<String> = reports
+        .iter()
+        .map(|report| write_pgn(&report.game))
+        .collect();
+    let annotated = annotated.join("\n");
+    match args.out.as_deref() {
+        Some(path) => write_file(path, &annotated),
+        None => print!("{annotated}"),
+    }
+
+    let mut rows = vec![CSV_HEADER.to_string()];
+    rows.extend(
+        reports
+            .iter()
+            .enumerate()
+            .map(|(index, report)| format_summary_row(index + 1, report)),
+    );
+    let summary = rows.join("\n") + "\n";
+    match args.csv.as_deref() {
+        Some(path) => write_file(path, &summary),
+        None => eprint!("{summary}"),
+    }
+}
+
+fn write_file(path: &str, contents: &str) {
+    if let Err(err) = fs::write(path, contents) {
+        eprintln!("failed to write {path}: {err}");
+        std::process::exit(1);
+    }
+}
+
+fn parse_args() -> Args {
+    let mut parsed = Args {
+        pgn: None,
+        out: None,
+        csv: None,
+        depth: 4,
+        threads: thread::available_parallelism().map_or(1, usize::from),
+        format: MoveFormat::default(),
+    };
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--pgn" => match args.next() {
+                Some(value) => parsed.pgn = Some(value),
+                None => eprintln!("missing value for --pgn"),
+            },
+            "--out" => match args.next() {
+                Some(value) => parsed.out = Some(value),
+                None => eprintln!("missing value for --out"),
+            },
+            "--csv" => match args.next() {
+                Some(value) => parsed.csv = Some(value),
+                None => eprintln!("missing value for --csv"),
+            },
+            "--depth" => match args.next() {
+                Some(value) => match value.parse::<u32>() {
+                    Ok(value) => parsed.depth = value,
+                    Err(_) => eprintln!("invalid --depth: {value}"),
+                },
+                None => eprintln!("missing value for --depth"),
+            },
+            "--threads" => match args.next() {
+                Some(value) => match value.parse::<usize>() {
+                    Ok(value) if value > 0 => parsed.threads = value,
+                    _ => eprintln!("invalid --threads: {value}"),
+                },
+                None => eprintln!("missing value for --threads"),
+            },
+            "--notation" => match args.next() {
+                Some(value) => match Notation::parse(&value) {
+                    Ok(notation) => parsed.format.notation = notation,
+                    Err(err) => eprintln!("invalid --notation: {err}"),
+                },
+                None => eprintln!("missing value for --notation"),
+            },
+            "--piece-letters" => match args.next() {
+                Some(value) => match PieceLetters::for_language(&value) {
+                    Some(letters) => parsed.format.letters = letters,
+                    None => eprintln!("invalid --piece-letters: {value}"),
+                },
+                None => eprintln!("missing value for --piece-letters"),
+            },
+            _ => eprintln!("unknown argument: {arg}"),
+        }
+    }
+
+    parsed
+}
+
+// Workers pull the next unanalysed game from a shared counter; reports are
+// returned in input order regardless of which thread finished first.
+fn analyse_games(
+    games: Vec<PgnGame>,
+    depth: u32,
+    threads: usize,
+    format: MoveFormat,
+) -> Vec<GameReport> {
+    let next = AtomicUsize::new(0);
+    let slots: Vec<Mutex<Option<GameReport>>> = games.iter().map(|_| Mutex::new(None)).collect();
+
+    thread::scope(|scope| {
+        for _ in 0..threads.min(games.len()) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(game) = games.get(index) else {
+                    break;
+                };
+                let report = analyse_game(game.clone(), depth, format);
+                *slots[index].lock().expect("report slot") = Some(report);
+            });
+        }
+    });
+
+    slots
+        .into_iter()
+        .filter_map(|slot| slot.into_inner().expect("report slot"))
+        .collect()
+}
+
+// Each position is searched once: the score after a move is the next
+// position's score negated, so a move's loss is `score[i] + score[i + 1]`.
+// The preferred move in mistake comments is written in `format`; the
+// movetext itself stays SAN as PGN requires.
+fn analyse_game(mut game: PgnGame, depth: u32, format: MoveFormat) -> GameReport {
+    let mut white = PlayerStats::default();
+    let mut black = PlayerStats::default();
+    let Ok(mut board) = game.start_board() else {
+        return GameReport { game, white, black };
+    };
+    let mut search = AlphaBetaSearch::new();
+
+    let mut before = search.search(&mut board, &MaterialEvaluator, depth);
+    for pgn_move in &mut game.moves {
+        let mover = board.side_to_move;
+        let quiet = classify(&mut board, &MaterialEvaluator).is_quiet();
+        let best = before.best_moves.first().copied();
+        let best_text = best.and_then(|mv| format.format(&mut board, mv).ok());
+        if board.apply_move(pgn_move.mv).is_err() {
+            break;
+        }
+        let after = if generate_legal(&mut board).is_empty() {
+            // Mate or stalemate needs no search and costs nothing to the mover.
+            None
+        } else {
+            Some(search.search(&mut board, &MaterialEvaluator, depth))
+        };
+        let loss = match &after {
+            Some(after) if !before.best_moves.contains(&pgn_move.mv) => {
+                before.score.saturating_add(after.score).clamp(0, MAX_LOSS)
+            }
+            _ => 0,
+        };
+
+        let stats = match mover {
+            Color::White => &mut white,
+            Color::Black => &mut black,
+        };
+        stats.moves += 1;
+        stats.total_loss += i64::from(loss);
+        if loss >= BLUNDER {
+            stats.blunders += 1;
+            stats.quiet_blunders += u32::from(quiet);
+        }
+
+        if let Some(nag) = judgement_nag(loss)
+            && !pgn_move.nags.contains(&nag)
+        {
+            pgn_move.nags.push(nag);
+        }
+        if let Some(after) = &after {
+            let white_score = match mover {
+                Color::White => -after.score,
+                Color::Black => after.score,
+            };
+            let mut note = format_eval(white_score);
+            if loss >= MISTAKE
+                && let Some(best_text) = best_text
+            {
+                note.push_str(&format!(" best: {best_text}"));
+            }
+            pgn_move.comment = Some(match pgn_move.comment.take() {
+                Some(existing) => format!("{existing} {note}"),
+                None => note,
+            });
+        }
+
+        match after {
+            Some(after) => before = after,
+            None => break,
+        }
+    }
+
+    game.set_tag("Annotator", &format!("prune depth {depth}"));
+    GameReport { game, white, black }
+}
+
+fn judgement_nag(loss: i32) -> Option<u8> {
+    if loss >= BLUNDER {
+        Some(4)
+    } else if loss >= MISTAKE {
+        Some(2)
+    } else if loss >= INACCURACY {
+        Some(6)
+    } else {
+        None
+    }
+}
+
+fn format_eval(white_score: i32) -> String {
+    format!("{:+.2}", white_score as f64 / 100.0)
+}
+
+fn format_summary_row(index: usize, report: &GameReport) -> String {
+    let game = &report.game;
+    format!(
+        "{index},{},{},{},{},{:.1},{:.1},{},{},{},{}",
+        csv_field(game.tag("White").unwrap_or("?")),
+        csv_field(game.tag("Black").unwrap_or("?")),
+        game.result,
+        game.moves.len(),
+        report.white.acpl(),
+        report.black.acpl(),
+        report.white.blunders,
+        report.black.blunders,
+        report.white.quiet_blunders,
+        report.black.quiet_blunders,
+    )
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prune::engine::pgn::parse_pgn;
+
+    const HANGS_QUEEN: &str = r#"[White "A"]
+[Black "B, C"]
+
+1. e4 e5 2. Qh5 Nc6 3. Qxe5+ Nxe5 *
+"#;
+
+    #[test]
+    fn flags_hung_queen_as_blunder() {
+        let games = parse_pgn(HANGS_QUEEN).expect("pgn");
+        let report = analyse_game(games[0].clone(), 2, MoveFormat::default());
+
+        assert_eq!(report.white.moves, 3);
+        assert_eq!(report.white.blunders, 1);
+        assert_eq!(report.black.blunders, 0);
+        // 3. Qxe5+ walks into a capture from a position without tactics.
+        assert_eq!(report.white.quiet_blunders, 1);
+        assert!(report.game.moves[4].nags.contains(&4));
+        assert!(report.game.moves[4]
+            .comment
+            .as_deref()
+            .is_some_and(|comment| comment.contains("best:")));
+
+        // The preferred move follows the chosen notation.
+        let uci = MoveFormat::new(Notation::Uci, PieceLetters::ENGLISH);
+        let report = analyse_game(games[0].clone(), 2, uci);
+        let comment = report.game.moves[4].comment.clone().expect("comment");
+        let best = comment.split("best: ").nth(1).expect("best move");
+        assert!(best.len() == 4 && best.chars().all(|ch| ch.is_ascii_alphanumeric()));
+    }
+
+    #[test]
+    fn parallel_analysis_keeps_input_order() {
+        let text = format!("{HANGS_QUEEN}\n[White \"D\"]\n\n1. d4 d5 *\n");
+        let games = parse_pgn(&text).expect("pgn");
+        let reports = analyse_games(games, 1, 4, MoveFormat::default());
+
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[1].game.tag("White"), Some("D"));
+        assert_eq!(
+            format_summary_row(1, &reports[0]).split(',').nth(2),
+            Some("\"B")
+        );
+    }
+}
@@ -0,0 +1,252 @@
+use prune::engine::board::Board;
+use prune::engine::eval::MaterialEvaluator;
+use prune::engine::movegen::generate_legal;
+use prune::engine::search::{AlphaBetaSearch, SearchAlgorithm};
+use prune::engine::types::{move_from_uci, uci_from_move, Move};
+use std::fs;
+
+const CSV_HEADER: &str =
+    "PuzzleId,FEN,Moves,Rating,RatingDeviation,Popularity,NbPlays,Themes,GameUrl,OpeningTags";
+const MATE_THRESHOLD: i32 = 29_000;
+
+struct Args {
+    games: Option<String>,
+    out: Option<String>,
+    depth: u32,
+    verify_depth: u32,
+    swing: i32,
+    margin: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Puzzle {
+    id: String,
+    fen: String,
+    blunder: Move,
+    solution: Move,
+    mate: bool,
+}
+
+fn main() {
+    let args = parse_args();
+    let Some(games_path) = args.games.as_deref() else {
+        eprintln!("missing --games <path>");
+        std::process::exit(1);
+    };
+    let contents = fs::read_to_string(games_path)
+        .unwrap_or_else(|err| panic!("failed to read {games_path}: {err}"));
+
+    let mut rows = vec![CSV_HEADER.to_string()];
+    for (game_index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let moves = match parse_game(line) {
+            Ok(moves) => moves,
+            Err(err) => {
+                eprintln!("game {}: {err}", game_index + 1);
+                continue;
+            }
+        };
+        for puzzle in scan_game(game_index + 1, &moves, &args) {
+            rows.push(format_puzzle_row(&puzzle));
+        }
+    }
+
+    let output = rows.join("\n") + "\n";
+    match args.out.as_deref() {
+        Some(path) => {
+            fs::write(path, output).unwrap_or_else(|err| panic!("failed to write {path}: {err}"))
+        }
+        None => print!("{output}"),
+    }
+}
+
+fn parse_args() -> Args {
+    let mut parsed = Args {
+        games: None,
+        out: None,
+        depth: 2,
+        verify_depth: 4,
+        swing: 200,
+        margin: 150,
+    };
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--games" => match args.next() {
+                Some(value) => parsed.games = Some(value),
+                None => eprintln!("missing value for --games"),
+            },
+            "--out" => match args.next() {
+                Some(value) => parsed.out = Some(value),
+                None => eprintln!("missing value for --out"),
+            },
+            "--depth" => match args.next() {
+                Some(value) => match value.parse::<u32>() {
+                    Ok(value) => parsed.depth = value,
+                    Err(_) => eprintln!("invalid --depth: {value}"),
+                },
+                None => eprintln!("missing value for --depth"),
+            },
+            "--verify-depth" => match args.next() {
+                Some(value) => match value.parse::<u32>() {
+                    Ok(value) => parsed.verify_depth = value,
+                    Err(_) => eprintln!("invalid --verify-depth: {value}"),
+                },
+                None => eprintln!("missing value for --verify-depth"),
+            },
+            "--swing" => match args.next() {
+                Some(value) => match value.parse::<i32>() {
+                    Ok(value) => parsed.swing = value,
+                    Err(_) => eprintln!("invalid --swing: {value}"),
+                },
+                None => eprintln!("missing value for --swing"),
+            },
+            "--margin" => match args.next() {
+                Some(value) => match value.parse::<i32>() {
+                    Ok(value) => parsed.margin = value,
+                    Err(_) => eprintln!("invalid --margin: {value}"),
+                },
+                None => eprintln!("missing value for --margin"),
+            },
+            _ => eprintln!("unknown argument: {arg}"),
+        }
+    }
+
+    parsed
+}
+
+fn parse_game(line: &str) -> Result<Vec<Move>, String> {
+    line.split_whitespace()
+        .map(|text| move_from_uci(text).ok_or_else(|| format!("invalid UCI move: {text}")))
+        .collect()
+}
+
+// A tactical moment is a move that swings the shallow evaluation against the
+// mover by at least `swing`, after which the opponent has exactly one move that
+// keeps the advantage at `verify_depth`.
+fn scan_game(game: usize, moves: &[Move], args: &Args) -> Vec<Puzzle> {
+    let mut puzzles = Vec::new();
+    let mut search = AlphaBetaSearch::new();
+    let mut board = Board::new();
+    board.set_startpos();
+
+    for (ply, mv) in moves.iter().enumerate() {
+        if !generate_legal(&mut board).contains(mv) {
+            eprintln!("game {game}: illegal move at ply {}", ply + 1);
+            break;
+        }
+
+        let fen = board.to_fen();
+        let before = search.search(&mut board, &MaterialEvaluator, args.depth);
+        if board.apply_move(*mv).is_err() {
+            break;
+        }
+        let after = search.search(&mut board, &MaterialEvaluator, args.depth);
+        let swing = before.score.saturating_add(after.score);
+
+        if !after.best_moves.is_empty()
+            && swing >= args.swing
+            && let Some((solution, mate)) = unique_solution(&mut search, &mut board, args)
+        {
+            puzzles.push(Puzzle {
+                id: format!("g{game}p{}", ply + 1),
+                fen,
+                blunder: *mv,
+                solution,
+                mate,
+            });
+        }
+    }
+
+    puzzles
+}
+
+fn unique_solution(
+    search: &mut AlphaBetaSearch,
+    board: &mut Board,
+    args: &Args,
+) -> Option<(Move, bool)> {
+    let best = search.search(board, &MaterialEvaluator, args.verify_depth);
+    let [solution] = best.best_moves.as_slice() else {
+        return None;
+    };
+
+    let others: Vec<Move> = generate_legal(board)
+        .into_iter()
+        .filter(|mv| mv != solution)
+        .collect();
+    if !others.is_empty() {
+        let second = search.search_with_root_moves(
+            board,
+            &MaterialEvaluator,
+            args.verify_depth,
+            None,
+            Some(&others),
+        );
+        if best.score.saturating_sub(second.score) < args.margin {
+            return None;
+        }
+    }
+
+    Some((*solution, best.score >= MATE_THRESHOLD))
+}
+
+fn format_puzzle_row(puzzle: &Puzzle) -> String {
+    let blunder = uci_from_move(puzzle.blunder).unwrap_or_default();
+    let solution = uci_from_move(puzzle.solution).unwrap_or_default();
+    let themes = if puzzle.mate {
+        "mate oneMove"
+    } else {
+        "advantage oneMove"
+    };
+    format!(
+        "{},{},{blunder} {solution},0,0,0,0,{themes},,",
+        puzzle.id, puzzle.fen
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args() -> Args {
+        Args {
+            games: None,
+            out: None,
+            depth: 1,
+            verify_depth: 2,
+            swing: 200,
+            margin: 150,
+        }
+    }
+
+    #[test]
+    fn finds_hanging_queen_puzzle() {
+        let moves = parse_game("e2e4 e7e5 d1h5 b8c6 h5f7").expect("game");
+        let puzzles = scan_game(1, &moves, &args());
+
+        let puzzle = puzzles.last().expect("puzzle");
+        assert_eq!(puzzle.id, "g1p5");
+        assert_eq!(uci_from_move(puzzle.blunder).as_deref(), Some("h5f7"));
+        assert_eq!(uci_from_move(puzzle.solution).as_deref(), Some("e8f7"));
+    }
+
+    #[test]
+    fn formats_bench_compatible_row() {
+        let puzzle = Puzzle {
+            id: "g1p5".to_string(),
+            fen: "4k3/8/8/8/8/8/8/4K3 w - - 0 1".to_string(),
+            blunder: move_from_uci("e1e2").unwrap(),
+            solution: move_from_uci("e8e7").unwrap(),
+            mate: false,
+        };
+        assert_eq!(
+            format_puzzle_row(&puzzle),
+            "g1p5,4k3/8/8/8/8/8/8/4K3 w - - 0 1,e1e2 e8e7,0,0,0,0,advantage oneMove,,"
+        );
+    }
+}
@@ -0,0 +1,664 @@
+use prune::engine::board::Board;
+use prune::engine::cache::{AnalysisCache, CachedAnalysis};
+use prune::engine::eval::MaterialEvaluator;
+use prune::engine::limits::SearchLimits;
+use prune::engine::pool::EnginePool;
+use prune::engine::search::AlphaBetaSearch;
+use prune::engine::types::uci_from_move;
+use prune::engine::{Engine, SearchInfo};
+use prune::json::{self, Json};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+type Pool = EnginePool<MaterialEvaluator, AlphaBetaSearch>;
+
+const EVENT_STREAM_HEADER: &str = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n";
+
+/// Larger request bodies are refused with 413 rather than read into memory.
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// How long a read or write may block before the connection is dropped.
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+const BODY_TOO_LARGE: &str = "request body too large";
+
+/// Longer request or header lines, or more headers, are refused with 431.
+const MAX_HEADER_LINE_BYTES: usize = 8 * 1024;
+const MAX_HEADERS: usize = 64;
+
+const HEADERS_TOO_LARGE: &str = "request headers too large";
+
+/// Positions kept by the analysis cache when only `--cache-file` is given.
+const DEFAULT_CACHE_SIZE: usize = 10_000;
+
+/// How often new cache results are written to `--cache-file`.
+const CACHE_FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+struct Args {
+    port: u16,
+    engines: usize,
+    max_depth: u32,
+    cache_size: Option<usize>,
+    cache_file: Option<String>,
+}
+
+/// Finished analyses shared by all connections, saved to `file` when set by
+/// a background thread every `CACHE_FLUSH_INTERVAL` and when dropped.
+struct Cache {
+    entries: Mutex<AnalysisCache>,
+    file: Option<String>,
+    /// Whether `entries` holds results not yet saved to `file`.
+    dirty: AtomicBool,
+}
+
+impl Cache {
+    fn lookup(&self, board: &Board) -> Option<CachedAnalysis> {
+        self.entries.lock().unwrap().get(board).cloned()
+    }
+
+    fn store(&self, board: &Board, info: &SearchInfo) {
+        let mut pv = info.pv.clone();
+        if pv.is_empty() {
+            pv.extend(info.best_moves.first());
+        }
+        let analysis = CachedAnalysis {
+            depth: info.depth,
+            score: info.score,
+            nodes: info.nodes,
+            pv,
+        };
+        if self.entries.lock().unwrap().insert(board, analysis) {
+            self.dirty.store(true, Ordering::Release);
+        }
+    }
+
+    /// Saves the entries to `file` if any were added since the last flush.
+    /// Only the snapshot is taken under the lock; the write happens outside
+    /// it, so searches finishing meanwhile are not held up.
+    fn flush(&self) {
+        let Some(path) = &self.file else {
+            return;
+        };
+        if !self.dirty.swap(false, Ordering::AcqRel) {
+            return;
+        }
+        let text = self.entries.lock().unwrap().to_text();
+        if let Err(err) = AnalysisCache::write_text(path, &text) {
+            eprintln!("{err}");
+        }
+    }
+}
+
+impl Drop for Cache {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct AnalyseRequest {
+    fen: Option<String>,
+    moves: Vec<String>,
+    depth: Option<u32>,
+    /// Milliseconds.
+    movetime: Option<u64>,
+    nodes: Option<u64>,
+}
+
+struct HttpRequest {
+    method: String,
+    path: String,
+    headers: BTreeMap<String, String>,
+    body: String,
+}
+
+fn main() {
+    let args = parse_args();
+    let pool = Arc::new(Pool::new(args.engines, || {
+        Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new())
+    }));
+    let cache = match open_cache(&args) {
+        Ok(cache) => cache.map(Arc::new),
+        Err(err) => {
+            eprintln!("invalid --cache-file: {err}");
+            std::process::exit(1);
+        }
+    };
+    if let Some(cache) = cache.as_ref().filter(|cache| cache.file.is_some()) {
+        let cache = Arc::clone(cache);
+        thread::spawn(move || loop {
+            thread::sleep(CACHE_FLUSH_INTERVAL);
+            cache.flush();
+        });
+    }
+    let listener = TcpListener::bind(("127.0.0.1", args.port))
+        .unwrap_or_else(|err| panic!("failed to bind port {}: {err}", args.port));
+    eprintln!("listening on 127.0.0.1:{}", args.port);
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                eprintln!("connection failed: {err}");
+                continue;
+            }
+        };
+        let pool = Arc::clone(&pool);
+        let cache = cache.clone();
+        let max_depth = args.max_depth;
+        thread::spawn(move || {
+            if let Err(err) = handle_connection(stream, &pool, cache.as_deref(), max_depth) {
+                eprintln!("request failed: {err}");
+            }
+        });
+    }
+}
+
+fn parse_args() -> Args {
+    let mut parsed = Args {
+        port: 8080,
+        engines: 4,
+        max_depth: 8,
+        cache_size: None,
+        cache_file: None,
+    };
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => match args.next() {
+                Some(value) => match value.parse::<u16>() {
+                    Ok(value) => parsed.port = value,
+                    Err(_) => eprintln!("invalid --port: {value}"),
+                },
+                None => eprintln!("missing value for --port"),
+            },
+            "--engines" => match args.next() {
+                Some(value) => match value.parse::<usize>() {
+                    Ok(value) if value > 0 => parsed.engines = value,
+                    _ => eprintln!("invalid --engines: {value}"),
+                },
+                None => eprintln!("missing value for --engines"),
+            },
+            "--max-depth" => match args.next() {
+                Some(value) => match value.parse::<u32>() {
+                    Ok(value) => parsed.max_depth = value,
+                    Err(_) => eprintln!("invalid --max-depth: {value}"),
+                },
+                None => eprintln!("missing value for --max-depth"),
+            },
+            "--cache-size" => match args.next() {
+                Some(value) => match value.parse::<usize>() {
+                    Ok(value) => parsed.cache_size = Some(value),
+                    Err(_) => eprintln!("invalid --cache-size: {value}"),
+                },
+                None => eprintln!("missing value for --cache-size"),
+            },
+            "--cache-file" => match args.next() {
+                Some(value) => parsed.cache_file = Some(value),
+                None => eprintln!("missing value for --cache-file"),
+            },
+            _ => eprintln!("unknown argument: {arg}"),
+        }
+    }
+
+    parsed
+}
+
+// The cache is off unless it is given a size or a file; a size of 0 also
+// turns it off.
+fn open_cache(args: &Args) -> Result<Option<Cache>, String> {
+    let size = match (args.cache_size, &args.cache_file) {
+        (Some(0), _) | (None, None) => return Ok(None),
+        (Some(size), _) => size,
+        (None, Some(_)) => DEFAULT_CACHE_SIZE,
+    };
+    let entries = match &args.cache_file {
+        Some(path) => AnalysisCache::load(path, size)?,
+        None => AnalysisCache::new(size),
+    };
+    Ok(Some(Cache {
+        entries: Mutex::new(entries),
+        file: args.cache_file.clone(),
+        dirty: AtomicBool::new(false),
+    }))
+}
+
+fn handle_connection(
+    mut stream: TcpStream,
+    pool: &Pool,
+    cache: Option<&Cache>,
+    max_depth: u32,
+) -> Result<(), String> {
+    stream
+        .set_read_timeout(Some(IO_TIMEOUT))
+        .and_then(|_| stream.set_write_timeout(Some(IO_TIMEOUT)))
+        .map_err(|err| err.to_string())?;
+    let request = match read_request(&mut stream) {
+        Err(err) if err == BODY_TOO_LARGE => {
+            let body = format!("{{\"error\":{}}}", json::quote(&err));
+            return write_response(&mut stream, "413 Payload Too Large", &body);
+        }
+        Err(err) if err == HEADERS_TOO_LARGE => {
+            let body = format!("{{\"error\":{}}}", json::quote(&err));
+            return write_response(&mut stream, "431 Request Header Fields Too Large", &body);
+        }
+        request => request?,
+    };
+    if request.method != "POST" || request.path.split('?').next() != Some("/analyse") {
+        return write_response(&mut stream, "404 Not Found", "{\"error\":\"not found\"}");
+    }
+
+    let analyse = match parse_analyse_request(&request.body) {
+        Ok(analyse) => analyse,
+        Err(err) => {
+            let body = format!("{{\"error\":{}}}", json::quote(&err));
+            return write_response(&mut stream, "400 Bad Request", &body);
+        }
+    };
+    let depth = analyse.depth.unwrap_or(max_depth).min(max_depth);
+    let limits = SearchLimits {
+        depth: Some(depth),
+        nodes: analyse.nodes,
+        movetime: analyse.movetime.map(Duration::from_millis),
+        ..SearchLimits::default()
+    };
+    let streaming = request.path.contains("stream=1")
+        || request
+            .headers
+            .get("accept")
+            .is_some_and(|accept| accept.contains("text/event-stream"));
+
+    let mut engine = pool.checkout();
+    let position = match analyse.fen.as_deref() {
+        Some(fen) => engine.set_position_fen(fen),
+        None => {
+            engine.set_position_startpos();
+            Ok(())
+        }
+    };
+    if let Err(err) = position.and_then(|_| engine.try_apply_move_list(&analyse.moves)) {
+        let body = format!("{{\"error\":{}}}", json::quote(&err));
+        return write_response(&mut stream, "400 Bad Request", &body);
+    }
+
+    // A deep enough earlier analysis is the answer; a shallower one still
+    // tells the search which move to try first.
+    let cached = cache.and_then(|cache| cache.lookup(engine.board()));
+    if let Some(analysis) = &cached
+        && analysis.depth >= depth
+        && let Some(best) = analysis.best_move().and_then(uci_from_move)
+    {
+        let info = cached_info(analysis);
+        let body = format_result(&best, 0, Some(&info), true);
+        if !streaming {
+            return write_response(&mut stream, "200 OK", &body);
+        }
+        let events = format!(
+            "{EVENT_STREAM_HEADER}event: info\ndata: {}\n\nevent: bestmove\ndata: {body}\n\n",
+            format_info(&info)
+        );
+        return stream
+            .write_all(events.as_bytes())
+            .map_err(|err| err.to_string());
+    }
+    engine.set_root_hint(cached.as_ref().and_then(CachedAnalysis::best_move));
+
+    if !streaming {
+        let mut last = None;
+        let (bestmove, nodes) = engine.search(&limits, |info| {
+            last = Some(info.clone());
+        });
+        if let (Some(cache), Some(info)) = (cache, &last) {
+            cache.store(engine.board(), info);
+        }
+        let body = format_result(&bestmove, nodes, last.as_ref(), false);
+        return write_response(&mut stream, "200 OK", &body);
+    }
+
+    stream
+        .write_all(EVENT_STREAM_HEADER.as_bytes())
+        .map_err(|err| err.to_string())?;
+    // A client that hangs up mid-stream cancels the search.
+    let stop = engine.stop_handle();
+    let mut last = None;
+    let (bestmove, nodes) = engine.search(&limits, |info| {
+        let event = format!("event: info\ndata: {}\n\n", format_info(info));
+        if stream.write_all(event.as_bytes()).is_err() {
+            stop.stop();
+        }
+        last = Some(info.clone());
+    });
+    if let (Some(cache), Some(info)) = (cache, &last) {
+        cache.store(engine.board(), info);
+    }
+    let event = format!(
+        "event: bestmove\ndata: {}\n\n",
+        format_result(&bestmove, nodes, last.as_ref(), false)
+    );
+    stream
+        .write_all(event.as_bytes())
+        .map_err(|err| err.to_string())
+}
+
+fn read_request(stream: &mut TcpStream) -> Result<HttpRequest, String> {
+    let mut reader = BufReader::new(stream);
+    let request_line = read_head_line(&mut reader)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().ok_or("missing method")?.to_string();
+    let path = parts.next().ok_or("missing path")?.to_string();
+
+    let mut headers = BTreeMap::new();
+    for count in 0.. {
+        let line = read_head_line(&mut reader)?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if count == MAX_HEADERS {
+            return Err(HEADERS_TOO_LARGE.to_string());
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let length = headers
+        .get("content-length")
+        .and_then(|value| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    if length > MAX_BODY_BYTES {
+        return Err(BODY_TOO_LARGE.to_string());
+    }
+    let mut body = vec![0u8; length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|err| err.to_string())?;
+
+    Ok(HttpRequest {
+        method,
+        path,
+        headers,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    })
+}
+
+// One line of the request head, read no further than `MAX_HEADER_LINE_BYTES`.
+fn read_head_line(reader: &mut impl BufRead) -> Result<String, String> {
+    let mut line = String::new();
+    reader
+        .take(MAX_HEADER_LINE_BYTES as u64)
+        .read_line(&mut line)
+        .map_err(|err| err.to_string())?;
+    if line.len() == MAX_HEADER_LINE_BYTES && !line.ends_with('\n') {
+        return Err(HEADERS_TOO_LARGE.to_string());
+    }
+    Ok(line)
+}
+
+fn write_response(stream: &mut TcpStream, status: &str, body: &str) -> Result<(), String> {
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream
+        .write_all(response.as_bytes())
+        .map_err(|err| err.to_string())
+}
+
+// Accepts a JSON object: {"fen": "...", "moves": ["e2e4", ...], "depth": 6},
+// optionally with "movetime" (milliseconds) and "nodes" limits.
+fn parse_analyse_request(body: &str) -> Result<AnalyseRequest, String> {
+    let mut request = AnalyseRequest::default();
+    let body = body.trim();
+    if body.is_empty() {
+        return Ok(request);
+    }
+    let json = Json::parse(body)?;
+    let fields = json.as_object().ok_or("body must be a JSON object")?;
+    for (key, value) in fields {
+        match key.as_str() {
+            "fen" => {
+                let fen = value.as_str().ok_or("fen must be a string")?;
+                request.fen = Some(fen.to_string());
+            }
+            "moves" => {
+                request.moves = value
+                    .as_array()
+                    .and_then(|moves| {
+                        moves
+                            .iter()
+                            .map(|mv| mv.as_str().map(str::to_string))
+                            .collect()
+                    })
+                    .ok_or("moves must be an array of strings")?;
+            }
+            "depth" => {
+                let depth = non_negative_integer(value, "depth", f64::from(u32::MAX))?;
+                request.depth = Some(depth as u32);
+            }
+            "movetime" => {
+                request.movetime = Some(non_negative_integer(value, "movetime", u32::MAX.into())?);
+            }
+            "nodes" => {
+                request.nodes = Some(non_negative_integer(value, "nodes", u32::MAX.into())?);
+            }
+            _ => return Err(format!("unknown field: {key}")),
+        }
+    }
+
+    Ok(request)
+}
+
+// A whole number from 0 to `max`; JSON numbers are doubles, so larger ones
+// would not be exact anyway.
+fn non_negative_integer(value: &Json, name: &str, max: f64) -> Result<u64, String> {
+    value
+        .as_f64()
+        .filter(|number| number.fract() == 0.0 && (0.0..=max).contains(number))
+        .map(|number| number as u64)
+        .ok_or_else(|| format!("{name} must be a non-negative integer"))
+}
+
+fn format_info(info: &SearchInfo) -> String {
+    let best_moves: Vec<String> = info
+        .best_moves
+        .iter()
+        .filter_map(|mv| uci_from_move(*mv).map(|mv| json::quote(&mv)))
+        .collect();
+    let pv: Vec<String> = info
+        .pv
+        .iter()
+        .filter_map(|mv| uci_from_move(*mv).map(|mv| json::quote(&mv)))
+        .collect();
+    format!(
+        "{{\"depth\":{},\"score\":{},\"nodes\":{},\"time\":{},\"best_moves\":[{}],\"pv\":[{}],\"best_move_changed\":{}}}",
+        info.depth,
+        info.score,
+        info.nodes,
+        info.time_ms,
+        best_moves.join(","),
+        pv.join(","),
+        info.best_move_changed
+    )
+}
+
+fn format_result(bestmove: &str, nodes: u64, last: Option<&SearchInfo>, cached: bool) -> String {
+    let info = last.map(format_info).unwrap_or_else(|| "null".to_string());
+    format!(
+        "{{\"bestmove\":{},\"nodes\":{nodes},\"info\":{info},\"cached\":{cached}}}",
+        json::quote(bestmove)
+    )
+}
+
+// The info of a cached analysis, as reported when it was searched.
+fn cached_info(analysis: &CachedAnalysis) -> SearchInfo {
+    SearchInfo {
+        depth: analysis.depth,
+        seldepth: analysis.depth,
+        score: analysis.score,
+        nodes: analysis.nodes,
+        time_ms: 0,
+        best_moves: analysis.best_move().into_iter().collect(),
+        hashfull: None,
+        stats: None,
+        pv: analysis.pv.clone(),
+        best_move_changed: false,
+        ebf: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_analyse_request() {
+        let request = parse_analyse_request(
+            r#"{"fen": "4k3/8/8/8/8/8/8/4K3 w - - 0 1", "moves": ["e1e2", "e8e7"], "depth": 5,
+                "movetime": 250, "nodes": 10000}"#,
+        )
+        .expect("request");
+        assert_eq!(
+            request,
+            AnalyseRequest {
+                fen: Some("4k3/8/8/8/8/8/8/4K3 w - - 0 1".to_string()),
+                moves: vec!["e1e2".to_string(), "e8e7".to_string()],
+                depth: Some(5),
+                movetime: Some(250),
+                nodes: Some(10000),
+            }
+        );
+    }
+
+    #[test]
+    fn empty_body_analyses_startpos() {
+        assert_eq!(
+            parse_analyse_request("").unwrap(),
+            AnalyseRequest::default()
+        );
+    }
+
+    #[test]
+    fn cached_results_report_their_analysis() {
+        let analysis = CachedAnalysis {
+            depth: 6,
+            score: 35,
+            nodes: 1200,
+            pv: ["e2e4", "e7e5"]
+                .iter()
+                .filter_map(|mv| prune::engine::types::move_from_uci(mv))
+                .collect(),
+        };
+        let body = format_result("e2e4", 0, Some(&cached_info(&analysis)), true);
+        assert!(body.contains(r#""depth":6,"score":35,"nodes":1200"#));
+        assert!(body.contains(r#""pv":["e2e4","e7e5"]"#));
+        assert!(body.ends_with(r#""cached":true}"#));
+    }
+
+    #[test]
+    fn cache_writes_its_file_only_when_flushed() {
+        let path = std::env::temp_dir().join(format!("prune-cache-{}.txt", std::process::id()));
+        let path = path.to_str().expect("path").to_string();
+        let _ = std::fs::remove_file(&path);
+        let cache = Cache {
+            entries: Mutex::new(AnalysisCache::new(4)),
+            file: Some(path.clone()),
+            dirty: AtomicBool::new(false),
+        };
+        let analysis = CachedAnalysis {
+            depth: 3,
+            score: 20,
+            nodes: 500,
+            pv: Vec::new(),
+        };
+        let mut board = Board::new();
+        board.set_startpos();
+
+        cache.store(&board, &cached_info(&analysis));
+        assert!(!std::path::Path::new(&path).exists());
+        cache.flush();
+        let saved = AnalysisCache::load(&path, 4).expect("load");
+        assert_eq!(saved.len(), 1);
+
+        // Nothing new: the file is left alone.
+        std::fs::remove_file(&path).expect("remove");
+        cache.flush();
+        assert!(!std::path::Path::new(&path).exists());
+
+        board.set_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").expect("fen");
+        cache.store(&board, &cached_info(&analysis));
+        drop(cache);
+        assert_eq!(AnalysisCache::load(&path, 4).expect("load").len(), 2);
+        std::fs::remove_file(&path).expect("remove");
+    }
+
+    #[test]
+    fn refuses_oversized_bodies_before_reading_them() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).expect("bind");
+        let mut client = TcpStream::connect(listener.local_addr().expect("addr")).expect("connect");
+        client
+            .write_all(b"POST /analyse HTTP/1.1\r\nContent-Length: 1000000000\r\n\r\n")
+            .expect("write");
+        let (mut stream, _) = listener.accept().expect("accept");
+        assert_eq!(
+            read_request(&mut stream).err().as_deref(),
+            Some(BODY_TOO_LARGE)
+        );
+    }
+
+    #[test]
+    fn refuses_long_or_many_headers() {
+        let long = format!("X-Long: {}\r\n", "a".repeat(MAX_HEADER_LINE_BYTES));
+        let many = "X-Header: 1\r\n".repeat(MAX_HEADERS + 1);
+        for headers in [long, many] {
+            let listener = TcpListener::bind(("127.0.0.1", 0)).expect("bind");
+            let mut client =
+                TcpStream::connect(listener.local_addr().expect("addr")).expect("connect");
+            client
+                .write_all(format!("POST /analyse HTTP/1.1\r\n{headers}\r\n").as_bytes())
+                .expect("write");
+            let (mut stream, _) = listener.accept().expect("accept");
+            assert_eq!(
+                read_request(&mut stream).err().as_deref(),
+                Some(HEADERS_TOO_LARGE)
+            );
+        }
+
+        // Up to the limits is fine.
+        let listener = TcpListener::bind(("127.0.0.1", 0)).expect("bind");
+        let mut client = TcpStream::connect(listener.local_addr().expect("addr")).expect("connect");
+        let headers = "X-Header: 1\r\n".repeat(MAX_HEADERS);
+        client
+            .write_all(format!("POST /analyse HTTP/1.1\r\n{headers}\r\n").as_bytes())
+            .expect("write");
+        let (mut stream, _) = listener.accept().expect("accept");
+        assert!(read_request(&mut stream).is_ok());
+    }
+
+    #[test]
+    fn rejects_unknown_field() {
+        let err = parse_analyse_request(r#"{"wtime": 10}"#).unwrap_err();
+        assert!(err.contains("unknown field"));
+    }
+
+    #[test]
+    fn rejects_fields_of_the_wrong_type() {
+        for body in [
+            r#"{"fen": 1}"#,
+            r#"{"moves": ["e2e4", 5]}"#,
+            r#"{"depth": -1}"#,
+            r#"{"depth": 2.5}"#,
+            r#"{"movetime": -10}"#,
+            r#"{"nodes": "100"}"#,
+            r#"["e2e4"]"#,
+        ] {
+            assert!(parse_analyse_request(body).is_err(), "{body}");
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! The legal moves of a position with static annotations, for front-ends
+//! that mark candidate moves as the user hovers them. Nothing here searches,
+//! so a whole move list is annotated in microseconds.
+
+use crate::engine::board::Board;
+use crate::engine::movegen::generate_legal;
+use crate::engine::notation::move_to_san;
+use crate::engine::see::see;
+use crate::engine::types::Move;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnnotatedMove {
+    pub mv: Move,
+    pub san: String,
+    /// Static exchange evaluation: the material the move wins (positive) or
+    /// gives away (negative) once every profitable recapture is made.
+    pub see: i32,
+    /// The move loses material immediately: it captures into a losing
+    /// exchange or puts the moved piece where it can be won.
+    pub loses_material: bool,
+}
+
+/// Every legal move of `board` in generation order, annotated.
+pub fn legal_moves(board: &mut Board) -> Vec<AnnotatedMove> {
+    generate_legal(board)
+        .into_iter()
+        .filter_map(|mv| {
+            let san = move_to_san(board, mv).ok()?;
+            let see = see(board, mv);
+            Some(AnnotatedMove {
+                mv,
+                san,
+                see,
+                loses_material: see < 0,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_moves_that_give_material_away() {
+        // The knight can take a defended pawn or step onto a pawn's attack.
+        let mut board = Board::new();
+        board
+            .set_fen("4k3/8/8/3p4/2p5/8/8/2N1K3 w - - 0 1")
+            .expect("fen");
+        let moves = legal_moves(&mut board);
+        let find = |san: &str| {
+            moves
+                .iter()
+                .find(|annotated| annotated.san == san)
+                .unwrap_or_else(|| panic!("{san} missing"))
+        };
+
+        assert_eq!(find("Nd3").see, -320);
+        assert!(find("Nd3").loses_material);
+        assert_eq!(find("Ne2").see, 0);
+        assert!(!find("Ne2").loses_material);
+        assert!(!find("Kd2").loses_material);
+        assert_eq!(moves.len(), generate_legal(&mut board).len());
+    }
+}
@@ -1,5 +1,8 @@
 use crate::engine::board::Board;
-use crate::engine::castling::{revoke_all, revoke_kingside, revoke_queenside};
+use crate::engine::castling::{
+    castling_squares, castling_wing, revoke_all, revoke_kingside, revoke_queenside, Wing,
+};
+use crate::engine::checks::check_hash;
 use crate::engine::types::{Color, Move, Piece, PieceKind, Square};
 use crate::engine::zobrist;
 
@@ -13,8 +16,13 @@ pub struct MoveUndo {
     pub previous_fullmove_number: u32,
     pub previous_side_to_move: Color,
     pub rook_move: Option<(Square, Square)>,
+    /// Where the moved piece landed: `mv.to`, except for Chess960 castling,
+    /// written as the king taking its own rook.
+    pub landed: Square,
     pub moved_piece: Piece,
     pub previous_hash: u64,
+    pub previous_mirror_hash: u64,
+    pub previous_in_check: Option<bool>,
 }
 
 pub fn apply_move(board: &mut Board, mv: Move) -> Result<(), String> {
@@ -41,22 +49,43 @@ pub fn make_move(board: &mut Board, mv: Move) -> Result<MoveUndo, String> {
         previous_fullmove_number: board.fullmove_number,
         previous_side_to_move: board.side_to_move,
         rook_move: None,
+        landed: mv.to,
         moved_piece: ctx.piece,
         previous_hash: board.hash,
+        previous_mirror_hash: board.mirror_hash,
+        previous_in_check: board.check_cache.get(),
     };
 
-    let was_capture = apply_piece_move(board, &ctx, moved_piece, &mut undo)?;
-    if ctx.is_castle {
-        undo.rook_move = Some(apply_castle_rook_move(board, &ctx)?);
-    }
+    let was_capture = match ctx.castle {
+        Some(wing) => {
+            apply_castle(board, &ctx, wing, &mut undo)?;
+            false
+        }
+        None => apply_piece_move(board, &ctx, moved_piece, &mut undo)?,
+    };
 
     update_en_passant(board, &ctx);
-    update_castling_rights(&mut board.castling_rights, &ctx, was_capture);
+    update_castling_rights(board, &ctx, was_capture);
     update_clocks(board, &ctx, was_capture);
 
+    let landed = Move {
+        to: undo.landed,
+        ..mv
+    };
     board.hash = zobrist::update_hash_for_move(
         board,
-        mv,
+        landed,
+        ctx.piece,
+        moved_piece,
+        undo.captured,
+        undo.captured_square,
+        undo.rook_move,
+        undo.previous_castling_rights,
+        undo.previous_en_passant,
+    );
+    board.mirror_hash = zobrist::update_mirror_hash_for_move(
+        board,
+        landed,
         ctx.piece,
         moved_piece,
         undo.captured,
@@ -65,6 +94,9 @@ pub fn make_move(board: &mut Board, mv: Move) -> Result<MoveUndo, String> {
         undo.previous_castling_rights,
         undo.previous_en_passant,
     );
+    board.history.push(undo.previous_hash);
+    board.check_cache.set(None);
+    check_hash(board);
 
     Ok(undo)
 }
@@ -76,19 +108,26 @@ pub fn unmake_move(board: &mut Board, mv: Move, undo: MoveUndo) {
     board.castling_rights = undo.previous_castling_rights;
     board.en_passant = undo.previous_en_passant;
     board.hash = undo.previous_hash;
+    board.mirror_hash = undo.previous_mirror_hash;
+    board.history.pop();
+    board.check_cache.set(undo.previous_in_check);
 
-    if let Some((rook_from, rook_to)) = undo.rook_move {
-        let rook = board.squares[rook_to.index() as usize];
-        board.squares[rook_to.index() as usize] = None;
+    // King and rook are both lifted before either is put back, as their
+    // squares may overlap in Chess960.
+    let rook = undo.rook_move.map(|(rook_from, rook_to)| {
+        let rook = board.squares[rook_to.index() as usize].take();
+        (rook_from, rook)
+    });
+    board.squares[undo.landed.index() as usize] = None;
+    if let Some((rook_from, rook)) = rook {
         board.squares[rook_from.index() as usize] = rook;
     }
-
-    board.squares[mv.to.index() as usize] = None;
     board.squares[mv.from.index() as usize] = Some(undo.moved_piece);
 
     if let Some(square) = undo.captured_square {
         board.squares[square.index() as usize] = undo.captured;
     }
+    check_hash(board);
 }
 
 struct MoveContext {
@@ -102,7 +141,7 @@ struct MoveContext {
     to_rank: u8,
     was_capture: bool,
     is_pawn: bool,
-    is_castle: bool,
+    castle: Option<Wing>,
     is_en_passant_capture: bool,
 }
 
@@ -115,16 +154,14 @@ impl MoveContext {
         if piece.color != board.side_to_move {
             return Err("piece does not match side to move".to_string());
         }
-        let was_capture = board.squares[to_index as usize].is_some();
+        let castle = castling_wing(board, mv);
+        let was_capture = board.squares[to_index as usize].is_some() && castle.is_none();
         let is_en_passant_capture =
             piece.kind == PieceKind::Pawn && board.en_passant == Some(mv.to) && !was_capture;
         let from_file = from_index & 0x0f;
         let to_file = to_index & 0x0f;
         let from_rank = from_index >> 4;
         let to_rank = to_index >> 4;
-        let is_castle = piece.kind == PieceKind::King
-            && from_rank == to_rank
-            && (from_file as i8 - to_file as i8).abs() == 2;
         let is_pawn = piece.kind == PieceKind::Pawn;
 
         Ok(Self {
@@ -138,7 +175,7 @@ impl MoveContext {
             to_rank,
             was_capture,
             is_pawn,
-            is_castle,
+            castle,
             is_en_passant_capture,
         })
     }
@@ -172,25 +209,26 @@ fn apply_piece_move(
     Ok(was_capture)
 }
 
-fn apply_castle_rook_move(
+fn apply_castle(
     board: &mut Board,
     ctx: &MoveContext,
-) -> Result<(Square, Square), String> {
-    let (rook_from_file, rook_to_file) = match ctx.to_file {
-        6 => (7, 5),
-        2 => (0, 3),
-        _ => return Err("invalid castling target".to_string()),
-    };
-    let rook_rank = ctx.from_rank;
-    let rook_from_index = (rook_rank * 16 + rook_from_file) as usize;
-    let rook_to_index = (rook_rank * 16 + rook_to_file) as usize;
-    let rook = board.squares[rook_from_index].ok_or_else(|| "no rook for castling".to_string())?;
+    wing: Wing,
+    undo: &mut MoveUndo,
+) -> Result<(), String> {
+    let (king_to, rook_from, rook_to) =
+        castling_squares(&board.castling_files, ctx.piece.color, wing);
+    let rook = board.squares[rook_from.index() as usize]
+        .ok_or_else(|| "no rook for castling".to_string())?;
     if rook.kind != PieceKind::Rook || rook.color != ctx.piece.color {
         return Err("invalid rook for castling".to_string());
     }
-    board.squares[rook_from_index] = None;
-    board.squares[rook_to_index] = Some(rook);
-    Ok((Square(rook_from_index as u8), Square(rook_to_index as u8)))
+    board.squares[ctx.from_index as usize] = None;
+    board.squares[rook_from.index() as usize] = None;
+    board.squares[king_to.index() as usize] = Some(ctx.piece);
+    board.squares[rook_to.index() as usize] = Some(rook);
+    undo.rook_move = Some((rook_from, rook_to));
+    undo.landed = king_to;
+    Ok(())
 }
 
 fn update_en_passant(board: &mut Board, ctx: &MoveContext) {
@@ -205,28 +243,31 @@ fn update_en_passant(board: &mut Board, ctx: &MoveContext) {
     board.en_passant = new_en_passant;
 }
 
-fn update_castling_rights(rights: &mut u8, ctx: &MoveContext, was_capture: bool) {
+fn update_castling_rights(board: &mut Board, ctx: &MoveContext, was_capture: bool) {
+    let files = board.castling_files;
+    let rights = &mut board.castling_rights;
     if ctx.piece.kind == PieceKind::King {
         revoke_all(rights, ctx.piece.color);
     }
 
-    if ctx.piece.kind == PieceKind::Rook {
-        match (ctx.piece.color, ctx.from_file, ctx.from_rank) {
-            (Color::White, 0, 0) => revoke_queenside(rights, Color::White),
-            (Color::White, 7, 0) => revoke_kingside(rights, Color::White),
-            (Color::Black, 0, 7) => revoke_queenside(rights, Color::Black),
-            (Color::Black, 7, 7) => revoke_kingside(rights, Color::Black),
-            _ => {}
-        }
-    }
-
-    if was_capture {
-        match (ctx.to_file, ctx.to_rank) {
-            (0, 0) => revoke_queenside(rights, Color::White),
-            (7, 0) => revoke_kingside(rights, Color::White),
-            (0, 7) => revoke_queenside(rights, Color::Black),
-            (7, 7) => revoke_kingside(rights, Color::Black),
-            _ => {}
+    // A right goes with its rook, whether the rook moves or is captured.
+    for color in [Color::White, Color::Black] {
+        let home_rank = match color {
+            Color::White => 0,
+            Color::Black => 7,
+        };
+        let moved = ctx.piece.kind == PieceKind::Rook
+            && ctx.piece.color == color
+            && ctx.from_rank == home_rank;
+        let captured = was_capture && ctx.to_rank == home_rank;
+        for wing in Wing::BOTH {
+            let file = files.rook(color, wing);
+            if (moved && ctx.from_file == file) || (captured && ctx.to_file == file) {
+                match wing {
+                    Wing::Kingside => revoke_kingside(rights, color),
+                    Wing::Queenside => revoke_queenside(rights, color),
+                }
+            }
         }
     }
 }
@@ -0,0 +1,121 @@
+use crate::engine::movegen::{offset_square, KING_OFFSETS, KNIGHT_OFFSETS};
+use crate::engine::types::{is_valid_square, Color, Square};
+use std::sync::OnceLock;
+
+const WHITE_PAWN_CAPTURE_OFFSETS: [i8; 2] = [15, 17];
+const BLACK_PAWN_CAPTURE_OFFSETS: [i8; 2] = [-15, -17];
+
+/// Up to eight target squares, stored inline so lookups never allocate.
+#[derive(Clone, Copy)]
+struct SquareList {
+    squares: [Square; 8],
+    len: u8,
+}
+
+impl SquareList {
+    const EMPTY: Self = Self {
+        squares: [Square(0); 8],
+        len: 0,
+    };
+
+    fn from_offsets(from: Square, offsets: &[i8]) -> Self {
+        let mut list = Self::EMPTY;
+        for offset in offsets {
+            if let Some(to) = offset_square(from, *offset) {
+                list.squares[list.len as usize] = to;
+                list.len += 1;
+            }
+        }
+        list
+    }
+
+    fn as_slice(&self) -> &[Square] {
+        &self.squares[..self.len as usize]
+    }
+}
+
+// Indexed by 0x88 square; off-board entries stay empty.
+struct AttackTables {
+    knight: [SquareList; 128],
+    king: [SquareList; 128],
+    white_pawn: [SquareList; 128],
+    black_pawn: [SquareList; 128],
+}
+
+/// Squares a knight on `square` attacks.
+pub fn knight_attacks(square: Square) -> &'static [Square] {
+    tables().knight[square.index() as usize].as_slice()
+}
+
+/// Squares a king on `square` attacks.
+pub fn king_attacks(square: Square) -> &'static [Square] {
+    tables().king[square.index() as usize].as_slice()
+}
+
+/// Squares a pawn of `color` on `square` attacks.
+pub fn pawn_attacks(square: Square, color: Color) -> &'static [Square] {
+    let tables = tables();
+    match color {
+        Color::White => tables.white_pawn[square.index() as usize].as_slice(),
+        Color::Black => tables.black_pawn[square.index() as usize].as_slice(),
+    }
+}
+
+fn tables() -> &'static AttackTables {
+    static TABLES: OnceLock<AttackTables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let mut tables = AttackTables {
+            knight: [SquareList::EMPTY; 128],
+            king: [SquareList::EMPTY; 128],
+            white_pawn: [SquareList::EMPTY; 128],
+            black_pawn: [SquareList::EMPTY; 128],
+        };
+        for index in 0u8..128u8 {
+            if !is_valid_square(index) {
+                continue;
+            }
+            let square = Square(index);
+            let slot = index as usize;
+            tables.knight[slot] = SquareList::from_offsets(square, &KNIGHT_OFFSETS);
+            tables.king[slot] = SquareList::from_offsets(square, &KING_OFFSETS);
+            tables.white_pawn[slot] = SquareList::from_offsets(square, &WHITE_PAWN_CAPTURE_OFFSETS);
+            tables.black_pawn[slot] = SquareList::from_offsets(square, &BLACK_PAWN_CAPTURE_OFFSETS);
+        }
+        tables
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::types::square_from_algebraic;
+
+    fn names(squares: &[Square]) -> Vec<String> {
+        let mut names: Vec<String> = squares
+            .iter()
+            .filter_map(|sq| crate::engine::types::algebraic_from_square(*sq))
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn knight_attacks_from_corner() {
+        let a1 = square_from_algebraic("a1").unwrap();
+        assert_eq!(names(knight_attacks(a1)), vec!["b3", "c2"]);
+    }
+
+    #[test]
+    fn king_attacks_from_center() {
+        let e4 = square_from_algebraic("e4").unwrap();
+        assert_eq!(king_attacks(e4).len(), 8);
+    }
+
+    #[test]
+    fn pawn_attacks_by_color() {
+        let a2 = square_from_algebraic("a2").unwrap();
+        assert_eq!(names(pawn_attacks(a2, Color::White)), vec!["b3"]);
+        let e7 = square_from_algebraic("e7").unwrap();
+        assert_eq!(names(pawn_attacks(e7, Color::Black)), vec!["d6", "f6"]);
+    }
+}
@@ -0,0 +1,89 @@
+use crate::engine::eval::Evaluator;
+use crate::engine::search::SearchAlgorithm;
+use crate::engine::types::{uci_from_move, Move};
+use crate::engine::{Engine, SearchInfo};
+
+/// Positions used by `--verify-determinism` when no FEN is given.
+pub const AUDIT_FENS: [&str; 2] = [
+    crate::engine::fen::STARTPOS_FEN,
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+];
+
+/// Runs the same iterative search twice on fresh engines and reports the first
+/// depth where node counts, scores, best moves or PVs differ.
+///
+/// `make_engine` must build a new engine each call so no search state (such as
+/// the transposition table) carries over between the two runs.
+pub fn verify_determinism<E, S, F>(make_engine: F, fen: &str, depth: u32) -> Result<(), String>
+where
+    E: Evaluator,
+    S: SearchAlgorithm,
+    F: Fn() -> Engine<E, S>,
+{
+    let first = run(&make_engine, fen, depth)?;
+    let second = run(&make_engine, fen, depth)?;
+
+    if first.0 != second.0 {
+        return Err(format!("total nodes differ: {} vs {}", first.0, second.0));
+    }
+    if first.1.len() != second.1.len() {
+        return Err(format!(
+            "completed iterations differ: {} vs {}",
+            first.1.len(),
+            second.1.len()
+        ));
+    }
+    for (a, b) in first.1.iter().zip(second.1.iter()) {
+        let depth = a.depth;
+        if a.nodes != b.nodes {
+            return Err(format!(
+                "depth {depth}: nodes differ: {} vs {}",
+                a.nodes, b.nodes
+            ));
+        }
+        if a.score != b.score {
+            return Err(format!(
+                "depth {depth}: score differs: {} vs {}",
+                a.score, b.score
+            ));
+        }
+        if a.best_moves != b.best_moves {
+            return Err(format!(
+                "depth {depth}: best moves differ: [{}] vs [{}]",
+                format_moves(&a.best_moves),
+                format_moves(&b.best_moves)
+            ));
+        }
+        if a.pv != b.pv {
+            return Err(format!(
+                "depth {depth}: pv differs: [{}] vs [{}]",
+                format_moves(&a.pv),
+                format_moves(&b.pv)
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// The total nodes and what each completed iteration reported.
+fn run<E, S, F>(make_engine: &F, fen: &str, depth: u32) -> Result<(u64, Vec<SearchInfo>), String>
+where
+    E: Evaluator,
+    S: SearchAlgorithm,
+    F: Fn() -> Engine<E, S>,
+{
+    let mut engine = make_engine();
+    engine.set_position_fen(fen)?;
+    let mut iterations = Vec::new();
+    let (_, nodes) = engine.search_iterative_with_info(depth, |info| iterations.push(info.clone()));
+    Ok((nodes, iterations))
+}
+
+fn format_moves(moves: &[Move]) -> String {
+    moves
+        .iter()
+        .filter_map(|mv| uci_from_move(*mv))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -1,16 +1,49 @@
 use crate::engine::apply_move;
-use crate::engine::fen::{parse_fen, validate_fen_semantics, STARTPOS_FEN};
+use crate::engine::castling::{pieces_in_place, CastlingFiles, Wing};
+use crate::engine::checks::check_hash;
+use crate::engine::fen::{
+    board_to_fen, en_passant_capturable, fen_from_piece, parse_fen, validate_fen_semantics,
+    FenData, STARTPOS_FEN,
+};
+use crate::engine::movegen::is_king_in_check;
 use crate::engine::types::{move_from_uci, Color, Move, Piece, Square};
 use crate::engine::zobrist;
+use std::cell::Cell;
+
+/// State restored by `Board::unmake_null_move`.
+pub struct NullMoveUndo {
+    previous_en_passant: Option<Square>,
+    previous_hash: u64,
+    previous_mirror_hash: u64,
+    previous_in_check: Option<bool>,
+}
 
+#[derive(Clone)]
 pub struct Board {
     pub squares: [Option<Piece>; 128],
     pub side_to_move: Color,
     pub castling_rights: u8,
+    /// Files the kings and castling rooks start on, standard unless a
+    /// Chess960 FEN said otherwise.
+    pub castling_files: CastlingFiles,
     pub en_passant: Option<Square>,
     pub halfmove_clock: u32,
     pub fullmove_number: u32,
     pub hash: u64,
+    /// Hash of the color-flipped mirror position, kept up to date alongside
+    /// `hash`.
+    pub(crate) mirror_hash: u64,
+    /// Hashes of the positions before each move made since the position was
+    /// set, oldest first.
+    pub(crate) history: Vec<u64>,
+    /// Memoized "side to move is in check", cleared by make/unmake and position setters.
+    pub(crate) check_cache: Cell<Option<bool>>,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Board {
@@ -19,12 +52,16 @@ impl Board {
             squares: [None; 128],
             side_to_move: Color::White,
             castling_rights: 0,
+            castling_files: CastlingFiles::STANDARD,
             en_passant: None,
             halfmove_clock: 0,
             fullmove_number: 1,
             hash: 0,
+            mirror_hash: 0,
+            history: Vec::new(),
+            check_cache: Cell::new(None),
         };
-        board.hash = zobrist::compute_hash(&board);
+        board.rehash();
         board
     }
 
@@ -32,10 +69,13 @@ impl Board {
         self.squares = [None; 128];
         self.side_to_move = Color::White;
         self.castling_rights = 0;
+        self.castling_files = CastlingFiles::STANDARD;
         self.en_passant = None;
         self.halfmove_clock = 0;
         self.fullmove_number = 1;
-        self.hash = zobrist::compute_hash(self);
+        self.rehash();
+        self.history.clear();
+        self.check_cache.set(None);
     }
 
     pub fn set_startpos(&mut self) {
@@ -49,21 +89,189 @@ impl Board {
         self.squares = data.squares;
         self.side_to_move = data.side_to_move;
         self.castling_rights = data.castling_rights;
+        self.castling_files = data.castling_files;
         self.en_passant = data.en_passant;
         self.halfmove_clock = data.halfmove_clock;
         self.fullmove_number = data.fullmove_number;
-        self.hash = zobrist::compute_hash(self);
+        self.rehash();
+        self.history.clear();
+        self.check_cache.set(None);
         Ok(())
     }
 
+    pub fn to_fen(&self) -> String {
+        board_to_fen(self)
+    }
+
+    /// An ASCII diagram from White's side, pieces as FEN letters, with rank
+    /// numbers on the right and files underneath.
+    pub fn diagram(&self) -> String {
+        const SEPARATOR: &str = " +---+---+---+---+---+---+---+---+\n";
+        let mut diagram = String::from(SEPARATOR);
+        for rank in (0u8..8).rev() {
+            for file in 0u8..8 {
+                let piece = self.squares[(rank * 16 + file) as usize];
+                diagram.push_str(" | ");
+                diagram.push(piece.map_or(' ', fen_from_piece));
+            }
+            diagram.push_str(&format!(" | {}\n", rank + 1));
+            diagram.push_str(SEPARATOR);
+        }
+        diagram.push_str("   a   b   c   d   e   f   g   h\n");
+        diagram
+    }
+
+    /// The same position seen from the other side: ranks mirrored, colors
+    /// swapped, and castling rights and en passant carried over. The side to
+    /// move is swapped too, so evaluations should be equal.
+    pub fn flipped(&self) -> Board {
+        let mut squares = [None; 128];
+        for (index, piece) in self.squares.iter().enumerate() {
+            if let Some(piece) = piece {
+                squares[index ^ 0x70] = Some(Piece {
+                    color: opposite(piece.color),
+                    kind: piece.kind,
+                });
+            }
+        }
+        let castling_rights =
+            ((self.castling_rights & 0b0011) << 2) | ((self.castling_rights & 0b1100) >> 2);
+        let mut board = Board {
+            squares,
+            side_to_move: opposite(self.side_to_move),
+            castling_rights,
+            castling_files: self.castling_files.swapped(),
+            en_passant: self.en_passant.map(|square| Square(square.index() ^ 0x70)),
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            hash: 0,
+            mirror_hash: 0,
+            history: Vec::new(),
+            check_cache: Cell::new(None),
+        };
+        board.rehash();
+        board
+    }
+
+    /// Places `piece` on `square`, or empties it, for position editing.
+    ///
+    /// Castling rights whose king or rook is no longer in place are dropped,
+    /// as is the en passant square. The result may still be illegal (say, a
+    /// missing king), so call `validate` before searching.
+    pub fn set_piece(&mut self, square: Square, piece: Option<Piece>) {
+        self.squares[square.index() as usize] = piece;
+        self.en_passant = None;
+        for color in [Color::White, Color::Black] {
+            for wing in Wing::BOTH {
+                if !pieces_in_place(&self.squares, &self.castling_files, color, wing) {
+                    self.castling_rights &= !wing.right(color);
+                }
+            }
+        }
+        self.position_edited();
+    }
+
+    pub fn set_side_to_move(&mut self, color: Color) {
+        self.side_to_move = color;
+        self.en_passant = None;
+        self.position_edited();
+    }
+
+    pub fn set_castling_rights(&mut self, rights: u8) {
+        self.castling_rights = rights;
+        self.position_edited();
+    }
+
+    pub fn set_en_passant(&mut self, square: Option<Square>) {
+        self.en_passant = square;
+        self.position_edited();
+    }
+
+    /// Checks the same rules as loading a FEN: one king each, no pawns on the
+    /// back ranks, consistent castling/en passant, and no check on the side
+    /// that just moved. An en passant square no pawn can capture on is
+    /// ignored rather than rejected. The zobrist hash must also match the
+    /// position, or the search would read the wrong table entries.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.hash != self.compute_hash()
+            || self.mirror_hash != zobrist::compute_mirror_hash(self)
+        {
+            return Err("hash out of sync with the position".to_string());
+        }
+        let mut data = FenData {
+            squares: self.squares,
+            side_to_move: self.side_to_move,
+            castling_rights: self.castling_rights,
+            castling_files: self.castling_files,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+        };
+        // Moves record the square after every double push, whether or not
+        // a pawn can capture there; only FEN input is held to that rule.
+        if data
+            .en_passant
+            .is_some_and(|ep| !en_passant_capturable(&data, ep))
+        {
+            data.en_passant = None;
+        }
+        validate_fen_semantics(&data)
+    }
+
+    // An edited position has no meaningful history to repeat.
+    fn position_edited(&mut self) {
+        self.rehash();
+        self.history.clear();
+        self.check_cache.set(None);
+    }
+
+    /// Whether the side to move is in check, computed once per position.
+    pub fn in_check(&self) -> bool {
+        if let Some(in_check) = self.check_cache.get() {
+            return in_check;
+        }
+        let in_check = is_king_in_check(self, self.side_to_move);
+        self.check_cache.set(Some(in_check));
+        in_check
+    }
+
     pub fn hash(&self) -> u64 {
         self.hash
     }
 
+    /// The hash `flipped()` would have.
+    pub fn mirror_hash(&self) -> u64 {
+        self.mirror_hash
+    }
+
+    fn rehash(&mut self) {
+        self.hash = zobrist::compute_hash(self);
+        self.mirror_hash = zobrist::compute_mirror_hash(self);
+    }
+
     pub fn compute_hash(&self) -> u64 {
         zobrist::compute_hash(self)
     }
 
+    /// Hashes of the earlier positions in the game, oldest first. History
+    /// starts over when a position is set from a FEN or edited.
+    pub fn hash_history(&self) -> &[u64] {
+        &self.history
+    }
+
+    /// How many times the current position has occurred, counting this one.
+    /// Only positions since the last capture or pawn move can match.
+    pub fn repetition_count(&self) -> usize {
+        let reversible = self.halfmove_clock as usize;
+        1 + self
+            .history
+            .iter()
+            .rev()
+            .take(reversible)
+            .filter(|hash| **hash == self.hash)
+            .count()
+    }
+
     pub fn apply_uci_move_list(&mut self, moves: &[String]) -> Result<(), String> {
         for mv in moves {
             let parsed = move_from_uci(mv).ok_or_else(|| format!("invalid UCI move: {mv}"))?;
@@ -84,6 +292,37 @@ impl Board {
     pub fn unmake_move(&mut self, mv: Move, undo: apply_move::MoveUndo) {
         apply_move::unmake_move(self, mv, undo)
     }
+
+    /// Passes the turn without moving. Only meaningful when not in check.
+    pub fn make_null_move(&mut self) -> NullMoveUndo {
+        let undo = NullMoveUndo {
+            previous_en_passant: self.en_passant,
+            previous_hash: self.hash,
+            previous_mirror_hash: self.mirror_hash,
+            previous_in_check: self.check_cache.get(),
+        };
+        self.side_to_move = opposite(self.side_to_move);
+        self.en_passant = None;
+        self.rehash();
+        self.check_cache.set(None);
+        undo
+    }
+
+    pub fn unmake_null_move(&mut self, undo: NullMoveUndo) {
+        self.side_to_move = opposite(self.side_to_move);
+        self.en_passant = undo.previous_en_passant;
+        self.hash = undo.previous_hash;
+        self.mirror_hash = undo.previous_mirror_hash;
+        self.check_cache.set(undo.previous_in_check);
+        check_hash(self);
+    }
+}
+
+fn opposite(color: Color) -> Color {
+    match color {
+        Color::White => Color::Black,
+        Color::Black => Color::White,
+    }
 }
 
 #[cfg(test)]
@@ -109,6 +348,21 @@ mod tests {
         assert_eq!(board.side_to_move, Color::Black);
     }
 
+    #[test]
+    fn diagram_shows_pieces_by_rank() {
+        let mut board = Board::new();
+        board
+            .set_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1")
+            .expect("fen");
+
+        let diagram = board.diagram();
+        let lines: Vec<&str> = diagram.lines().collect();
+        assert_eq!(lines.len(), 18);
+        assert_eq!(lines[1], " |   |   |   |   | k |   |   |   | 8");
+        assert_eq!(lines[13], " |   |   |   |   | P |   |   |   | 2");
+        assert_eq!(lines[17], "   a   b   c   d   e   f   g   h");
+    }
+
     #[test]
     fn apply_move_rejects_wrong_side() {
         let mut board = Board::new();
@@ -129,6 +383,15 @@ mod tests {
 
         let ep = board.en_passant.expect("en passant square");
         assert_eq!(square_from_algebraic("e3").unwrap(), ep);
+        // No black pawn can take on e3, which a FEN would not allow, so the
+        // FEN leaves the square out and still loads.
+        assert_eq!(board.validate(), Ok(()));
+        let fen = board.to_fen();
+        assert_eq!(
+            fen,
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"
+        );
+        assert!(Board::new().set_fen(&fen).is_ok());
     }
 
     #[test]
@@ -327,4 +590,144 @@ mod tests {
         assert!(!has_queenside(board.castling_rights, Color::Black));
         assert!(has_kingside(board.castling_rights, Color::Black));
     }
+
+    #[test]
+    fn in_check_tracks_make_and_unmake() {
+        let mut board = Board::new();
+        board
+            .set_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1")
+            .expect("fen");
+        assert!(!board.in_check());
+
+        let mv = move_from_uci("a1a8").unwrap();
+        let undo = board.make_move(mv).expect("move");
+        assert!(board.in_check());
+
+        board.unmake_move(mv, undo);
+        assert!(!board.in_check());
+        assert_eq!(board.side_to_move, Color::White);
+    }
+
+    #[test]
+    fn editing_pieces_drops_stale_rights_and_revalidates() {
+        let mut board = Board::new();
+        board.set_startpos();
+        let h1 = square_from_algebraic("h1").unwrap();
+        let e1 = square_from_algebraic("e1").unwrap();
+        let f5 = square_from_algebraic("f5").unwrap();
+
+        board.set_piece(h1, None);
+        assert!(!has_kingside(board.castling_rights, Color::White));
+        assert!(has_queenside(board.castling_rights, Color::White));
+
+        let knight = Some(Piece {
+            color: Color::White,
+            kind: PieceKind::Knight,
+        });
+        board.set_piece(f5, knight);
+        assert_eq!(board.hash(), board.compute_hash());
+        assert!(board.validate().is_ok());
+
+        board.set_piece(e1, None);
+        assert!(board.validate().is_err());
+        assert_eq!(board.castling_rights & 0b11, 0);
+
+        // Writing a square directly leaves the hash behind.
+        board.set_piece(
+            e1,
+            Some(Piece {
+                color: Color::White,
+                kind: PieceKind::King,
+            }),
+        );
+        assert!(board.validate().is_ok());
+        board.squares[f5.index() as usize] = None;
+        assert_eq!(
+            board.validate(),
+            Err("hash out of sync with the position".to_string())
+        );
+    }
+
+    #[test]
+    fn repetition_count_follows_hash_history() {
+        let mut board = Board::new();
+        board.set_startpos();
+        assert_eq!(board.repetition_count(), 1);
+
+        let shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+        let moves: Vec<String> = shuffle.iter().map(|mv| mv.to_string()).collect();
+        board.apply_uci_move_list(&moves).expect("moves");
+        assert_eq!(board.repetition_count(), 2);
+        assert_eq!(board.hash_history().len(), 4);
+        assert_eq!(board.hash_history()[0], board.hash());
+
+        let mv = move_from_uci("g1f3").expect("move");
+        let undo = board.make_move(mv).expect("make");
+        assert_eq!(board.repetition_count(), 2);
+        board.unmake_move(mv, undo);
+        assert_eq!(board.hash_history().len(), 4);
+
+        // A pawn move makes earlier positions unreachable.
+        board
+            .apply_uci_move_list(&["e2e4".to_string()])
+            .expect("move");
+        assert_eq!(board.repetition_count(), 1);
+
+        board.set_startpos();
+        assert!(board.hash_history().is_empty());
+    }
+
+    #[test]
+    fn null_move_passes_the_turn_and_restores() {
+        let mut board = Board::new();
+        board
+            .set_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1")
+            .expect("fen");
+        let before = board.to_fen();
+        let hash = board.hash();
+
+        let undo = board.make_null_move();
+        assert_eq!(board.side_to_move, Color::Black);
+        assert_eq!(board.en_passant, None);
+        assert_eq!(board.hash(), board.compute_hash());
+
+        board.unmake_null_move(undo);
+        assert_eq!(board.to_fen(), before);
+        assert_eq!(board.hash(), hash);
+    }
+
+    #[test]
+    fn flipped_mirrors_position() {
+        let mut board = Board::new();
+        board
+            .set_fen("r3k2r/8/8/3pP3/8/8/8/R3K3 w Qkq d6 0 1")
+            .expect("fen");
+        let flipped = board.flipped();
+        assert_eq!(flipped.to_fen(), "r3k3/8/8/8/3Pp3/8/8/R3K2R b KQq d3 0 1");
+        assert!(flipped.validate().is_ok());
+        assert_eq!(flipped.hash(), flipped.compute_hash());
+        assert_eq!(flipped.flipped().to_fen(), board.to_fen());
+        assert_eq!(board.mirror_hash(), flipped.hash());
+        assert_eq!(flipped.mirror_hash(), board.hash());
+    }
+
+    #[test]
+    fn mirror_hash_follows_moves_and_unmakes() {
+        let mut board = Board::new();
+        board
+            .set_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1")
+            .expect("fen");
+        let start = board.mirror_hash();
+
+        let mut undos = Vec::new();
+        for text in ["e5d6", "e8c8", "e1g1", "c8d7", "b7b8q"] {
+            let mv = move_from_uci(text).expect("move");
+            undos.push((mv, board.make_move(mv).expect("legal")));
+            assert_eq!(board.mirror_hash(), board.flipped().hash(), "after {text}");
+        }
+        while let Some((mv, undo)) = undos.pop() {
+            board.unmake_move(mv, undo);
+        }
+        assert_eq!(board.mirror_hash(), start);
+    }
 }
@@ -0,0 +1,212 @@
+use crate::engine::board::Board;
+use crate::engine::movegen::generate_legal;
+use crate::engine::types::{move_from_uci, Move};
+use std::collections::HashMap;
+use std::fs;
+use std::sync::OnceLock;
+
+/// The lines of `Repertoire::builtin`, compiled into the binary.
+const DEFAULT_LINES: &str = include_str!("default_book.txt");
+
+/// A set of opening lines, indexed by the positions they pass through.
+///
+/// Lines are stored by Zobrist hash so transpositions into a known position
+/// still count as being in book.
+#[derive(Debug, Clone, Default)]
+pub struct Repertoire {
+    positions: HashMap<u64, Vec<Move>>,
+}
+
+/// The first move of a game that leaves the repertoire.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deviation {
+    pub ply: usize,
+    pub played: Move,
+    pub expected: Vec<Move>,
+}
+
+impl Repertoire {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses one line per row, each a space-separated list of UCI moves from
+    /// the start position. Blank rows and rows starting with `#` are skipped.
+    pub fn from_lines(text: &str) -> Result<Self, String> {
+        let mut repertoire = Self::new();
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let moves: Vec<&str> = line.split_whitespace().collect();
+            repertoire
+                .add_line(&moves)
+                .map_err(|err| format!("line {}: {err}", line_number + 1))?;
+        }
+        Ok(repertoire)
+    }
+
+    /// A small book of mainline openings built into the binary, for playing
+    /// varied openings without a book file. Parsed on first use.
+    pub fn builtin() -> &'static Self {
+        static BUILTIN: OnceLock<Repertoire> = OnceLock::new();
+        BUILTIN.get_or_init(|| Self::from_lines(DEFAULT_LINES).expect("built-in book is valid"))
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let contents =
+            fs::read_to_string(path).map_err(|err| format!("failed to read {path}: {err}"))?;
+        Self::from_lines(&contents)
+    }
+
+    pub fn add_line(&mut self, moves: &[&str]) -> Result<(), String> {
+        let mut board = Board::new();
+        board.set_startpos();
+        for text in moves {
+            let mv = move_from_uci(text).ok_or_else(|| format!("invalid UCI move: {text}"))?;
+            if !generate_legal(&mut board).contains(&mv) {
+                return Err(format!("illegal move: {text}"));
+            }
+            let entry = self.positions.entry(board.hash()).or_default();
+            if !entry.contains(&mv) {
+                entry.push(mv);
+            }
+            board.apply_move(mv)?;
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Positions with at least one repertoire move.
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Estimated bytes held by the index: its slots plus the move lists.
+    pub fn memory_bytes(&self) -> usize {
+        let slots = self.positions.capacity() * size_of::<(u64, Vec<Move>)>();
+        let moves: usize = self.positions.values().map(Vec::capacity).sum();
+        slots + moves * size_of::<Move>()
+    }
+
+    /// Returns the repertoire moves for `board`, or `None` when the position is out of book.
+    pub fn book_moves(&self, board: &Board) -> Option<&[Move]> {
+        self.positions.get(&board.hash()).map(Vec::as_slice)
+    }
+
+    /// Replays `moves` from the start position and reports the first one that
+    /// leaves the repertoire. Returns `Ok(None)` when every move stays in book
+    /// or the game only leaves book after a position the repertoire does not cover.
+    pub fn first_deviation(&self, moves: &[String]) -> Result<Option<Deviation>, String> {
+        let mut board = Board::new();
+        board.set_startpos();
+        for (ply, text) in moves.iter().enumerate() {
+            let mv = move_from_uci(text).ok_or_else(|| format!("invalid UCI move: {text}"))?;
+            let expected = match self.book_moves(&board) {
+                Some(expected) => expected,
+                None => return Ok(None),
+            };
+            if !expected.contains(&mv) {
+                return Ok(Some(Deviation {
+                    ply,
+                    played: mv,
+                    expected: expected.to_vec(),
+                }));
+            }
+            board.apply_move(mv)?;
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Repertoire {
+        Repertoire::from_lines(
+            "# Italian and Ruy Lopez\n\
+             e2e4 e7e5 g1f3 b8c6 f1c4\n\
+             e2e4 e7e5 g1f3 b8c6 f1b5\n",
+        )
+        .expect("repertoire")
+    }
+
+    #[test]
+    fn book_moves_merges_lines() {
+        let repertoire = sample();
+        let mut board = Board::new();
+        board.set_startpos();
+        board
+            .apply_uci_move_list(&["e2e4", "e7e5", "g1f3", "b8c6"].map(String::from))
+            .expect("moves");
+
+        let moves = repertoire.book_moves(&board).expect("in book");
+        assert_eq!(moves.len(), 2);
+        assert!(moves.contains(&move_from_uci("f1c4").unwrap()));
+        assert!(moves.contains(&move_from_uci("f1b5").unwrap()));
+    }
+
+    #[test]
+    fn counts_positions_and_memory() {
+        let repertoire = sample();
+        // Five positions hold six moves: both bishop moves follow 2...Nc6.
+        assert_eq!(repertoire.len(), 5);
+        let least = 5 * size_of::<(u64, Vec<Move>)>() + 6 * size_of::<Move>();
+        assert!(repertoire.memory_bytes() >= least);
+        assert_eq!(Repertoire::new().memory_bytes(), 0);
+    }
+
+    #[test]
+    fn builtin_book_covers_the_main_first_moves() {
+        let builtin = Repertoire::builtin();
+        assert!(builtin.len() >= 300, "{} positions", builtin.len());
+        let mut board = Board::new();
+        board.set_startpos();
+        let first = builtin.book_moves(&board).expect("in book");
+        for mv in ["e2e4", "d2d4", "c2c4", "g1f3"] {
+            assert!(first.contains(&move_from_uci(mv).unwrap()), "{mv}");
+        }
+    }
+
+    #[test]
+    fn book_moves_out_of_book() {
+        let repertoire = sample();
+        let mut board = Board::new();
+        board.set_startpos();
+        board
+            .apply_uci_move_list(&["d2d4".to_string()])
+            .expect("moves");
+        assert!(repertoire.book_moves(&board).is_none());
+    }
+
+    #[test]
+    fn first_deviation_reports_ply() {
+        let repertoire = sample();
+        let game = ["e2e4", "e7e5", "g1f3", "g8f6"].map(String::from);
+        let deviation = repertoire
+            .first_deviation(&game)
+            .expect("replay")
+            .expect("deviation");
+        assert_eq!(deviation.ply, 3);
+        assert_eq!(deviation.played, move_from_uci("g8f6").unwrap());
+        assert_eq!(deviation.expected, vec![move_from_uci("b8c6").unwrap()]);
+    }
+
+    #[test]
+    fn first_deviation_none_when_in_book() {
+        let repertoire = sample();
+        let game = ["e2e4", "e7e5", "g1f3"].map(String::from);
+        assert_eq!(repertoire.first_deviation(&game).expect("replay"), None);
+    }
+
+    #[test]
+    fn rejects_illegal_line() {
+        let err = Repertoire::from_lines("e2e5").unwrap_err();
+        assert!(err.contains("illegal"));
+    }
+}
@@ -0,0 +1,290 @@
+//! A cache of finished analyses keyed by position, so repeated requests for a
+//! position are answered at once and deeper requests start from the stored
+//! best move.
+//!
+//! Entries are looked up by zobrist hash and checked against the position's
+//! FEN fields, so a hash collision is a miss rather than a wrong answer.
+//! The cache keeps the deepest analysis of each position, evicts the least
+//! recently used entry when full, and can be saved to and loaded from a text
+//! file with one analysis per line.
+
+use crate::engine::board::Board;
+use crate::engine::types::{move_from_uci, uci_from_move, Move};
+use std::collections::HashMap;
+use std::fs;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedAnalysis {
+    pub depth: u32,
+    /// Centipawns for the side to move.
+    pub score: i32,
+    pub nodes: u64,
+    /// The principal variation; its first move is the best move.
+    pub pv: Vec<Move>,
+}
+
+impl CachedAnalysis {
+    pub fn best_move(&self) -> Option<Move> {
+        self.pv.first().copied()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    /// Placement, side to move, castling and en passant fields.
+    position: String,
+    analysis: CachedAnalysis,
+    last_used: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct AnalysisCache {
+    entries: HashMap<u64, Entry>,
+    capacity: usize,
+    /// Ticks on every lookup and insert; entries remember when they were last
+    /// touched, and the oldest goes first.
+    clock: u64,
+}
+
+impl AnalysisCache {
+    /// A cache holding at most `capacity` positions (at least one).
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            capacity: capacity.max(1),
+            clock: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The stored analysis of `board`'s position, however deep.
+    pub fn get(&mut self, board: &Board) -> Option<&CachedAnalysis> {
+        self.clock += 1;
+        let position = position_key(board);
+        let entry = self
+            .entries
+            .get_mut(&board.hash())
+            .filter(|entry| entry.position == position)?;
+        entry.last_used = self.clock;
+        Some(&entry.analysis)
+    }
+
+    /// Stores `analysis` for `board`'s position unless a deeper one is
+    /// already stored. Returns whether the cache changed.
+    pub fn insert(&mut self, board: &Board, analysis: CachedAnalysis) -> bool {
+        self.insert_position(board.hash(), position_key(board), analysis)
+    }
+
+    fn insert_position(&mut self, hash: u64, position: String, analysis: CachedAnalysis) -> bool {
+        self.clock += 1;
+        if let Some(entry) = self.entries.get_mut(&hash)
+            && entry.position == position
+        {
+            entry.last_used = self.clock;
+            if entry.analysis.depth > analysis.depth {
+                return false;
+            }
+            entry.analysis = analysis;
+            return true;
+        }
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&hash) {
+            self.evict();
+        }
+        self.entries.insert(
+            hash,
+            Entry {
+                position,
+                analysis,
+                last_used: self.clock,
+            },
+        );
+        true
+    }
+
+    fn evict(&mut self) {
+        let oldest = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(hash, _)| *hash);
+        if let Some(hash) = oldest {
+            self.entries.remove(&hash);
+        }
+    }
+
+    /// One line per position, least recently used first so that loading
+    /// the file into a smaller cache keeps the most recent entries:
+    /// `<position>;<depth>;<score>;<nodes>;<pv>`.
+    pub fn to_text(&self) -> String {
+        let mut entries: Vec<&Entry> = self.entries.values().collect();
+        entries.sort_by_key(|entry| entry.last_used);
+        let mut text = String::new();
+        for entry in entries {
+            let analysis = &entry.analysis;
+            let pv: Vec<String> = analysis
+                .pv
+                .iter()
+                .filter_map(|mv| uci_from_move(*mv))
+                .collect();
+            text.push_str(&format!(
+                "{};{};{};{};{}\n",
+                entry.position,
+                analysis.depth,
+                analysis.score,
+                analysis.nodes,
+                pv.join(" ")
+            ));
+        }
+        text
+    }
+
+    /// Adds the analyses in `text`, as written by `to_text`.
+    pub fn extend_from_text(&mut self, text: &str) -> Result<(), String> {
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let fields: Vec<&str> = line.split(';').collect();
+            let [position, depth, score, nodes, pv] = fields[..] else {
+                return Err(format!("invalid cache line: {line}"));
+            };
+            let number = |name: &str, value: &str| {
+                value
+                    .parse::<i64>()
+                    .map_err(|_| format!("invalid {name} in cache line: {line}"))
+            };
+            let mut board = Board::new();
+            board.set_fen(&format!("{position} 0 1"))?;
+            let pv = pv
+                .split_whitespace()
+                .map(|mv| move_from_uci(mv).ok_or_else(|| format!("invalid move {mv} in cache")))
+                .collect::<Result<Vec<_>, _>>()?;
+            self.insert_position(
+                board.hash(),
+                position.to_string(),
+                CachedAnalysis {
+                    depth: number("depth", depth)? as u32,
+                    score: number("score", score)? as i32,
+                    nodes: number("nodes", nodes)? as u64,
+                    pv,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Loads `path` into an empty cache of `capacity`; a missing file gives
+    /// an empty cache.
+    pub fn load(path: &str, capacity: usize) -> Result<Self, String> {
+        let mut cache = Self::new(capacity);
+        match fs::read_to_string(path) {
+            Ok(text) => cache.extend_from_text(&text)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(format!("failed to read {path}: {err}")),
+        }
+        Ok(cache)
+    }
+
+    /// Writes the cache to `path`, through a temporary file so a crash never
+    /// leaves a truncated cache behind.
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        Self::write_text(path, &self.to_text())
+    }
+
+    /// Writes `text` from `to_text` to `path` as `save` does, so a shared
+    /// cache can be saved without holding its lock during the write.
+    pub fn write_text(path: &str, text: &str) -> Result<(), String> {
+        let tmp = format!("{path}.tmp");
+        fs::write(&tmp, text).map_err(|err| format!("failed to write {tmp}: {err}"))?;
+        fs::rename(&tmp, path).map_err(|err| format!("failed to write {path}: {err}"))
+    }
+}
+
+// The FEN without its move counters: positions that differ only in those
+// share their analysis.
+fn position_key(board: &Board) -> String {
+    let fen = board.to_fen();
+    let fields: Vec<&str> = fen.split_whitespace().take(4).collect();
+    fields.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board_from(fen: &str) -> Board {
+        let mut board = Board::new();
+        board.set_fen(fen).expect("fen");
+        board
+    }
+
+    fn analysis(depth: u32, pv: &[&str]) -> CachedAnalysis {
+        CachedAnalysis {
+            depth,
+            score: 10 * depth as i32,
+            nodes: 100,
+            pv: pv.iter().map(|mv| move_from_uci(mv).unwrap()).collect(),
+        }
+    }
+
+    #[test]
+    fn keeps_the_deepest_analysis_per_position() {
+        let mut cache = AnalysisCache::new(4);
+        let start = board_from("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert!(cache.insert(&start, analysis(4, &["e2e4", "e7e5"])));
+        assert!(!cache.insert(&start, analysis(2, &["d2d4"])));
+        assert_eq!(cache.get(&start).map(|a| a.depth), Some(4));
+
+        // Move counters do not matter.
+        let later = board_from("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 4 9");
+        assert_eq!(
+            cache.get(&later).and_then(CachedAnalysis::best_move),
+            move_from_uci("e2e4")
+        );
+        assert!(cache.insert(&later, analysis(6, &["g1f3"])));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_position() {
+        let mut cache = AnalysisCache::new(2);
+        let a = board_from("4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+        let b = board_from("4k3/8/8/8/8/8/8/3K4 w - - 0 1");
+        let c = board_from("4k3/8/8/8/8/8/8/5K2 w - - 0 1");
+        cache.insert(&a, analysis(1, &["e1e2"]));
+        cache.insert(&b, analysis(1, &["d1d2"]));
+        cache.get(&a);
+        cache.insert(&c, analysis(1, &["f1f2"]));
+        assert!(cache.get(&a).is_some());
+        assert!(cache.get(&b).is_none());
+        assert!(cache.get(&c).is_some());
+    }
+
+    #[test]
+    fn round_trips_through_text() {
+        let mut cache = AnalysisCache::new(8);
+        let a = board_from("4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+        let b = board_from("r7/8/8/8/8/4k3/8/Q3K3 w - - 0 1");
+        cache.insert(&a, analysis(3, &["e1e2", "e8e7"]));
+        cache.insert(&b, analysis(5, &["a1a8"]));
+
+        let mut loaded = AnalysisCache::new(8);
+        loaded
+            .extend_from_text(&cache.to_text())
+            .expect("cache text");
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded.get(&b), Some(&analysis(5, &["a1a8"])));
+        assert!(loaded.extend_from_text("not a cache line").is_err());
+
+        // A smaller cache keeps the most recently used entries.
+        let mut small = AnalysisCache::new(1);
+        small
+            .extend_from_text(&cache.to_text())
+            .expect("cache text");
+        assert!(small.get(&b).is_some());
+    }
+}
@@ -1,4 +1,8 @@
-use crate::engine::types::Color;
+//! Castling rights, and the squares each castling move needs.
+
+use crate::engine::board::Board;
+use crate::engine::movegen::is_square_attacked;
+use crate::engine::types::{Color, Move, Piece, PieceKind, Square};
 
 pub const CASTLE_WHITE_KING: u8 = 1 << 0;
 pub const CASTLE_WHITE_QUEEN: u8 = 1 << 1;
@@ -37,3 +41,266 @@ pub fn revoke_all(rights: &mut u8, color: Color) {
     revoke_kingside(rights, color);
     revoke_queenside(rights, color);
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wing {
+    Kingside,
+    Queenside,
+}
+
+impl Wing {
+    pub const BOTH: [Wing; 2] = [Wing::Kingside, Wing::Queenside];
+
+    /// The castling right flag of `color` on this wing.
+    pub fn right(self, color: Color) -> u8 {
+        match (self, color) {
+            (Wing::Kingside, Color::White) => CASTLE_WHITE_KING,
+            (Wing::Queenside, Color::White) => CASTLE_WHITE_QUEEN,
+            (Wing::Kingside, Color::Black) => CASTLE_BLACK_KING,
+            (Wing::Queenside, Color::Black) => CASTLE_BLACK_QUEEN,
+        }
+    }
+
+    // Files of the rook's home in standard chess, and of where king and
+    // rook end up, which Chess960 keeps.
+    fn standard_rook_file(self) -> u8 {
+        match self {
+            Wing::Kingside => 7,
+            Wing::Queenside => 0,
+        }
+    }
+
+    fn king_to_file(self) -> u8 {
+        match self {
+            Wing::Kingside => 6,
+            Wing::Queenside => 2,
+        }
+    }
+
+    fn rook_to_file(self) -> u8 {
+        match self {
+            Wing::Kingside => 5,
+            Wing::Queenside => 3,
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Wing::Kingside => 0,
+            Wing::Queenside => 1,
+        }
+    }
+}
+
+/// The files each side's king and castling rooks start on. Standard chess
+/// has the kings on the e-file and the rooks on the a- and h-files; in
+/// Chess960 the king starts on any file between its two rooks. FENs give
+/// the files with X-FEN or Shredder-FEN castling rights.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CastlingFiles {
+    king: [u8; 2],
+    rooks: [[u8; 2]; 2],
+}
+
+impl Default for CastlingFiles {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+impl CastlingFiles {
+    pub const STANDARD: Self = Self {
+        king: [4, 4],
+        rooks: [[7, 0], [7, 0]],
+    };
+
+    pub fn king(&self, color: Color) -> u8 {
+        self.king[color_index(color)]
+    }
+
+    pub fn rook(&self, color: Color, wing: Wing) -> u8 {
+        self.rooks[color_index(color)][wing.index()]
+    }
+
+    pub fn set_king(&mut self, color: Color, file: u8) {
+        self.king[color_index(color)] = file;
+    }
+
+    pub fn set_rook(&mut self, color: Color, wing: Wing, file: u8) {
+        self.rooks[color_index(color)][wing.index()] = file;
+    }
+
+    /// Whether castling on `wing` starts from the standard squares, so it is
+    /// written as the king moving two files; Chess960 castling is written as
+    /// the king taking its own rook instead, which a king move can never be.
+    pub fn is_standard(&self, color: Color, wing: Wing) -> bool {
+        self.king(color) == 4 && self.rook(color, wing) == wing.standard_rook_file()
+    }
+
+    /// The files with the colors swapped, for the mirrored position.
+    pub fn swapped(&self) -> Self {
+        Self {
+            king: [self.king[1], self.king[0]],
+            rooks: [self.rooks[1], self.rooks[0]],
+        }
+    }
+}
+
+fn color_index(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
+
+fn home_square(color: Color, file: u8) -> Square {
+    let rank = match color {
+        Color::White => 0,
+        Color::Black => 7,
+    };
+    Square(rank * 16 + file)
+}
+
+/// Whether the king and the rook of `color` stand where castling on `wing`
+/// needs them; rights without them are invalid.
+pub fn pieces_in_place(
+    squares: &[Option<Piece>; 128],
+    files: &CastlingFiles,
+    color: Color,
+    wing: Wing,
+) -> bool {
+    let holds = |square: Square, kind: PieceKind| {
+        squares[square.index() as usize] == Some(Piece { color, kind })
+    };
+    let (king, rook) = (files.king(color), files.rook(color, wing));
+    let beside = match wing {
+        Wing::Kingside => rook > king,
+        Wing::Queenside => rook < king,
+    };
+    beside
+        && holds(home_square(color, king), PieceKind::King)
+        && holds(home_square(color, rook), PieceKind::Rook)
+}
+
+/// Whether `side` may castle on `wing` in this position: it keeps the right,
+/// king and rook are home, every square either crosses is empty but for the
+/// two of them, and the king is not in check and does not pass through or
+/// land on an attacked square. The side to move is not checked, so
+/// front-ends can ask for either side.
+pub fn can_castle(board: &Board, side: Color, wing: Wing) -> bool {
+    let opponent = match side {
+        Color::White => Color::Black,
+        Color::Black => Color::White,
+    };
+    let files = &board.castling_files;
+    let (king, rook) = (files.king(side), files.rook(side, wing));
+    let span = |from: u8, to: u8| from.min(to)..=from.max(to);
+    board.castling_rights & wing.right(side) != 0
+        && pieces_in_place(&board.squares, files, side, wing)
+        && span(king, wing.king_to_file())
+            .chain(span(rook, wing.rook_to_file()))
+            .all(|file| {
+                file == king
+                    || file == rook
+                    || board.squares[home_square(side, file).index() as usize].is_none()
+            })
+        && span(king, wing.king_to_file())
+            .all(|file| !is_square_attacked(board, home_square(side, file), opponent))
+}
+
+/// The move that castles `side` on `wing`: the king two files over from the
+/// standard squares, or the king onto its rook in Chess960.
+pub fn castling_move(files: &CastlingFiles, side: Color, wing: Wing) -> Move {
+    let to_file = if files.is_standard(side, wing) {
+        wing.king_to_file()
+    } else {
+        files.rook(side, wing)
+    };
+    Move {
+        from: home_square(side, files.king(side)),
+        to: home_square(side, to_file),
+        promotion: None,
+    }
+}
+
+/// The wing `mv` castles on, if it is a castling move of the king on
+/// `board`: two files over from the standard squares, or onto its own
+/// castling rook in Chess960.
+pub fn castling_wing(board: &Board, mv: Move) -> Option<Wing> {
+    let piece = board.squares[mv.from.index() as usize]?;
+    let files = &board.castling_files;
+    if piece.kind != PieceKind::King || mv.from != home_square(piece.color, files.king(piece.color))
+    {
+        return None;
+    }
+    Wing::BOTH.into_iter().find(|&wing| {
+        mv.to == castling_move(files, piece.color, wing).to
+            && (files.is_standard(piece.color, wing)
+                || board.squares[mv.to.index() as usize]
+                    == Some(Piece {
+                        color: piece.color,
+                        kind: PieceKind::Rook,
+                    }))
+    })
+}
+
+/// Where king and rook land when `side` castles on `wing`, as
+/// `(king_to, rook_from, rook_to)`.
+pub fn castling_squares(
+    files: &CastlingFiles,
+    side: Color,
+    wing: Wing,
+) -> (Square, Square, Square) {
+    (
+        home_square(side, wing.king_to_file()),
+        home_square(side, files.rook(side, wing)),
+        home_square(side, wing.rook_to_file()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(fen: &str) -> Board {
+        let mut board = Board::new();
+        board.set_fen(fen).expect("fen");
+        board
+    }
+
+    #[test]
+    fn can_castle_checks_rights_path_and_attacks() {
+        let open = board("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        for side in [Color::White, Color::Black] {
+            for wing in Wing::BOTH {
+                assert!(can_castle(&open, side, wing), "{side:?} {wing:?}");
+            }
+        }
+
+        // No right, a blocked path, and an attacked transit square.
+        let limited = board("r3k2r/8/8/8/8/8/8/R3K2R w Kq - 0 1");
+        assert!(!can_castle(&limited, Color::White, Wing::Queenside));
+        assert!(!can_castle(&limited, Color::Black, Wing::Kingside));
+        let blocked = board("rn2k2r/8/8/8/8/8/8/R3K1NR w KQkq - 0 1");
+        assert!(!can_castle(&blocked, Color::White, Wing::Kingside));
+        assert!(!can_castle(&blocked, Color::Black, Wing::Queenside));
+        assert!(can_castle(&blocked, Color::White, Wing::Queenside));
+        let attacked = board("r3k2r/8/8/8/8/8/5r2/R3K2R w KQkq - 0 1");
+        assert!(!can_castle(&attacked, Color::White, Wing::Kingside));
+        assert!(can_castle(&attacked, Color::White, Wing::Queenside));
+
+        // Only the king's path counts: b1 may be attacked, d1 may not.
+        let queenside = board("1r2k3/8/8/8/8/8/8/R3K3 w Q - 0 1");
+        assert!(can_castle(&queenside, Color::White, Wing::Queenside));
+        let in_check = board("4k3/8/8/8/8/8/8/R3K2r w Q - 0 1");
+        assert!(!can_castle(&in_check, Color::White, Wing::Queenside));
+        assert_eq!(
+            castling_move(&CastlingFiles::STANDARD, Color::Black, Wing::Queenside),
+            Move {
+                from: Square(116),
+                to: Square(114),
+                promotion: None,
+            }
+        );
+    }
+}
@@ -0,0 +1,94 @@
+//! Invariant checks for bug hunts, enabled by the `debug-checks` feature.
+//!
+//! Each check panics with the offending position when its invariant breaks.
+//! Without the feature they compile to nothing, so the hot path can call
+//! them freely.
+
+use crate::engine::board::Board;
+use crate::engine::movegen::generate_legal;
+use crate::engine::search::MATE_SCORE;
+use crate::engine::types::Move;
+use crate::engine::zobrist;
+
+pub const ENABLED: bool = cfg!(feature = "debug-checks");
+
+/// No score may lie beyond a mate this many plies away.
+const MAX_MATE_PLY: i32 = 1024;
+
+/// The incrementally updated hashes match ones computed from scratch.
+#[inline]
+pub fn check_hash(board: &Board) {
+    if ENABLED {
+        let expected = zobrist::compute_hash(board);
+        assert_eq!(
+            board.hash(),
+            expected,
+            "hash out of sync in {}",
+            board.to_fen()
+        );
+        assert_eq!(
+            board.mirror_hash(),
+            zobrist::compute_mirror_hash(board),
+            "mirror hash out of sync in {}",
+            board.to_fen()
+        );
+    }
+}
+
+/// A search window is never empty.
+#[inline]
+pub fn check_window(alpha: i32, beta: i32) {
+    if ENABLED {
+        assert!(alpha < beta, "empty search window [{alpha}, {beta}]");
+    }
+}
+
+/// A score returned by a search node lies within the mate bounds.
+#[inline]
+pub fn check_score(board: &Board, score: i32) {
+    if ENABLED {
+        assert!(
+            score.abs() <= MATE_SCORE + MAX_MATE_PLY,
+            "score {score} beyond mate bounds in {}",
+            board.to_fen()
+        );
+    }
+}
+
+/// A move about to be made is legal in `board`.
+#[inline]
+pub fn check_legal(board: &mut Board, mv: Move) {
+    if ENABLED {
+        assert!(
+            generate_legal(board).contains(&mv),
+            "illegal move {mv:?} in {}",
+            board.to_fen()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg_attr(
+        feature = "debug-checks",
+        should_panic(expected = "empty search window")
+    )]
+    fn checks_only_fire_with_the_feature() {
+        check_window(10, 10);
+    }
+
+    #[test]
+    fn consistent_positions_pass() {
+        let mut board = Board::new();
+        board.set_startpos();
+        check_hash(&board);
+        check_score(&board, -MATE_SCORE - 3);
+        check_legal(
+            &mut board,
+            crate::engine::types::move_from_uci("e2e4").unwrap(),
+        );
+    }
+}
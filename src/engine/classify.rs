@@ -0,0 +1,116 @@
+//! Labels a position by game phase and by whether it is quiet: nobody is in
+//! check, no piece hangs and resolving the captures leaves the evaluation
+//! where it was. Quiet positions are the ones a static evaluation can be
+//! trained on or judged by; the others are tactical.
+
+use crate::engine::board::Board;
+use crate::engine::composite::Phases;
+use crate::engine::eval::Evaluator;
+use crate::engine::movegen::generate_captures;
+use crate::engine::search::quiescence::quiesce_mm;
+use crate::engine::search::score::evaluate;
+use crate::engine::see::see;
+
+/// How far the capture-resolved score may move from the static evaluation
+/// for the position to count as stable.
+pub const STABLE_MARGIN: i32 = 50;
+
+// Capture plies resolved, as in the searches' quiescence.
+const QUIESCE_DEPTH: u32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Classification {
+    /// The single phase the position is in.
+    pub phase: Phases,
+    pub in_check: bool,
+    /// Some piece of either side can be won by a capture.
+    pub hanging: bool,
+    /// The quiescence score is within `STABLE_MARGIN` of the static one.
+    pub stable: bool,
+}
+
+impl Classification {
+    pub fn is_quiet(&self) -> bool {
+        !self.in_check && !self.hanging && self.stable
+    }
+
+    pub fn is_tactical(&self) -> bool {
+        !self.is_quiet()
+    }
+}
+
+pub fn classify(board: &mut Board, evaluator: &impl Evaluator) -> Classification {
+    let in_check = board.in_check();
+    let static_score = evaluate(evaluator, board);
+    let (mut nodes, mut deepest) = (0, 0);
+    let resolved = quiesce_mm(board, evaluator, &mut nodes, &mut deepest, QUIESCE_DEPTH);
+
+    Classification {
+        phase: Phases::of(board),
+        in_check,
+        hanging: wins_material(board) || (!in_check && opponent_wins_material(board)),
+        stable: resolved.get().abs_diff(static_score.get()) <= STABLE_MARGIN as u32,
+    }
+}
+
+// Whether the side to move has a capture that wins material by exchange.
+fn wins_material(board: &mut Board) -> bool {
+    generate_captures(board)
+        .into_iter()
+        .any(|mv| see(board, mv) > 0)
+}
+
+// Whether the opponent would have one if it were their move; only asked
+// when the side to move is not in check, where passing is illegal.
+fn opponent_wins_material(board: &mut Board) -> bool {
+    let undo = board.make_null_move();
+    let wins = wins_material(board);
+    board.unmake_null_move(undo);
+    wins
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::eval::MaterialEvaluator;
+
+    fn classify_fen(fen: &str) -> Classification {
+        let mut board = Board::new();
+        board.set_fen(fen).expect("fen");
+        let classification = classify(&mut board, &MaterialEvaluator);
+        assert_eq!(
+            board.to_fen(),
+            fen,
+            "classifying leaves the board as it was"
+        );
+        classification
+    }
+
+    #[test]
+    fn the_start_position_is_a_quiet_opening() {
+        let start = classify_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(start.phase, Phases::OPENING);
+        assert!(start.is_quiet());
+
+        let ending = classify_fen("8/5k2/8/3p4/3P4/8/5K2/8 w - - 0 40");
+        assert_eq!(ending.phase, Phases::ENDGAME);
+        assert!(ending.is_quiet());
+    }
+
+    #[test]
+    fn checks_and_hanging_pieces_are_tactical() {
+        let check = classify_fen("4k3/8/8/8/8/8/4r3/R3K3 w - - 0 1");
+        assert!(check.in_check && check.is_tactical());
+
+        // White's knight hangs to Black's queen with Black to move...
+        let loses = classify_fen("4k3/8/8/4N3/3q4/8/8/4K3 b - - 0 1");
+        assert!(loses.hanging && !loses.stable && loses.is_tactical());
+        // ...and still counts as hanging when it is White's move.
+        let threatened = classify_fen("4k3/8/8/4N3/3q4/8/8/4K3 w - - 0 1");
+        assert!(threatened.hanging && threatened.stable);
+
+        // A defended piece does not hang.
+        let defended = classify_fen("4k3/8/8/4N3/3q1P2/5K2/8/8 b - - 0 1");
+        assert!(!defended.hanging && defended.is_quiet());
+    }
+}
@@ -0,0 +1,239 @@
+use crate::engine::types::Color;
+use std::time::Duration;
+
+/// Moves the remaining time is spread over when the GUI does not say.
+const DEFAULT_MOVES_TO_GO: u64 = 30;
+/// Kept in reserve for communication lag on every move.
+const MOVE_OVERHEAD_MS: u64 = 30;
+
+/// Below this much time left a player counts as short of time.
+const TIME_TROUBLE_MS: u64 = 30_000;
+
+/// Time limits for one move: no new iteration starts after `soft`, and the
+/// search is aborted at `hard`. Before `min` the search keeps deepening
+/// whatever the soft limit and time predictions say.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeBudget {
+    pub soft: Duration,
+    pub hard: Duration,
+    pub min: Duration,
+}
+
+/// Human-like pacing for bot deployments: every timed move takes at least
+/// `min_think`, and the planned time of clock-managed moves varies by up to
+/// `variance_percent` either way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Pacing {
+    pub min_think: Duration,
+    pub variance_percent: u32,
+}
+
+impl Pacing {
+    /// `budget` varied by `draw`, from -1 to 1, and with its soft limit and
+    /// minimum raised to the minimum think time. None goes past the hard
+    /// limit, and a fixed move time (`soft == hard`) is not varied.
+    pub fn pace(&self, budget: TimeBudget, draw: f64) -> TimeBudget {
+        let mut soft = budget.soft;
+        if budget.soft < budget.hard {
+            let factor =
+                1.0 + f64::from(self.variance_percent.min(100)) / 100.0 * draw.clamp(-1.0, 1.0);
+            soft = soft.mul_f64(factor);
+        }
+        TimeBudget {
+            soft: soft.max(self.min_think).min(budget.hard),
+            hard: budget.hard,
+            min: budget.min.max(self.min_think).min(budget.hard),
+        }
+    }
+}
+
+/// Predicts how long the next iteration of iterative deepening takes from
+/// the effective branching factor: how many times more nodes each iteration
+/// searches than the one before.
+#[derive(Debug, Clone, Default)]
+pub struct IterationTimes {
+    nodes: Vec<u64>,
+    last_time: Duration,
+}
+
+impl IterationTimes {
+    /// Records a completed iteration's own nodes and time.
+    pub fn record(&mut self, nodes: u64, time: Duration) {
+        self.nodes.push(nodes);
+        self.last_time = time;
+    }
+
+    /// The effective branching factor, or `None` before two iterations.
+    /// Odd and even depths grow differently, so it is taken over the last
+    /// two steps once there are three iterations.
+    pub fn ebf(&self) -> Option<f64> {
+        match self.nodes[..] {
+            [.., older, _, last] if older > 0 => Some((last as f64 / older as f64).sqrt()),
+            [.., previous, last] if previous > 0 => Some(last as f64 / previous as f64),
+            _ => None,
+        }
+    }
+
+    /// The expected time of the next iteration.
+    pub fn predict_next(&self) -> Option<Duration> {
+        self.ebf().map(|ebf| self.last_time.mul_f64(ebf))
+    }
+}
+
+/// Remaining time and increment for both sides, in milliseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Clocks {
+    pub white_ms: Option<u64>,
+    pub black_ms: Option<u64>,
+    pub white_inc_ms: u64,
+    pub black_inc_ms: u64,
+}
+
+impl Clocks {
+    pub fn remaining(&self, color: Color) -> Option<u64> {
+        match color {
+            Color::White => self.white_ms,
+            Color::Black => self.black_ms,
+        }
+    }
+
+    pub fn increment(&self, color: Color) -> u64 {
+        match color {
+            Color::White => self.white_inc_ms,
+            Color::Black => self.black_inc_ms,
+        }
+    }
+
+    /// The budget for `color`'s move, or `None` without a clock. The soft
+    /// limit is an even share of the remaining time plus most of the
+    /// increment; the hard limit allows up to four times that for a move
+    /// that needs it, but never more than half of what is left.
+    pub fn budget(&self, color: Color, moves_to_go: Option<u32>) -> Option<TimeBudget> {
+        let usable = self.remaining(color)?.saturating_sub(MOVE_OVERHEAD_MS);
+        let moves_to_go = moves_to_go.map_or(DEFAULT_MOVES_TO_GO, |moves| u64::from(moves.max(1)));
+        let soft = (usable / moves_to_go + self.increment(color) * 3 / 4).min(usable);
+        let hard = (soft * 4).min(usable / 2).max(soft);
+        Some(TimeBudget {
+            soft: Duration::from_millis(soft),
+            hard: Duration::from_millis(hard),
+            min: Duration::ZERO,
+        })
+    }
+
+    /// Whether the opponent of `color` is short of time: under 30 seconds
+    /// (ten increments count as extra time) and under half of `color`'s clock.
+    pub fn opponent_in_time_trouble(&self, color: Color) -> bool {
+        let opponent = match color {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        };
+        let (Some(own), Some(theirs)) = (self.remaining(color), self.remaining(opponent)) else {
+            return false;
+        };
+        theirs < TIME_TROUBLE_MS + 10 * self.increment(opponent) && theirs < own / 2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_spreads_remaining_time() {
+        let clocks = Clocks {
+            white_ms: Some(60_030),
+            white_inc_ms: 1_000,
+            ..Clocks::default()
+        };
+        let budget = clocks.budget(Color::White, None).expect("budget");
+        assert_eq!(budget.soft, Duration::from_millis(2_000 + 750));
+        assert_eq!(budget.hard, Duration::from_millis(11_000));
+        assert_eq!(clocks.budget(Color::Black, None), None);
+
+        // The last move before the time control may use everything.
+        let budget = clocks.budget(Color::White, Some(1)).expect("budget");
+        assert_eq!(budget.soft, Duration::from_millis(60_000));
+        assert_eq!(budget.hard, budget.soft);
+    }
+
+    #[test]
+    fn pacing_varies_the_soft_limit_within_the_hard_one() {
+        let budget = TimeBudget {
+            soft: Duration::from_millis(1_000),
+            hard: Duration::from_millis(4_000),
+            min: Duration::ZERO,
+        };
+        let pacing = Pacing {
+            min_think: Duration::from_millis(1_500),
+            variance_percent: 50,
+        };
+        assert_eq!(pacing.pace(budget, 1.0).soft, Duration::from_millis(1_500));
+        assert_eq!(pacing.pace(budget, 0.0).soft, Duration::from_millis(1_500));
+        assert_eq!(pacing.pace(budget, 0.0).min, Duration::from_millis(1_500));
+        let varied = Pacing {
+            min_think: Duration::ZERO,
+            ..pacing
+        };
+        assert_eq!(varied.pace(budget, -1.0).soft, Duration::from_millis(500));
+        assert_eq!(varied.pace(budget, 0.5).soft, Duration::from_millis(1_250));
+        assert_eq!(varied.pace(budget, 0.5).hard, budget.hard);
+
+        // A fixed move time is kept, and bounds the minimum.
+        let fixed = TimeBudget {
+            soft: Duration::from_millis(800),
+            hard: Duration::from_millis(800),
+            min: Duration::ZERO,
+        };
+        assert_eq!(
+            pacing.pace(fixed, -1.0),
+            TimeBudget {
+                min: fixed.hard,
+                ..fixed
+            }
+        );
+        assert_eq!(Pacing::default().pace(budget, 1.0), budget);
+    }
+
+    #[test]
+    fn predicts_the_next_iteration_from_the_branching_factor() {
+        let mut times = IterationTimes::default();
+        times.record(20, Duration::from_millis(1));
+        assert_eq!(times.ebf(), None);
+        assert_eq!(times.predict_next(), None);
+
+        times.record(100, Duration::from_millis(4));
+        assert_eq!(times.ebf(), Some(5.0));
+        assert_eq!(times.predict_next(), Some(Duration::from_millis(20)));
+
+        // 20 to 1620 is a factor of 81 over two steps, so 9 per step.
+        times.record(1_620, Duration::from_millis(60));
+        assert_eq!(times.ebf(), Some(9.0));
+        assert_eq!(times.predict_next(), Some(Duration::from_millis(540)));
+    }
+
+    #[test]
+    fn detects_opponent_time_trouble() {
+        let clocks = Clocks {
+            white_ms: Some(120_000),
+            black_ms: Some(20_000),
+            ..Clocks::default()
+        };
+        assert!(clocks.opponent_in_time_trouble(Color::White));
+        assert!(!clocks.opponent_in_time_trouble(Color::Black));
+
+        let with_increment = Clocks {
+            black_inc_ms: 2_000,
+            black_ms: Some(45_000),
+            ..clocks
+        };
+        assert!(with_increment.opponent_in_time_trouble(Color::White));
+
+        // Both low on time is a scramble, not an edge.
+        let both_low = Clocks {
+            white_ms: Some(25_000),
+            ..clocks
+        };
+        assert!(!both_low.opponent_in_time_trouble(Color::White));
+        assert!(!Clocks::default().opponent_in_time_trouble(Color::White));
+    }
+}
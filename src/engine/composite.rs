@@ -0,0 +1,345 @@
+//! An evaluator assembled from independent terms, each declaring the game
+//! phases it applies to. The phase is worked out once per position and the
+//! terms outside it are skipped without being computed, so terms that only
+//! matter in the opening or the endgame cost nothing elsewhere.
+
+use crate::engine::board::Board;
+use crate::engine::eval::{material, white_view, EvalTerm, EvalTrace, Evaluator};
+use crate::engine::golden::GoldenPosition;
+use crate::engine::imbalance::ImbalanceParams;
+use crate::engine::types::{Color, PieceKind};
+use std::ops::BitOr;
+
+/// Non-pawn material of both sides from which a position counts as an
+/// opening, as long as it is early enough; the start position has 6400.
+const OPENING_MATERIAL: i32 = 5600;
+/// Moves after which no position is an opening any more.
+const OPENING_MOVES: u32 = 12;
+/// Non-pawn material of both sides at or below which the endgame starts.
+const ENDGAME_MATERIAL: i32 = 2600;
+
+/// A set of game phases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Phases(u8);
+
+impl Phases {
+    pub const OPENING: Self = Self(1);
+    pub const MIDDLEGAME: Self = Self(2);
+    pub const ENDGAME: Self = Self(4);
+    pub const ALL: Self = Self(7);
+
+    /// The phase `board` is in, from the non-pawn material left and, for
+    /// the opening, the move number.
+    pub fn of(board: &Board) -> Self {
+        let pawns = |color| {
+            board
+                .squares
+                .iter()
+                .flatten()
+                .filter(|piece| piece.color == color && piece.kind == PieceKind::Pawn)
+                .count() as i32
+                * 100
+        };
+        let non_pawn = material(board, Color::White) + material(board, Color::Black)
+            - pawns(Color::White)
+            - pawns(Color::Black);
+        if non_pawn <= ENDGAME_MATERIAL {
+            Self::ENDGAME
+        } else if non_pawn >= OPENING_MATERIAL && board.fullmove_number <= OPENING_MOVES {
+            Self::OPENING
+        } else {
+            Self::MIDDLEGAME
+        }
+    }
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for Phases {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+/// One evaluation term of a `CompositeEvaluator`.
+pub trait Term: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// The phases the term applies in; it is not computed in the others.
+    fn phases(&self) -> Phases {
+        Phases::ALL
+    }
+
+    /// White's and Black's own scores, in centipawns.
+    fn score(&self, board: &Board) -> (i32, i32);
+
+    /// Positions the term must score in a given range, checked by the test
+    /// suite with `golden::check_term`. Every term should have some.
+    fn golden_positions(&self) -> &'static [GoldenPosition] {
+        &[]
+    }
+}
+
+/// Piece values.
+pub struct MaterialTerm;
+
+impl Term for MaterialTerm {
+    fn name(&self) -> &'static str {
+        "Material"
+    }
+
+    fn score(&self, board: &Board) -> (i32, i32) {
+        (material(board, Color::White), material(board, Color::Black))
+    }
+
+    fn golden_positions(&self) -> &'static [GoldenPosition] {
+        const POSITIONS: &[GoldenPosition] = &[
+            GoldenPosition::exactly(
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                0,
+                "start position",
+            ),
+            GoldenPosition::exactly("4k3/8/8/8/8/8/8/3QK3 b - - 0 1", 900, "White has a queen"),
+            GoldenPosition::exactly("r3k3/8/8/8/8/8/8/4K3 w - - 0 1", -500, "Black has a rook"),
+        ];
+        POSITIONS
+    }
+}
+
+/// The `ImbalanceParams` terms.
+pub struct ImbalanceTerm(pub ImbalanceParams);
+
+impl Term for ImbalanceTerm {
+    fn name(&self) -> &'static str {
+        "Imbalance"
+    }
+
+    fn score(&self, board: &Board) -> (i32, i32) {
+        (
+            self.0.side_score(board, Color::White),
+            self.0.side_score(board, Color::Black),
+        )
+    }
+
+    fn golden_positions(&self) -> &'static [GoldenPosition] {
+        const POSITIONS: &[GoldenPosition] = &[
+            GoldenPosition::exactly(
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                0,
+                "start position",
+            ),
+            GoldenPosition::white_better(
+                "2b1kn2/ppppp3/8/8/8/8/PPPPP3/2B1KB2 w - - 0 1",
+                "White has the bishop pair",
+            ),
+            GoldenPosition::white_better(
+                "2b1k3/pppppppp/8/8/8/8/PPPPPPPP/2N1K3 w - - 0 1",
+                "knight against bishop with all pawns on",
+            ),
+            GoldenPosition::black_better(
+                "r3k3/pp6/8/8/8/8/PPPPPPPP/R3K3 b - - 0 1",
+                "rook with two pawns against rook with eight",
+            ),
+        ];
+        POSITIONS
+    }
+}
+
+/// A bonus for the side to move in the opening, where having the move is
+/// worth most; later the position decides.
+pub struct TempoTerm(pub i32);
+
+impl Term for TempoTerm {
+    fn name(&self) -> &'static str {
+        "Tempo"
+    }
+
+    fn phases(&self) -> Phases {
+        Phases::OPENING
+    }
+
+    fn score(&self, board: &Board) -> (i32, i32) {
+        match board.side_to_move {
+            Color::White => (self.0, 0),
+            Color::Black => (0, self.0),
+        }
+    }
+
+    fn golden_positions(&self) -> &'static [GoldenPosition] {
+        const POSITIONS: &[GoldenPosition] = &[
+            GoldenPosition::white_better(
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                "White to move in the opening",
+            ),
+            GoldenPosition::black_better(
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+                "Black to move in the opening",
+            ),
+            GoldenPosition::exactly(
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 20",
+                0,
+                "past the opening",
+            ),
+        ];
+        POSITIONS
+    }
+}
+
+/// Sums its terms, skipping those that do not apply in the position's phase.
+#[derive(Default)]
+pub struct CompositeEvaluator {
+    terms: Vec<Box<dyn Term>>,
+}
+
+impl CompositeEvaluator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_term(mut self, term: impl Term + 'static) -> Self {
+        self.terms.push(Box::new(term));
+        self
+    }
+
+    fn applicable<'a>(&'a self, board: &Board) -> impl Iterator<Item = &'a dyn Term> {
+        let phase = Phases::of(board);
+        self.terms
+            .iter()
+            .map(|term| term.as_ref())
+            .filter(move |term| term.phases().contains(phase))
+    }
+}
+
+impl Evaluator for CompositeEvaluator {
+    fn evaluate(&self, board: &Board) -> i32 {
+        let total = self
+            .applicable(board)
+            .map(|term| {
+                let (white, black) = term.score(board);
+                white - black
+            })
+            .sum();
+        white_view(board, total)
+    }
+
+    fn trace(&self, board: &Board) -> EvalTrace {
+        EvalTrace {
+            terms: self
+                .applicable(board)
+                .map(|term| {
+                    let (white, black) = term.score(board);
+                    EvalTerm::from_sides(term.name(), white, black)
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::eval::MaterialEvaluator;
+    use crate::engine::golden::check_term;
+    use crate::engine::imbalance::ImbalanceEvaluator;
+
+    fn board_from(fen: &str) -> Board {
+        let mut board = Board::new();
+        board.set_fen(fen).expect("fen");
+        board
+    }
+
+    struct EndgameOnly;
+
+    impl Term for EndgameOnly {
+        fn name(&self) -> &'static str {
+            "Endgame"
+        }
+
+        fn phases(&self) -> Phases {
+            Phases::ENDGAME
+        }
+
+        fn score(&self, _: &Board) -> (i32, i32) {
+            panic!("computed outside the endgame")
+        }
+    }
+
+    #[test]
+    fn phases_follow_material_and_move_number() {
+        let start = board_from("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(Phases::of(&start), Phases::OPENING);
+        let late = board_from("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 20");
+        assert_eq!(Phases::of(&late), Phases::MIDDLEGAME);
+        let ending = board_from("4k3/pp3r2/8/8/8/8/PP6/3RK3 w - - 0 40");
+        assert_eq!(Phases::of(&ending), Phases::ENDGAME);
+        assert!((Phases::OPENING | Phases::ENDGAME).contains(Phases::ENDGAME));
+        assert!(!Phases::OPENING.contains(Phases::ALL));
+    }
+
+    #[test]
+    fn terms_outside_the_phase_are_skipped() {
+        let evaluator = CompositeEvaluator::new()
+            .with_term(MaterialTerm)
+            .with_term(TempoTerm(15))
+            .with_term(EndgameOnly);
+        let start = board_from("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1");
+        assert_eq!(evaluator.evaluate(&start), 15);
+        let names: Vec<&str> = evaluator
+            .trace(&start)
+            .terms
+            .iter()
+            .map(|term| term.name)
+            .collect();
+        assert_eq!(names, ["Material", "Tempo"]);
+
+        let middlegame = board_from("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 20");
+        assert_eq!(evaluator.evaluate(&middlegame), 0);
+    }
+
+    #[test]
+    fn built_in_terms_score_their_golden_positions() {
+        let terms: Vec<Box<dyn Term>> = vec![
+            Box::new(MaterialTerm),
+            Box::new(ImbalanceTerm(ImbalanceParams::default())),
+            Box::new(TempoTerm(15)),
+        ];
+        let evaluator = CompositeEvaluator::new()
+            .with_term(MaterialTerm)
+            .with_term(ImbalanceTerm(ImbalanceParams::default()))
+            .with_term(TempoTerm(15));
+        for term in &terms {
+            assert!(!term.golden_positions().is_empty(), "{}", term.name());
+            check_term(&evaluator, term.name(), term.golden_positions())
+                .unwrap_or_else(|err| panic!("{err}"));
+        }
+
+        // The evaluators tracing the same terms must agree with them.
+        let material = MaterialTerm.golden_positions();
+        let imbalance = ImbalanceTerm(ImbalanceParams::default()).golden_positions();
+        check_term(&MaterialEvaluator, "Material", material).unwrap_or_else(|err| panic!("{err}"));
+        check_term(&ImbalanceEvaluator::default(), "Material", material)
+            .unwrap_or_else(|err| panic!("{err}"));
+        check_term(&ImbalanceEvaluator::default(), "Imbalance", imbalance)
+            .unwrap_or_else(|err| panic!("{err}"));
+    }
+
+    #[test]
+    fn material_and_imbalance_terms_match_the_imbalance_evaluator() {
+        let evaluator = CompositeEvaluator::new()
+            .with_term(MaterialTerm)
+            .with_term(ImbalanceTerm(ImbalanceParams::default()));
+        let board = board_from("1r2kb2/pppppppp/8/8/8/8/PPPPPPP1/2B1KBN1 b - - 0 1");
+        assert_eq!(
+            evaluator.evaluate(&board),
+            ImbalanceEvaluator::default().evaluate(&board)
+        );
+        assert_eq!(
+            evaluator.trace(&board),
+            ImbalanceEvaluator::default().trace(&board)
+        );
+    }
+}
@@ -0,0 +1,78 @@
+//! Runtime CPU feature detection, so one portable binary can still use
+//! faster instructions where the machine has them.
+//!
+//! Hot kernels get a portable version plus versions compiled for a feature
+//! set; the best one is picked once on first use and called through a
+//! function pointer afterwards. `eval::material_balance` is one.
+
+use std::fmt;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CpuFeatures {
+    pub popcnt: bool,
+    pub bmi2: bool,
+    pub avx2: bool,
+}
+
+impl CpuFeatures {
+    fn detect() -> Self {
+        #[cfg(target_arch = "x86_64")]
+        {
+            Self {
+                popcnt: std::arch::is_x86_feature_detected!("popcnt"),
+                bmi2: std::arch::is_x86_feature_detected!("bmi2"),
+                avx2: std::arch::is_x86_feature_detected!("avx2"),
+            }
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        {
+            Self::default()
+        }
+    }
+}
+
+impl fmt::Display for CpuFeatures {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = [
+            (self.popcnt, "popcnt"),
+            (self.bmi2, "bmi2"),
+            (self.avx2, "avx2"),
+        ]
+        .into_iter()
+        .filter_map(|(present, name)| present.then_some(name))
+        .collect();
+        if names.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", names.join(" "))
+        }
+    }
+}
+
+/// The features of the running CPU, detected on first call.
+pub fn cpu_features() -> CpuFeatures {
+    static FEATURES: OnceLock<CpuFeatures> = OnceLock::new();
+    *FEATURES.get_or_init(CpuFeatures::detect)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_features_once() {
+        assert_eq!(cpu_features(), cpu_features());
+    }
+
+    #[test]
+    fn displays_detected_features() {
+        let features = CpuFeatures {
+            popcnt: true,
+            avx2: true,
+            ..CpuFeatures::default()
+        };
+        assert_eq!(features.to_string(), "popcnt avx2");
+        assert_eq!(CpuFeatures::default().to_string(), "none");
+    }
+}
@@ -0,0 +1,255 @@
+//! Labelled positions for evaluation training, taken from played games. Each
+//! position before a move is scored by a fixed-depth search and labelled with
+//! the game's result; how draws are labelled and which positions are kept
+//! are up to the pipeline consuming the data.
+
+use crate::engine::board::Board;
+use crate::engine::classify::classify;
+use crate::engine::composite::Phases;
+use crate::engine::eval::{white_view, Evaluator};
+use crate::engine::game::{GameRecord, GameResult};
+use crate::engine::search::{AlphaBetaSearch, SearchAlgorithm};
+use crate::engine::types::Color;
+
+/// How the positions of drawn games are labelled.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum DrawLabel {
+    /// Half a point for both sides.
+    #[default]
+    Half,
+    /// A draw is worth this much less than half a point to the side to move,
+    /// as with contempt in the search.
+    Contempt(f32),
+    /// Drawn games are left out.
+    Discard,
+}
+
+impl DrawLabel {
+    /// `half`, `discard`, or `contempt:<c>` with `c` in points, e.g.
+    /// `contempt:0.1`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text.to_ascii_lowercase().as_str() {
+            "half" | "0.5" => Ok(Self::Half),
+            "discard" => Ok(Self::Discard),
+            other => {
+                let contempt = other
+                    .strip_prefix("contempt:")
+                    .and_then(|value| value.parse::<f32>().ok())
+                    .filter(|value| (0.0..=0.5).contains(value))
+                    .ok_or_else(|| {
+                        format!(
+                            "unknown draw label: {text} (expected half, discard or contempt:<0..0.5>)"
+                        )
+                    })?;
+                Ok(Self::Contempt(contempt))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DataGenConfig {
+    /// Depth of the search scoring each position.
+    pub depth: u32,
+    pub draws: DrawLabel,
+    /// Only positions in these phases are kept.
+    pub phases: Phases,
+    /// Only quiet positions are kept; see `classify`.
+    pub quiet_only: bool,
+    /// Positions whose score is smaller than this in absolute value are
+    /// skipped.
+    pub min_eval: Option<i32>,
+    /// Positions whose score is larger than this in absolute value, decided
+    /// positions and mates, are skipped.
+    pub max_eval: Option<i32>,
+}
+
+impl Default for DataGenConfig {
+    fn default() -> Self {
+        Self {
+            depth: 4,
+            draws: DrawLabel::Half,
+            phases: Phases::ALL,
+            quiet_only: false,
+            min_eval: None,
+            max_eval: None,
+        }
+    }
+}
+
+/// One position, its score and the game's result, both from White's view.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrainingPosition {
+    pub fen: String,
+    /// Centipawns.
+    pub score: i32,
+    /// 1 for a White win, 0 for a Black win, the draw label otherwise.
+    pub result: f32,
+}
+
+impl TrainingPosition {
+    /// `<fen> | <score> | <result>`.
+    pub fn to_line(&self) -> String {
+        format!("{} | {} | {}", self.fen, self.score, self.result)
+    }
+}
+
+/// The positions of `record` kept by `config`, labelled with its result.
+/// Drawn games yield nothing with `DrawLabel::Discard`.
+pub fn label_game(
+    record: &GameRecord,
+    evaluator: &impl Evaluator,
+    config: &DataGenConfig,
+) -> Result<Vec<TrainingPosition>, String> {
+    if record.result == GameResult::Draw && config.draws == DrawLabel::Discard {
+        return Ok(Vec::new());
+    }
+
+    let mut board = Board::new();
+    board.set_fen(&record.start_fen)?;
+    let mut search = AlphaBetaSearch::new();
+    let mut positions = Vec::new();
+
+    for mv in &record.moves {
+        // Classifying resolves captures, so it is left to the positions
+        // where quietness matters.
+        if config.phases.contains(Phases::of(&board))
+            && (!config.quiet_only || classify(&mut board, evaluator).is_quiet())
+        {
+            let score = search.search(&mut board, evaluator, config.depth).score;
+            let magnitude = score.saturating_abs();
+            let in_bounds = config.min_eval.is_none_or(|min| magnitude >= min)
+                && config.max_eval.is_none_or(|max| magnitude <= max);
+            if in_bounds {
+                positions.push(TrainingPosition {
+                    fen: board.to_fen(),
+                    score: white_view(&board, score),
+                    result: result_label(record.result, config.draws, board.side_to_move),
+                });
+            }
+        }
+        board.apply_move(*mv)?;
+    }
+
+    Ok(positions)
+}
+
+fn result_label(result: GameResult, draws: DrawLabel, side_to_move: Color) -> f32 {
+    match (result, draws) {
+        (GameResult::WhiteWins, _) => 1.0,
+        (GameResult::BlackWins, _) => 0.0,
+        (GameResult::Draw, DrawLabel::Contempt(contempt)) => match side_to_move {
+            Color::White => 0.5 - contempt,
+            Color::Black => 0.5 + contempt,
+        },
+        (GameResult::Draw, _) => 0.5,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::eval::MaterialEvaluator;
+    use crate::engine::fen::STARTPOS_FEN;
+    use crate::engine::game::Termination;
+    use crate::engine::types::move_from_uci;
+
+    fn record(moves: &[&str], result: GameResult) -> GameRecord {
+        GameRecord {
+            start_fen: STARTPOS_FEN.to_string(),
+            moves: moves.iter().filter_map(|mv| move_from_uci(mv)).collect(),
+            clocks: Vec::new(),
+            result,
+            termination: Termination::MoveLimit,
+        }
+    }
+
+    #[test]
+    fn draws_are_labelled_as_configured() {
+        let game = record(&["e2e4", "e7e5"], GameResult::Draw);
+        let labels = |draws| {
+            let config = DataGenConfig {
+                depth: 1,
+                draws,
+                ..DataGenConfig::default()
+            };
+            label_game(&game, &MaterialEvaluator, &config)
+                .expect("label")
+                .iter()
+                .map(|position| position.result)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(labels(DrawLabel::Half), [0.5, 0.5]);
+        assert_eq!(labels(DrawLabel::Contempt(0.25)), [0.25, 0.75]);
+        assert!(labels(DrawLabel::Discard).is_empty());
+
+        let won = record(&["e2e4", "e7e5"], GameResult::BlackWins);
+        let config = DataGenConfig {
+            depth: 1,
+            draws: DrawLabel::Discard,
+            ..DataGenConfig::default()
+        };
+        let positions = label_game(&won, &MaterialEvaluator, &config).expect("label");
+        assert_eq!(positions.len(), 2);
+        assert_eq!(positions[0].to_line(), format!("{STARTPOS_FEN} | 0 | 0"));
+    }
+
+    #[test]
+    fn positions_outside_the_phases_or_eval_bounds_are_skipped() {
+        // White wins a knight for nothing with 3.Qxg4.
+        let game = record(
+            &["e2e4", "g8f6", "d2d3", "f6g4", "d1g4", "d7d6"],
+            GameResult::WhiteWins,
+        );
+        let keep = |config: DataGenConfig| {
+            label_game(&game, &MaterialEvaluator, &config)
+                .expect("label")
+                .len()
+        };
+        let config = DataGenConfig {
+            depth: 1,
+            ..DataGenConfig::default()
+        };
+
+        assert_eq!(keep(config), 6);
+        assert_eq!(
+            keep(DataGenConfig {
+                phases: Phases::MIDDLEGAME | Phases::ENDGAME,
+                ..config
+            }),
+            0
+        );
+        let balanced = keep(DataGenConfig {
+            max_eval: Some(100),
+            ..config
+        });
+        let unbalanced = keep(DataGenConfig {
+            min_eval: Some(101),
+            ..config
+        });
+        assert!(balanced > 0 && unbalanced > 0);
+        assert_eq!(balanced + unbalanced, 6);
+
+        // The e4 pawn hangs after 1...Nf6 and the knight after 2...Ng4.
+        assert_eq!(
+            keep(DataGenConfig {
+                quiet_only: true,
+                ..config
+            }),
+            4
+        );
+    }
+
+    #[test]
+    fn parses_draw_labels() {
+        assert_eq!(DrawLabel::parse("half"), Ok(DrawLabel::Half));
+        assert_eq!(DrawLabel::parse("Discard"), Ok(DrawLabel::Discard));
+        assert_eq!(
+            DrawLabel::parse("contempt:0.1"),
+            Ok(DrawLabel::Contempt(0.1))
+        );
+        assert!(DrawLabel::parse("contempt:0.9").is_err());
+        assert!(DrawLabel::parse("zero").is_err());
+    }
+}
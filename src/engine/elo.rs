@@ -0,0 +1,276 @@
+//! Elo estimates for engine matches, from the pentanomial model.
+//!
+//! Matches play each opening twice with colors swapped, so the two games of
+//! a pair are not independent: an opening that favours one side tends to
+//! give one win each. Counting pairs by their total (0, ½, 1, 1½ or 2 points)
+//! rather than games by their result keeps that correlation out of the
+//! variance, which gives tighter and honest error bars. The same counts
+//! drive the SPRT that stops a match once it has decided between two Elo
+//! hypotheses.
+
+use crate::engine::game::GameResult;
+use crate::engine::types::Color;
+use std::fmt;
+
+/// Two-sided 95% quantile of the normal distribution.
+const Z_95: f64 = 1.959964;
+
+/// Added to empty pentanomial counts for the LLR, so a short match whose
+/// pairs all scored alike still has a variance.
+const LLR_EPSILON: f64 = 1e-3;
+
+/// One game's outcome for the player being measured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Win,
+    Draw,
+    Loss,
+}
+
+impl Outcome {
+    /// The outcome for the player who had `color` in a game ending `result`.
+    pub fn for_player(result: GameResult, color: Color) -> Self {
+        match (result, color) {
+            (GameResult::Draw, _) => Outcome::Draw,
+            (GameResult::WhiteWins, Color::White) | (GameResult::BlackWins, Color::Black) => {
+                Outcome::Win
+            }
+            _ => Outcome::Loss,
+        }
+    }
+
+    fn half_points(self) -> usize {
+        match self {
+            Outcome::Win => 2,
+            Outcome::Draw => 1,
+            Outcome::Loss => 0,
+        }
+    }
+}
+
+/// Game pairs counted by the points the measured player scored in them:
+/// `counts[i]` pairs brought `i` half points.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Pentanomial {
+    pub counts: [u32; 5],
+}
+
+impl Pentanomial {
+    pub fn add_pair(&mut self, first: Outcome, second: Outcome) {
+        self.counts[first.half_points() + second.half_points()] += 1;
+    }
+
+    pub fn pairs(&self) -> u32 {
+        self.counts.iter().sum()
+    }
+
+    pub fn games(&self) -> u32 {
+        2 * self.pairs()
+    }
+
+    /// Points scored per game, between 0 and 1; `None` before any pair.
+    pub fn score(&self) -> Option<f64> {
+        let pairs = self.pairs();
+        (pairs > 0).then(|| {
+            let half_points: u32 = (0..).zip(self.counts).map(|(i, n)| i * n).sum();
+            f64::from(half_points) / f64::from(4 * pairs)
+        })
+    }
+
+    /// The Elo difference with its 95% confidence interval, or `None`
+    /// before any pair. A player who scored everything (or nothing) is
+    /// infinitely stronger (weaker) as far as the games show.
+    pub fn elo(&self) -> Option<EloEstimate> {
+        let score = self.score()?;
+        let counts = self.counts.map(f64::from);
+        let pairs = f64::from(self.pairs());
+        let margin = Z_95 * (pair_variance(&counts, score) / pairs).sqrt();
+        Some(EloEstimate {
+            elo: elo_from_score(score),
+            lower: elo_from_score((score - margin).max(0.0)),
+            upper: elo_from_score((score + margin).min(1.0)),
+        })
+    }
+
+    /// The log-likelihood ratio of the player being `elo1` rather than
+    /// `elo0` stronger, from the normal approximation over pair scores as in
+    /// engine testing frameworks; `None` before any pair.
+    pub fn llr(&self, elo0: f64, elo1: f64) -> Option<f64> {
+        if self.pairs() == 0 {
+            return None;
+        }
+        let counts = self.counts.map(|n| f64::from(n).max(LLR_EPSILON));
+        let pairs: f64 = counts.iter().sum();
+        let score = (0..)
+            .zip(counts)
+            .map(|(i, n)| f64::from(i) / 4.0 * n)
+            .sum::<f64>()
+            / pairs;
+        let (s0, s1) = (score_from_elo(elo0), score_from_elo(elo1));
+        Some(pairs * (s1 - s0) * (2.0 * score - s0 - s1) / (2.0 * pair_variance(&counts, score)))
+    }
+}
+
+// Variance of the pair scores, each pair scoring its half points over 4.
+fn pair_variance(counts: &[f64; 5], score: f64) -> f64 {
+    let pairs: f64 = counts.iter().sum();
+    (0..)
+        .zip(counts)
+        .map(|(i, n)| n * (f64::from(i) / 4.0 - score).powi(2))
+        .sum::<f64>()
+        / pairs
+}
+
+/// `ptnml(0-2): LL LD DD/WL WD WW`, as in engine testing frameworks.
+impl fmt::Display for Pentanomial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [ll, ld, dd, wd, ww] = self.counts;
+        write!(f, "ptnml(0-2): {ll}, {ld}, {dd}, {wd}, {ww}")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EloEstimate {
+    pub elo: f64,
+    /// Bounds of the 95% confidence interval.
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl fmt::Display for EloEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Elo {:+.1} (95% {:+.1} to {:+.1})",
+            self.elo, self.lower, self.upper
+        )
+    }
+}
+
+/// The Elo difference that makes `score` the expected score per game.
+pub fn elo_from_score(score: f64) -> f64 {
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+/// The expected score per game of a player `elo` stronger.
+pub fn score_from_elo(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+/// A sequential probability ratio test of H0, the player is `elo0` stronger,
+/// against H1, it is `elo1` stronger. Play pairs until it decides; it errs
+/// with probability `alpha` when accepting H1 and `beta` when accepting H0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SprtResult {
+    AcceptH0,
+    AcceptH1,
+    Continue,
+}
+
+impl Sprt {
+    /// The test with the usual 5% error rates.
+    pub fn new(elo0: f64, elo1: f64) -> Self {
+        Self {
+            elo0,
+            elo1,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+
+    /// The LLR bounds below which H0 and above which H1 is accepted.
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    pub fn test(&self, stats: &Pentanomial) -> SprtResult {
+        let (lower, upper) = self.bounds();
+        match stats.llr(self.elo0, self.elo1) {
+            Some(llr) if llr >= upper => SprtResult::AcceptH1,
+            Some(llr) if llr <= lower => SprtResult::AcceptH0,
+            _ => SprtResult::Continue,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 0.01
+    }
+
+    #[test]
+    fn estimates_elo_with_pentanomial_error_bars() {
+        let stats = Pentanomial {
+            counts: [5, 10, 30, 40, 15],
+        };
+        assert_eq!(stats.games(), 200);
+        assert_eq!(stats.score(), Some(0.625));
+        let estimate = stats.elo().expect("estimate");
+        assert!(close(estimate.elo, 88.74), "{estimate}");
+        assert!(close(estimate.lower, 52.36), "{estimate}");
+        assert!(close(estimate.upper, 127.13), "{estimate}");
+        assert_eq!(stats.to_string(), "ptnml(0-2): 5, 10, 30, 40, 15");
+    }
+
+    #[test]
+    fn color_swapped_wins_cancel_out() {
+        // Each side won the opening with White: even, and no doubt about it.
+        let mut stats = Pentanomial::default();
+        for _ in 0..10 {
+            stats.add_pair(
+                Outcome::for_player(GameResult::WhiteWins, Color::White),
+                Outcome::for_player(GameResult::WhiteWins, Color::Black),
+            );
+        }
+        assert_eq!(stats.counts, [0, 0, 10, 0, 0]);
+        let estimate = stats.elo().expect("estimate");
+        assert_eq!(
+            (estimate.lower, estimate.elo, estimate.upper),
+            (0.0, 0.0, 0.0)
+        );
+
+        assert_eq!(Pentanomial::default().elo(), None);
+        stats.add_pair(Outcome::Loss, Outcome::Draw);
+        assert!(stats.elo().expect("estimate").elo < 0.0);
+    }
+
+    #[test]
+    fn sprt_decides_from_the_pentanomial_llr() {
+        let stronger = Pentanomial {
+            counts: [5, 10, 30, 40, 15],
+        };
+        assert!(close(stronger.llr(0.0, 10.0).expect("llr"), 2.5827));
+        assert!(close(stronger.llr(100.0, 200.0).expect("llr"), -13.6608));
+        assert!(close(score_from_elo(elo_from_score(0.625)), 0.625));
+
+        let sprt = Sprt::new(0.0, 10.0);
+        let (lower, upper) = sprt.bounds();
+        assert!(close(lower, -2.9444) && close(upper, 2.9444));
+        assert_eq!(sprt.test(&stronger), SprtResult::Continue);
+        assert_eq!(Sprt::new(0.0, 100.0).test(&stronger), SprtResult::AcceptH1);
+        let weaker = Pentanomial {
+            counts: [15, 40, 30, 10, 5],
+        };
+        assert_eq!(sprt.test(&weaker), SprtResult::Continue);
+        let even = Pentanomial {
+            counts: [0, 0, 10, 0, 0],
+        };
+        assert!(close(even.llr(0.0, 10.0).expect("llr"), -16.5725));
+        assert_eq!(sprt.test(&even), SprtResult::AcceptH0);
+        assert_eq!(sprt.test(&Pentanomial::default()), SprtResult::Continue);
+    }
+}
@@ -0,0 +1,242 @@
+//! Extended Position Description records, as read and written by ChessBase,
+//! SCID and test-suite tools: the first four FEN fields followed by
+//! `opcode operand...;` operations.
+
+use crate::engine::board::Board;
+use crate::engine::eval::Evaluator;
+use crate::engine::notation::MoveFormat;
+use crate::engine::search::SearchAlgorithm;
+use crate::engine::Engine;
+use std::fmt;
+use std::time::Instant;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpdRecord {
+    /// Placement, side to move, castling and en passant fields.
+    pub position: String,
+    /// Operations in order, each an opcode with its operands.
+    pub operations: Vec<(String, Vec<String>)>,
+}
+
+impl EpdRecord {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let mut fields = Vec::new();
+        let mut rest = line;
+        for _ in 0..4 {
+            let (field, tail) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            if field.is_empty() {
+                return Err(format!("EPD needs four position fields: {line}"));
+            }
+            fields.push(field);
+            rest = tail.trim_start();
+        }
+
+        let mut record = Self {
+            position: fields.join(" "),
+            operations: Vec::new(),
+        };
+        for operation in split_operations(rest)? {
+            let mut words = operation.into_iter();
+            let Some(opcode) = words.next() else {
+                continue;
+            };
+            record.operations.push((opcode, words.collect()));
+        }
+        Ok(record)
+    }
+
+    /// An empty record for the board's position.
+    pub fn from_board(board: &Board) -> Self {
+        let fen = board.to_fen();
+        let position: Vec<&str> = fen.split_whitespace().take(4).collect();
+        Self {
+            position: position.join(" "),
+            operations: Vec::new(),
+        }
+    }
+
+    /// The position, with the move counters taken from the `hmvc` and `fmvn`
+    /// operations when present.
+    pub fn board(&self) -> Result<Board, String> {
+        let halfmove = self.operand("hmvc").unwrap_or("0");
+        let fullmove = self.operand("fmvn").unwrap_or("1");
+        let mut board = Board::new();
+        board.set_fen(&format!("{} {halfmove} {fullmove}", self.position))?;
+        Ok(board)
+    }
+
+    pub fn operation(&self, opcode: &str) -> Option<&[String]> {
+        self.operations
+            .iter()
+            .find(|(name, _)| name == opcode)
+            .map(|(_, operands)| operands.as_slice())
+    }
+
+    /// The first operand of `opcode`.
+    pub fn operand(&self, opcode: &str) -> Option<&str> {
+        self.operation(opcode)?.first().map(String::as_str)
+    }
+
+    /// Replaces the operands of `opcode`, or appends the operation.
+    pub fn set_operation(&mut self, opcode: &str, operands: Vec<String>) {
+        match self.operations.iter_mut().find(|(name, _)| name == opcode) {
+            Some((_, existing)) => *existing = operands,
+            None => self.operations.push((opcode.to_string(), operands)),
+        }
+    }
+}
+
+impl fmt::Display for EpdRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.position)?;
+        for (opcode, operands) in &self.operations {
+            write!(f, " {opcode}")?;
+            for operand in operands {
+                if takes_string(opcode) || operand.is_empty() || operand.contains([' ', ';', '"']) {
+                    write!(f, " \"{}\"", operand.replace('"', "'"))?;
+                } else {
+                    write!(f, " {operand}")?;
+                }
+            }
+            write!(f, ";")?;
+        }
+        Ok(())
+    }
+}
+
+// Opcodes whose operand is a string, always written quoted: `id` and the
+// comments `c0` to `c9`.
+fn takes_string(opcode: &str) -> bool {
+    opcode == "id"
+        || (opcode.len() == 2 && opcode.starts_with('c') && opcode.as_bytes()[1].is_ascii_digit())
+}
+
+// Splits `op a b; op "quoted; string";` into words per operation.
+fn split_operations(text: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut operations = Vec::new();
+    let mut words = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&ch) = chars.peek() {
+        match ch {
+            ';' => {
+                chars.next();
+                operations.push(std::mem::take(&mut words));
+            }
+            '"' => {
+                chars.next();
+                let mut word = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(ch) => word.push(ch),
+                        None => return Err(format!("unterminated string in EPD: {text}")),
+                    }
+                }
+                words.push(word);
+            }
+            _ if ch.is_whitespace() => {
+                chars.next();
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if ch.is_whitespace() || ch == ';' || ch == '"' {
+                        break;
+                    }
+                    word.push(ch);
+                    chars.next();
+                }
+                words.push(word);
+            }
+        }
+    }
+    if !words.is_empty() {
+        return Err(format!("EPD operation missing ';': {text}"));
+    }
+    Ok(operations)
+}
+
+impl<E: Evaluator, S: SearchAlgorithm> Engine<E, S> {
+    /// Searches the current position to `depth` and records the result with
+    /// the standard analysis opcodes: `acd` (depth), `acs` (seconds), `pm`
+    /// (predicted move), `ce` (centipawns for the side to move) and `pv`,
+    /// moves in SAN. A test suite's `bm` is left for comparing against.
+    pub fn analyse_epd(&mut self, depth: u32) -> EpdRecord {
+        let started = Instant::now();
+        let mut last = None;
+        self.search_iterative_with_info(depth, |info| last = Some(info.clone()));
+
+        let mut record = EpdRecord::from_board(&self.board);
+        let Some(info) = last else {
+            return record;
+        };
+        let pv = MoveFormat::default().format_line(&self.board, &info.pv);
+
+        record.set_operation("acd", vec![info.depth.to_string()]);
+        record.set_operation("acs", vec![started.elapsed().as_secs().to_string()]);
+        if let Some(best) = pv.first() {
+            record.set_operation("pm", vec![best.clone()]);
+        }
+        record.set_operation("ce", vec![info.score.to_string()]);
+        record.set_operation("pv", pv);
+        record
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::eval::MaterialEvaluator;
+    use crate::engine::search::AlphaBetaSearch;
+
+    #[test]
+    fn parses_and_writes_operations() {
+        let line = r#"r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - bm Bb5 Bc4; id "WAC; 1"; hmvc 2; fmvn 3;"#;
+        let record = EpdRecord::parse(line).expect("epd");
+        assert_eq!(
+            record.position,
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq -"
+        );
+        assert_eq!(
+            record.operation("bm"),
+            Some(&["Bb5".to_string(), "Bc4".to_string()][..])
+        );
+        assert_eq!(record.operand("id"), Some("WAC; 1"));
+        assert_eq!(record.board().expect("board").fullmove_number, 3);
+        assert_eq!(record.to_string(), line);
+        assert_eq!(EpdRecord::parse(&record.to_string()), Ok(record));
+
+        assert!(EpdRecord::parse("8/8/8 w").is_err());
+        assert!(EpdRecord::parse("4k3/8/8/8/8/8/8/4K3 w - - bm Kd2").is_err());
+    }
+
+    #[test]
+    fn analysis_records_standard_opcodes() {
+        let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+        engine
+            .set_position_fen("r7/8/8/8/8/4k3/8/Q3K3 w - - 0 1")
+            .expect("fen");
+
+        let record = engine.analyse_epd(1);
+        assert_eq!(record.operand("acd"), Some("1"));
+        assert!(record.operand("acs").is_some());
+        assert_eq!(record.operand("pm"), Some("Qxa8"));
+        assert_eq!(record.operand("bm"), None);
+        assert_eq!(record.operation("pv").map(<[String]>::len), Some(1));
+        assert!(record.operand("ce").expect("ce").parse::<i32>().is_ok());
+        let text = record.to_string();
+        assert!(text.starts_with("r7/8/8/8/8/4k3/8/Q3K3 w - - acd 1; acs "));
+        assert_eq!(EpdRecord::parse(&text), Ok(record.clone()));
+
+        // Added to a test suite record, the analysis keeps its `bm`.
+        let mut suite =
+            EpdRecord::parse("r7/8/8/8/8/4k3/8/Q3K3 w - - bm Qa5; id \"t1\";").expect("epd");
+        for (opcode, operands) in record.operations {
+            suite.set_operation(&opcode, operands);
+        }
+        assert_eq!(suite.operand("bm"), Some("Qa5"));
+        assert_eq!(suite.operand("pm"), Some("Qxa8"));
+        assert_eq!(suite.operand("id"), Some("t1"));
+    }
+}
@@ -1,6 +1,6 @@
 use crate::engine::board::Board;
 use crate::engine::castling::{
-    has_kingside, has_queenside, CASTLE_BLACK_KING, CASTLE_BLACK_QUEEN, CASTLE_WHITE_KING,
+    pieces_in_place, CastlingFiles, Wing, CASTLE_BLACK_KING, CASTLE_BLACK_QUEEN, CASTLE_WHITE_KING,
     CASTLE_WHITE_QUEEN,
 };
 use crate::engine::movegen::is_square_attacked;
@@ -8,6 +8,8 @@ use crate::engine::types::{
     algebraic_from_square, is_valid_square, square_from_algebraic, Color, Piece, PieceKind, Square,
 };
 
+use std::cell::Cell;
+
 pub const STARTPOS_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
 
 #[derive(Debug)]
@@ -15,6 +17,7 @@ pub struct FenData {
     pub squares: [Option<Piece>; 128],
     pub side_to_move: Color,
     pub castling_rights: u8,
+    pub castling_files: CastlingFiles,
     pub en_passant: Option<Square>,
     pub halfmove_clock: u32,
     pub fullmove_number: u32,
@@ -32,7 +35,7 @@ pub fn parse_fen(fen: &str) -> Result<FenData, String> {
         "b" => Color::Black,
         _ => return Err("invalid side to move".to_string()),
     };
-    let castling_rights = parse_castling_rights(parts[2])?;
+    let (castling_rights, castling_files) = parse_castling(parts[2], &squares)?;
     let en_passant = parse_en_passant(parts[3])?;
     let halfmove_clock = parts[4]
         .parse::<u32>()
@@ -45,6 +48,7 @@ pub fn parse_fen(fen: &str) -> Result<FenData, String> {
         squares,
         side_to_move,
         castling_rights,
+        castling_files,
         en_passant,
         halfmove_clock,
         fullmove_number,
@@ -89,32 +93,21 @@ pub fn validate_fen_semantics(data: &FenData) -> Result<(), String> {
     let white_king = white_king.ok_or_else(|| "missing white king".to_string())?;
     let black_king = black_king.ok_or_else(|| "missing black king".to_string())?;
 
-    if has_kingside(data.castling_rights, Color::White) {
-        if !is_piece_at(data, Square(4), Color::White, PieceKind::King)
-            || !is_piece_at(data, Square(7), Color::White, PieceKind::Rook)
-        {
-            return Err("invalid white kingside castling rights".to_string());
-        }
-    }
-    if has_queenside(data.castling_rights, Color::White) {
-        if !is_piece_at(data, Square(4), Color::White, PieceKind::King)
-            || !is_piece_at(data, Square(0), Color::White, PieceKind::Rook)
-        {
-            return Err("invalid white queenside castling rights".to_string());
-        }
-    }
-    if has_kingside(data.castling_rights, Color::Black) {
-        if !is_piece_at(data, Square(116), Color::Black, PieceKind::King)
-            || !is_piece_at(data, Square(119), Color::Black, PieceKind::Rook)
-        {
-            return Err("invalid black kingside castling rights".to_string());
-        }
-    }
-    if has_queenside(data.castling_rights, Color::Black) {
-        if !is_piece_at(data, Square(116), Color::Black, PieceKind::King)
-            || !is_piece_at(data, Square(112), Color::Black, PieceKind::Rook)
-        {
-            return Err("invalid black queenside castling rights".to_string());
+    for color in [Color::White, Color::Black] {
+        for wing in Wing::BOTH {
+            if data.castling_rights & wing.right(color) != 0
+                && !pieces_in_place(&data.squares, &data.castling_files, color, wing)
+            {
+                let color = match color {
+                    Color::White => "white",
+                    Color::Black => "black",
+                };
+                let wing = match wing {
+                    Wing::Kingside => "kingside",
+                    Wing::Queenside => "queenside",
+                };
+                return Err(format!("invalid {color} {wing} castling rights"));
+            }
         }
     }
 
@@ -122,10 +115,14 @@ pub fn validate_fen_semantics(data: &FenData) -> Result<(), String> {
         squares: data.squares,
         side_to_move: data.side_to_move,
         castling_rights: data.castling_rights,
+        castling_files: data.castling_files,
         en_passant: data.en_passant,
         halfmove_clock: data.halfmove_clock,
         fullmove_number: data.fullmove_number,
         hash: 0,
+        mirror_hash: 0,
+        history: Vec::new(),
+        check_cache: Cell::new(None),
     };
     let white_in_check = is_square_attacked(&board, white_king, Color::Black);
     let black_in_check = is_square_attacked(&board, black_king, Color::White);
@@ -147,6 +144,85 @@ pub fn validate_fen_semantics(data: &FenData) -> Result<(), String> {
     Ok(())
 }
 
+pub fn board_to_fen(board: &Board) -> String {
+    let mut placement = String::new();
+    for rank in (0u8..8).rev() {
+        let mut empty = 0;
+        for file in 0u8..8 {
+            match board.squares[(rank * 16 + file) as usize] {
+                Some(piece) => {
+                    if empty > 0 {
+                        placement.push_str(&empty.to_string());
+                        empty = 0;
+                    }
+                    placement.push(fen_from_piece(piece));
+                }
+                None => empty += 1,
+            }
+        }
+        if empty > 0 {
+            placement.push_str(&empty.to_string());
+        }
+        if rank > 0 {
+            placement.push('/');
+        }
+    }
+
+    let side = match board.side_to_move {
+        Color::White => "w",
+        Color::Black => "b",
+    };
+
+    // Standard castling is written as KQkq, and Chess960 castling with the
+    // rook's file as Shredder-FEN does, which every Chess960 GUI reads.
+    let mut castling = String::new();
+    for color in [Color::White, Color::Black] {
+        for wing in Wing::BOTH {
+            if board.castling_rights & wing.right(color) == 0 {
+                continue;
+            }
+            let files = &board.castling_files;
+            let ch = if files.is_standard(color, wing) {
+                match wing {
+                    Wing::Kingside => 'k',
+                    Wing::Queenside => 'q',
+                }
+            } else {
+                (b'a' + files.rook(color, wing)) as char
+            };
+            castling.push(match color {
+                Color::White => ch.to_ascii_uppercase(),
+                Color::Black => ch,
+            });
+        }
+    }
+    if castling.is_empty() {
+        castling.push('-');
+    }
+
+    // Only a square a pawn can capture on is written, as FEN requires, so
+    // the FEN of any position reached by moves loads again.
+    let data = FenData {
+        squares: board.squares,
+        side_to_move: board.side_to_move,
+        castling_rights: board.castling_rights,
+        castling_files: board.castling_files,
+        en_passant: board.en_passant,
+        halfmove_clock: board.halfmove_clock,
+        fullmove_number: board.fullmove_number,
+    };
+    let en_passant = board
+        .en_passant
+        .filter(|ep| en_passant_capturable(&data, *ep))
+        .and_then(algebraic_from_square)
+        .unwrap_or_else(|| "-".to_string());
+
+    format!(
+        "{placement} {side} {castling} {en_passant} {} {}",
+        board.halfmove_clock, board.fullmove_number
+    )
+}
+
 fn is_piece_at(data: &FenData, square: Square, color: Color, kind: PieceKind) -> bool {
     matches!(
         data.squares[square.index() as usize],
@@ -175,10 +251,7 @@ fn validate_en_passant(data: &FenData, ep: Square) -> Result<(), String> {
         Color::White => ep.index() as i16 + 16,
         Color::Black => ep.index() as i16 - 16,
     };
-    if opponent_pawn_index < 0
-        || opponent_pawn_index > 127
-        || !is_valid_square(opponent_pawn_index as u8)
-    {
+    if !(0..=127).contains(&opponent_pawn_index) || !is_valid_square(opponent_pawn_index as u8) {
         return Err("invalid en passant pawn position".to_string());
     }
     let opponent_pawn_square = Square(opponent_pawn_index as u8);
@@ -186,30 +259,31 @@ fn validate_en_passant(data: &FenData, ep: Square) -> Result<(), String> {
         return Err("missing pawn for en passant".to_string());
     }
 
+    if !en_passant_capturable(data, ep) {
+        return Err("no pawn can capture en passant".to_string());
+    }
+
+    Ok(())
+}
+
+/// Whether a pawn of the side to move stands next to the pawn that can be
+/// captured en passant on `ep`.
+pub(crate) fn en_passant_capturable(data: &FenData, ep: Square) -> bool {
     let (left_offset, right_offset) = match data.side_to_move {
         Color::White => (-17, -15),
         Color::Black => (17, 15),
     };
-    let mut can_capture = false;
-    for offset in [left_offset, right_offset] {
+    [left_offset, right_offset].into_iter().any(|offset| {
         let candidate = ep.index() as i16 + offset;
-        if candidate < 0 || candidate > 127 {
-            continue;
-        }
-        if !is_valid_square(candidate as u8) {
-            continue;
-        }
-        let square = Square(candidate as u8);
-        if is_piece_at(data, square, data.side_to_move, PieceKind::Pawn) {
-            can_capture = true;
-            break;
-        }
-    }
-    if !can_capture {
-        return Err("no pawn can capture en passant".to_string());
-    }
-
-    Ok(())
+        (0..=127).contains(&candidate)
+            && is_valid_square(candidate as u8)
+            && is_piece_at(
+                data,
+                Square(candidate as u8),
+                data.side_to_move,
+                PieceKind::Pawn,
+            )
+    })
 }
 
 fn parse_piece_placement(placement: &str) -> Result<[Option<Piece>; 128], String> {
@@ -244,7 +318,7 @@ fn parse_piece_placement(placement: &str) -> Result<[Option<Piece>; 128], String
             return Err("invalid FEN rank length".to_string());
         }
 
-        let square = (rank_index * 16 + file_index) as u8;
+        let square = rank_index * 16 + file_index;
         if !is_valid_square(square) {
             return Err("invalid square in FEN".to_string());
         }
@@ -260,7 +334,7 @@ fn parse_piece_placement(placement: &str) -> Result<[Option<Piece>; 128], String
     Ok(squares)
 }
 
-fn piece_from_fen(ch: char) -> Option<Piece> {
+pub(crate) fn piece_from_fen(ch: char) -> Option<Piece> {
     let (color, kind) = match ch {
         'P' => (Color::White, PieceKind::Pawn),
         'N' => (Color::White, PieceKind::Knight),
@@ -280,7 +354,22 @@ fn piece_from_fen(ch: char) -> Option<Piece> {
     Some(Piece { color, kind })
 }
 
-fn parse_castling_rights(text: &str) -> Result<u8, String> {
+pub(crate) fn fen_from_piece(piece: Piece) -> char {
+    let ch = match piece.kind {
+        PieceKind::Pawn => 'p',
+        PieceKind::Knight => 'n',
+        PieceKind::Bishop => 'b',
+        PieceKind::Rook => 'r',
+        PieceKind::Queen => 'q',
+        PieceKind::King => 'k',
+    };
+    match piece.color {
+        Color::White => ch.to_ascii_uppercase(),
+        Color::Black => ch,
+    }
+}
+
+pub(crate) fn parse_castling_rights(text: &str) -> Result<u8, String> {
     if text == "-" {
         return Ok(0);
     }
@@ -299,7 +388,70 @@ fn parse_castling_rights(text: &str) -> Result<u8, String> {
     Ok(rights)
 }
 
-fn parse_en_passant(text: &str) -> Result<Option<Square>, String> {
+/// Parses the castling field of a FEN against its placement. Besides KQkq
+/// this reads X-FEN, where K and Q stand for the outermost rook on that
+/// wing, and Shredder-FEN, which names the rook's file; both give the files
+/// Chess960 castling starts from.
+fn parse_castling(
+    text: &str,
+    squares: &[Option<Piece>; 128],
+) -> Result<(u8, CastlingFiles), String> {
+    let mut files = CastlingFiles::STANDARD;
+    if text == "-" {
+        return Ok((0, files));
+    }
+
+    let mut rights = 0u8;
+    for ch in text.chars() {
+        let color = if ch.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let rank = match color {
+            Color::White => 0,
+            Color::Black => 7,
+        };
+        let holds = |file: u8, kind: PieceKind| {
+            squares[(rank * 16 + file) as usize] == Some(Piece { color, kind })
+        };
+        let king = (0..8)
+            .find(|&file| holds(file, PieceKind::King))
+            .unwrap_or(4);
+        let (wing, rook) = match ch.to_ascii_lowercase() {
+            'k' => (
+                Wing::Kingside,
+                (king + 1..8)
+                    .rev()
+                    .find(|&file| holds(file, PieceKind::Rook))
+                    .unwrap_or(7),
+            ),
+            'q' => (
+                Wing::Queenside,
+                (0..king)
+                    .find(|&file| holds(file, PieceKind::Rook))
+                    .unwrap_or(0),
+            ),
+            letter @ 'a'..='h' => {
+                let file = letter as u8 - b'a';
+                let wing = if file > king {
+                    Wing::Kingside
+                } else {
+                    Wing::Queenside
+                };
+                (wing, file)
+            }
+            _ => return Err("invalid castling rights".to_string()),
+        };
+        rights |= wing.right(color);
+        files.set_king(color, king);
+        files.set_rook(color, wing, rook);
+    }
+
+    Ok((rights, files))
+}
+
+pub(crate) fn parse_en_passant(text: &str) -> Result<Option<Square>, String> {
     if text == "-" {
         return Ok(None);
     }
@@ -380,6 +532,33 @@ mod tests {
         assert!(err.contains("castling"));
     }
 
+    #[test]
+    fn reads_x_fen_and_shredder_fen_castling_rights() {
+        // X-FEN letters stand for the outermost rook, written back with its
+        // file; rooks on the a- and h-files keep the standard letters.
+        let mut board = Board::new();
+        board
+            .set_fen("rk2r3/pppppppp/8/8/8/8/PPPPPPPP/1R1KR1R1 w KQkq - 0 1")
+            .expect("x-fen");
+        assert_eq!(board.castling_files.king(Color::White), 3);
+        assert_eq!(board.castling_files.rook(Color::White, Wing::Kingside), 6);
+        assert_eq!(
+            board.to_fen(),
+            "rk2r3/pppppppp/8/8/8/8/PPPPPPPP/1R1KR1R1 w GBea - 0 1"
+        );
+
+        let shredder = "rk2r3/pppppppp/8/8/8/8/PPPPPPPP/1R1KR1R1 w EBea - 0 1";
+        board.set_fen(shredder).expect("shredder");
+        assert_eq!(board.castling_files.rook(Color::White, Wing::Kingside), 4);
+        assert_eq!(board.to_fen(), shredder);
+
+        board
+            .set_fen("r3k2r/8/8/8/8/8/8/R3K2R w HAha - 0 1")
+            .expect("standard");
+        assert_eq!(board.to_fen(), "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        assert!(board.set_fen("rk6/8/8/8/8/8/8/1R1K4 w C - 0 1").is_err());
+    }
+
     #[test]
     fn rejects_invalid_en_passant() {
         let mut board = Board::new();
@@ -401,4 +580,18 @@ mod tests {
         let err = validate_fen_semantics(&data).expect_err("invalid check state");
         assert!(err.contains("black king in check"));
     }
+
+    #[test]
+    fn board_to_fen_round_trips() {
+        let fens = [
+            STARTPOS_FEN,
+            "r3k2r/8/8/8/8/8/8/R3K2R b Kq - 3 17",
+            "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+        ];
+        for fen in fens {
+            let mut board = Board::new();
+            board.set_fen(fen).expect("fen");
+            assert_eq!(board_to_fen(&board), fen);
+        }
+    }
 }
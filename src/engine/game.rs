@@ -0,0 +1,312 @@
+//! Playing complete games between engines or scripted players.
+
+use crate::engine::board::Board;
+use crate::engine::clock::Clocks;
+use crate::engine::eval::Evaluator;
+use crate::engine::fen::STARTPOS_FEN;
+use crate::engine::limits::SearchLimits;
+use crate::engine::movegen::generate_legal;
+use crate::engine::search::SearchAlgorithm;
+use crate::engine::types::{move_from_uci, Color, Move};
+use crate::engine::Engine;
+use std::time::Instant;
+
+/// Anything that can pick a move: an `Engine`, or a closure taking the
+/// position, the clocks and the configured depth for its side.
+pub trait Player {
+    /// `None` resigns.
+    fn choose_move(&mut self, board: &mut Board, clocks: &Clocks, depth: u32) -> Option<Move>;
+}
+
+impl<F> Player for F
+where
+    F: FnMut(&mut Board, &Clocks, u32) -> Option<Move>,
+{
+    fn choose_move(&mut self, board: &mut Board, clocks: &Clocks, depth: u32) -> Option<Move> {
+        self(board, clocks, depth)
+    }
+}
+
+impl<E: Evaluator, S: SearchAlgorithm> Player for Engine<E, S> {
+    fn choose_move(&mut self, board: &mut Board, clocks: &Clocks, depth: u32) -> Option<Move> {
+        self.set_position_board(board);
+        self.clear_stop();
+        let limits = SearchLimits {
+            clocks: *clocks,
+            ..SearchLimits::depth(depth)
+        };
+        let (bestmove, _) = self.search(&limits, |_| {});
+        move_from_uci(&bestmove)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GameConfig {
+    /// Defaults to the start position.
+    pub start_fen: Option<String>,
+    pub white_depth: u32,
+    pub black_depth: u32,
+    /// Starting clocks; time is only tracked for sides that have one.
+    pub clocks: Clocks,
+    /// The game is adjudicated a draw after this many plies.
+    pub max_plies: u32,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            start_fen: None,
+            white_depth: 4,
+            black_depth: 4,
+            clocks: Clocks::default(),
+            max_plies: 400,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+}
+
+impl GameResult {
+    /// The PGN result token.
+    pub fn as_pgn(self) -> &'static str {
+        match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+        }
+    }
+
+    fn win_for(color: Color) -> Self {
+        match color {
+            Color::White => GameResult::WhiteWins,
+            Color::Black => GameResult::BlackWins,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Termination {
+    Checkmate,
+    Stalemate,
+    Repetition,
+    FiftyMoveRule,
+    MoveLimit,
+    Resignation,
+    /// The player returned a move that is not legal in the position.
+    IllegalMove,
+    TimeForfeit,
+}
+
+#[derive(Debug, Clone)]
+pub struct GameRecord {
+    pub start_fen: String,
+    pub moves: Vec<Move>,
+    /// Both clocks after each move, parallel to `moves`.
+    pub clocks: Vec<Clocks>,
+    pub result: GameResult,
+    pub termination: Termination,
+}
+
+/// Plays a game from `config.start_fen` until it ends or reaches
+/// `config.max_plies`.
+pub fn play_game(
+    white: &mut impl Player,
+    black: &mut impl Player,
+    config: &GameConfig,
+) -> Result<GameRecord, String> {
+    let start_fen = config.start_fen.as_deref().unwrap_or(STARTPOS_FEN);
+    let mut board = Board::new();
+    board.set_fen(start_fen)?;
+
+    let mut clocks = config.clocks;
+    let mut moves = Vec::new();
+    let mut clock_history = Vec::new();
+
+    let (result, termination) = loop {
+        let side = board.side_to_move;
+        if generate_legal(&mut board).is_empty() {
+            break if board.in_check() {
+                (GameResult::win_for(opposite(side)), Termination::Checkmate)
+            } else {
+                (GameResult::Draw, Termination::Stalemate)
+            };
+        }
+        if board.repetition_count() >= 3 {
+            break (GameResult::Draw, Termination::Repetition);
+        }
+        if board.halfmove_clock >= 100 {
+            break (GameResult::Draw, Termination::FiftyMoveRule);
+        }
+        if moves.len() as u32 >= config.max_plies {
+            break (GameResult::Draw, Termination::MoveLimit);
+        }
+
+        let started = Instant::now();
+        let chosen = match side {
+            Color::White => white.choose_move(&mut board, &clocks, config.white_depth),
+            Color::Black => black.choose_move(&mut board, &clocks, config.black_depth),
+        };
+        if !spend_time(&mut clocks, side, started.elapsed().as_millis() as u64) {
+            break (
+                GameResult::win_for(opposite(side)),
+                Termination::TimeForfeit,
+            );
+        }
+        let Some(mv) = chosen else {
+            break (
+                GameResult::win_for(opposite(side)),
+                Termination::Resignation,
+            );
+        };
+        if !generate_legal(&mut board).contains(&mv) {
+            break (
+                GameResult::win_for(opposite(side)),
+                Termination::IllegalMove,
+            );
+        }
+
+        board.apply_move(mv)?;
+        moves.push(mv);
+        clock_history.push(clocks);
+    };
+
+    Ok(GameRecord {
+        start_fen: start_fen.to_string(),
+        moves,
+        clocks: clock_history,
+        result,
+        termination,
+    })
+}
+
+impl<E: Evaluator, S: SearchAlgorithm> Engine<E, S> {
+    /// Plays a full game with this engine as White against `black`.
+    pub fn play_game(
+        &mut self,
+        black: &mut impl Player,
+        config: &GameConfig,
+    ) -> Result<GameRecord, String> {
+        play_game(self, black, config)
+    }
+}
+
+// Charges `elapsed_ms` to `side` and adds its increment; false when the flag
+// falls. Sides without a clock are never charged.
+fn spend_time(clocks: &mut Clocks, side: Color, elapsed_ms: u64) -> bool {
+    let (remaining, increment) = match side {
+        Color::White => (&mut clocks.white_ms, clocks.white_inc_ms),
+        Color::Black => (&mut clocks.black_ms, clocks.black_inc_ms),
+    };
+    let Some(remaining) = remaining else {
+        return true;
+    };
+    if elapsed_ms > *remaining {
+        *remaining = 0;
+        return false;
+    }
+    *remaining = *remaining - elapsed_ms + increment;
+    true
+}
+
+fn opposite(color: Color) -> Color {
+    match color {
+        Color::White => Color::Black,
+        Color::Black => Color::White,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::eval::MaterialEvaluator;
+    use crate::engine::search::AlphaBetaSearch;
+    use crate::engine::types::uci_from_move;
+
+    // Plays the given moves in order, then resigns.
+    fn scripted(moves: &[&str]) -> impl FnMut(&mut Board, &Clocks, u32) -> Option<Move> {
+        let mut moves: Vec<Move> = moves
+            .iter()
+            .rev()
+            .filter_map(|mv| move_from_uci(mv))
+            .collect();
+        move |_, _, _| moves.pop()
+    }
+
+    #[test]
+    fn engine_finds_fools_mate_against_scripted_player() {
+        let mut white = scripted(&[]);
+        let mut black = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+        let config = GameConfig {
+            start_fen: Some(
+                "rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2".to_string(),
+            ),
+            black_depth: 2,
+            ..GameConfig::default()
+        };
+
+        let record = play_game(&mut white, &mut black, &config).expect("game");
+        assert_eq!(record.termination, Termination::Checkmate);
+        assert_eq!(record.result, GameResult::BlackWins);
+        assert_eq!(record.moves.len(), 1);
+        assert_eq!(uci_from_move(record.moves[0]).as_deref(), Some("d8h4"));
+        assert_eq!(record.clocks.len(), record.moves.len());
+    }
+
+    #[test]
+    fn shuffling_knights_draws_by_repetition() {
+        let shuffle = ["g1f3", "f3g1", "g1f3", "f3g1"];
+        let mut white = scripted(&shuffle);
+        let mut black = scripted(&["g8f6", "f6g8", "g8f6", "f6g8"]);
+
+        let record = play_game(&mut white, &mut black, &GameConfig::default()).expect("game");
+        assert_eq!(record.termination, Termination::Repetition);
+        assert_eq!(record.result, GameResult::Draw);
+        assert_eq!(record.moves.len(), 8);
+    }
+
+    #[test]
+    fn engines_search_with_the_game_history() {
+        let mut board = Board::new();
+        board.set_startpos();
+        for text in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+            board
+                .apply_move(move_from_uci(text).expect("move"))
+                .expect("legal");
+        }
+        let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+        engine.choose_move(&mut board, &Clocks::default(), 1);
+        assert_eq!(engine.hash_history(), board.hash_history());
+        assert_eq!(engine.repetition_count(), 2);
+    }
+
+    #[test]
+    fn illegal_move_and_flag_fall_lose() {
+        let mut white = scripted(&["e2e5"]);
+        let mut black = scripted(&[]);
+        let record = play_game(&mut white, &mut black, &GameConfig::default()).expect("game");
+        assert_eq!(record.termination, Termination::IllegalMove);
+        assert_eq!(record.result, GameResult::BlackWins);
+
+        let mut slow = |_: &mut Board, _: &Clocks, _: u32| {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            move_from_uci("e2e4")
+        };
+        let mut black = scripted(&[]);
+        let config = GameConfig {
+            clocks: Clocks {
+                white_ms: Some(5),
+                ..Clocks::default()
+            },
+            ..GameConfig::default()
+        };
+        let record = play_game(&mut slow, &mut black, &config).expect("game");
+        assert_eq!(record.termination, Termination::TimeForfeit);
+        assert_eq!(record.result, GameResult::BlackWins);
+    }
+}
@@ -0,0 +1,167 @@
+//! Golden positions for evaluation terms: positions paired with the range a
+//! term must score them in. Each `Term` registers its own, and the test
+//! suite checks them through the evaluator's trace, so a new or reworked
+//! term cannot quietly flip a sign or count a feature twice.
+
+use crate::engine::board::Board;
+use crate::engine::eval::Evaluator;
+
+/// A position and the range, in centipawns from White's point of view, that
+/// a term must score it in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GoldenPosition {
+    pub fen: &'static str,
+    pub min: i32,
+    pub max: i32,
+    /// What the position is meant to show, for failure messages.
+    pub note: &'static str,
+}
+
+impl GoldenPosition {
+    pub const fn exactly(fen: &'static str, score: i32, note: &'static str) -> Self {
+        Self::between(fen, score, score, note)
+    }
+
+    pub const fn between(fen: &'static str, min: i32, max: i32, note: &'static str) -> Self {
+        Self {
+            fen,
+            min,
+            max,
+            note,
+        }
+    }
+
+    /// Any score in White's favour.
+    pub const fn white_better(fen: &'static str, note: &'static str) -> Self {
+        Self::between(fen, 1, i32::MAX, note)
+    }
+
+    /// Any score in Black's favour.
+    pub const fn black_better(fen: &'static str, note: &'static str) -> Self {
+        Self::between(fen, i32::MIN, -1, note)
+    }
+}
+
+/// Checks the term named `term` in `evaluator`'s trace of each position:
+///
+/// - its score lies in the position's range (a term missing from the trace,
+///   say outside its phases, scores 0);
+/// - the color-flipped position scores exactly the negation, so the term
+///   treats both sides alike;
+/// - the trace adds up to `Evaluator::evaluate`, so no term is counted
+///   twice or left out of the trace.
+///
+/// All failures are reported together.
+pub fn check_term(
+    evaluator: &impl Evaluator,
+    term: &str,
+    positions: &[GoldenPosition],
+) -> Result<(), String> {
+    let mut failures = Vec::new();
+    for position in positions {
+        let mut board = Board::new();
+        if let Err(err) = board.set_fen(position.fen) {
+            failures.push(format!("{term}: invalid FEN {}: {err}", position.fen));
+            continue;
+        }
+        let flipped = board.flipped();
+        let score = term_score(evaluator, &board, term);
+        let flipped_score = term_score(evaluator, &flipped, term);
+        let context = format!("{term} in {} ({})", position.fen, position.note);
+
+        if !(position.min..=position.max).contains(&score) {
+            failures.push(format!(
+                "{context}: scored {score}, expected {}..={}",
+                position.min, position.max
+            ));
+        }
+        if flipped_score != -score {
+            failures.push(format!(
+                "{context}: scored {score} but {flipped_score} with colors flipped"
+            ));
+        }
+        for board in [&board, &flipped] {
+            let traced = evaluator.trace(board).for_side_to_move(board);
+            let evaluated = evaluator.evaluate(board);
+            if traced != evaluated {
+                failures.push(format!(
+                    "{context}: trace sums to {traced} but the evaluation is {evaluated}"
+                ));
+            }
+        }
+    }
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(failures.join("\n"))
+    }
+}
+
+fn term_score(evaluator: &impl Evaluator, board: &Board, term: &str) -> i32 {
+    evaluator
+        .trace(board)
+        .terms
+        .iter()
+        .filter(|traced| traced.name == term)
+        .map(|traced| traced.total)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::eval::{EvalTerm, EvalTrace, MaterialEvaluator};
+
+    const QUEEN_UP: GoldenPosition =
+        GoldenPosition::white_better("4k3/8/8/8/8/8/8/3QK3 w - - 0 1", "White has a queen");
+
+    // Scores material for White only, so its sign depends on the colors.
+    struct OneSided;
+
+    impl Evaluator for OneSided {
+        fn evaluate(&self, board: &Board) -> i32 {
+            self.trace(board).for_side_to_move(board)
+        }
+
+        fn trace(&self, board: &Board) -> EvalTrace {
+            let white = crate::engine::eval::material(board, crate::engine::types::Color::White);
+            EvalTrace {
+                terms: vec![EvalTerm::from_sides("Material", white, 0)],
+            }
+        }
+    }
+
+    // Traces material but evaluates it twice.
+    struct Doubled;
+
+    impl Evaluator for Doubled {
+        fn evaluate(&self, board: &Board) -> i32 {
+            2 * MaterialEvaluator.evaluate(board)
+        }
+
+        fn trace(&self, board: &Board) -> EvalTrace {
+            MaterialEvaluator.trace(board)
+        }
+    }
+
+    #[test]
+    fn accepts_a_correct_term() {
+        assert_eq!(
+            check_term(&MaterialEvaluator, "Material", &[QUEEN_UP]),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn reports_wrong_signs_asymmetry_and_double_counting() {
+        let behind = GoldenPosition::black_better(QUEEN_UP.fen, "deliberately wrong");
+        let err = check_term(&MaterialEvaluator, "Material", &[behind]).unwrap_err();
+        assert!(err.contains("expected"), "{err}");
+
+        let err = check_term(&OneSided, "Material", &[QUEEN_UP]).unwrap_err();
+        assert!(err.contains("with colors flipped"), "{err}");
+
+        let err = check_term(&Doubled, "Material", &[QUEEN_UP]).unwrap_err();
+        assert!(err.contains("trace sums to"), "{err}");
+    }
+}
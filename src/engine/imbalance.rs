@@ -0,0 +1,183 @@
+//! Material imbalance terms in the style of classical engines: piece values
+//! that depend on the rest of the material. Additive material misjudges
+//! trades like two minors for rook and pawn; these terms correct for the
+//! bishop pair, knights and rooks gaining or losing value with the pawn
+//! count, and redundancy between major pieces.
+
+use crate::engine::board::Board;
+use crate::engine::eval::{EvalTerm, EvalTrace, Evaluator, MaterialEvaluator};
+use crate::engine::search::params::{parse_key_values, parse_value};
+use crate::engine::types::{Color, PieceKind};
+use std::fs;
+
+/// Imbalance weights in centipawns; loadable from `key = value` lines like
+/// `SearchParams`, so they can be tuned without rebuilding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImbalanceParams {
+    /// Bonus for owning two or more bishops.
+    pub bishop_pair: i32,
+    /// Added to each knight per own pawn above five; knights like closed,
+    /// pawn-rich positions.
+    pub knight_per_pawn: i32,
+    /// Added to each rook per own pawn above five; rooks want open files.
+    pub rook_per_pawn: i32,
+    /// Added once for a second rook, which duplicates the first's work.
+    pub rook_pair: i32,
+    /// Added per rook alongside a queen.
+    pub queen_rook: i32,
+}
+
+impl Default for ImbalanceParams {
+    fn default() -> Self {
+        Self {
+            bishop_pair: 50,
+            knight_per_pawn: 6,
+            rook_per_pawn: -12,
+            rook_pair: -16,
+            queen_rook: -8,
+        }
+    }
+}
+
+impl ImbalanceParams {
+    /// Parses `key = value` lines; `#` starts a comment and keys not given
+    /// keep their defaults.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut params = Self::default();
+        parse_key_values(text, |key, value| params.set(key, value))?;
+        Ok(params)
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let text =
+            fs::read_to_string(path).map_err(|err| format!("failed to read {path}: {err}"))?;
+        Self::parse(&text).map_err(|err| format!("{path}: {err}"))
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "bishop_pair" => self.bishop_pair = parse_value(key, value)?,
+            "knight_per_pawn" => self.knight_per_pawn = parse_value(key, value)?,
+            "rook_per_pawn" => self.rook_per_pawn = parse_value(key, value)?,
+            "rook_pair" => self.rook_pair = parse_value(key, value)?,
+            "queen_rook" => self.queen_rook = parse_value(key, value)?,
+            _ => return Err(format!("unknown parameter: {key}")),
+        }
+        Ok(())
+    }
+
+    /// The imbalance score of `color`'s pieces alone.
+    pub fn side_score(&self, board: &Board, color: Color) -> i32 {
+        let count = |kind| {
+            board
+                .squares
+                .iter()
+                .flatten()
+                .filter(|piece| piece.color == color && piece.kind == kind)
+                .count() as i32
+        };
+        let pawns_above_five = count(PieceKind::Pawn) - 5;
+        let knights = count(PieceKind::Knight);
+        let rooks = count(PieceKind::Rook);
+
+        let mut score = knights * self.knight_per_pawn * pawns_above_five
+            + rooks * self.rook_per_pawn * pawns_above_five;
+        if count(PieceKind::Bishop) >= 2 {
+            score += self.bishop_pair;
+        }
+        if rooks >= 2 {
+            score += self.rook_pair;
+        }
+        if count(PieceKind::Queen) >= 1 {
+            score += rooks * self.queen_rook;
+        }
+        score
+    }
+
+    /// The imbalance score for the side to move.
+    pub fn score(&self, board: &Board) -> i32 {
+        let (us, them) = match board.side_to_move {
+            Color::White => (Color::White, Color::Black),
+            Color::Black => (Color::Black, Color::White),
+        };
+        self.side_score(board, us) - self.side_score(board, them)
+    }
+}
+
+/// Material plus the imbalance terms.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ImbalanceEvaluator {
+    pub params: ImbalanceParams,
+}
+
+impl Evaluator for ImbalanceEvaluator {
+    fn evaluate(&self, board: &Board) -> i32 {
+        MaterialEvaluator.evaluate(board) + self.params.score(board)
+    }
+
+    fn is_symmetric(&self) -> bool {
+        true
+    }
+
+    fn trace(&self, board: &Board) -> EvalTrace {
+        let mut trace = MaterialEvaluator.trace(board);
+        trace.terms.push(EvalTerm::from_sides(
+            "Imbalance",
+            self.params.side_score(board, Color::White),
+            self.params.side_score(board, Color::Black),
+        ));
+        trace
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board_from(fen: &str) -> Board {
+        let mut board = Board::new();
+        board.set_fen(fen).expect("fen");
+        board
+    }
+
+    #[test]
+    fn two_minors_beat_rook_and_pawn_with_the_bishop_pair() {
+        // White traded rook and pawn for bishop and knight, keeping the
+        // bishop pair against a rook on a pawn-heavy board.
+        let board = board_from("1r2kb2/pppppppp/8/8/8/8/PPPPPPP1/2B1KBN1 w - - 0 1");
+        let material = MaterialEvaluator.evaluate(&board);
+        assert_eq!(material, 50);
+        assert_eq!(ImbalanceEvaluator::default().evaluate(&board), 50 + 62 + 36);
+
+        let start = board_from("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(ImbalanceEvaluator::default().evaluate(&start), 0);
+
+        let trace = ImbalanceEvaluator::default().trace(&board);
+        let names: Vec<&str> = trace.terms.iter().map(|term| term.name).collect();
+        assert_eq!(names, ["Material", "Imbalance"]);
+        assert_eq!(trace.for_side_to_move(&board), 50 + 62 + 36);
+    }
+
+    #[test]
+    fn knights_and_rooks_follow_the_pawn_count() {
+        let params = ImbalanceParams::default();
+        let board = board_from("4k3/8/8/8/8/8/PPPPPPPP/1N2K2R w - - 0 1");
+        // Three pawns above five: the knight gains 18, the rook loses 36.
+        assert_eq!(params.side_score(&board, Color::White), 18 - 36);
+        assert_eq!(params.side_score(&board, Color::Black), 0);
+        assert_eq!(params.score(&board), params.score(&board.flipped()));
+    }
+
+    #[test]
+    fn parses_tuned_weights() {
+        let params =
+            ImbalanceParams::parse("bishop_pair = 30\nrook_pair = 0 # off").expect("parse");
+        assert_eq!(params.bishop_pair, 30);
+        assert_eq!(params.rook_pair, 0);
+        assert_eq!(params.queen_rook, ImbalanceParams::default().queen_rook);
+        assert_eq!(
+            ImbalanceParams::parse("knight_value = 3").unwrap_err(),
+            "line 1: unknown parameter: knight_value"
+        );
+    }
+}
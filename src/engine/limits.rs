@@ -0,0 +1,94 @@
+//! What ends a search: a depth, a node count, a fixed time, the clocks, or
+//! only `stop`.
+
+use crate::engine::clock::{Clocks, TimeBudget};
+use crate::engine::types::Color;
+use std::time::Duration;
+
+/// Deepest iteration a search runs when no depth is given.
+pub const MAX_DEPTH: u32 = 64;
+
+/// The limits of one `Engine::search`, as in UCI `go`. The search ends at
+/// whichever it reaches first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+    /// Deepest iteration; `MAX_DEPTH` when not given.
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    /// A fixed time for the move, which takes precedence over the clocks.
+    pub movetime: Option<Duration>,
+    /// The budget comes from the side to move's remaining time.
+    pub clocks: Clocks,
+    pub movestogo: Option<u32>,
+    /// Ignore the time limits and search until stopped (or `depth`).
+    pub infinite: bool,
+}
+
+impl SearchLimits {
+    /// Only a depth limit.
+    pub fn depth(depth: u32) -> Self {
+        Self {
+            depth: Some(depth),
+            ..Self::default()
+        }
+    }
+
+    /// The time `side` may spend on its move, if any.
+    pub fn time_budget(&self, side: Color) -> Option<TimeBudget> {
+        match self.movetime {
+            _ if self.infinite => None,
+            Some(movetime) => Some(TimeBudget {
+                soft: movetime,
+                hard: movetime,
+                min: Duration::ZERO,
+            }),
+            None => self.clocks.budget(side, self.movestogo),
+        }
+    }
+
+    /// Whether anything but the depth ends a search by `side`, so leaving
+    /// the depth out does not make it run to `MAX_DEPTH` unchecked.
+    pub fn is_bounded(&self, side: Color) -> bool {
+        self.infinite || self.nodes.is_some() || self.time_budget(side).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn movetime_takes_precedence_over_the_clocks() {
+        let clocks = Clocks {
+            white_ms: Some(60_000),
+            ..Clocks::default()
+        };
+        let limits = SearchLimits {
+            clocks,
+            ..SearchLimits::default()
+        };
+        assert_eq!(
+            limits.time_budget(Color::White),
+            clocks.budget(Color::White, None)
+        );
+        assert!(limits.is_bounded(Color::White));
+        // Black has no clock, so only a depth would stop its search.
+        assert!(!limits.is_bounded(Color::Black));
+
+        let fixed = SearchLimits {
+            movetime: Some(Duration::from_millis(250)),
+            ..limits
+        };
+        let budget = fixed.time_budget(Color::Black).expect("budget");
+        assert_eq!(budget.soft, Duration::from_millis(250));
+        assert_eq!(budget.hard, budget.soft);
+
+        let infinite = SearchLimits {
+            infinite: true,
+            ..fixed
+        };
+        assert_eq!(infinite.time_budget(Color::White), None);
+        assert!(infinite.is_bounded(Color::White));
+        assert!(!SearchLimits::depth(4).is_bounded(Color::White));
+    }
+}
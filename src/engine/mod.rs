@@ -1,35 +1,138 @@
+pub mod annotate;
 pub mod apply_move;
+pub mod attacks;
+pub mod audit;
 pub mod board;
+pub mod book;
+pub mod cache;
 pub mod castling;
+pub mod checks;
+pub mod classify;
+pub mod clock;
+pub mod composite;
+pub mod cpu;
+pub mod datagen;
+pub mod elo;
+pub mod epd;
 pub mod eval;
 pub mod fen;
+pub mod game;
+pub mod golden;
+pub mod imbalance;
+pub mod limits;
 pub mod movegen;
+pub mod notation;
+pub mod pgn;
+pub mod pool;
 pub mod search;
+pub mod see;
+pub mod skill;
+pub mod tablebase;
+pub mod trace;
 pub mod types;
+pub mod wdl;
 pub mod zobrist;
 
 use board::Board;
+use book::Repertoire;
+use clock::{IterationTimes, Pacing, TimeBudget};
 use eval::Evaluator;
+use limits::{SearchLimits, MAX_DEPTH};
 use movegen::game_status;
 use rand::rngs::SmallRng;
 use rand::{Rng, SeedableRng};
-use search::{SearchAlgorithm, SearchResult};
-use types::GameStatus;
+use search::easy_move::{detect_easy_move, easy_budget_ms, EASY_MOVE_DEPTH};
+use search::threat::extended_budget_ms;
+use search::{
+    HashUsage, RootBoundObserver, RootMoveObserver, SearchAlgorithm, SearchResult, SearchStats,
+    StopSignal,
+};
+use skill::{NoisyEvaluator, Skill};
+use std::time::{Duration, Instant};
+use tablebase::Tablebases;
+use trace::span;
+use types::{GameStatus, Move};
+
+/// Progress report for one completed iteration of iterative deepening.
+#[derive(Debug, Clone)]
+pub struct SearchInfo {
+    pub depth: u32,
+    /// Deepest ply reached in this iteration, quiescence included.
+    pub seldepth: u32,
+    pub score: i32,
+    /// Nodes searched so far, over all iterations.
+    pub nodes: u64,
+    pub time_ms: u128,
+    pub best_moves: Vec<Move>,
+    /// Transposition table fill rate in permille, if the search has one.
+    pub hashfull: Option<u32>,
+    /// The search's diagnostic counters for this iteration, if it keeps them.
+    pub stats: Option<SearchStats>,
+    /// The expected line, starting with the first best move.
+    pub pv: Vec<Move>,
+    /// Whether this iteration's best move differs from the previous one.
+    pub best_move_changed: bool,
+    /// How many times more nodes each iteration searches than the one
+    /// before, once there are two to compare.
+    pub ebf: Option<f64>,
+}
+
+/// An iteration whose best move differed from the previous iteration's.
+/// The log shows how settled the choice is: late changes mean it is not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BestMoveChange {
+    pub depth: u32,
+    pub time_ms: u128,
+    pub best_move: Move,
+    pub score: i32,
+}
 
 pub struct Engine<E: Evaluator, S: SearchAlgorithm> {
     evaluator: E,
     search: S,
     board: Board,
     rng: Option<SmallRng>,
+    repertoire: Option<Repertoire>,
+    analysing: bool,
+    stop: StopSignal,
+    node_limit: Option<u64>,
+    time_budget: Option<TimeBudget>,
+    pacing: Pacing,
+    nodes_time: Option<u64>,
+    best_move_changes: Vec<BestMoveChange>,
+    search_moves: Option<Vec<Move>>,
+    root_move_observer: Option<RootMoveObserver>,
+    root_bound_observer: Option<RootBoundObserver>,
+    skill: Option<Skill>,
+    easy_move: bool,
+    root_hint: Option<Vec<Move>>,
+    tablebases: Option<Tablebases>,
 }
 
 impl<E: Evaluator, S: SearchAlgorithm> Engine<E, S> {
-    pub fn with_components(evaluator: E, search: S) -> Self {
+    pub fn with_components(evaluator: E, mut search: S) -> Self {
+        let stop = StopSignal::new();
+        search.set_stop_signal(stop.clone());
         Self {
             evaluator,
             search,
             board: Board::new(),
             rng: None,
+            repertoire: None,
+            analysing: false,
+            stop,
+            node_limit: None,
+            time_budget: None,
+            pacing: Pacing::default(),
+            nodes_time: None,
+            best_move_changes: Vec::new(),
+            search_moves: None,
+            root_move_observer: None,
+            root_bound_observer: None,
+            skill: None,
+            easy_move: true,
+            root_hint: None,
+            tablebases: None,
         }
     }
 
@@ -37,6 +140,71 @@ impl<E: Evaluator, S: SearchAlgorithm> Engine<E, S> {
         self.rng = Some(SmallRng::seed_from_u64(seed));
     }
 
+    /// Restricts the root to repertoire moves while the current position is in book.
+    pub fn set_repertoire(&mut self, repertoire: Option<Repertoire>) {
+        self.repertoire = repertoire;
+    }
+
+    pub fn repertoire(&self) -> Option<&Repertoire> {
+        self.repertoire.as_ref()
+    }
+
+    /// Analysis searches every move of book positions instead of only the
+    /// repertoire's (UCI `UCI_AnalyseMode`); `go infinite` does for its own
+    /// search.
+    pub fn set_analysing(&mut self, analysing: bool) {
+        self.analysing = analysing;
+    }
+
+    /// The endgame tablebases found under `SyzygyPath`, if any.
+    pub fn set_tablebases(&mut self, tablebases: Option<Tablebases>) {
+        self.tablebases = tablebases;
+    }
+
+    pub fn tablebases(&self) -> Option<&Tablebases> {
+        self.tablebases.as_ref()
+    }
+
+    /// Restricts the root of following searches to `moves` (UCI
+    /// `searchmoves`); it takes precedence over the repertoire. Moves that
+    /// are not legal are ignored, and without any legal one the whole
+    /// position is searched.
+    pub fn set_search_moves(&mut self, moves: Option<Vec<Move>>) {
+        self.search_moves = moves;
+    }
+
+    /// The moves the root is restricted to: the legal search moves if set,
+    /// otherwise the book moves unless analysing.
+    pub(crate) fn root_moves(&mut self) -> Option<Vec<Move>> {
+        if let Some(search_moves) = &self.search_moves {
+            let legal = movegen::generate_legal(&mut self.board);
+            let moves: Vec<_> = search_moves
+                .iter()
+                .copied()
+                .filter(|mv| legal.contains(mv))
+                .collect();
+            if !moves.is_empty() {
+                return Some(moves);
+            }
+        }
+        if self.analysing {
+            return None;
+        }
+        self.book_root_moves()
+    }
+
+    /// Legal repertoire moves for the current position, or `None` when out of book.
+    pub(crate) fn book_root_moves(&mut self) -> Option<Vec<crate::engine::types::Move>> {
+        let book = self.repertoire.as_ref()?.book_moves(&self.board)?.to_vec();
+        let legal = movegen::generate_legal(&mut self.board);
+        let moves: Vec<_> = book.into_iter().filter(|mv| legal.contains(mv)).collect();
+        if moves.is_empty() {
+            None
+        } else {
+            Some(moves)
+        }
+    }
+
     pub fn set_position_startpos(&mut self) {
         self.board.set_startpos();
     }
@@ -45,12 +213,87 @@ impl<E: Evaluator, S: SearchAlgorithm> Engine<E, S> {
         self.board.set_fen(fen)
     }
 
+    /// Copies `board` along with its move history, so repetitions and the
+    /// fifty-move count carry over into the search.
+    pub fn set_position_board(&mut self, board: &Board) {
+        self.board = board.clone();
+    }
+
     pub fn apply_move_list(&mut self, _moves: &[String]) {
-        if let Err(err) = self.board.apply_uci_move_list(_moves) {
+        if let Err(err) = self.try_apply_move_list(_moves) {
             eprintln!("invalid move list: {err}");
         }
     }
 
+    /// Like `apply_move_list`, but checks legality and reports the first
+    /// illegal move and its 0-based index in `moves` to the caller. The
+    /// board is left after the moves before it.
+    pub fn try_apply_move_list(&mut self, moves: &[String]) -> Result<(), String> {
+        for (index, text) in moves.iter().enumerate() {
+            let mv = types::move_from_uci(text)
+                .filter(|mv| movegen::generate_legal(&mut self.board).contains(mv))
+                .ok_or_else(|| format!("illegal move {text} at index {index}"))?;
+            self.board.apply_move(mv)?;
+        }
+        Ok(())
+    }
+
+    /// How many times the current position has occurred in the game so far,
+    /// counting this one, for callers that handle draw claims themselves.
+    pub fn repetition_count(&self) -> usize {
+        self.board.repetition_count()
+    }
+
+    /// Hashes of the earlier positions since the last `position` command.
+    pub fn hash_history(&self) -> &[u64] {
+        self.board.hash_history()
+    }
+
+    /// Position editing for analysis sessions; see `Board::set_piece`.
+    pub fn set_piece(&mut self, square: types::Square, piece: Option<types::Piece>) {
+        self.board.set_piece(square, piece);
+    }
+
+    pub fn set_side_to_move(&mut self, color: types::Color) {
+        self.board.set_side_to_move(color);
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn side_to_move(&self) -> types::Color {
+        self.board.side_to_move
+    }
+
+    pub fn set_castling_rights(&mut self, rights: u8) {
+        self.board.set_castling_rights(rights);
+    }
+
+    pub fn set_en_passant(&mut self, square: Option<types::Square>) {
+        self.board.set_en_passant(square);
+    }
+
+    /// Replaces the position with its color-flipped mirror, to analyse it
+    /// from the other side.
+    pub fn flip_position(&mut self) {
+        self.board = self.board.flipped();
+    }
+
+    /// Material of both sides on the board, for `wdl::Wdl::from_score`.
+    pub fn material(&self) -> i32 {
+        wdl::total_material(&self.board)
+    }
+
+    /// The static evaluation of the current position, term by term.
+    pub fn eval_trace(&self) -> eval::EvalTrace {
+        self.evaluator.trace(&self.board)
+    }
+
+    pub fn validate_position(&self) -> Result<(), String> {
+        self.board.validate()
+    }
+
     pub fn search_depth(&mut self, _depth: u32) -> String {
         let (best_move, _) = self.search_depth_with_stats(_depth);
         best_move
@@ -73,19 +316,112 @@ impl<E: Evaluator, S: SearchAlgorithm> Engine<E, S> {
         (total_nodes, per_depth)
     }
 
+    /// Iterative deepening within `limits`, reporting each completed depth
+    /// to `on_info`; returns the move chosen and the nodes searched. The
+    /// limits replace those set with `set_node_limit` and `set_time_budget`
+    /// for this search only.
+    pub fn search(
+        &mut self,
+        limits: &SearchLimits,
+        on_info: impl FnMut(&SearchInfo),
+    ) -> (String, u64) {
+        let budget = limits.time_budget(self.side_to_move());
+        let node_limit = std::mem::replace(&mut self.node_limit, limits.nodes);
+        let time_budget = std::mem::replace(&mut self.time_budget, budget);
+        let analysing = self.analysing;
+        self.analysing |= limits.infinite;
+        let searched = self.search_iterative_with_info(limits.depth.unwrap_or(MAX_DEPTH), on_info);
+        self.node_limit = node_limit;
+        self.time_budget = time_budget;
+        self.analysing = analysing;
+        searched
+    }
+
+    /// Iterative deepening that reports each completed depth to `on_info`.
+    pub fn search_iterative_with_info(
+        &mut self,
+        depth: u32,
+        mut on_info: impl FnMut(&SearchInfo),
+    ) -> (String, u64) {
+        let (last_result, total_nodes, _) =
+            self.search_iterative_depth_with_info(depth, &mut on_info);
+        (self.pick_best_move(&last_result.best_moves), total_nodes)
+    }
+
     pub fn search_depth_result(
         &mut self,
         depth: u32,
         preferred_root: Option<&[crate::engine::types::Move]>,
     ) -> SearchResult {
-        self.search.search_with_root_ordering(
+        let root_moves = self.root_moves();
+        self.search.search_with_root_moves(
             &mut self.board,
             &self.evaluator,
             depth,
             preferred_root,
+            root_moves.as_deref(),
         )
     }
 
+    /// Looks for a forced mate in at most `moves` moves of the side to move,
+    /// until one is proven or the search is stopped.
+    pub fn search_mate(&mut self, moves: u32) -> search::mate::MateSearch {
+        let mate = search::mate::solve_mate_until(&mut self.board, moves, u64::MAX, &self.stop);
+        self.stop.end_search();
+        mate
+    }
+
+    /// Whether timed searches may answer early when one root move clearly
+    /// beats the rest (on by default). Turn it off for analysis, where the
+    /// full budget should be spent.
+    pub fn set_easy_move(&mut self, enabled: bool) {
+        self.easy_move = enabled;
+    }
+
+    /// A move to search first at the root until an iteration has found its
+    /// own best move, such as the best move of an earlier, shallower
+    /// analysis of the position. It stays set until replaced.
+    pub fn set_root_hint(&mut self, hint: Option<Move>) {
+        self.root_hint = hint.map(|mv| vec![mv]);
+    }
+
+    /// Whether the opponent threatens something serious in the current
+    /// position, given the best score found at `depth`; time management uses
+    /// it to extend the budget for the move.
+    pub fn root_threat(&mut self, depth: u32, best_score: i32) -> Option<search::Threat> {
+        search::detect_threat(
+            &mut self.search,
+            &mut self.board,
+            &self.evaluator,
+            depth,
+            best_score,
+        )
+    }
+
+    // Follows the search's remembered best moves from the first best move of
+    // `result`, for at most `max_len` plies.
+    fn principal_variation(&mut self, result: &SearchResult, max_len: usize) -> Vec<Move> {
+        let first = result.best_moves.first().copied();
+        follow_hash_moves(&self.search, &mut self.board, first, max_len)
+    }
+
+    /// The line the transposition table holds from `board`, any position
+    /// reached in earlier searches, for at most `max_len` plies; e.g. for a
+    /// GUI showing the line of a position the user clicks into. Nothing is
+    /// searched, so the line is empty for positions the table does not know.
+    pub fn pv_for(&self, board: &Board, max_len: usize) -> Vec<Move> {
+        let mut board = board.clone();
+        let first = self.search.hash_move(&board);
+        follow_hash_moves(&self.search, &mut board, first, max_len)
+    }
+
+    /// Like `pv_for`, for the position of `fen`.
+    pub fn pv_for_fen(&self, fen: &str, max_len: usize) -> Result<Vec<Move>, String> {
+        let mut board = Board::new();
+        board.set_fen(fen)?;
+        Ok(self.pv_for(&board, max_len))
+    }
+
     fn search_iterative_depth(&mut self, depth: u32) -> (SearchResult, u64) {
         let (last_result, total_nodes, _) = self.search_iterative_depth_with_results(depth);
         (last_result, total_nodes)
@@ -95,75 +431,516 @@ impl<E: Evaluator, S: SearchAlgorithm> Engine<E, S> {
         &mut self,
         depth: u32,
     ) -> (SearchResult, u64, Vec<SearchResult>) {
+        self.search_iterative_depth_with_info(depth, &mut |_| {})
+    }
+
+    fn search_iterative_depth_with_info(
+        &mut self,
+        depth: u32,
+        on_info: &mut dyn FnMut(&SearchInfo),
+    ) -> (SearchResult, u64, Vec<SearchResult>) {
+        let started = Instant::now();
         let mut total_nodes = 0u64;
         let mut last_result = None;
         let mut preferred_root: Option<Vec<crate::engine::types::Move>> = None;
         let mut per_depth = Vec::new();
+        let root_moves = self.root_moves();
+        self.best_move_changes.clear();
+        let depth = self
+            .skill
+            .map_or(depth, |skill| depth.min(skill.max_depth()));
+        let node_limit = match (self.node_limit, self.skill) {
+            (limit, None) => limit,
+            (Some(limit), Some(skill)) => Some(limit.min(skill.max_nodes())),
+            (None, Some(skill)) => Some(skill.max_nodes()),
+        };
+        let draw = if self.time_budget.is_some() && self.pacing.variance_percent > 0 {
+            self.pacing_draw()
+        } else {
+            0.0
+        };
+        let time_budget = self
+            .time_budget
+            .map(|budget| self.pacing.pace(budget, draw));
+        // With `nodes_time` the time budget is spent in nodes rather than
+        // wall-clock time, so timed searches repeat exactly.
+        let nodes_time = self.nodes_time;
+        let budget_nodes = nodes_time
+            .zip(time_budget)
+            .map(|(per_ms, budget)| (budget.hard.as_millis() as u64).saturating_mul(per_ms));
+        let node_limit = [node_limit, budget_nodes].into_iter().flatten().min();
+        let elapsed = |total_nodes: u64| match nodes_time {
+            Some(per_ms) => Duration::from_millis(total_nodes / per_ms),
+            None => started.elapsed(),
+        };
+        let noise = self.skill.map_or(0, |skill| skill.eval_noise());
+        let noise_seed = if noise > 0 { self.noise_seed() } else { 0 };
 
         if depth == 0 {
-            let result = self.search.search_with_root_ordering(
+            let result = self.search.search_with_root_moves(
                 &mut self.board,
-                &self.evaluator,
+                &NoisyEvaluator::new(&self.evaluator, noise, noise_seed),
                 0,
                 preferred_root.as_deref(),
+                root_moves.as_deref(),
             );
             total_nodes = total_nodes.saturating_add(result.nodes);
+            let changed = self.record_best_move(0, &result, started);
+            let pv = self.principal_variation(&result, 1);
+            on_info(&search_info(
+                0,
+                &result,
+                total_nodes,
+                started,
+                &self.search,
+                pv,
+                changed,
+                None,
+            ));
             last_result = Some(result);
             if let Some(snapshot) = last_result.clone() {
                 per_depth.push(snapshot);
             }
         } else {
+            let deadline = self
+                .time_budget
+                .filter(|_| nodes_time.is_none())
+                .map(|budget| started + budget.hard);
+            let mut soft_budget = time_budget.map(|budget| budget.soft);
+            let mut threat_checked = false;
+            let mut easy = false;
+            let mut iterations = IterationTimes::default();
+            self.search.set_deadline(deadline);
             for current_depth in 1..=depth {
-                let result = self.search.search_with_root_ordering(
+                // Past the soft limit a new iteration is unlikely to finish.
+                if current_depth > 1 && soft_budget.is_some_and(|soft| elapsed(total_nodes) >= soft)
+                {
+                    break;
+                }
+                // Nor is one predicted to run past the hard limit, which
+                // would only be aborted, unless the minimum think time has
+                // not passed yet.
+                if let Some(budget) = time_budget
+                    && elapsed(total_nodes) >= budget.min
+                    && iterations
+                        .predict_next()
+                        .is_some_and(|next| elapsed(total_nodes) + next > budget.hard)
+                {
+                    break;
+                }
+                span!(DEBUG, "iteration", depth = current_depth);
+                let iteration_started = elapsed(total_nodes);
+                let remaining = node_limit.map(|limit| limit.saturating_sub(total_nodes));
+                self.search.set_node_limit(remaining);
+                // Only the iteration itself reports root moves, not the
+                // threat probe searched below.
+                self.search
+                    .set_root_move_observer(self.root_move_observer.clone());
+                self.search
+                    .set_root_bound_observer(self.root_bound_observer.clone());
+                let result = self.search.search_with_root_moves(
                     &mut self.board,
-                    &self.evaluator,
+                    &NoisyEvaluator::new(&self.evaluator, noise, noise_seed),
                     current_depth,
-                    preferred_root.as_deref(),
+                    preferred_root.as_deref().or(self.root_hint.as_deref()),
+                    root_moves.as_deref(),
                 );
+                self.search.set_root_move_observer(None);
+                self.search.set_root_bound_observer(None);
                 total_nodes = total_nodes.saturating_add(result.nodes);
+                iterations.record(result.nodes, elapsed(total_nodes) - iteration_started);
+                if self.stop.is_stopped() {
+                    if trust_partial_result(&result, preferred_root.as_deref()) {
+                        per_depth.push(result.clone());
+                        last_result = Some(result);
+                    }
+                    break;
+                }
+                let changed = self.record_best_move(current_depth, &result, started);
+                let pv = self.principal_variation(&result, current_depth as usize);
+                on_info(&search_info(
+                    current_depth,
+                    &result,
+                    total_nodes,
+                    started,
+                    &self.search,
+                    pv,
+                    changed,
+                    iterations.ebf(),
+                ));
+                // A best move that has held from the start and beats every
+                // alternative by a wide margin is played almost at once.
+                if let Some(budget) = time_budget
+                    && budget.soft < budget.hard
+                    && self.easy_move
+                    && current_depth == EASY_MOVE_DEPTH
+                    && self.best_move_changes.len() == 1
+                    && let [best] = result.best_moves[..]
+                    && detect_easy_move(
+                        &mut self.search,
+                        &mut self.board,
+                        &self.evaluator,
+                        current_depth,
+                        best,
+                        result.score,
+                        root_moves.as_deref(),
+                    )
+                {
+                    easy = true;
+                    // The cut applies to the planned time; pacing then
+                    // varies it and keeps the minimum think time.
+                    let base = self.time_budget.unwrap_or(budget);
+                    let soft = easy_budget_ms(base.soft.as_millis() as u64);
+                    let cut = TimeBudget {
+                        soft: Duration::from_millis(soft),
+                        ..base
+                    };
+                    soft_budget = Some(self.pacing.pace(cut, draw).soft);
+                }
+                // Once per move, spend extra time when the opponent threatens
+                // something the chosen move has to answer.
+                if let Some(budget) = time_budget
+                    && budget.soft < budget.hard
+                    && current_depth >= 2
+                    && !easy
+                    && !threat_checked
+                {
+                    threat_checked = true;
+                    if self.root_threat(current_depth, result.score).is_some() {
+                        let extended = extended_budget_ms(budget.soft.as_millis() as u64);
+                        soft_budget = Some(Duration::from_millis(extended).min(budget.hard));
+                    }
+                }
                 preferred_root = Some(result.best_moves.clone());
                 last_result = Some(result);
                 if let Some(snapshot) = last_result.clone() {
                     per_depth.push(snapshot);
                 }
             }
+            self.search.set_node_limit(None);
+            self.search.set_deadline(None);
+            // A stop raised by a budget should not abort the next search.
+            let out_of_nodes = node_limit.is_some_and(|limit| total_nodes > limit);
+            let out_of_time = deadline.is_some_and(|deadline| Instant::now() >= deadline);
+            if out_of_nodes || out_of_time {
+                self.stop.reset();
+            }
         }
+        self.stop.end_search();
 
-        (
-            last_result.unwrap_or(SearchResult {
-                best_moves: Vec::new(),
+        // Stopped before any depth finished: still answer with a legal move.
+        let last_result = last_result.unwrap_or_else(|| {
+            let fallback = root_moves.unwrap_or_else(|| movegen::generate_legal(&mut self.board));
+            SearchResult {
+                best_moves: fallback.into_iter().take(1).collect(),
                 score: 0,
                 nodes: 0,
-            }),
-            total_nodes,
-            per_depth,
-        )
+                seldepth: 0,
+            }
+        });
+
+        (last_result, total_nodes, per_depth)
+    }
+
+    /// Best-move changes of the last iterative search, starting with the
+    /// first completed iteration.
+    pub fn best_move_changes(&self) -> &[BestMoveChange] {
+        &self.best_move_changes
+    }
+
+    // Logs the iteration if its best move is new; returns whether it was.
+    fn record_best_move(&mut self, depth: u32, result: &SearchResult, started: Instant) -> bool {
+        let Some(&best_move) = result.best_moves.first() else {
+            return false;
+        };
+        if self
+            .best_move_changes
+            .last()
+            .is_some_and(|change| change.best_move == best_move)
+        {
+            return false;
+        }
+        self.best_move_changes.push(BestMoveChange {
+            depth,
+            time_ms: started.elapsed().as_millis(),
+            best_move,
+            score: result.score,
+        });
+        true
+    }
+
+    /// The legal moves of the current position with their SAN and static
+    /// exchange evaluation, flagging those that lose material at once.
+    pub fn legal_moves(&mut self) -> Vec<annotate::AnnotatedMove> {
+        annotate::legal_moves(&mut self.board)
+    }
+
+    /// A legal repertoire move for the current position, chosen at random,
+    /// or `None` when out of book. Without a repertoire the moves come from
+    /// `Repertoire::builtin`.
+    pub fn book_move(&mut self) -> Option<Move> {
+        let moves = match self.repertoire {
+            Some(_) => self.book_root_moves()?,
+            None => {
+                let book = Repertoire::builtin().book_moves(&self.board)?;
+                let legal = movegen::generate_legal(&mut self.board);
+                book.iter()
+                    .copied()
+                    .filter(|mv| legal.contains(mv))
+                    .collect()
+            }
+        };
+        if moves.is_empty() {
+            return None;
+        }
+        Some(moves[self.random_index(moves.len())])
     }
 
     pub(crate) fn pick_best_move(&mut self, best_moves: &[crate::engine::types::Move]) -> String {
-        let mv = if best_moves.is_empty() {
+        let mut mv = if best_moves.is_empty() {
             None
-        } else if let Some(rng) = &mut self.rng {
-            let index = rng.gen_range(0..best_moves.len());
-            Some(best_moves[index])
         } else {
-            let mut rng = rand::thread_rng();
-            let index = rng.gen_range(0..best_moves.len());
-            Some(best_moves[index])
+            Some(best_moves[self.random_index(best_moves.len())])
         };
+        // A limited skill sometimes overlooks the best move.
+        if let (Some(chosen), Some(skill)) = (mv, self.skill)
+            && (self.random_index(1000) as u32) < skill.blunder_chance()
+        {
+            let pick = self.random_index(usize::MAX);
+            if let Some(blunder) = skill::blunder(
+                &mut self.board,
+                &self.evaluator,
+                chosen,
+                skill.blunder_margin(),
+                |count| pick % count,
+            ) {
+                mv = Some(blunder);
+            }
+        }
         mv.and_then(crate::engine::types::uci_from_move)
             .unwrap_or_else(|| "0000".to_string())
     }
 
+    // A random index below `len`, from the seeded RNG when there is one.
+    fn random_index(&mut self, len: usize) -> usize {
+        match &mut self.rng {
+            Some(rng) => rng.gen_range(0..len),
+            None => rand::thread_rng().gen_range(0..len),
+        }
+    }
+
     pub fn game_status(&mut self) -> GameStatus {
         game_status(&mut self.board)
     }
 
+    /// Limits the playing strength (`None` for full strength): iterative
+    /// searches are capped at the skill's depth and nodes and see noisy
+    /// evaluations.
+    pub fn set_skill(&mut self, skill: Option<Skill>) {
+        self.skill = skill;
+    }
+
+    pub fn skill(&self) -> Option<Skill> {
+        self.skill
+    }
+
+    // Where in its variance a paced move's time falls, from -1 to 1.
+    fn pacing_draw(&mut self) -> f64 {
+        match &mut self.rng {
+            Some(rng) => rng.gen_range(-1.0..=1.0),
+            None => rand::thread_rng().gen_range(-1.0..=1.0),
+        }
+    }
+
+    // A fresh seed for each search's evaluation noise, from the seeded RNG
+    // when there is one so seeded games replay.
+    fn noise_seed(&mut self) -> u64 {
+        match &mut self.rng {
+            Some(rng) => rng.r#gen(),
+            None => rand::thread_rng().r#gen(),
+        }
+    }
+
+    /// Caps the nodes of each iterative-deepening search (`None` for no cap).
+    /// A search that runs out answers with the last completed iteration.
+    pub fn set_node_limit(&mut self, limit: Option<u64>) {
+        self.node_limit = limit;
+    }
+
+    /// Caps the wall-clock time of each iterative-deepening search; a search
+    /// that runs out answers with the deepest fully searched iteration.
+    pub fn set_move_time(&mut self, move_time: Option<Duration>) {
+        self.time_budget = move_time.map(|move_time| TimeBudget {
+            soft: move_time,
+            hard: move_time,
+            min: Duration::ZERO,
+        });
+    }
+
+    /// Counts every `nodes_per_ms` nodes searched as a millisecond of the
+    /// time budget instead of using the wall clock (`None` to use the
+    /// clock), so timed searches do the same work on every machine.
+    pub fn set_nodes_time(&mut self, nodes_per_ms: Option<u64>) {
+        self.nodes_time = nodes_per_ms.filter(|&per_ms| per_ms > 0);
+    }
+
+    /// Like `set_move_time`, but no new iteration starts after `budget.soft`
+    /// (extended when a threat is detected) and the search aborts at
+    /// `budget.hard`. See `Clocks::budget`.
+    pub fn set_time_budget(&mut self, budget: Option<TimeBudget>) {
+        self.time_budget = budget;
+    }
+
+    /// Sets a minimum think time and a variance in time usage for timed
+    /// searches, so a bot's moves are paced like a person's. Searches keep
+    /// deepening until the minimum has passed, unless `stop` or a depth or
+    /// node limit ends them first.
+    pub fn set_pacing(&mut self, pacing: Pacing) {
+        self.pacing = pacing;
+    }
+
+    /// Reports each root move as iterative deepening starts searching it,
+    /// with its 1-based number in the root move order.
+    pub fn set_root_move_observer(&mut self, observer: Option<RootMoveObserver>) {
+        self.root_move_observer = observer;
+    }
+
+    /// Reports each root move that fails high during iterative deepening,
+    /// with the lower bound its scout proved, before it is searched again.
+    pub fn set_root_bound_observer(&mut self, observer: Option<RootBoundObserver>) {
+        self.root_bound_observer = observer;
+    }
+
+    pub fn set_contempt(&mut self, centipawns: i32) {
+        self.search.set_contempt(centipawns);
+    }
+
+    /// Folds color-flipped mirror positions onto one transposition table
+    /// entry (UCI `SymmetricHash`). Off by default.
+    pub fn set_symmetric_hash(&mut self, enabled: bool) {
+        self.search.set_symmetric_hash(enabled);
+    }
+
+    /// How many threads searches use (UCI `Threads`).
+    pub fn set_threads(&mut self, threads: usize) {
+        self.search.set_threads(threads);
+    }
+
+    /// Sets the transposition table size in MiB (UCI `Hash`).
+    pub fn set_hash_size(&mut self, megabytes: usize) {
+        self.search.set_hash_size(megabytes);
+    }
+
+    /// Memory held by the transposition tables, or `None` when the search
+    /// has none.
+    pub fn hash_usage(&self) -> Option<HashUsage> {
+        self.search.hash_usage()
+    }
+
+    /// Empties the transposition table (UCI `Clear Hash`).
+    pub fn clear_hash(&mut self) {
+        self.search.clear_hash();
+    }
+
     pub fn stop_search(&mut self) {
-        let _ = self;
+        self.stop.stop();
     }
 
+    /// A handle that aborts this engine's current search from another thread.
+    ///
+    /// The signal stays raised until `clear_stop` is called, so a stop that
+    /// arrives before the search starts is not lost. One raised with
+    /// `StopSignal::cancel` is lowered when the search it aborted ends.
+    pub fn stop_handle(&self) -> StopSignal {
+        self.stop.clone()
+    }
+
+    pub fn clear_stop(&mut self) {
+        self.stop.reset();
+    }
+
+    /// Starts over for a new game (UCI `ucinewgame`): clears the board and
+    /// its repetition history, the search's transposition table and
+    /// move-ordering memory, the root move hint and `searchmoves`.
     pub fn reset_state(&mut self) {
         self.board.clear();
+        self.search.new_game();
+        self.best_move_changes.clear();
+        self.search_moves = None;
+        self.root_hint = None;
+    }
+}
+
+/// Whether an aborted iteration should replace the last completed one.
+///
+/// Searches try the previous best moves first, ahead of the table move (see
+/// `SearchAlgorithm::search_with_root_ordering`), so an aborted result only
+/// names a different move when that move was fully searched and scored above
+/// them.
+/// With no completed depth, any fully searched root move beats guessing.
+fn trust_partial_result(partial: &SearchResult, previous_best: Option<&[Move]>) -> bool {
+    match (partial.best_moves.first(), previous_best) {
+        (None, _) => false,
+        (Some(_), None) => true,
+        (Some(_), Some(previous)) => !partial.best_moves.iter().any(|mv| previous.contains(mv)),
+    }
+}
+
+// Plays `first` and then the search's remembered best move of each position
+// reached, for at most `max_len` plies, and returns the moves with `board`
+// restored. Stops at an illegal or repeated position, so a stale or
+// colliding hash entry cannot derail it.
+fn follow_hash_moves(
+    search: &impl SearchAlgorithm,
+    board: &mut Board,
+    first: Option<Move>,
+    max_len: usize,
+) -> Vec<Move> {
+    let mut pv = Vec::new();
+    let mut undos = Vec::new();
+    let mut seen = vec![board.hash()];
+    let mut next = first;
+    while let Some(mv) = next
+        && pv.len() < max_len
+        && movegen::generate_legal(board).contains(&mv)
+        && let Ok(undo) = board.make_move(mv)
+    {
+        pv.push(mv);
+        undos.push(undo);
+        if seen.contains(&board.hash()) {
+            break;
+        }
+        seen.push(board.hash());
+        next = search.hash_move(board);
+    }
+    for (mv, undo) in pv.iter().zip(undos).rev() {
+        board.unmake_move(*mv, undo);
+    }
+    pv
+}
+
+#[allow(clippy::too_many_arguments)]
+fn search_info(
+    depth: u32,
+    result: &SearchResult,
+    nodes: u64,
+    started: Instant,
+    search: &impl SearchAlgorithm,
+    pv: Vec<Move>,
+    best_move_changed: bool,
+    ebf: Option<f64>,
+) -> SearchInfo {
+    SearchInfo {
+        depth,
+        seldepth: result.seldepth,
+        score: result.score,
+        nodes,
+        time_ms: started.elapsed().as_millis(),
+        best_moves: result.best_moves.clone(),
+        hashfull: search.hashfull(),
+        stats: search.stats(),
+        pv,
+        best_move_changed,
+        ebf,
     }
 }
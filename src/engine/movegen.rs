@@ -1,6 +1,11 @@
+use crate::engine::attacks::{king_attacks, knight_attacks, pawn_attacks};
 use crate::engine::board::Board;
-use crate::engine::castling::{has_kingside, has_queenside};
+use crate::engine::castling::{can_castle, castling_move, castling_wing, Wing};
+use crate::engine::eval::piece_value;
+use crate::engine::see::see;
+use crate::engine::trace::span;
 use crate::engine::types::{is_valid_square, Color, GameStatus, Move, Piece, PieceKind, Square};
+use std::cmp::Reverse;
 
 pub type MoveList = Vec<Move>;
 
@@ -15,7 +20,7 @@ pub fn is_onboard(square: Square) -> bool {
 
 pub fn offset_square(square: Square, offset: i8) -> Option<Square> {
     let index = square.index() as i16 + offset as i16;
-    if index < 0 || index > 127 {
+    if !(0..=127).contains(&index) {
         return None;
     }
     let candidate = Square(index as u8);
@@ -26,7 +31,142 @@ pub fn offset_square(square: Square, offset: i8) -> Option<Square> {
     }
 }
 
+// Which moves the shared generators emit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GenMode {
+    All,
+    /// Captures, en passant and promotions only.
+    Captures,
+}
+
 pub fn generate_pseudo_legal(board: &Board) -> MoveList {
+    generate_pseudo(board, GenMode::All)
+}
+
+/// Legal captures and promotions, generated directly rather than filtered out
+/// of every move, for quiescence.
+pub fn generate_captures(board: &mut Board) -> MoveList {
+    span!(TRACE, "movegen", kind = "captures");
+    let mut captures = generate_pseudo(board, GenMode::Captures);
+    captures.retain(|mv| is_legal(board, *mv));
+    captures
+}
+
+/// Legal quiet moves (no captures or promotions) that give check, directly
+/// or by uncovering a slider. Targets come from reverse attacks out of the
+/// enemy king square instead of trying every move.
+pub fn generate_quiet_checks(board: &mut Board) -> MoveList {
+    let side = board.side_to_move;
+    let Some(king) = find_king(board, opposite_color(side)) else {
+        return Vec::new();
+    };
+
+    let diagonal_checks = check_rays(board, king, &BISHOP_OFFSETS);
+    let straight_checks = check_rays(board, king, &ROOK_OFFSETS);
+    let uncovering = discovered_check_lines(board, king, side);
+
+    let mut checks = Vec::new();
+    for mv in generate_pseudo(board, GenMode::All) {
+        let is_castle = castling_wing(board, mv).is_some();
+        if mv.promotion.is_some() || (board.squares[mv.to.index() as usize].is_some() && !is_castle)
+        {
+            continue;
+        }
+        let Some(piece) = board.squares[mv.from.index() as usize] else {
+            continue;
+        };
+        if piece.kind == PieceKind::Pawn && board.en_passant == Some(mv.to) {
+            continue;
+        }
+
+        let direct = match piece.kind {
+            PieceKind::Pawn => pawn_attacks(king, opposite_color(side)).contains(&mv.to),
+            PieceKind::Knight => knight_attacks(king).contains(&mv.to),
+            PieceKind::Bishop => diagonal_checks.contains(&mv.to),
+            PieceKind::Rook => straight_checks.contains(&mv.to),
+            PieceKind::Queen => {
+                diagonal_checks.contains(&mv.to) || straight_checks.contains(&mv.to)
+            }
+            PieceKind::King => false,
+        };
+        let discovered = uncovering
+            .iter()
+            .any(|(blocker, line)| *blocker == mv.from && !line.contains(&mv.to));
+        // The rook's check after castling is rare enough to test directly.
+        let gives_check = if is_castle {
+            castle_gives_check(board, mv)
+        } else {
+            direct || discovered
+        };
+        if gives_check && is_legal(board, mv) {
+            checks.push(mv);
+        }
+    }
+    checks
+}
+
+// Empty squares a slider moving along `offsets` could check `king` from.
+fn check_rays(board: &Board, king: Square, offsets: &[i8]) -> Vec<Square> {
+    let mut squares = Vec::new();
+    for offset in offsets {
+        let mut current = king;
+        while let Some(next) = offset_square(current, *offset) {
+            if board.squares[next.index() as usize].is_some() {
+                break;
+            }
+            squares.push(next);
+            current = next;
+        }
+    }
+    squares
+}
+
+// Pieces of `side` that are the only thing between `king` and one of
+// `side`'s sliders, with every square of that ray: moving off the ray
+// uncovers check.
+fn discovered_check_lines(board: &Board, king: Square, side: Color) -> Vec<(Square, Vec<Square>)> {
+    let mut lines = Vec::new();
+    for (offsets, slider) in [
+        (&BISHOP_OFFSETS, PieceKind::Bishop),
+        (&ROOK_OFFSETS, PieceKind::Rook),
+    ] {
+        for offset in offsets {
+            let mut line = Vec::new();
+            let mut blocker = None;
+            let mut current = king;
+            while let Some(next) = offset_square(current, *offset) {
+                line.push(next);
+                current = next;
+                let Some(piece) = board.squares[next.index() as usize] else {
+                    continue;
+                };
+                match blocker {
+                    None if piece.color == side => blocker = Some(next),
+                    Some(blocker)
+                        if piece.color == side
+                            && (piece.kind == slider || piece.kind == PieceKind::Queen) =>
+                    {
+                        lines.push((blocker, line.clone()));
+                        break;
+                    }
+                    _ => break,
+                }
+            }
+        }
+    }
+    lines
+}
+
+fn castle_gives_check(board: &mut Board, mv: Move) -> bool {
+    let Ok(undo) = board.make_move(mv) else {
+        return false;
+    };
+    let gives_check = board.in_check();
+    board.unmake_move(mv, undo);
+    gives_check
+}
+
+fn generate_pseudo(board: &Board, mode: GenMode) -> MoveList {
     let mut moves = Vec::new();
     let side = board.side_to_move;
 
@@ -39,44 +179,94 @@ pub fn generate_pseudo_legal(board: &Board) -> MoveList {
             _ => continue,
         };
 
-        let from = Square(index);
-        match piece.kind {
-            PieceKind::Pawn => generate_pawn_moves(board, from, piece, &mut moves),
-            PieceKind::Knight => {
-                generate_jump_moves(board, from, piece, &KNIGHT_OFFSETS, &mut moves)
-            }
-            PieceKind::Bishop => {
-                generate_slider_moves(board, from, piece, &BISHOP_OFFSETS, &mut moves)
-            }
-            PieceKind::Rook => generate_slider_moves(board, from, piece, &ROOK_OFFSETS, &mut moves),
-            PieceKind::Queen => {
-                generate_slider_moves(board, from, piece, &BISHOP_OFFSETS, &mut moves);
-                generate_slider_moves(board, from, piece, &ROOK_OFFSETS, &mut moves);
-            }
-            PieceKind::King => generate_jump_moves(board, from, piece, &KING_OFFSETS, &mut moves),
+        generate_piece_moves(board, Square(index), piece, mode, &mut moves);
+    }
+
+    if mode == GenMode::All {
+        generate_castling_moves(board, &mut moves);
+    }
+
+    moves
+}
+
+fn generate_piece_moves(
+    board: &Board,
+    from: Square,
+    piece: Piece,
+    mode: GenMode,
+    moves: &mut MoveList,
+) {
+    match piece.kind {
+        PieceKind::Pawn => generate_pawn_moves(board, from, piece, mode, moves),
+        PieceKind::Knight => {
+            generate_jump_moves(board, from, piece, knight_attacks(from), mode, moves)
+        }
+        PieceKind::Bishop => {
+            generate_slider_moves(board, from, piece, &BISHOP_OFFSETS, mode, moves)
         }
+        PieceKind::Rook => generate_slider_moves(board, from, piece, &ROOK_OFFSETS, mode, moves),
+        PieceKind::Queen => {
+            generate_slider_moves(board, from, piece, &BISHOP_OFFSETS, mode, moves);
+            generate_slider_moves(board, from, piece, &ROOK_OFFSETS, mode, moves);
+        }
+        PieceKind::King => generate_jump_moves(board, from, piece, king_attacks(from), mode, moves),
     }
+}
 
-    generate_castling_moves(board, &mut moves);
+// One piece's pseudo-legal moves, castling included for the king.
+fn generate_single_piece(board: &Board, from: Square, moves: &mut MoveList) {
+    let Some(piece) = board.squares[from.index() as usize] else {
+        return;
+    };
+    if piece.color != board.side_to_move {
+        return;
+    }
+    generate_piece_moves(board, from, piece, GenMode::All, moves);
+    if piece.kind == PieceKind::King {
+        generate_castling_moves(board, moves);
+    }
+}
 
+/// Legal moves of the piece on `square`, castling included for the king;
+/// empty unless the piece belongs to the side to move. Only that piece's
+/// moves are generated, for click-to-move hints and legality checks.
+pub fn moves_from(board: &mut Board, square: Square) -> MoveList {
+    let mut moves = Vec::new();
+    generate_single_piece(board, square, &mut moves);
+    moves.retain(|mv| is_legal(board, *mv));
+    moves
+}
+
+/// Legal moves that land on `square`, such as the rivals SAN has to tell
+/// apart. Only pieces that can reach the square are generated: those
+/// attacking it, the pawns in front of it and the king, for castling.
+pub fn moves_to(board: &mut Board, square: Square) -> MoveList {
+    let side = board.side_to_move;
+    let behind: i8 = match side {
+        Color::White => -16,
+        Color::Black => 16,
+    };
+    let mut candidates = attackers_of(board, square, side);
+    candidates.extend(offset_square(square, behind));
+    candidates.extend(offset_square(square, 2 * behind));
+    candidates.extend(find_king(board, side));
+    candidates.sort_unstable_by_key(|candidate| candidate.index());
+    candidates.dedup();
+
+    let mut moves = Vec::new();
+    for from in candidates {
+        generate_single_piece(board, from, &mut moves);
+    }
+    moves.retain(|mv| mv.to == square && is_legal(board, *mv));
     moves
 }
 
 pub fn generate_legal(board: &mut Board) -> MoveList {
+    span!(TRACE, "movegen", kind = "legal");
     let pseudo = generate_pseudo_legal(board);
     let mut legal = Vec::new();
     for mv in pseudo {
-        let undo = match board.make_move(mv) {
-            Ok(undo) => undo,
-            Err(_) => continue,
-        };
-        let mover = match board.side_to_move {
-            Color::White => Color::Black,
-            Color::Black => Color::White,
-        };
-        let in_check = is_king_in_check(board, mover);
-        board.unmake_move(mv, undo);
-        if !in_check {
+        if is_legal(board, mv) {
             legal.push(mv);
         }
     }
@@ -84,6 +274,105 @@ pub fn generate_legal(board: &mut Board) -> MoveList {
     legal
 }
 
+/// Legal moves for a side in check, ordered for search: captures of the
+/// checking piece (least valuable capturer first), then interpositions by
+/// SEE, then king moves. Falls back to `generate_legal` when not in check.
+pub fn generate_evasions(board: &mut Board) -> MoveList {
+    span!(TRACE, "movegen", kind = "evasions");
+    let side = board.side_to_move;
+    let Some(king) = find_king(board, side) else {
+        return generate_legal(board);
+    };
+    let checkers = attackers_of(board, king, opposite_color(side));
+    let single_checker = match checkers.as_slice() {
+        [] => return generate_legal(board),
+        [checker] => Some(*checker),
+        _ => None,
+    };
+    let blocks_on = single_checker
+        .map(|checker| squares_between(board, king, checker))
+        .unwrap_or_default();
+
+    let mut captures = Vec::new();
+    let mut blocks = Vec::new();
+    let mut king_moves = Vec::new();
+    for mv in generate_pseudo_legal(board) {
+        let bucket = if mv.from == king {
+            &mut king_moves
+        } else if single_checker.is_some_and(|checker| captures_square(board, mv, checker)) {
+            &mut captures
+        } else if blocks_on.contains(&mv.to) {
+            &mut blocks
+        } else {
+            continue;
+        };
+        if is_legal(board, mv) {
+            bucket.push(mv);
+        }
+    }
+
+    captures.sort_by_key(|mv| {
+        board.squares[mv.from.index() as usize].map_or(0, |piece| piece_value(piece.kind))
+    });
+    blocks.sort_by_cached_key(|mv| Reverse(see(board, *mv)));
+    captures.extend(blocks);
+    captures.extend(king_moves);
+    captures
+}
+
+fn is_legal(board: &mut Board, mv: Move) -> bool {
+    let undo = match board.make_move(mv) {
+        Ok(undo) => undo,
+        Err(_) => return false,
+    };
+    let mover = opposite_color(board.side_to_move);
+    let in_check = is_king_in_check(board, mover);
+    board.unmake_move(mv, undo);
+    !in_check
+}
+
+// Whether `mv` removes the piece on `square`, including en passant.
+fn captures_square(board: &Board, mv: Move, square: Square) -> bool {
+    if mv.to == square {
+        return true;
+    }
+    let is_pawn =
+        board.squares[mv.from.index() as usize].is_some_and(|piece| piece.kind == PieceKind::Pawn);
+    let captured = match board.side_to_move {
+        Color::White => offset_square(mv.to, -16),
+        Color::Black => offset_square(mv.to, 16),
+    };
+    is_pawn && board.en_passant == Some(mv.to) && captured == Some(square)
+}
+
+// Empty squares strictly between `from` and a sliding piece on `to`.
+fn squares_between(board: &Board, from: Square, to: Square) -> Vec<Square> {
+    let is_slider = board.squares[to.index() as usize].is_some_and(|piece| {
+        matches!(
+            piece.kind,
+            PieceKind::Bishop | PieceKind::Rook | PieceKind::Queen
+        )
+    });
+    if !is_slider {
+        return Vec::new();
+    }
+    for offset in KING_OFFSETS {
+        let mut between = Vec::new();
+        let mut current = from;
+        while let Some(next) = offset_square(current, offset) {
+            if next == to {
+                return between;
+            }
+            if board.squares[next.index() as usize].is_some() {
+                break;
+            }
+            between.push(next);
+            current = next;
+        }
+    }
+    Vec::new()
+}
+
 pub fn is_noisy_move(board: &mut Board, mv: Move) -> bool {
     if mv.promotion.is_some() {
         return true;
@@ -106,7 +395,7 @@ pub fn is_noisy_move(board: &mut Board, mv: Move) -> bool {
         Ok(undo) => undo,
         Err(_) => return false,
     };
-    let gives_check = is_king_in_check(board, board.side_to_move);
+    let gives_check = board.in_check();
     board.unmake_move(mv, undo);
     gives_check
 }
@@ -130,10 +419,79 @@ pub fn perft(board: &mut Board, depth: u32) -> u64 {
     nodes
 }
 
+/// Leaf counts by move type, matching the columns of the classic perft tables.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PerftStats {
+    pub nodes: u64,
+    pub captures: u64,
+    pub en_passants: u64,
+    pub castles: u64,
+    pub promotions: u64,
+    pub checks: u64,
+    pub checkmates: u64,
+}
+
+impl PerftStats {
+    fn add(&mut self, other: PerftStats) {
+        self.nodes += other.nodes;
+        self.captures += other.captures;
+        self.en_passants += other.en_passants;
+        self.castles += other.castles;
+        self.promotions += other.promotions;
+        self.checks += other.checks;
+        self.checkmates += other.checkmates;
+    }
+}
+
+pub fn perft_with_stats(board: &mut Board, depth: u32) -> PerftStats {
+    if depth == 0 {
+        return PerftStats {
+            nodes: 1,
+            ..PerftStats::default()
+        };
+    }
+
+    let moves = generate_legal(board);
+    let mut stats = PerftStats::default();
+    for mv in moves {
+        let piece = board.squares[mv.from.index() as usize];
+        let is_pawn = matches!(piece, Some(p) if p.kind == PieceKind::Pawn);
+        let is_en_passant = is_pawn
+            && board.en_passant == Some(mv.to)
+            && board.squares[mv.to.index() as usize].is_none();
+        let is_castle = castling_wing(board, mv).is_some();
+        let is_capture =
+            (board.squares[mv.to.index() as usize].is_some() && !is_castle) || is_en_passant;
+
+        let undo = match board.make_move(mv) {
+            Ok(undo) => undo,
+            Err(_) => continue,
+        };
+        if depth == 1 {
+            stats.nodes += 1;
+            stats.captures += is_capture as u64;
+            stats.en_passants += is_en_passant as u64;
+            stats.castles += is_castle as u64;
+            stats.promotions += mv.promotion.is_some() as u64;
+            if board.in_check() {
+                stats.checks += 1;
+                if generate_legal(board).is_empty() {
+                    stats.checkmates += 1;
+                }
+            }
+        } else {
+            stats.add(perft_with_stats(board, depth - 1));
+        }
+        board.unmake_move(mv, undo);
+    }
+
+    stats
+}
+
 pub fn game_status(board: &mut Board) -> GameStatus {
     let moves = generate_legal(board);
     if moves.is_empty() {
-        if is_king_in_check(board, board.side_to_move) {
+        if board.in_check() {
             GameStatus::Checkmate
         } else {
             GameStatus::Stalemate
@@ -143,60 +501,39 @@ pub fn game_status(board: &mut Board) -> GameStatus {
     }
 }
 
-fn generate_pawn_moves(board: &Board, from: Square, piece: Piece, moves: &mut MoveList) {
-    let from_rank = from.index() >> 4;
-    match piece.color {
-        Color::White => {
-            let one = offset_square(from, 16);
-            if let Some(to) = one {
-                if board.squares[to.index() as usize].is_none() {
-                    add_pawn_advance(from, to, moves);
-                    if from_rank == 1 {
-                        let two = offset_square(from, 32);
-                        if let Some(to2) = two {
-                            if board.squares[to2.index() as usize].is_none() {
-                                moves.push(Move {
-                                    from,
-                                    to: to2,
-                                    promotion: None,
-                                });
-                            }
-                        }
-                    }
-                }
-            }
-
-            generate_pawn_capture(board, from, 15, moves);
-            generate_pawn_capture(board, from, 17, moves);
-            generate_en_passant(board, from, 15, moves);
-            generate_en_passant(board, from, 17, moves);
-        }
-        Color::Black => {
-            let one = offset_square(from, -16);
-            if let Some(to) = one {
-                if board.squares[to.index() as usize].is_none() {
-                    add_pawn_advance(from, to, moves);
-                    if from_rank == 6 {
-                        let two = offset_square(from, -32);
-                        if let Some(to2) = two {
-                            if board.squares[to2.index() as usize].is_none() {
-                                moves.push(Move {
-                                    from,
-                                    to: to2,
-                                    promotion: None,
-                                });
-                            }
-                        }
-                    }
-                }
-            }
-
-            generate_pawn_capture(board, from, -15, moves);
-            generate_pawn_capture(board, from, -17, moves);
-            generate_en_passant(board, from, -15, moves);
-            generate_en_passant(board, from, -17, moves);
+fn generate_pawn_moves(
+    board: &Board,
+    from: Square,
+    piece: Piece,
+    mode: GenMode,
+    moves: &mut MoveList,
+) {
+    let (forward, start_rank, promotion_rank) = match piece.color {
+        Color::White => (16, 1, 7),
+        Color::Black => (-16, 6, 0),
+    };
+    if let Some(to) = offset_square(from, forward)
+        && board.squares[to.index() as usize].is_none()
+        && (mode == GenMode::All || to.index() >> 4 == promotion_rank)
+    {
+        add_pawn_advance(from, to, moves);
+        if mode == GenMode::All
+            && from.index() >> 4 == start_rank
+            && let Some(to2) = offset_square(from, 2 * forward)
+            && board.squares[to2.index() as usize].is_none()
+        {
+            moves.push(Move {
+                from,
+                to: to2,
+                promotion: None,
+            });
         }
     }
+
+    for target in pawn_attacks(from, piece.color) {
+        generate_pawn_capture(board, from, piece, *target, moves);
+        generate_en_passant(board, from, *target, moves);
+    }
 }
 
 fn add_pawn_advance(from: Square, to: Square, moves: &mut MoveList) {
@@ -223,32 +560,29 @@ fn add_pawn_advance(from: Square, to: Square, moves: &mut MoveList) {
     }
 }
 
-fn generate_pawn_capture(board: &Board, from: Square, offset: i8, moves: &mut MoveList) {
-    let target = match offset_square(from, offset) {
-        Some(square) => square,
-        None => return,
-    };
+fn generate_pawn_capture(
+    board: &Board,
+    from: Square,
+    piece: Piece,
+    target: Square,
+    moves: &mut MoveList,
+) {
     let target_piece = match board.squares[target.index() as usize] {
         Some(piece) => piece,
         None => return,
     };
-    let from_piece = board.squares[from.index() as usize].expect("pawn missing");
-    if target_piece.color == from_piece.color {
+    if target_piece.color == piece.color {
         return;
     }
 
     add_pawn_advance(from, target, moves);
 }
 
-fn generate_en_passant(board: &Board, from: Square, offset: i8, moves: &mut MoveList) {
+fn generate_en_passant(board: &Board, from: Square, target: Square, moves: &mut MoveList) {
     let ep = match board.en_passant {
         Some(square) => square,
         None => return,
     };
-    let target = match offset_square(from, offset) {
-        Some(square) => square,
-        None => return,
-    };
     if target != ep {
         return;
     }
@@ -264,16 +598,13 @@ fn generate_jump_moves(
     board: &Board,
     from: Square,
     piece: Piece,
-    offsets: &[i8],
+    targets: &[Square],
+    mode: GenMode,
     moves: &mut MoveList,
 ) {
-    for offset in offsets {
-        let to = match offset_square(from, *offset) {
-            Some(square) => square,
-            None => continue,
-        };
+    for &to in targets {
         match board.squares[to.index() as usize] {
-            None => moves.push(Move {
+            None if mode == GenMode::All => moves.push(Move {
                 from,
                 to,
                 promotion: None,
@@ -293,22 +624,21 @@ fn generate_slider_moves(
     from: Square,
     piece: Piece,
     offsets: &[i8],
+    mode: GenMode,
     moves: &mut MoveList,
 ) {
     for offset in offsets {
         let mut current = from;
-        loop {
-            let next = match offset_square(current, *offset) {
-                Some(square) => square,
-                None => break,
-            };
+        while let Some(next) = offset_square(current, *offset) {
             match board.squares[next.index() as usize] {
                 None => {
-                    moves.push(Move {
-                        from,
-                        to: next,
-                        promotion: None,
-                    });
+                    if mode == GenMode::All {
+                        moves.push(Move {
+                            from,
+                            to: next,
+                            promotion: None,
+                        });
+                    }
                     current = next;
                 }
                 Some(target) => {
@@ -328,60 +658,9 @@ fn generate_slider_moves(
 
 fn generate_castling_moves(board: &Board, moves: &mut MoveList) {
     let side = board.side_to_move;
-    match side {
-        Color::White => generate_castling_for_color(board, side, 0, moves),
-        Color::Black => generate_castling_for_color(board, side, 7, moves),
-    }
-}
-
-fn generate_castling_for_color(board: &Board, color: Color, rank: u8, moves: &mut MoveList) {
-    let king_square = Square(rank * 16 + 4);
-    match board.squares[king_square.index() as usize] {
-        Some(piece) if piece.kind == PieceKind::King && piece.color == color => {}
-        _ => return,
-    };
-    let opponent = opposite_color(color);
-    if is_square_attacked(board, king_square, opponent) {
-        return;
-    }
-
-    if has_kingside(board.castling_rights, color) {
-        let f_square = Square(rank * 16 + 5);
-        let g_square = Square(rank * 16 + 6);
-        let rook_square = Square(rank * 16 + 7);
-        let rook_ok = matches!(board.squares[rook_square.index() as usize], Some(Piece { color: c, kind: PieceKind::Rook }) if c == color);
-        if rook_ok
-            && board.squares[f_square.index() as usize].is_none()
-            && board.squares[g_square.index() as usize].is_none()
-            && !is_square_attacked(board, f_square, opponent)
-            && !is_square_attacked(board, g_square, opponent)
-        {
-            moves.push(Move {
-                from: king_square,
-                to: g_square,
-                promotion: None,
-            });
-        }
-    }
-
-    if has_queenside(board.castling_rights, color) {
-        let b_square = Square(rank * 16 + 1);
-        let c_square = Square(rank * 16 + 2);
-        let d_square = Square(rank * 16 + 3);
-        let rook_square = Square(rank * 16 + 0);
-        let rook_ok = matches!(board.squares[rook_square.index() as usize], Some(Piece { color: c, kind: PieceKind::Rook }) if c == color);
-        if rook_ok
-            && board.squares[b_square.index() as usize].is_none()
-            && board.squares[c_square.index() as usize].is_none()
-            && board.squares[d_square.index() as usize].is_none()
-            && !is_square_attacked(board, d_square, opponent)
-            && !is_square_attacked(board, c_square, opponent)
-        {
-            moves.push(Move {
-                from: king_square,
-                to: c_square,
-                promotion: None,
-            });
+    for wing in Wing::BOTH {
+        if can_castle(board, side, wing) {
+            moves.push(castling_move(&board.castling_files, side, wing));
         }
     }
 }
@@ -413,7 +692,7 @@ pub fn is_square_attacked(board: &Board, square: Square, by_color: Color) -> boo
     if is_attacked_by_pawn(board, square, by_color) {
         return true;
     }
-    if is_attacked_by_jump(board, square, by_color, PieceKind::Knight, &KNIGHT_OFFSETS) {
+    if is_attacked_by_jump(board, square, by_color, PieceKind::Knight, knight_attacks) {
         return true;
     }
     if is_attacked_by_slider(board, square, by_color, PieceKind::Bishop, &BISHOP_OFFSETS) {
@@ -428,29 +707,68 @@ pub fn is_square_attacked(board: &Board, square: Square, by_color: Color) -> boo
     if is_attacked_by_slider(board, square, by_color, PieceKind::Queen, &ROOK_OFFSETS) {
         return true;
     }
-    if is_attacked_by_jump(board, square, by_color, PieceKind::King, &KING_OFFSETS) {
+    if is_attacked_by_jump(board, square, by_color, PieceKind::King, king_attacks) {
         return true;
     }
 
     false
 }
 
-fn is_attacked_by_pawn(board: &Board, square: Square, by_color: Color) -> bool {
-    let offsets: [i8; 2] = match by_color {
-        Color::White => [-15, -17],
-        Color::Black => [15, 17],
+/// Squares holding a piece of `by_color` that attacks `square`.
+pub(crate) fn attackers_of(board: &Board, square: Square, by_color: Color) -> Vec<Square> {
+    let mut attackers = Vec::new();
+    let holds = |candidate: Square, kinds: &[PieceKind]| {
+        board.squares[candidate.index() as usize]
+            .is_some_and(|piece| piece.color == by_color && kinds.contains(&piece.kind))
     };
-    for offset in offsets {
-        let attacker = match offset_square(square, offset) {
-            Some(attacker) => attacker,
-            None => continue,
-        };
-        if let Some(piece) = board.squares[attacker.index() as usize] {
-            if piece.color == by_color && piece.kind == PieceKind::Pawn {
-                return true;
+
+    for candidate in pawn_attacks(square, opposite_color(by_color)) {
+        if holds(*candidate, &[PieceKind::Pawn]) {
+            attackers.push(*candidate);
+        }
+    }
+    for candidate in knight_attacks(square) {
+        if holds(*candidate, &[PieceKind::Knight]) {
+            attackers.push(*candidate);
+        }
+    }
+    for candidate in king_attacks(square) {
+        if holds(*candidate, &[PieceKind::King]) {
+            attackers.push(*candidate);
+        }
+    }
+    for (offsets, kinds) in [
+        (&BISHOP_OFFSETS, [PieceKind::Bishop, PieceKind::Queen]),
+        (&ROOK_OFFSETS, [PieceKind::Rook, PieceKind::Queen]),
+    ] {
+        for offset in offsets {
+            let mut current = square;
+            while let Some(next) = offset_square(current, *offset) {
+                if board.squares[next.index() as usize].is_some() {
+                    if holds(next, &kinds) {
+                        attackers.push(next);
+                    }
+                    break;
+                }
+                current = next;
             }
         }
     }
+
+    attackers
+}
+
+fn is_attacked_by_pawn(board: &Board, square: Square, by_color: Color) -> bool {
+    // A pawn of `by_color` attacks `square` from the squares a pawn of the
+    // other color on `square` would attack.
+    for attacker in pawn_attacks(square, opposite_color(by_color)) {
+        if let Some(piece) = board.squares[attacker.index() as usize]
+            && piece.color == by_color
+            && piece.kind == PieceKind::Pawn
+        {
+            return true;
+        }
+    }
     false
 }
 
@@ -459,17 +777,16 @@ fn is_attacked_by_jump(
     square: Square,
     by_color: Color,
     kind: PieceKind,
-    offsets: &[i8],
+    attacks: fn(Square) -> &'static [Square],
 ) -> bool {
-    for offset in offsets {
-        let attacker = match offset_square(square, *offset) {
-            Some(attacker) => attacker,
-            None => continue,
-        };
-        if let Some(piece) = board.squares[attacker.index() as usize] {
-            if piece.color == by_color && piece.kind == kind {
-                return true;
-            }
+    // Knight and king attacks are symmetric, so the attackers of `square` are
+    // the squares a piece of the same kind on `square` would attack.
+    for attacker in attacks(square) {
+        if let Some(piece) = board.squares[attacker.index() as usize]
+            && piece.color == by_color
+            && piece.kind == kind
+        {
+            return true;
         }
     }
     false
@@ -484,11 +801,7 @@ fn is_attacked_by_slider(
 ) -> bool {
     for offset in offsets {
         let mut current = square;
-        loop {
-            let next = match offset_square(current, *offset) {
-                Some(square) => square,
-                None => break,
-            };
+        while let Some(next) = offset_square(current, *offset) {
             match board.squares[next.index() as usize] {
                 None => {
                     current = next;
@@ -518,6 +831,114 @@ mod tests {
     use crate::engine::board::Board;
     use crate::engine::types::{move_from_uci, square_from_algebraic, uci_from_move, GameStatus};
 
+    #[test]
+    fn quiet_checks_match_brute_force() {
+        let fens = [
+            // Direct checks from every piece type.
+            "4k3/8/8/8/8/8/1P3P2/RNBQK1NR w KQ - 0 1",
+            // Discovered checks: the knight and bishop mask the rook and queen.
+            "4k3/8/8/4N3/8/8/2B5/Q3R1K1 w - - 0 1",
+            // Castling with check, and a pawn masking a rook on its file.
+            "5k2/8/8/8/8/8/5P2/4K2R w K - 0 1",
+            "r3k3/8/8/8/8/8/8/3K4 b q - 0 1",
+        ];
+        for fen in fens {
+            let mut board = Board::new();
+            board.set_fen(fen).expect("fen");
+            let mut expected = Vec::new();
+            for mv in generate_legal(&mut board) {
+                // None of these positions has an en passant square.
+                let quiet =
+                    mv.promotion.is_none() && board.squares[mv.to.index() as usize].is_none();
+                let undo = board.make_move(mv).expect("make");
+                if quiet && board.in_check() {
+                    expected.push(mv);
+                }
+                board.unmake_move(mv, undo);
+            }
+            let checks = generate_quiet_checks(&mut board);
+            let sorted = |mut moves: Vec<Move>| {
+                moves.sort_by_key(|mv| (mv.from.index(), mv.to.index()));
+                moves
+            };
+            assert!(!expected.is_empty(), "{fen}");
+            assert_eq!(sorted(checks), sorted(expected), "{fen}");
+        }
+    }
+
+    #[test]
+    fn captures_match_filtered_legal_moves() {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "4k3/1P6/8/3pP3/8/8/6p1/4K2R w K d6 0 1",
+            "4k3/1P6/8/8/8/8/6p1/4K2R b K - 0 1",
+        ];
+        for fen in fens {
+            let mut board = Board::new();
+            board.set_fen(fen).expect("fen");
+            let side = board.side_to_move;
+            let expected: Vec<Move> = generate_legal(&mut board)
+                .into_iter()
+                .filter(|mv| {
+                    let captures = match board.squares[mv.to.index() as usize] {
+                        Some(piece) => piece.color != side,
+                        None => {
+                            board.en_passant == Some(mv.to)
+                                && board.squares[mv.from.index() as usize]
+                                    .is_some_and(|piece| piece.kind == PieceKind::Pawn)
+                        }
+                    };
+                    captures || mv.promotion.is_some()
+                })
+                .collect();
+            let mut captures = generate_captures(&mut board);
+            let mut expected = expected;
+            captures.sort_by_key(|mv| (mv.from.index(), mv.to.index(), mv.promotion.is_some()));
+            expected.sort_by_key(|mv| (mv.from.index(), mv.to.index(), mv.promotion.is_some()));
+            assert_eq!(captures, expected, "{fen}");
+        }
+    }
+
+    #[test]
+    fn targeted_moves_match_filtered_legal_moves() {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            // En passant, promotions with and without capture, and a pin.
+            "2r1k3/1P6/8/3pP3/8/8/6p1/4K2R w K d6 0 1",
+            "4k3/1P2n3/8/8/8/7K/6p1/4R2R b - - 0 1",
+            // In check: only evasions land anywhere.
+            "4k3/8/8/8/1b6/8/3N4/4K1NR w K - 0 1",
+        ];
+        for fen in fens {
+            let mut board = Board::new();
+            board.set_fen(fen).expect("fen");
+            let legal = generate_legal(&mut board);
+            for index in (0u8..128).filter(|index| is_valid_square(*index)) {
+                let square = Square(index);
+                let from: Vec<Move> = legal
+                    .iter()
+                    .copied()
+                    .filter(|mv| mv.from == square)
+                    .collect();
+                assert_eq!(moves_from(&mut board, square), from, "{fen} from {index}");
+
+                let mut to: Vec<String> = moves_to(&mut board, square)
+                    .into_iter()
+                    .filter_map(uci_from_move)
+                    .collect();
+                let mut expected: Vec<String> = legal
+                    .iter()
+                    .filter(|mv| mv.to == square)
+                    .filter_map(|mv| uci_from_move(*mv))
+                    .collect();
+                to.sort();
+                expected.sort();
+                assert_eq!(to, expected, "{fen} to {index}");
+            }
+            assert_eq!(board.to_fen(), fen);
+        }
+    }
+
     #[test]
     fn offset_square_rejects_offboard() {
         let a1 = square_from_algebraic("a1").unwrap();
@@ -617,6 +1038,70 @@ mod tests {
         assert_eq!(perft(&mut board, 4), 197281);
     }
 
+    #[test]
+    fn perft_chess960_positions() {
+        // Reference counts from the published Chess960 perft suite.
+        let cases = [
+            (
+                "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+                [21, 528, 12189, 326672],
+            ),
+            (
+                "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+                [21, 807, 18002, 667366],
+            ),
+        ];
+        for (fen, counts) in cases {
+            let mut board = Board::new();
+            board.set_fen(fen).expect("fen");
+            for (depth, count) in (1..).zip(counts) {
+                assert_eq!(perft(&mut board, depth), count, "{fen} depth {depth}");
+            }
+            assert_eq!(board.to_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn perft_stats_startpos_depth3() {
+        let mut board = Board::new();
+        board.set_startpos();
+        let stats = perft_with_stats(&mut board, 3);
+        assert_eq!(
+            stats,
+            PerftStats {
+                nodes: 8902,
+                captures: 34,
+                en_passants: 0,
+                castles: 0,
+                promotions: 0,
+                checks: 12,
+                checkmates: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn perft_stats_kiwipete_depth2() {
+        let mut board = Board::new();
+        board
+            .set_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+            .expect("fen");
+        let stats = perft_with_stats(&mut board, 2);
+        assert_eq!(
+            stats,
+            PerftStats {
+                nodes: 2039,
+                captures: 351,
+                en_passants: 1,
+                castles: 91,
+                promotions: 0,
+                checks: 3,
+                checkmates: 0,
+            }
+        );
+        assert_eq!(stats.nodes, perft(&mut board, 2));
+    }
+
     #[test]
     fn game_status_detects_checkmate() {
         let mut board = Board::new();
@@ -671,4 +1156,37 @@ mod tests {
         let mv = move_from_uci("e1e7").expect("move");
         assert!(is_noisy_move(&mut board, mv));
     }
+
+    #[test]
+    fn evasions_match_legal_moves_in_order() {
+        let mut board = Board::new();
+        board
+            .set_fen("4r2k/8/5N2/8/8/8/3B4/R3K3 w - - 0 1")
+            .expect("fen");
+        let evasions = generate_evasions(&mut board);
+        let mut legal = generate_legal(&mut board);
+        assert_eq!(evasions.len(), legal.len());
+        legal.retain(|mv| !evasions.contains(mv));
+        assert!(legal.is_empty());
+
+        let uci: Vec<String> = evasions
+            .iter()
+            .filter_map(|mv| uci_from_move(*mv))
+            .collect();
+        // Capture, then blocks with the cheaper loss first, then king moves.
+        assert_eq!(uci[..3], ["f6e8", "f6e4", "d2e3"]);
+        assert!(uci[3..].iter().all(|mv| mv.starts_with("e1")));
+    }
+
+    #[test]
+    fn double_check_evasions_are_king_moves() {
+        let mut board = Board::new();
+        board
+            .set_fen("4r1k1/8/8/8/8/5n2/3B4/R3K3 w - - 0 1")
+            .expect("fen");
+        let evasions = generate_evasions(&mut board);
+        assert!(!evasions.is_empty());
+        assert!(evasions.iter().all(|mv| mv.from.index() == 4));
+        assert_eq!(evasions.len(), generate_legal(&mut board).len());
+    }
 }
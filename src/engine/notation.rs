@@ -0,0 +1,384 @@
+use crate::engine::board::Board;
+use crate::engine::castling::{castling_wing, Wing};
+use crate::engine::movegen::{game_status, generate_legal, moves_from, moves_to};
+use crate::engine::search::extensions::is_capture;
+use crate::engine::types::{algebraic_from_square, uci_from_move, GameStatus, Move, PieceKind};
+
+/// How moves are written for people and tools reading analysis output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Notation {
+    /// Standard algebraic: `Nf3`, `exd6`, `e8=Q+`.
+    #[default]
+    San,
+    /// Long algebraic with piece letters: `Ng1-f3`, `e5xd6`, `e7-e8=Q+`.
+    Lan,
+    /// Pure coordinates as in UCI: `g1f3`, `e5d6`, `e7e8q`.
+    Uci,
+}
+
+impl Notation {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "san" => Ok(Self::San),
+            "lan" => Ok(Self::Lan),
+            "uci" | "coordinate" => Ok(Self::Uci),
+            _ => Err(format!(
+                "unknown notation: {name} (expected san, lan or uci)"
+            )),
+        }
+    }
+}
+
+/// The letters for pawn, knight, bishop, rook, queen and king. Pawns only
+/// show theirs in LAN promotions and nowhere else.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PieceLetters([char; 6]);
+
+impl PieceLetters {
+    pub const ENGLISH: Self = Self(['P', 'N', 'B', 'R', 'Q', 'K']);
+    pub const GERMAN: Self = Self(['B', 'S', 'L', 'T', 'D', 'K']);
+    pub const FRENCH: Self = Self(['P', 'C', 'F', 'T', 'D', 'R']);
+    pub const SPANISH: Self = Self(['P', 'C', 'A', 'T', 'D', 'R']);
+    pub const ITALIAN: Self = Self(['P', 'C', 'A', 'T', 'D', 'R']);
+    pub const DUTCH: Self = Self(['O', 'P', 'L', 'T', 'D', 'K']);
+
+    /// The letters of a language given by its ISO 639-1 code, such as `de`.
+    pub fn for_language(code: &str) -> Option<Self> {
+        match code.to_ascii_lowercase().as_str() {
+            "en" => Some(Self::ENGLISH),
+            "de" => Some(Self::GERMAN),
+            "fr" => Some(Self::FRENCH),
+            "es" => Some(Self::SPANISH),
+            "it" => Some(Self::ITALIAN),
+            "nl" => Some(Self::DUTCH),
+            _ => None,
+        }
+    }
+
+    pub fn letter(&self, kind: PieceKind) -> char {
+        let index = match kind {
+            PieceKind::Pawn => 0,
+            PieceKind::Knight => 1,
+            PieceKind::Bishop => 2,
+            PieceKind::Rook => 3,
+            PieceKind::Queen => 4,
+            PieceKind::King => 5,
+        };
+        self.0[index]
+    }
+}
+
+impl Default for PieceLetters {
+    fn default() -> Self {
+        Self::ENGLISH
+    }
+}
+
+/// A notation and the piece letters to write it with; the default is
+/// English SAN.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MoveFormat {
+    pub notation: Notation,
+    pub letters: PieceLetters,
+}
+
+impl MoveFormat {
+    pub fn new(notation: Notation, letters: PieceLetters) -> Self {
+        Self { notation, letters }
+    }
+
+    /// Writes a legal move of `board`, with check and mate suffixes in SAN
+    /// and LAN.
+    pub fn format(&self, board: &mut Board, mv: Move) -> Result<String, String> {
+        if !moves_from(board, mv.from).contains(&mv) {
+            return Err("move is not legal in this position".to_string());
+        }
+        let mut text = match self.notation {
+            Notation::San => san_without_suffix(board, mv, &self.letters)?,
+            Notation::Lan => lan_without_suffix(board, mv, &self.letters)?,
+            Notation::Uci => return uci_from_move(mv).ok_or_else(|| "invalid move".to_string()),
+        };
+
+        let undo = board.make_move(mv)?;
+        match game_status(board) {
+            GameStatus::Checkmate => text.push('#'),
+            _ if board.in_check() => text.push('+'),
+            _ => {}
+        }
+        board.unmake_move(mv, undo);
+
+        Ok(text)
+    }
+
+    /// Writes `moves` played in turn from `board`, stopping at the first
+    /// illegal one.
+    pub fn format_line(&self, board: &Board, moves: &[Move]) -> Vec<String> {
+        let mut board = board.clone();
+        let mut line = Vec::new();
+        for mv in moves {
+            let Ok(text) = self.format(&mut board, *mv) else {
+                break;
+            };
+            if board.apply_move(*mv).is_err() {
+                break;
+            }
+            line.push(text);
+        }
+        line
+    }
+}
+
+/// Formats a legal move in standard algebraic notation, including check and
+/// mate suffixes.
+pub fn move_to_san(board: &mut Board, mv: Move) -> Result<String, String> {
+    MoveFormat::default().format(board, mv)
+}
+
+/// Parses a SAN move such as `Nbd7`, `exd6`, `O-O` or `e8=Q+` against the
+/// legal moves of `board`. Annotation suffixes (`+`, `#`, `!`, `?`) are ignored.
+pub fn move_from_san(board: &mut Board, san: &str) -> Result<Move, String> {
+    let wanted = normalize_san(san);
+    if wanted.is_empty() {
+        return Err(format!("invalid SAN move: {san}"));
+    }
+
+    let legal = generate_legal(board);
+    let mut matches = Vec::new();
+    for mv in &legal {
+        let candidate = san_without_suffix(board, *mv, &PieceLetters::ENGLISH)?;
+        if normalize_san(&candidate) == wanted {
+            matches.push(*mv);
+        }
+    }
+
+    match matches.as_slice() {
+        [mv] => Ok(*mv),
+        [] => Err(format!("illegal SAN move: {san}")),
+        _ => Err(format!("ambiguous SAN move: {san}")),
+    }
+}
+
+fn san_without_suffix(
+    board: &mut Board,
+    mv: Move,
+    letters: &PieceLetters,
+) -> Result<String, String> {
+    let piece = board.squares[mv.from.index() as usize].ok_or("no piece on from square")?;
+    let from = algebraic_from_square(mv.from).ok_or("invalid from square")?;
+    let to = algebraic_from_square(mv.to).ok_or("invalid to square")?;
+    let from_file = mv.from.index() & 0x0f;
+
+    if let Some(castle) = castling(board, mv) {
+        return Ok(castle.to_string());
+    }
+
+    let capture = is_capture(board, mv);
+    let mut san = String::new();
+
+    if piece.kind == PieceKind::Pawn {
+        if capture {
+            san.push_str(&from[..1]);
+            san.push('x');
+        }
+        san.push_str(&to);
+        if let Some(promotion) = mv.promotion {
+            san.push('=');
+            san.push(letters.letter(promotion));
+        }
+        return Ok(san);
+    }
+
+    san.push(letters.letter(piece.kind));
+    let rivals: Vec<Move> = moves_to(board, mv.to)
+        .into_iter()
+        .filter(|other| {
+            other.to == mv.to
+                && other.from != mv.from
+                && board.squares[other.from.index() as usize].map(|p| p.kind) == Some(piece.kind)
+        })
+        .collect();
+    if !rivals.is_empty() {
+        let same_file = rivals
+            .iter()
+            .any(|other| other.from.index() & 0x0f == from_file);
+        let same_rank = rivals
+            .iter()
+            .any(|other| other.from.index() >> 4 == mv.from.index() >> 4);
+        if !same_file {
+            san.push_str(&from[..1]);
+        } else if !same_rank {
+            san.push_str(&from[1..]);
+        } else {
+            san.push_str(&from);
+        }
+    }
+    if capture {
+        san.push('x');
+    }
+    san.push_str(&to);
+    Ok(san)
+}
+
+// Both squares in full, joined by `-` or `x`; pawns go without a letter.
+fn lan_without_suffix(board: &Board, mv: Move, letters: &PieceLetters) -> Result<String, String> {
+    let piece = board.squares[mv.from.index() as usize].ok_or("no piece on from square")?;
+    let from = algebraic_from_square(mv.from).ok_or("invalid from square")?;
+    let to = algebraic_from_square(mv.to).ok_or("invalid to square")?;
+    if let Some(castle) = castling(board, mv) {
+        return Ok(castle.to_string());
+    }
+
+    let mut lan = String::new();
+    if piece.kind != PieceKind::Pawn {
+        lan.push(letters.letter(piece.kind));
+    }
+    lan.push_str(&from);
+    lan.push(if is_capture(board, mv) { 'x' } else { '-' });
+    lan.push_str(&to);
+    if let Some(promotion) = mv.promotion {
+        lan.push('=');
+        lan.push(letters.letter(promotion));
+    }
+    Ok(lan)
+}
+
+fn castling(board: &Board, mv: Move) -> Option<&'static str> {
+    castling_wing(board, mv).map(|wing| match wing {
+        Wing::Kingside => "O-O",
+        Wing::Queenside => "O-O-O",
+    })
+}
+
+// Drops decorations that different writers disagree on so SAN from other
+// tools compares equal to ours (`exd6e.p.`, `e8Q`, `0-0`, `Nf3!?`).
+fn normalize_san(san: &str) -> String {
+    san.trim()
+        .trim_end_matches("e.p.")
+        .replace('0', "O")
+        .chars()
+        .filter(|ch| !matches!(ch, 'x' | '=' | '+' | '#' | '!' | '?'))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::types::move_from_uci;
+
+    fn board_from(fen: &str) -> Board {
+        let mut board = Board::new();
+        board.set_fen(fen).expect("fen");
+        board
+    }
+
+    #[test]
+    fn formats_common_moves() {
+        let mut board =
+            board_from("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        let cases = [
+            ("e1g1", "O-O"),
+            ("e1c1", "O-O-O"),
+            ("e5f7", "Nxf7"),
+            ("d5e6", "dxe6"),
+            ("c3b1", "Nb1"),
+            ("e2a6", "Bxa6"),
+        ];
+        for (uci, expected) in cases {
+            let mv = move_from_uci(uci).unwrap();
+            assert_eq!(move_to_san(&mut board, mv).unwrap(), expected, "{uci}");
+        }
+    }
+
+    #[test]
+    fn disambiguates_by_file_then_rank() {
+        let mut board = board_from("4k3/8/8/R7/8/5N2/8/RN2K3 w - - 0 1");
+        let cases = [
+            ("b1d2", "Nbd2"),
+            ("f3d2", "Nfd2"),
+            ("a1a3", "R1a3"),
+            ("a5a3", "R5a3"),
+        ];
+        for (uci, expected) in cases {
+            let mv = move_from_uci(uci).unwrap();
+            assert_eq!(move_to_san(&mut board, mv).unwrap(), expected, "{uci}");
+            assert_eq!(move_from_san(&mut board, expected).unwrap(), mv);
+        }
+    }
+
+    #[test]
+    fn formats_check_mate_and_promotion() {
+        let mut board = board_from("6k1/4P3/8/8/8/8/8/R5K1 w - - 0 1");
+        let mate = move_from_uci("a1a8").unwrap();
+        assert_eq!(move_to_san(&mut board, mate).unwrap(), "Ra8+");
+        let promotion = move_from_uci("e7e8q").unwrap();
+        assert_eq!(move_to_san(&mut board, promotion).unwrap(), "e8=Q+");
+
+        let mut board = board_from("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        assert_eq!(move_to_san(&mut board, mate).unwrap(), "Ra8#");
+    }
+
+    #[test]
+    fn formats_long_algebraic_and_coordinates() {
+        let mut board =
+            board_from("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        let lan = MoveFormat::new(Notation::Lan, PieceLetters::ENGLISH);
+        let uci = MoveFormat::new(Notation::Uci, PieceLetters::ENGLISH);
+        let cases = [
+            ("e1g1", "O-O", "e1g1"),
+            ("e5f7", "Ne5xf7", "e5f7"),
+            ("d5e6", "d5xe6", "d5e6"),
+            ("c3b1", "Nc3-b1", "c3b1"),
+            ("a2a4", "a2-a4", "a2a4"),
+        ];
+        for (mv, expected_lan, expected_uci) in cases {
+            let mv = move_from_uci(mv).unwrap();
+            assert_eq!(lan.format(&mut board, mv).unwrap(), expected_lan);
+            assert_eq!(uci.format(&mut board, mv).unwrap(), expected_uci);
+        }
+
+        let mut board = board_from("6k1/4P3/8/8/8/8/8/R5K1 w - - 0 1");
+        let promotion = move_from_uci("e7e8q").unwrap();
+        assert_eq!(lan.format(&mut board, promotion).unwrap(), "e7-e8=Q+");
+        assert_eq!(Notation::parse("LAN"), Ok(Notation::Lan));
+        assert!(Notation::parse("figurine").is_err());
+    }
+
+    #[test]
+    fn localised_letters_replace_the_english_ones() {
+        let mut board = Board::new();
+        board.set_startpos();
+        let moves: Vec<Move> = ["e2e4", "e7e5", "g1f3", "b8c6", "f1b5"]
+            .iter()
+            .map(|mv| move_from_uci(mv).unwrap())
+            .collect();
+        let german = PieceLetters::for_language("de").expect("german");
+        assert_eq!(
+            MoveFormat::new(Notation::San, german).format_line(&board, &moves),
+            ["e4", "e5", "Sf3", "Sc6", "Lb5"]
+        );
+        assert_eq!(
+            MoveFormat::new(Notation::Lan, PieceLetters::FRENCH).format_line(&board, &moves[..3]),
+            ["e2-e4", "e7-e5", "Cg1-f3"]
+        );
+        assert!(PieceLetters::for_language("xx").is_none());
+
+        // A line stops at its first illegal move, and the board is untouched.
+        let broken = [moves[0], moves[0]];
+        assert_eq!(MoveFormat::default().format_line(&board, &broken), ["e4"]);
+        assert_eq!(move_to_san(&mut board, moves[0]).unwrap(), "e4");
+    }
+
+    #[test]
+    fn parses_san_round_trip() {
+        let mut board = Board::new();
+        board.set_startpos();
+        for san in ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Bxc6", "dxc6"] {
+            let mv = move_from_san(&mut board, san).expect(san);
+            assert_eq!(move_to_san(&mut board, mv).unwrap(), san);
+            board.apply_move(mv).unwrap();
+        }
+        let castle = move_from_san(&mut board, "0-0").expect("castle");
+        assert_eq!(move_to_san(&mut board, castle).unwrap(), "O-O");
+        assert!(move_from_san(&mut board, "Qh5").is_err());
+    }
+}
@@ -0,0 +1,548 @@
+use crate::engine::board::Board;
+use crate::engine::notation::move_from_san;
+use crate::engine::types::{Color, Move};
+
+const LINE_WIDTH: usize = 79;
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PgnMove {
+    pub mv: Move,
+    pub san: String,
+    pub nags: Vec<u8>,
+    pub comment: Option<String>,
+    /// A comment before the move; only kept for the first move of a line,
+    /// later ones attach to the previous move.
+    pub comment_before: Option<String>,
+    /// Alternatives to this move (recursive annotation variations), each
+    /// starting from the position before it.
+    pub variations: Vec<Vec<PgnMove>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<PgnMove>,
+    pub result: String,
+}
+
+impl PgnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(key, _)| key == name) {
+            Some((_, existing)) => *existing = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// The position before the first move: the `FEN` tag if present, else startpos.
+    pub fn start_board(&self) -> Result<Board, String> {
+        let mut board = Board::new();
+        match self.tag("FEN") {
+            Some(fen) => board.set_fen(fen)?,
+            None => board.set_startpos(),
+        }
+        Ok(board)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    /// A tag that does not parse; the game it belongs to is unreadable.
+    InvalidTag(String),
+    VariationStart,
+    VariationEnd,
+    Symbol(String),
+}
+
+/// Parses every game in a PGN database. SAN moves are resolved to engine
+/// moves; comments, NAGs and variations are kept so that `write_pgn` gives
+/// the annotations back. An illegal move inside a variation only drops the
+/// rest of that variation, and unbalanced parentheses and result tokens
+/// inside variations are tolerated. A game that cannot be read, through an
+/// illegal mainline move, a malformed tag or a bad `FEN` tag, fails the whole
+/// database; `parse_pgn_games` skips it instead.
+pub fn parse_pgn(text: &str) -> Result<Vec<PgnGame>, String> {
+    parse_pgn_games(text).into_iter().collect()
+}
+
+/// Like `parse_pgn`, but each game is parsed on its own: one that cannot be
+/// read is an error in its place, and parsing picks up at the next game.
+pub fn parse_pgn_games(text: &str) -> Vec<Result<PgnGame, String>> {
+    let mut games = Vec::new();
+    let mut game = PgnGame::default();
+    // The mainline first, then one entry per open variation.
+    let mut lines = vec![Line::default()];
+    // Why the current game cannot be read. A failed mainline is marked
+    // broken, so the rest of its movetext is skipped.
+    let mut error = None;
+
+    for token in tokenize(text) {
+        // A tag after the movetext starts the next game.
+        if matches!(token, Token::Tag(..) | Token::InvalidTag(_))
+            && (!lines[0].moves.is_empty() || lines[0].broken)
+        {
+            let finished = std::mem::take(&mut game);
+            games.push(finish_game(finished, &mut lines, error.take(), games.len()));
+        }
+        let line = lines.last_mut().expect("mainline");
+        match token {
+            Token::Tag(name, value) => game.tags.push((name, value)),
+            Token::InvalidTag(err) => {
+                error.get_or_insert(err);
+            }
+            Token::VariationStart => {
+                // A variation replaces the last move, so it starts from the
+                // position before it; without one it is ignored.
+                let board = line.before_last.clone().filter(|_| !line.broken);
+                lines.push(Line {
+                    broken: board.is_none(),
+                    board,
+                    ..Line::default()
+                });
+            }
+            Token::VariationEnd => {
+                if lines.len() > 1 {
+                    close_variation(&mut lines);
+                }
+            }
+            // Only the mainline's result ends the game.
+            Token::Symbol(symbol) if RESULTS.contains(&symbol.as_str()) => {
+                if lines.len() == 1 {
+                    game.result = symbol;
+                    let finished = std::mem::take(&mut game);
+                    games.push(finish_game(finished, &mut lines, error.take(), games.len()));
+                }
+            }
+            _ if line.broken => {}
+            Token::Comment(comment) => {
+                let target = match line.moves.last_mut() {
+                    Some(last) => &mut last.comment,
+                    None => &mut line.pending_comment,
+                };
+                *target = Some(match target.take() {
+                    Some(existing) => format!("{existing} {comment}"),
+                    None => comment,
+                });
+            }
+            Token::Nag(nag) => {
+                if let Some(last) = line.moves.last_mut() {
+                    last.nags.push(nag);
+                }
+            }
+            Token::Symbol(symbol) => {
+                let (san, suffix_nag) = split_suffix_annotation(strip_move_number(&symbol));
+                if san.is_empty() || san == "e.p." {
+                    continue;
+                }
+                let in_variation = lines.len() > 1;
+                let line = lines.last_mut().expect("mainline");
+                let ply = line.moves.len() + 1;
+                let played = match &mut line.board {
+                    Some(board) => Ok(board),
+                    None => game.start_board().map(|board| line.board.insert(board)),
+                }
+                .and_then(|board| {
+                    let mv = move_from_san(board, san)?;
+                    let before = board.clone();
+                    board.apply_move(mv)?;
+                    Ok((mv, before))
+                });
+                let (mv, before) = match played {
+                    Ok(played) => played,
+                    Err(err) => {
+                        if !in_variation {
+                            error.get_or_insert(format!("ply {ply}: {err}"));
+                        }
+                        line.broken = true;
+                        continue;
+                    }
+                };
+                line.before_last = Some(before);
+                let comment_before = line.pending_comment.take();
+                line.moves.push(PgnMove {
+                    mv,
+                    san: san.to_string(),
+                    nags: suffix_nag.into_iter().collect(),
+                    comment: None,
+                    comment_before,
+                    variations: Vec::new(),
+                });
+            }
+        }
+    }
+
+    if !game.tags.is_empty() || !lines[0].moves.is_empty() || error.is_some() {
+        games.push(finish_game(game, &mut lines, error, games.len()));
+    }
+    games
+}
+
+// A line of moves being parsed: the mainline or an open variation.
+#[derive(Default)]
+struct Line {
+    moves: Vec<PgnMove>,
+    /// The position after the last move; the mainline sets it up lazily
+    /// because its start depends on the tags.
+    board: Option<Board>,
+    /// The position before the last move, where a variation would start.
+    before_last: Option<Board>,
+    pending_comment: Option<String>,
+    /// Set after an illegal move; the rest of the line is skipped.
+    broken: bool,
+}
+
+// Pops the innermost variation and attaches it to the move it replaces.
+fn close_variation(lines: &mut Vec<Line>) {
+    let variation = lines.pop().expect("variation");
+    if variation.moves.is_empty() {
+        return;
+    }
+    if let Some(parent) = lines.last_mut().and_then(|line| line.moves.last_mut()) {
+        parent.variations.push(variation.moves);
+    }
+}
+
+/// Writes a game as export-style PGN: tags, then movetext wrapped at 79 columns.
+pub fn write_pgn(game: &PgnGame) -> String {
+    let mut out = String::new();
+    for (name, value) in &game.tags {
+        let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+        out.push_str(&format!("[{name} \"{value}\"]\n"));
+    }
+    out.push('\n');
+
+    let (color, number) = game
+        .start_board()
+        .map(|board| (board.side_to_move, board.fullmove_number))
+        .unwrap_or((Color::White, 1));
+    let mut tokens = Vec::new();
+    push_line(&mut tokens, &game.moves, color, number);
+    tokens.push(game.result.clone());
+
+    let mut line = String::new();
+    for token in tokens {
+        if !line.is_empty() && line.len() + 1 + token.len() > LINE_WIDTH {
+            out.push_str(&line);
+            out.push('\n');
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&token);
+    }
+    out.push_str(&line);
+    out.push('\n');
+    out
+}
+
+// Movetext tokens for `moves`, the first played by `color` on move `number`.
+fn push_line(tokens: &mut Vec<String>, moves: &[PgnMove], mut color: Color, mut number: u32) {
+    let mut need_number = true;
+    for pgn_move in moves {
+        if let Some(comment) = &pgn_move.comment_before {
+            tokens.push(format!("{{{comment}}}"));
+        }
+        match color {
+            Color::White => tokens.push(format!("{number}.")),
+            Color::Black if need_number => tokens.push(format!("{number}...")),
+            Color::Black => {}
+        }
+        tokens.push(pgn_move.san.clone());
+        tokens.extend(pgn_move.nags.iter().map(|nag| format!("${nag}")));
+        need_number = false;
+        if let Some(comment) = &pgn_move.comment {
+            tokens.push(format!("{{{comment}}}"));
+            need_number = true;
+        }
+        for variation in pgn_move.variations.iter().filter(|line| !line.is_empty()) {
+            let start = tokens.len();
+            push_line(tokens, variation, color, number);
+            tokens[start].insert(0, '(');
+            tokens.last_mut().expect("variation").push(')');
+            need_number = true;
+        }
+        if color == Color::Black {
+            number += 1;
+        }
+        color = match color {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        };
+    }
+}
+
+// Closes any variation left open and takes the mainline as the game's moves,
+// or reports why the game could not be read. `before` games precede it.
+fn finish_game(
+    mut game: PgnGame,
+    lines: &mut Vec<Line>,
+    error: Option<String>,
+    before: usize,
+) -> Result<PgnGame, String> {
+    while lines.len() > 1 {
+        close_variation(lines);
+    }
+    game.moves = std::mem::take(&mut lines[0]).moves;
+    if let Some(err) = error {
+        return Err(format!("game {}, {err}", before + 1));
+    }
+    if game.result.is_empty() {
+        game.result = game.tag("Result").unwrap_or("*").to_string();
+    }
+    Ok(game)
+}
+
+// `12.`, `12...` and `12.e4` all reduce to the SAN part (possibly empty).
+fn strip_move_number(symbol: &str) -> &str {
+    let rest = symbol.trim_start_matches(|ch: char| ch.is_ascii_digit());
+    if rest.len() == symbol.len() {
+        return symbol;
+    }
+    rest.trim_start_matches('.')
+}
+
+// Move suffixes like `?!` are shorthand for NAGs; store them as NAGs.
+fn split_suffix_annotation(san: &str) -> (&str, Option<u8>) {
+    let trimmed = san.trim_end_matches(['!', '?']);
+    let nag = match &san[trimmed.len()..] {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    };
+    (trimmed, nag)
+}
+
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(&ch) = chars.peek() {
+        match ch {
+            _ if ch.is_whitespace() => {
+                chars.next();
+            }
+            '[' => {
+                chars.next();
+                let body: String = chars.by_ref().take_while(|&ch| ch != ']').collect();
+                tokens.push(parse_tag(&body).unwrap_or_else(Token::InvalidTag));
+            }
+            '{' => {
+                chars.next();
+                let body: String = chars.by_ref().take_while(|&ch| ch != '}').collect();
+                tokens.push(Token::Comment(
+                    body.split_whitespace().collect::<Vec<_>>().join(" "),
+                ));
+            }
+            ';' | '%' => {
+                chars.by_ref().take_while(|&ch| ch != '\n').for_each(drop);
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::VariationStart);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::VariationEnd);
+            }
+            '$' => {
+                chars.next();
+                let mut digits = String::new();
+                while let Some(&digit) = chars.peek().filter(|ch| ch.is_ascii_digit()) {
+                    digits.push(digit);
+                    chars.next();
+                }
+                // An out-of-range NAG is dropped.
+                if let Ok(nag) = digits.parse::<u8>() {
+                    tokens.push(Token::Nag(nag));
+                }
+            }
+            _ => {
+                let mut symbol = String::new();
+                while let Some(&next) = chars.peek() {
+                    if next.is_whitespace() || "[]{}();$".contains(next) {
+                        break;
+                    }
+                    symbol.push(next);
+                    chars.next();
+                }
+                tokens.push(Token::Symbol(symbol));
+            }
+        }
+    }
+
+    tokens
+}
+
+fn parse_tag(body: &str) -> Result<Token, String> {
+    let body = body.trim();
+    let (name, rest) = body
+        .split_once(char::is_whitespace)
+        .ok_or_else(|| format!("invalid tag: [{body}]"))?;
+    let value = rest
+        .trim()
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .ok_or_else(|| format!("invalid tag value: [{body}]"))?;
+    Ok(Token::Tag(
+        name.to_string(),
+        value.replace("\\\"", "\"").replace("\\\\", "\\"),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::types::move_from_uci;
+
+    const SAMPLE: &str = r#"[Event "Casual"]
+[White "Alice"]
+[Black "Bob"]
+[Result "1-0"]
+
+1. e4 e5 2. Nf3 {developing} Nc6 (2... d6 3. d4) 3. Bc4 $1 Nd4?
+4. Nxe5 Qg5 5. Nxf7 Qxg2 6. Rf1 Qxe4+ 7. Be2 Nf3# 0-1
+
+[Event "Second"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 b - - 0 40"]
+
+40... Kd7 41. e4 *
+"#;
+
+    #[test]
+    fn parses_games_with_tags_comments_and_variations() {
+        let games = parse_pgn(SAMPLE).expect("pgn");
+        assert_eq!(games.len(), 2);
+
+        let first = &games[0];
+        assert_eq!(first.tag("White"), Some("Alice"));
+        assert_eq!(first.result, "0-1");
+        assert_eq!(first.moves.len(), 14);
+        assert_eq!(first.moves[2].comment.as_deref(), Some("developing"));
+        assert_eq!(first.moves[4].nags, vec![1]);
+        assert_eq!(first.moves[5].san, "Nd4");
+        assert_eq!(first.moves[5].nags, vec![2]);
+        assert_eq!(first.moves[13].mv, move_from_uci("d4f3").unwrap());
+        let variation = &first.moves[3].variations[0];
+        assert_eq!(variation.len(), 2);
+        assert_eq!(variation[0].mv, move_from_uci("d7d6").unwrap());
+        assert_eq!(variation[1].san, "d4");
+
+        let second = &games[1];
+        assert_eq!(second.result, "*");
+        assert_eq!(second.moves[0].mv, move_from_uci("e8d7").unwrap());
+    }
+
+    #[test]
+    fn rejects_illegal_moves() {
+        let err = parse_pgn("1. e4 e5 2. Ke3 *").unwrap_err();
+        assert!(err.contains("ply 3"), "{err}");
+    }
+
+    #[test]
+    fn keeps_nested_variations_and_leading_comments() {
+        let text = "{Opening} 1. e4 $1 {best by test} (1. d4 {solid} d5 (1... Nf6 2. c4) 2. c4) \
+                    1... e5 ( {or} 1... c5 $5) 2. Nf3 *";
+        let games = parse_pgn(text).expect("pgn");
+        let moves = &games[0].moves;
+        assert_eq!(moves.len(), 3);
+        assert_eq!(moves[0].comment_before.as_deref(), Some("Opening"));
+        assert_eq!(moves[0].comment.as_deref(), Some("best by test"));
+
+        let d4_line = &moves[0].variations[0];
+        assert_eq!(d4_line[0].comment.as_deref(), Some("solid"));
+        assert_eq!(d4_line[1].variations[0][0].san, "Nf6");
+        assert_eq!(d4_line[1].variations[0][1].san, "c4");
+        let c5 = &moves[1].variations[0][0];
+        assert_eq!(c5.comment_before.as_deref(), Some("or"));
+        assert_eq!(c5.nags, vec![5]);
+
+        let written = write_pgn(&games[0]);
+        let movetext = written.replace('\n', " ");
+        assert!(movetext.contains(
+            "{Opening} 1. e4 $1 {best by test} (1. d4 {solid} 1... d5 (1... Nf6 2. c4) 2. c4)"
+        ));
+        assert!(movetext.contains("1... e5 ({or} 1... c5 $5) 2. Nf3 *"));
+        assert_eq!(parse_pgn(&written).expect("reparse"), games);
+    }
+
+    #[test]
+    fn tolerates_broken_variations() {
+        // An illegal move drops the rest of its variation, and unbalanced
+        // parentheses are ignored.
+        let games = parse_pgn("1. e4 (1. d4 Ke3 2. c4) ) e5 (1... c5 *").expect("pgn");
+        let moves = &games[0].moves;
+        assert_eq!(moves.len(), 2);
+        assert_eq!(moves[0].variations.len(), 1);
+        assert_eq!(moves[0].variations[0].len(), 1);
+        assert_eq!(moves[0].variations[0][0].san, "d4");
+        assert_eq!(moves[1].variations[0][0].san, "c5");
+        assert_eq!(games[0].result, "*");
+    }
+
+    #[test]
+    fn skips_games_that_cannot_be_read() {
+        let text = r#"1. e4 e5 2. Ke3 1-0
+
+[Event "Good"]
+
+1. d4 (1. e4 1-0) 1... d5 $300 2. c4 1/2-1/2
+
+[FEN "not a position"]
+
+1. e4 *
+
+[Event "Bad tag]
+[Broken]
+
+1. e4 *
+
+1. Nf3 0-1
+"#;
+        let games = parse_pgn_games(text);
+        assert_eq!(games.len(), 5);
+        assert!(games[0].as_ref().unwrap_err().starts_with("game 1, ply 3"));
+        let good = games[1].as_ref().expect("good game");
+        assert_eq!(good.tag("Event"), Some("Good"));
+        assert_eq!(good.moves.len(), 3);
+        assert_eq!(good.moves[0].variations[0][0].san, "e4");
+        assert_eq!(good.result, "1/2-1/2");
+        assert!(games[2].as_ref().unwrap_err().starts_with("game 3, ply 1"));
+        assert!(games[3]
+            .as_ref()
+            .unwrap_err()
+            .starts_with("game 4, invalid tag"));
+        assert_eq!(games[4].as_ref().expect("last game").result, "0-1");
+
+        assert_eq!(
+            parse_pgn(text).unwrap_err(),
+            *games[0].as_ref().unwrap_err()
+        );
+    }
+
+    #[test]
+    fn write_round_trips_mainline() {
+        let games = parse_pgn(SAMPLE).expect("pgn");
+        for game in &games {
+            let written = write_pgn(game);
+            let reparsed = parse_pgn(&written).expect("reparse");
+            assert_eq!(reparsed, vec![game.clone()]);
+        }
+        assert!(write_pgn(&games[1]).contains("40... Kd7 41. e4 *"));
+    }
+}
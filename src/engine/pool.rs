@@ -0,0 +1,256 @@
+use crate::engine::eval::Evaluator;
+use crate::engine::search::{SearchAlgorithm, StopSignal};
+use crate::engine::Engine;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Condvar, Mutex};
+
+type Configure<E, S> = Box<dyn FnOnce(&mut Engine<E, S>) + Send>;
+
+/// A fixed set of engines built up front and shared between callers.
+///
+/// Engines are checked out for the duration of one request and returned to the
+/// pool when the guard is dropped, so transposition tables and other per-engine
+/// allocations are reused across requests. Each engine keeps its index, below
+/// `size`, by which its options are set with `configure`.
+pub struct EnginePool<E: Evaluator, S: SearchAlgorithm> {
+    idle: Mutex<Vec<(usize, Engine<E, S>)>>,
+    available: Condvar,
+    active: Mutex<Vec<StopSignal>>,
+    /// Configuration waiting for each checked-out engine to come back.
+    pending: Mutex<Vec<Vec<Configure<E, S>>>>,
+    size: usize,
+}
+
+impl<E: Evaluator, S: SearchAlgorithm> EnginePool<E, S> {
+    pub fn new(size: usize, factory: impl Fn() -> Engine<E, S>) -> Self {
+        let engines = (0..size).map(|index| (index, factory())).collect();
+        Self {
+            idle: Mutex::new(engines),
+            available: Condvar::new(),
+            active: Mutex::new(Vec::new()),
+            pending: Mutex::new((0..size).map(|_| Vec::new()).collect()),
+            size,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn idle_count(&self) -> usize {
+        self.idle.lock().expect("pool lock").len()
+    }
+
+    /// Blocks until an engine is free.
+    pub fn checkout(&self) -> PooledEngine<'_, E, S> {
+        let mut idle = self.idle.lock().expect("pool lock");
+        loop {
+            if let Some((index, engine)) = idle.pop() {
+                return self.lease(index, engine);
+            }
+            idle = self.available.wait(idle).expect("pool lock");
+        }
+    }
+
+    pub fn try_checkout(&self) -> Option<PooledEngine<'_, E, S>> {
+        let (index, engine) = self.idle.lock().expect("pool lock").pop()?;
+        Some(self.lease(index, engine))
+    }
+
+    /// Sets options on engine `index`, such as its hash size or skill: at
+    /// once if it is idle, otherwise when it is checked in, before anyone
+    /// can check it out again.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is not below `size`.
+    pub fn configure(
+        &self,
+        index: usize,
+        configure: impl FnOnce(&mut Engine<E, S>) + Send + 'static,
+    ) {
+        assert!(
+            index < self.size,
+            "no engine {index} in a pool of {}",
+            self.size
+        );
+        let mut idle = self.idle.lock().expect("pool lock");
+        match idle.iter_mut().find(|(idle_index, _)| *idle_index == index) {
+            Some((_, engine)) => configure(engine),
+            None => self.pending.lock().expect("pool lock")[index].push(Box::new(configure)),
+        }
+    }
+
+    /// `configure` for every engine.
+    pub fn configure_all(&self, configure: impl Fn(&mut Engine<E, S>) + Send + Sync + 'static) {
+        let configure = Arc::new(configure);
+        for index in 0..self.size {
+            let configure = Arc::clone(&configure);
+            self.configure(index, move |engine| configure(engine));
+        }
+    }
+
+    /// Aborts every search running on a checked-out engine. The cancel
+    /// sticks until a search ends: an engine between searches aborts its
+    /// next one instead, and the search after that runs normally.
+    pub fn cancel_all(&self) {
+        for stop in self.active.lock().expect("pool lock").iter() {
+            stop.cancel();
+        }
+    }
+
+    fn lease(&self, index: usize, mut engine: Engine<E, S>) -> PooledEngine<'_, E, S> {
+        engine.clear_stop();
+        self.active
+            .lock()
+            .expect("pool lock")
+            .push(engine.stop_handle());
+        PooledEngine {
+            engine: Some(engine),
+            index,
+            pool: self,
+        }
+    }
+
+    fn checkin(&self, index: usize, mut engine: Engine<E, S>) {
+        let stop = engine.stop_handle();
+        self.active
+            .lock()
+            .expect("pool lock")
+            .retain(|active| !active.same_signal(&stop));
+        let mut idle = self.idle.lock().expect("pool lock");
+        let pending = std::mem::take(&mut self.pending.lock().expect("pool lock")[index]);
+        for configure in pending {
+            configure(&mut engine);
+        }
+        idle.push((index, engine));
+        self.available.notify_one();
+    }
+}
+
+/// An engine on loan from an [`EnginePool`]; dereferences to the engine and
+/// returns it to the pool on drop. Use `stop_handle` to cancel its search
+/// from another thread.
+pub struct PooledEngine<'a, E: Evaluator, S: SearchAlgorithm> {
+    engine: Option<Engine<E, S>>,
+    index: usize,
+    pool: &'a EnginePool<E, S>,
+}
+
+impl<E: Evaluator, S: SearchAlgorithm> PooledEngine<'_, E, S> {
+    /// The engine's index in its pool, as `EnginePool::configure` takes it.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl<E: Evaluator, S: SearchAlgorithm> Deref for PooledEngine<'_, E, S> {
+    type Target = Engine<E, S>;
+
+    fn deref(&self) -> &Self::Target {
+        self.engine.as_ref().expect("engine checked out")
+    }
+}
+
+impl<E: Evaluator, S: SearchAlgorithm> DerefMut for PooledEngine<'_, E, S> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.engine.as_mut().expect("engine checked out")
+    }
+}
+
+impl<E: Evaluator, S: SearchAlgorithm> Drop for PooledEngine<'_, E, S> {
+    fn drop(&mut self) {
+        if let Some(engine) = self.engine.take() {
+            self.pool.checkin(self.index, engine);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::eval::MaterialEvaluator;
+    use crate::engine::search::AlphaBetaSearch;
+    use crate::engine::skill::Skill;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    fn pool(size: usize) -> EnginePool<MaterialEvaluator, AlphaBetaSearch> {
+        EnginePool::new(size, || {
+            Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new())
+        })
+    }
+
+    #[test]
+    fn checkout_and_checkin() {
+        let pool = pool(2);
+        {
+            let _a = pool.checkout();
+            let _b = pool.checkout();
+            assert_eq!(pool.idle_count(), 0);
+            assert!(pool.try_checkout().is_none());
+        }
+        assert_eq!(pool.idle_count(), 2);
+    }
+
+    #[test]
+    fn cancel_stops_running_search() {
+        let pool = pool(1);
+        thread::scope(|scope| {
+            let mut engine = pool.checkout();
+            let cancel = engine.stop_handle();
+            let started = Instant::now();
+            let search = scope.spawn(move || {
+                engine.set_position_startpos();
+                engine.search_depth(64)
+            });
+            thread::sleep(Duration::from_millis(50));
+            cancel.stop();
+            let best_move = search.join().expect("search thread");
+            assert_ne!(best_move, "0000");
+            assert!(started.elapsed() < Duration::from_secs(10));
+        });
+        assert_eq!(pool.idle_count(), 1);
+    }
+
+    #[test]
+    fn cancel_all_sticks_until_the_next_search_ends() {
+        let pool = pool(1);
+        let mut engine = pool.checkout();
+        engine.set_position_startpos();
+        pool.cancel_all();
+
+        let started = Instant::now();
+        let (_, cancelled) = engine.search_iterative_results(64);
+        assert!(cancelled.is_empty());
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert!(!engine.stop_handle().is_stopped());
+        let (_, searched) = engine.search_iterative_results(3);
+        assert_eq!(searched.len(), 3);
+    }
+
+    #[test]
+    fn configures_engines_by_index_once_idle() {
+        let pool = pool(2);
+        let (skill, idle_skill) = (Some(Skill::new(1500)), Some(Skill::new(1200)));
+        let engine = pool.checkout();
+        let busy = engine.index();
+        pool.configure(busy, move |engine| engine.set_skill(skill));
+        pool.configure(1 - busy, move |engine| engine.set_skill(idle_skill));
+        assert_eq!(engine.skill(), None);
+        drop(engine);
+
+        let (first, second) = (pool.checkout(), pool.checkout());
+        let (configured, other) = if first.index() == busy {
+            (first, second)
+        } else {
+            (second, first)
+        };
+        assert_eq!(configured.skill(), skill);
+        assert_eq!(other.skill(), idle_skill);
+        drop((configured, other));
+
+        pool.configure_all(|engine| engine.set_skill(None));
+        assert!(pool.checkout().skill().is_none());
+    }
+}
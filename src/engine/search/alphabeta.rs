@@ -1,18 +1,56 @@
 use crate::engine::board::Board;
-use crate::engine::eval::Evaluator;
-use crate::engine::movegen::{generate_legal, is_king_in_check};
+use crate::engine::checks::{check_legal, check_score, check_window};
+use crate::engine::eval::{piece_value, Evaluator};
+use crate::engine::movegen::{generate_evasions, generate_legal};
+use crate::engine::search::extensions::{is_capture, LineState};
+use crate::engine::search::history::MoveHistory;
+use crate::engine::search::node::NodeType;
+use crate::engine::search::params::SearchParams;
 #[cfg(feature = "qsearch")]
 use crate::engine::search::quiescence::quiesce_ab;
-use crate::engine::search::traits::{SearchAlgorithm, SearchResult};
-use crate::engine::search::tt::{Bound, TTEntry, TranspositionTable};
-use crate::engine::types::Move;
+use crate::engine::search::score::{evaluate, Score};
+use crate::engine::search::signal::StopSignal;
+use crate::engine::search::threads::{aggregate, run_with_helpers};
+use crate::engine::search::traits::{
+    HashUsage, RootBound, RootBoundObserver, RootMoveObserver, SearchAlgorithm, SearchResult,
+    SearchStats,
+};
+use crate::engine::search::tt::{
+    Bound, NoTable, TTEntry, TableBackend, TranspositionTable, DEFAULT_HASH_MB,
+};
+use crate::engine::types::{Color, Move, Square};
+use std::time::Instant;
 
-const MATE_SCORE: i32 = 30_000;
-const QUIESCE_DEPTH: u32 = 4;
-const TT_SIZE: usize = 1 << 20;
+// Poll the stop signal once every 1024 nodes.
+const STOP_CHECK_MASK: u64 = 1023;
 
-pub struct AlphaBetaSearch {
-    tt: TranspositionTable,
+/// Alpha-beta search, keeping the positions it has seen in a `T`: a
+/// `TranspositionTable` unless built `without_table`.
+pub struct AlphaBetaSearch<T = TranspositionTable> {
+    tt: T,
+    stop: StopSignal,
+    params: SearchParams,
+    root_color: Color,
+    node_limit: Option<u64>,
+    deadline: Option<Instant>,
+    root_move_observer: Option<RootMoveObserver>,
+    root_bound_observer: Option<RootBoundObserver>,
+    /// Longest move history reached in the current search, for seldepth.
+    deepest: usize,
+    hash_megabytes: usize,
+    /// Per-thread state of the helper threads, one per thread beyond the
+    /// calling one.
+    helpers: Vec<AlphaBetaSearch<T>>,
+    helper_stop: StopSignal,
+    history: MoveHistory,
+    /// Length of the board's move history at the root, to find a node's ply.
+    root_ply: usize,
+    stats: SearchStats,
+    /// Whether `SymmetricHash` is on.
+    symmetric_hash: bool,
+    /// Whether the current search shares entries between mirror positions;
+    /// see `table_key`.
+    fold: bool,
 }
 
 impl Default for AlphaBetaSearch {
@@ -23,80 +61,349 @@ impl Default for AlphaBetaSearch {
 
 impl AlphaBetaSearch {
     pub fn new() -> Self {
+        Self::with_params(SearchParams::default())
+    }
+
+    pub fn with_params(params: SearchParams) -> Self {
+        Self::with_backend(params)
+    }
+}
+
+impl AlphaBetaSearch<NoTable> {
+    /// A search that allocates no transposition table, for targets with
+    /// strict memory limits. It plays the same moves, searching more nodes
+    /// to find them.
+    pub fn without_table() -> Self {
+        Self::with_backend(SearchParams::default())
+    }
+}
+
+impl<T: TableBackend> AlphaBetaSearch<T> {
+    /// A search keeping its positions in a `T` of the default `Hash` size.
+    pub fn with_backend(params: SearchParams) -> Self {
         Self {
-            tt: TranspositionTable::new(TT_SIZE),
+            tt: T::with_megabytes(DEFAULT_HASH_MB),
+            stop: StopSignal::new(),
+            params,
+            root_color: Color::White,
+            node_limit: None,
+            deadline: None,
+            root_move_observer: None,
+            root_bound_observer: None,
+            deepest: 0,
+            hash_megabytes: DEFAULT_HASH_MB,
+            helpers: Vec::new(),
+            helper_stop: StopSignal::new(),
+            history: MoveHistory::new(),
+            root_ply: 0,
+            stats: SearchStats::default(),
+            symmetric_hash: false,
+            fold: false,
         }
     }
 }
 
-impl SearchAlgorithm for AlphaBetaSearch {
+impl<T: TableBackend> SearchAlgorithm for AlphaBetaSearch<T> {
+    fn set_stop_signal(&mut self, signal: StopSignal) {
+        self.stop = signal;
+    }
+
+    fn set_contempt(&mut self, centipawns: i32) {
+        self.params.contempt = centipawns;
+    }
+
+    fn set_symmetric_hash(&mut self, enabled: bool) {
+        self.symmetric_hash = enabled;
+        for helper in &mut self.helpers {
+            helper.set_symmetric_hash(enabled);
+        }
+    }
+
+    fn set_node_limit(&mut self, limit: Option<u64>) {
+        self.node_limit = limit;
+    }
+
+    fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = deadline;
+    }
+
+    fn set_root_move_observer(&mut self, observer: Option<RootMoveObserver>) {
+        self.root_move_observer = observer;
+    }
+
+    fn set_root_bound_observer(&mut self, observer: Option<RootBoundObserver>) {
+        self.root_bound_observer = observer;
+    }
+
+    fn set_hash_size(&mut self, megabytes: usize) {
+        self.hash_megabytes = megabytes;
+        self.resize_tables();
+    }
+
+    fn clear_hash(&mut self) {
+        self.tt.clear();
+        for helper in &mut self.helpers {
+            helper.clear_hash();
+        }
+    }
+
+    fn new_game(&mut self) {
+        self.tt.clear();
+        self.history.clear();
+        for helper in &mut self.helpers {
+            helper.new_game();
+        }
+    }
+
+    fn set_threads(&mut self, threads: usize) {
+        let helpers = threads.saturating_sub(1);
+        self.helpers.truncate(helpers);
+        while self.helpers.len() < helpers {
+            let mut helper = Self::with_backend(self.params);
+            helper.symmetric_hash = self.symmetric_hash;
+            self.helpers.push(helper);
+        }
+        self.resize_tables();
+    }
+
+    // Helper threads fill tables of their own, so the estimate averages
+    // over all of them.
+    fn hashfull(&self) -> Option<u32> {
+        let sum: u32 = self.helpers.iter().map(|helper| helper.tt.hashfull()).sum();
+        Some((self.tt.hashfull() + sum) / (1 + self.helpers.len() as u32))
+    }
+
+    fn hash_usage(&self) -> Option<HashUsage> {
+        let tables = std::iter::once(&self.tt).chain(self.helpers.iter().map(|helper| &helper.tt));
+        Some(tables.fold(HashUsage::default(), |usage, tt| HashUsage {
+            tables: usage.tables + 1,
+            entries: usage.entries + tt.len(),
+            bytes: usage.bytes + tt.memory_bytes(),
+        }))
+    }
+
+    fn stats(&self) -> Option<SearchStats> {
+        Some(self.stats)
+    }
+
+    fn hash_move(&self, board: &Board) -> Option<Move> {
+        self.probe(board).and_then(|entry| entry.best_move)
+    }
+
     fn search(
         &mut self,
         board: &mut Board,
         evaluator: &impl Evaluator,
         depth: u32,
     ) -> SearchResult {
-        self.search_root(board, evaluator, depth, None)
+        self.search_threads(board, evaluator, depth, None, None)
     }
 
-    fn search_with_root_ordering(
+    fn search_with_root_moves(
         &mut self,
         board: &mut Board,
         evaluator: &impl Evaluator,
         depth: u32,
-        preferred_root: Option<&[crate::engine::types::Move]>,
+        preferred_root: Option<&[Move]>,
+        root_moves: Option<&[Move]>,
     ) -> SearchResult {
-        self.search_root(board, evaluator, depth, preferred_root)
+        self.search_threads(board, evaluator, depth, preferred_root, root_moves)
     }
 }
 
-impl AlphaBetaSearch {
+impl<T: TableBackend> AlphaBetaSearch<T> {
+    // Every thread has a table of its own, so `Hash` is split evenly between
+    // them rather than allocated once per thread.
+    fn resize_tables(&mut self) {
+        let megabytes = self.hash_megabytes / (1 + self.helpers.len());
+        self.tt.resize(megabytes);
+        for helper in &mut self.helpers {
+            helper.hash_megabytes = megabytes;
+            helper.tt.resize(megabytes);
+        }
+    }
+
+    // The table key of `board`, and whether it is the key of its mirror.
+    // Folding keys each position and its color-flipped mirror by the smaller
+    // of their hashes. Scores are from the side to move's view, so a mirror's
+    // entry holds without negation; only its best move needs mirroring back.
+    fn table_key(&self, board: &Board) -> (u64, bool) {
+        if !self.fold {
+            return (board.hash(), false);
+        }
+        let mirror = board.mirror_hash();
+        if mirror < board.hash() {
+            (mirror, true)
+        } else {
+            (board.hash(), false)
+        }
+    }
+
+    fn probe(&self, board: &Board) -> Option<TTEntry> {
+        let (key, mirrored) = self.table_key(board);
+        let mut entry = self.tt.probe(key)?;
+        if mirrored {
+            entry.best_move = entry.best_move.map(mirror_move);
+        }
+        Some(entry)
+    }
+
+    fn store(
+        &mut self,
+        board: &Board,
+        depth: u32,
+        score: Score,
+        bound: Bound,
+        best_move: Option<Move>,
+    ) {
+        let (key, mirrored) = self.table_key(board);
+        self.tt.store(TTEntry {
+            key,
+            depth,
+            score: score.get(),
+            bound,
+            best_move: if mirrored {
+                best_move.map(mirror_move)
+            } else {
+                best_move
+            },
+        });
+    }
+
+    /// Score of a drawn position for the side to move there, seen from the
+    /// root side's contempt: a positive contempt makes draws look bad for it.
+    fn draw_score(&self, board: &Board) -> Score {
+        let contempt = Score::new(self.params.contempt);
+        if board.side_to_move == self.root_color {
+            -contempt
+        } else {
+            contempt
+        }
+    }
+
+    // A position met earlier on the path from the root is a draw: whichever
+    // side gains from the cycle could have avoided it, so searching on only
+    // burns nodes shuffling pieces. Positions from before the root count
+    // once they occur a third time, as in play. The board's hash history is
+    // the path stack; positions before the last capture or pawn move cannot
+    // repeat.
+    fn is_repetition(&self, board: &Board) -> bool {
+        let reversible = board.halfmove_clock as usize;
+        let start = self
+            .root_ply
+            .max(board.history.len().saturating_sub(reversible));
+        board
+            .history
+            .get(start..)
+            .is_some_and(|path| path.contains(&board.hash()))
+            || board.repetition_count() >= 3
+    }
+
+    // Searches on this thread and, while it runs, on every helper thread.
+    fn search_threads(
+        &mut self,
+        board: &mut Board,
+        evaluator: &impl Evaluator,
+        depth: u32,
+        preferred_root: Option<&[Move]>,
+        root_moves: Option<&[Move]>,
+    ) -> SearchResult {
+        if self.helpers.is_empty() {
+            return self.search_root(board, evaluator, depth, preferred_root, root_moves);
+        }
+
+        let mut helpers = std::mem::take(&mut self.helpers);
+        for helper in &mut helpers {
+            helper.stop = self.helper_stop.clone();
+            helper.params = self.params;
+            helper.deadline = self.deadline;
+        }
+        let helper_stop = self.helper_stop.clone();
+        // Boards cache state internally, so each helper gets its own copy.
+        let mut states: Vec<(&mut AlphaBetaSearch<T>, Board)> = helpers
+            .iter_mut()
+            .map(|helper| (helper, board.clone()))
+            .collect();
+        let (main, helper_results) = run_with_helpers(
+            &mut states,
+            &helper_stop,
+            || self.search_root(board, evaluator, depth, preferred_root, root_moves),
+            |_, (helper, board)| helper.search_root(board, evaluator, depth, None, root_moves),
+        );
+        drop(states);
+        self.helpers = helpers;
+        aggregate(main, &helper_results)
+    }
+
     fn search_root(
         &mut self,
         board: &mut Board,
         evaluator: &impl Evaluator,
         depth: u32,
         preferred_root: Option<&[Move]>,
+        root_moves: Option<&[Move]>,
     ) -> SearchResult {
-        let mut nodes = 0;
+        self.root_color = board.side_to_move;
+        let root_ply = board.history.len();
+        self.root_ply = root_ply;
+        self.deepest = root_ply;
+        self.history.age();
+        self.stats = SearchStats::default();
+        // The root is a visited position too.
+        let mut nodes = 1;
         let mut best_moves = Vec::new();
-        let mut best_score = i32::MIN;
-        let mut alpha = i32::MIN + 1;
-        let beta = i32::MAX;
+        let mut best_score = -Score::INFINITE;
+        let mut alpha = -Score::INFINITE;
+        let beta = Score::INFINITE;
         let alpha_orig = alpha;
 
         let mut moves = generate_legal(board);
-        let tt_best = self
-            .tt
-            .probe(board.hash())
-            .and_then(|entry| entry.best_move);
-        moves = reorder_moves(&moves, tt_best, preferred_root);
+        if let Some(allowed) = root_moves {
+            moves.retain(|mv| allowed.contains(mv));
+        }
+        // Mirror positions only share entries when they are worth the same:
+        // no contempt and an evaluator that scores every mirror alike.
+        self.fold = self.symmetric_hash && self.params.contempt == 0 && evaluator.is_symmetric();
+        // The preferred moves, the previous iteration's best, come ahead of
+        // the table move: a new best in an aborted iteration then has beaten
+        // them.
+        let tt_best = self.probe(board).and_then(|entry| entry.best_move);
+        moves = reorder_moves(&moves, preferred_root, tt_best);
 
         if moves.is_empty() {
             return SearchResult {
                 best_moves: Vec::new(),
-                score: evaluator.evaluate(board),
+                score: evaluate(evaluator, board).get(),
                 nodes,
+                seldepth: (self.deepest - root_ply) as u32,
             };
         }
 
+        let line = LineState::new(self.params.max_extensions);
         let mut first_move = true;
-        for mv in moves {
+        for (index, mv) in moves.into_iter().enumerate() {
+            if let Some(observer) = &self.root_move_observer {
+                observer(mv, index + 1);
+            }
+            let (extension, child_line) = line.extend(&self.params, board, mv);
+            check_legal(board, mv);
             let undo = match board.make_move(mv) {
                 Ok(undo) => undo,
                 Err(_) => continue,
             };
             let mut exact = false;
-            let mut score = i32::MIN;
+            let mut score;
             if first_move {
                 score = -alphabeta(
                     self,
                     board,
                     evaluator,
-                    depth.saturating_sub(1),
+                    (depth + extension).saturating_sub(1),
                     -beta,
                     -alpha,
                     &mut nodes,
+                    child_line,
+                    NodeType::Pv,
                 );
                 exact = true;
                 first_move = false;
@@ -106,25 +413,41 @@ impl AlphaBetaSearch {
                     self,
                     board,
                     evaluator,
-                    depth.saturating_sub(1),
+                    (depth + extension).saturating_sub(1),
                     -null_beta,
                     -alpha,
                     &mut nodes,
+                    child_line,
+                    NodeType::Cut,
                 );
                 if score > alpha {
+                    if let Some(observer) = &self.root_bound_observer {
+                        observer(&RootBound {
+                            depth,
+                            best_move: mv,
+                            score: score.get(),
+                            bound: Bound::Lower,
+                        });
+                    }
                     score = -alphabeta(
                         self,
                         board,
                         evaluator,
-                        depth.saturating_sub(1),
+                        (depth + extension).saturating_sub(1),
                         -beta,
                         -alpha,
                         &mut nodes,
+                        child_line,
+                        NodeType::Pv,
                     );
                     exact = true;
                 }
             }
             board.unmake_move(mv, undo);
+            if self.stop.is_stopped() {
+                break;
+            }
+            check_score(board, score.get());
             if exact {
                 if score > best_score {
                     best_score = score;
@@ -139,6 +462,15 @@ impl AlphaBetaSearch {
             }
         }
 
+        if self.stop.is_stopped() {
+            return SearchResult {
+                best_moves,
+                score: best_score.get(),
+                nodes,
+                seldepth: (self.deepest - root_ply) as u32,
+            };
+        }
+
         let bound = if best_score <= alpha_orig {
             Bound::Upper
         } else if best_score >= beta {
@@ -146,97 +478,220 @@ impl AlphaBetaSearch {
         } else {
             Bound::Exact
         };
-        let key = board.hash();
-        self.tt.store(TTEntry {
-            key,
-            depth,
-            score: best_score,
-            bound,
-            best_move: best_moves.first().copied(),
-        });
+        // A restricted root's best is only the best of the moves allowed;
+        // kept in the table, it would lead the next full search.
+        if root_moves.is_none() {
+            self.store(board, depth, best_score, bound, best_moves.first().copied());
+        }
 
         SearchResult {
             best_moves,
-            score: best_score,
+            score: best_score.get(),
             nodes,
+            seldepth: (self.deepest - root_ply) as u32,
         }
     }
 }
 
-fn alphabeta(
-    search: &mut AlphaBetaSearch,
+#[allow(clippy::too_many_arguments)]
+fn alphabeta<T: TableBackend>(
+    search: &mut AlphaBetaSearch<T>,
     board: &mut Board,
     evaluator: &impl Evaluator,
     depth: u32,
-    mut alpha: i32,
-    beta: i32,
+    mut alpha: Score,
+    beta: Score,
     nodes: &mut u64,
-) -> i32 {
+    line: LineState,
+    node: NodeType,
+) -> Score {
     *nodes += 1;
+    search.deepest = search.deepest.max(board.history.len());
+    if search.node_limit.is_some_and(|limit| *nodes > limit) {
+        search.stop.stop();
+    }
+    if *nodes & STOP_CHECK_MASK == 0 {
+        if search
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            search.stop.stop();
+        }
+        if search.stop.is_stopped() {
+            return Score::DRAW;
+        }
+    }
+    check_window(alpha.get(), beta.get());
     let alpha_orig = alpha;
+    if search.is_repetition(board) {
+        search.stats.repetitions += 1;
+        return search.draw_score(board);
+    }
 
-    if let Some(entry) = search.tt.probe(board.hash()) {
-        if entry.depth >= depth {
-            match entry.bound {
-                Bound::Exact => return entry.score,
-                Bound::Lower if entry.score >= beta => return entry.score,
-                Bound::Upper if entry.score <= alpha => return entry.score,
-                _ => {}
+    search.stats.tt_probes += 1;
+    if let Some(entry) = search.probe(board) {
+        search.stats.tt_hits += 1;
+        let score = Score::new(entry.score);
+        if entry.depth >= depth
+            && match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => score >= beta,
+                Bound::Upper => score <= alpha,
             }
+        {
+            search.stats.tt_cutoffs += 1;
+            return score;
         }
     }
 
     if depth == 0 {
-        if !is_king_in_check(board, board.side_to_move) {
+        if !board.in_check() {
             #[cfg(feature = "qsearch")]
             {
-                return quiesce_ab(board, evaluator, alpha, beta, nodes, QUIESCE_DEPTH);
+                return quiesce_ab(
+                    board,
+                    evaluator,
+                    alpha,
+                    beta,
+                    nodes,
+                    &mut search.deepest,
+                    search.params.quiesce_depth,
+                );
             }
             #[cfg(not(feature = "qsearch"))]
             {
-                return evaluator.evaluate(board);
+                return evaluate(evaluator, board);
             }
         }
 
         let moves = generate_legal(board);
         if moves.is_empty() {
-            // Subtract depth so faster mates score higher and slower losses are preferred.
-            return -MATE_SCORE - depth as i32;
+            return Score::mated_in(depth);
         }
         #[cfg(feature = "qsearch")]
         {
-            return quiesce_ab(board, evaluator, alpha, beta, nodes, QUIESCE_DEPTH);
+            return quiesce_ab(
+                board,
+                evaluator,
+                alpha,
+                beta,
+                nodes,
+                &mut search.deepest,
+                search.params.quiesce_depth,
+            );
         }
         #[cfg(not(feature = "qsearch"))]
         {
-            return evaluator.evaluate(board);
+            return evaluate(evaluator, board);
         }
     }
 
-    let moves = generate_legal(board);
+    let moves = if board.in_check() {
+        generate_evasions(board)
+    } else {
+        generate_legal(board)
+    };
     if moves.is_empty() {
-        if is_king_in_check(board, board.side_to_move) {
-            // Subtract depth so faster mates score higher and slower losses are preferred.
-            return -MATE_SCORE - depth as i32;
+        if board.in_check() {
+            return Score::mated_in(depth);
         }
-        return 0;
+        return search.draw_score(board);
     }
 
-    let tt_best = search
-        .tt
-        .probe(board.hash())
-        .and_then(|entry| entry.best_move);
-    let moves = reorder_moves(&moves, tt_best, None);
+    let tt_best = search.probe(board).and_then(|entry| entry.best_move);
+    let ply = board.history.len() - search.root_ply;
+    let color = board.side_to_move;
+    let moves = order_moves(board, &moves, tt_best, &search.history, ply);
 
-    let mut best = i32::MIN;
+    let in_check = board.in_check();
+    let params = search.params;
+    let late_move_pruning = params.late_move_pruning && depth <= params.lmp_depth && !in_check;
+    // Every node out of check records its evaluation, so the nodes two plies
+    // down can tell whether they are improving whatever their depth.
+    let line = if in_check {
+        line
+    } else {
+        line.with_static_eval(evaluate(evaluator, board).get())
+    };
+    let quiet_limit = params.lmp_quiet_limit(depth);
+    // The principal variation is never pruned, though its evaluations still
+    // tell the nodes below whether they are improving.
+    let prune_late_quiets = late_move_pruning && !node.is_pv() && !line.improving();
+
+    let mut best = -Score::INFINITE;
     let mut best_move = None;
+    let mut quiets_tried = Vec::new();
+    let mut searched = 0;
     for mv in moves {
+        let quiet = mv.promotion.is_none() && !is_capture(board, mv);
+        // Past the first few quiet moves, one that has never refuted
+        // anything is unlikely to, once some move avoids being mated.
+        let prunable = prune_late_quiets
+            && quiet
+            && quiets_tried.len() >= quiet_limit
+            && !best.is_mated()
+            && !search.history.is_killer(ply, mv)
+            && search.history.score(color, mv) <= 0;
+        let (extension, child_line) = line.extend(&params, board, mv);
+        check_legal(board, mv);
         let undo = match board.make_move(mv) {
             Ok(undo) => undo,
             Err(_) => continue,
         };
-        let score = -alphabeta(search, board, evaluator, depth - 1, -beta, -alpha, nodes);
+        if prunable && !board.in_check() {
+            board.unmake_move(mv, undo);
+            search.stats.lmp_pruned += 1;
+            continue;
+        }
+        let child = node.child(searched);
+        let mut score;
+        if node.is_pv() && child != NodeType::Pv {
+            // A later move at a PV node is scouted as the cut node it is
+            // expected to be, and searched again as a PV node if it beats
+            // the best so far, as at the root.
+            score = -alphabeta(
+                search,
+                board,
+                evaluator,
+                depth - 1 + extension,
+                (-alpha).saturating_sub(1),
+                -alpha,
+                nodes,
+                child_line,
+                child,
+            );
+            if score > alpha && score < beta && !search.stop.is_stopped() {
+                score = -alphabeta(
+                    search,
+                    board,
+                    evaluator,
+                    depth - 1 + extension,
+                    -beta,
+                    -alpha,
+                    nodes,
+                    child_line,
+                    NodeType::Pv,
+                );
+            }
+        } else {
+            score = -alphabeta(
+                search,
+                board,
+                evaluator,
+                depth - 1 + extension,
+                -beta,
+                -alpha,
+                nodes,
+                child_line,
+                child,
+            );
+        }
         board.unmake_move(mv, undo);
+        if search.stop.is_stopped() {
+            return Score::DRAW;
+        }
+        check_score(board, score.get());
+        searched += 1;
         if score > best {
             best = score;
             best_move = Some(mv);
@@ -245,8 +700,31 @@ fn alphabeta(
             alpha = score;
         }
         if alpha >= beta {
+            search.stats.beta_cutoffs += 1;
+            if searched == 1 {
+                search.stats.first_move_cutoffs += 1;
+            }
+            if quiet {
+                search
+                    .history
+                    .record_cutoff(color, ply, mv, depth, &quiets_tried);
+            }
             break;
         }
+        if quiet {
+            quiets_tried.push(mv);
+        }
+    }
+
+    match node {
+        NodeType::Pv => search.stats.pv_nodes += 1,
+        NodeType::Cut => {
+            search.stats.cut_nodes += 1;
+            if best >= beta {
+                search.stats.cut_nodes_confirmed += 1;
+            }
+        }
+        NodeType::All => {}
     }
 
     let bound = if best <= alpha_orig {
@@ -256,40 +734,60 @@ fn alphabeta(
     } else {
         Bound::Exact
     };
-    search.tt.store(TTEntry {
-        key: board.hash(),
-        depth,
-        score: best,
-        bound,
-        best_move,
-    });
+    search.store(board, depth, best, bound, best_move);
 
     best
 }
 
-fn reorder_moves(moves: &[Move], primary: Option<Move>, preferred: Option<&[Move]>) -> Vec<Move> {
-    let mut ordered = Vec::with_capacity(moves.len());
-    if let Some(primary) = primary {
-        if moves.iter().any(|candidate| *candidate == primary) {
-            ordered.push(primary);
-        }
+// The move on the color-flipped board, as `Board::flipped` maps squares.
+fn mirror_move(mv: Move) -> Move {
+    Move {
+        from: Square(mv.from.index() ^ 0x70),
+        to: Square(mv.to.index() ^ 0x70),
+        ..mv
     }
+}
 
-    if let Some(preferred) = preferred {
-        for mv in preferred {
-            if moves.iter().any(|candidate| candidate == mv)
-                && !ordered.iter().any(|candidate| candidate == mv)
-            {
-                ordered.push(*mv);
-            }
-        }
-    }
+// Interior move order: the hash move, then captures and promotions by
+// victim value (least valuable attacker first among equals), then killers,
+// then the remaining quiet moves by history score.
+fn order_moves(
+    board: &Board,
+    moves: &[Move],
+    tt_best: Option<Move>,
+    history: &MoveHistory,
+    ply: usize,
+) -> Vec<Move> {
+    let color = board.side_to_move;
+    let value = |square: Square| {
+        board.squares[square.index() as usize].map_or(0, |piece| piece_value(piece.kind))
+    };
+    let mut keyed: Vec<(i64, Move)> = moves
+        .iter()
+        .map(|&mv| {
+            let key = if Some(mv) == tt_best {
+                i64::MAX
+            } else if mv.promotion.is_some() || is_capture(board, mv) {
+                let victim = mv.promotion.map_or(0, piece_value) + value(mv.to).max(100);
+                (1 << 40) + i64::from(victim) * 16 - i64::from(value(mv.from)) / 100
+            } else if history.is_killer(ply, mv) {
+                1 << 32
+            } else {
+                i64::from(history.score(color, mv))
+            };
+            (key, mv)
+        })
+        .collect();
+    keyed.sort_by_key(|(key, _)| std::cmp::Reverse(*key));
+    keyed.into_iter().map(|(_, mv)| mv).collect()
+}
 
-    for mv in moves {
-        if !ordered.iter().any(|candidate| candidate == mv) {
+fn reorder_moves(moves: &[Move], preferred: Option<&[Move]>, next: Option<Move>) -> Vec<Move> {
+    let mut ordered = Vec::with_capacity(moves.len());
+    for mv in preferred.into_iter().flatten().chain(&next).chain(moves) {
+        if moves.contains(mv) && !ordered.contains(mv) {
             ordered.push(*mv);
         }
     }
-
     ordered
 }
@@ -0,0 +1,110 @@
+//! Easy move detection: search the root without the best move.
+//!
+//! When every other move scores far below the best one, as with a forced
+//! recapture, more thinking will not change the choice and the time manager
+//! can answer almost at once.
+
+use crate::engine::board::Board;
+use crate::engine::eval::Evaluator;
+use crate::engine::movegen::generate_legal;
+use crate::engine::search::traits::SearchAlgorithm;
+use crate::engine::types::Move;
+
+/// How far every alternative must score below the best move.
+pub const EASY_MOVE_MARGIN: i32 = 200;
+
+/// The iteration after which an easy move is looked for, provided the best
+/// move has not changed up to it.
+pub const EASY_MOVE_DEPTH: u32 = 2;
+
+/// Searches the root moves other than `best` at `depth` and reports whether
+/// all of them fall more than `EASY_MOVE_MARGIN` below `best_score`. A move
+/// without alternatives is always easy. `root_moves` restricts the root like
+/// `SearchAlgorithm::search_with_root_moves`.
+pub fn detect_easy_move(
+    search: &mut impl SearchAlgorithm,
+    board: &mut Board,
+    evaluator: &impl Evaluator,
+    depth: u32,
+    best: Move,
+    best_score: i32,
+    root_moves: Option<&[Move]>,
+) -> bool {
+    let alternatives: Vec<Move> = root_moves
+        .map_or_else(|| generate_legal(board), <[Move]>::to_vec)
+        .into_iter()
+        .filter(|mv| *mv != best)
+        .collect();
+    if alternatives.is_empty() {
+        return true;
+    }
+    let result = search.search_with_root_moves(board, evaluator, depth, None, Some(&alternatives));
+    !result.best_moves.is_empty() && best_score.saturating_sub(result.score) > EASY_MOVE_MARGIN
+}
+
+/// The time budget for a move once it has been found easy.
+pub fn easy_budget_ms(budget_ms: u64) -> u64 {
+    budget_ms / 16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::eval::MaterialEvaluator;
+    use crate::engine::search::AlphaBetaSearch;
+    use crate::engine::types::move_from_uci;
+
+    // Searches `fen` to `depth` and asks whether its first best move is easy.
+    fn search_easy(fen: &str, depth: u32) -> (Vec<Move>, bool) {
+        let mut board = Board::new();
+        board.set_fen(fen).expect("fen");
+        let mut search = AlphaBetaSearch::new();
+        let result = search.search(&mut board, &MaterialEvaluator, depth);
+        let easy = detect_easy_move(
+            &mut search,
+            &mut board,
+            &MaterialEvaluator,
+            depth,
+            result.best_moves[0],
+            result.score,
+            None,
+        );
+        (result.best_moves, easy)
+    }
+
+    #[test]
+    fn taking_a_hanging_queen_is_easy() {
+        let (best, easy) = search_easy("4k3/8/8/8/3q4/8/8/3QK3 w - - 0 1", 2);
+        assert_eq!(best, vec![move_from_uci("d1d4").expect("move")]);
+        assert!(easy);
+    }
+
+    #[test]
+    fn the_table_keeps_the_best_move_after_detection() {
+        let mut board = Board::new();
+        board
+            .set_fen("4k3/8/8/8/3q4/8/8/3QK3 w - - 0 1")
+            .expect("fen");
+        let mut search = AlphaBetaSearch::new();
+        let result = search.search(&mut board, &MaterialEvaluator, 2);
+        let best = result.best_moves[0];
+        detect_easy_move(
+            &mut search,
+            &mut board,
+            &MaterialEvaluator,
+            2,
+            best,
+            result.score,
+            None,
+        );
+        // The search without `best` must not leave an alternative as the
+        // root's table move, or the next iteration would try it first.
+        assert_eq!(search.hash_move(&board), Some(best));
+    }
+
+    #[test]
+    fn a_choice_between_similar_moves_is_not_easy() {
+        let (_, easy) = search_easy("8/8/8/8/8/4k3/8/Q3K3 w - - 0 1", 2);
+        assert!(!easy);
+    }
+}
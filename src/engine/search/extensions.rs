@@ -0,0 +1,226 @@
+//! Search extensions: moves that are searched a ply deeper than the rest.
+//!
+//! Every extension along a line spends from a budget capped by
+//! `SearchParams::max_extensions`, so a line of forcing moves cannot grow the
+//! tree without bound.
+
+use crate::engine::board::Board;
+use crate::engine::search::params::SearchParams;
+use crate::engine::see::see;
+use crate::engine::types::{Color, Move, PieceKind, Square};
+
+/// What the search stack knows about the line leading to a node: the
+/// extensions left, the previous move and recent static evaluations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineState {
+    remaining: u32,
+    /// Where the previous move captured, if it did.
+    capture_square: Option<Square>,
+    /// This node's static evaluation, when the search computed one.
+    static_eval: Option<i32>,
+    /// Static evaluations of the parent and grandparent nodes.
+    previous_evals: [Option<i32>; 2],
+}
+
+impl LineState {
+    pub fn new(max_extensions: u32) -> Self {
+        Self {
+            remaining: max_extensions,
+            capture_square: None,
+            static_eval: None,
+            previous_evals: [None; 2],
+        }
+    }
+
+    pub fn remaining(&self) -> u32 {
+        self.remaining
+    }
+
+    pub fn with_static_eval(self, eval: i32) -> Self {
+        Self {
+            static_eval: Some(eval),
+            ..self
+        }
+    }
+
+    /// Whether the side to move stands better than two plies ago. Without
+    /// both evaluations the position counts as improving, which keeps
+    /// pruning conservative.
+    pub fn improving(&self) -> bool {
+        match (self.static_eval, self.previous_evals[1]) {
+            (Some(now), Some(before)) => now > before,
+            _ => true,
+        }
+    }
+
+    /// Plies `mv` is extended by, and the state of the line below it.
+    /// `board` is the position before `mv`.
+    pub fn extend(self, params: &SearchParams, board: &Board, mv: Move) -> (u32, Self) {
+        let captures = is_capture(board, mv);
+        let extension = u32::from(
+            self.remaining > 0
+                && ((params.passed_pawn_extension && is_passed_pawn_push(board, mv))
+                    || (params.recapture_extension
+                        && captures
+                        && self.capture_square == Some(mv.to)
+                        && see(board, mv) >= 0)),
+        );
+        let child = Self {
+            remaining: self.remaining - extension,
+            capture_square: captures.then_some(mv.to),
+            static_eval: None,
+            previous_evals: [self.static_eval, self.previous_evals[0]],
+        };
+        (extension, child)
+    }
+}
+
+pub(crate) fn is_capture(board: &Board, mv: Move) -> bool {
+    // Chess960 castling is written as the king taking its own rook.
+    board.squares[mv.to.index() as usize].is_some_and(|piece| piece.color != board.side_to_move)
+        || (board.en_passant == Some(mv.to)
+            && board.squares[mv.from.index() as usize]
+                .is_some_and(|piece| piece.kind == PieceKind::Pawn))
+}
+
+/// Whether `mv` moves a passed pawn to its sixth or seventh rank, where it
+/// is close to promoting and the search should see the push through.
+pub fn is_passed_pawn_push(board: &Board, mv: Move) -> bool {
+    let Some(piece) = board.squares[mv.from.index() as usize] else {
+        return false;
+    };
+    if piece.kind != PieceKind::Pawn {
+        return false;
+    }
+    let rank = mv.to.index() >> 4;
+    let relative_rank = match piece.color {
+        Color::White => rank,
+        Color::Black => 7 - rank,
+    };
+    matches!(relative_rank, 5 | 6) && is_passed(board, piece.color, mv.to.index())
+}
+
+// No enemy pawn ahead of `square` on its own or a neighbouring file.
+fn is_passed(board: &Board, color: Color, square: u8) -> bool {
+    let (rank, file) = ((square >> 4) as i8, (square & 7) as i8);
+    let ahead: Vec<i8> = match color {
+        Color::White => (rank + 1..8).collect(),
+        Color::Black => (0..rank).collect(),
+    };
+    !ahead.iter().any(|&r| {
+        (file - 1..=file + 1)
+            .filter(|f| (0..8).contains(f))
+            .any(|f| {
+                board.squares[((r << 4) | f) as usize]
+                    .is_some_and(|piece| piece.kind == PieceKind::Pawn && piece.color != color)
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::types::{move_from_uci, square_from_algebraic};
+
+    fn board_from(fen: &str) -> Board {
+        let mut board = Board::new();
+        board.set_fen(fen).expect("fen");
+        board
+    }
+
+    #[test]
+    fn captures_include_en_passant_but_not_own_pieces() {
+        let board = board_from("4k3/8/8/3pP3/8/8/8/R3K2R w KQ d6 0 1");
+        let capture = |uci| is_capture(&board, move_from_uci(uci).expect("move"));
+        assert!(capture("e5d6"));
+        assert!(!capture("e5e6"));
+        // Chess960 castling, written as the king taking its own rook.
+        assert!(!capture("e1h1"));
+
+        let board = board_from("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1");
+        assert!(!is_capture(&board, move_from_uci("e5d6").expect("move")));
+    }
+
+    #[test]
+    fn detects_passed_pawn_pushes_to_the_sixth_and_seventh() {
+        let board = board_from("4k3/8/8/1P2P3/8/8/8/4K3 w - - 0 1");
+        let push = |uci| is_passed_pawn_push(&board, move_from_uci(uci).expect("move"));
+        assert!(push("b5b6"));
+        assert!(push("e5e6"));
+        assert!(!push("e1e2"));
+
+        let blocked = board_from("4k3/3p4/8/4P3/8/8/8/4K3 w - - 0 1");
+        assert!(!is_passed_pawn_push(
+            &blocked,
+            move_from_uci("e5e6").expect("move")
+        ));
+        let black = board_from("4k3/8/8/8/8/3p4/8/K7 b - - 0 1");
+        assert!(is_passed_pawn_push(
+            &black,
+            move_from_uci("d3d2").expect("move")
+        ));
+    }
+
+    #[test]
+    fn extensions_stop_when_the_budget_runs_out() {
+        let board = board_from("4k3/8/8/4P3/8/8/8/4K3 w - - 0 1");
+        let push = move_from_uci("e5e6").expect("move");
+        let params = SearchParams::default();
+
+        let (plies, left) = LineState::new(1).extend(&params, &board, push);
+        assert_eq!((plies, left.remaining()), (1, 0));
+        assert_eq!(left.extend(&params, &board, push).0, 0);
+
+        let off = SearchParams {
+            passed_pawn_extension: false,
+            ..params
+        };
+        assert_eq!(LineState::new(1).extend(&off, &board, push).0, 0);
+    }
+
+    #[test]
+    fn recaptures_on_the_capture_square_are_extended_when_enabled() {
+        let params = SearchParams {
+            recapture_extension: true,
+            ..SearchParams::default()
+        };
+        // The previous move captured on d5.
+        let after_capture = LineState {
+            capture_square: square_from_algebraic("d5"),
+            ..LineState::new(2)
+        };
+        let extend = |params: &SearchParams, line: LineState, fen, uci| {
+            let mv = move_from_uci(uci).expect("move");
+            line.extend(params, &board_from(fen), mv).0
+        };
+
+        // The pawn or the rook can take back the queen.
+        let queen = "3rk3/8/8/3q4/4P3/8/8/3RK2r w - - 0 1";
+        assert_eq!(extend(&params, after_capture, queen, "e4d5"), 1);
+        assert_eq!(extend(&params, after_capture, queen, "d1d5"), 1);
+        assert_eq!(extend(&params, after_capture, queen, "e1f1"), 0);
+        assert_eq!(extend(&params, LineState::new(2), queen, "e4d5"), 0);
+        let off = SearchParams::default();
+        assert_eq!(extend(&off, after_capture, queen, "e4d5"), 0);
+
+        // Taking a defended knight with the rook loses material.
+        let defended = "3rk3/8/8/3n4/8/8/8/3RK3 w - - 0 1";
+        assert_eq!(extend(&params, after_capture, defended, "d1d5"), 0);
+    }
+
+    #[test]
+    fn improving_compares_with_two_plies_ago() {
+        let board = board_from("4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+        let params = SearchParams::default();
+        let mv = move_from_uci("e1e2").expect("move");
+        let reply = move_from_uci("e8e7").expect("move");
+
+        let root = LineState::new(0).with_static_eval(10);
+        assert!(root.improving());
+        let (_, child) = root.extend(&params, &board, mv);
+        let (_, grandchild) = child.with_static_eval(-10).extend(&params, &board, reply);
+        assert!(grandchild.with_static_eval(20).improving());
+        assert!(!grandchild.with_static_eval(10).improving());
+        assert!(grandchild.improving());
+    }
+}
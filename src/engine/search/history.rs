@@ -0,0 +1,150 @@
+//! Move-ordering memory gathered during a search: killer moves per ply and
+//! a history score per quiet move. Both learn from beta cutoffs, so quiet
+//! moves that refuted siblings are tried early, and late move pruning knows
+//! which quiet moves have never been useful.
+
+use crate::engine::types::{Color, Move};
+
+// Squares are 0x88 indices, so from and to each fit in 128 slots.
+const SQUARES: usize = 128;
+// A score reaching this halves the whole table, so long searches keep
+// learning and old statistics decay without any score saturating.
+const HISTORY_LIMIT: i32 = 1 << 20;
+
+pub struct MoveHistory {
+    killers: Vec<[Option<Move>; 2]>,
+    scores: Vec<i32>,
+}
+
+impl Default for MoveHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MoveHistory {
+    pub fn new() -> Self {
+        Self {
+            killers: Vec::new(),
+            scores: vec![0; 2 * SQUARES * SQUARES],
+        }
+    }
+
+    /// Called before each search: killers belong to the old tree, and older
+    /// history counts half.
+    pub fn age(&mut self) {
+        self.killers.clear();
+        self.decay();
+    }
+
+    /// Forgets everything learned, for a new game.
+    pub fn clear(&mut self) {
+        self.killers.clear();
+        self.scores.fill(0);
+    }
+
+    pub fn is_killer(&self, ply: usize, mv: Move) -> bool {
+        self.killers
+            .get(ply)
+            .is_some_and(|killers| killers.contains(&Some(mv)))
+    }
+
+    pub fn score(&self, color: Color, mv: Move) -> i32 {
+        self.scores[index(color, mv)]
+    }
+
+    /// Records that quiet move `mv` caused a beta cutoff at `ply` with
+    /// `depth` left, after the quiet moves in `tried` failed to.
+    pub fn record_cutoff(
+        &mut self,
+        color: Color,
+        ply: usize,
+        mv: Move,
+        depth: u32,
+        tried: &[Move],
+    ) {
+        if self.killers.len() <= ply {
+            self.killers.resize(ply + 1, [None; 2]);
+        }
+        let killers = &mut self.killers[ply];
+        if killers[0] != Some(mv) {
+            killers[1] = killers[0];
+            killers[0] = Some(mv);
+        }
+
+        let bonus = (depth * depth) as i32;
+        self.adjust(color, mv, bonus);
+        for quiet in tried.iter().filter(|quiet| **quiet != mv) {
+            self.adjust(color, *quiet, -bonus);
+        }
+    }
+
+    fn adjust(&mut self, color: Color, mv: Move, delta: i32) {
+        let score = &mut self.scores[index(color, mv)];
+        *score += delta;
+        if score.abs() >= HISTORY_LIMIT {
+            self.decay();
+        }
+    }
+
+    fn decay(&mut self) {
+        for score in &mut self.scores {
+            *score /= 2;
+        }
+    }
+}
+
+fn index(color: Color, mv: Move) -> usize {
+    let side = match color {
+        Color::White => 0,
+        Color::Black => 1,
+    };
+    (side * SQUARES + mv.from.index() as usize) * SQUARES + mv.to.index() as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::types::move_from_uci;
+
+    #[test]
+    fn cutoffs_update_killers_and_history() {
+        let mut history = MoveHistory::new();
+        let cutoff = move_from_uci("g1f3").expect("move");
+        let tried = move_from_uci("a2a3").expect("move");
+        history.record_cutoff(Color::White, 2, cutoff, 3, &[tried, cutoff]);
+
+        assert!(history.is_killer(2, cutoff));
+        assert!(!history.is_killer(1, cutoff));
+        assert_eq!(history.score(Color::White, cutoff), 9);
+        assert_eq!(history.score(Color::White, tried), -9);
+        assert_eq!(history.score(Color::Black, cutoff), 0);
+
+        history.age();
+        assert!(!history.is_killer(2, cutoff));
+        assert_eq!(history.score(Color::White, cutoff), 4);
+
+        history.record_cutoff(Color::White, 2, cutoff, 3, &[cutoff]);
+        history.clear();
+        assert!(!history.is_killer(2, cutoff));
+        assert_eq!(history.score(Color::White, cutoff), 0);
+    }
+
+    #[test]
+    fn full_scores_age_the_table_instead_of_saturating() {
+        let mut history = MoveHistory::new();
+        let frequent = move_from_uci("g1f3").expect("move");
+        let rare = move_from_uci("b1c3").expect("move");
+        for cutoff in 0..20_000 {
+            let mv = if cutoff & 3 == 0 { rare } else { frequent };
+            history.record_cutoff(Color::White, 0, mv, 30, &[mv]);
+        }
+
+        let (frequent, rare) = (
+            history.score(Color::White, frequent),
+            history.score(Color::White, rare),
+        );
+        assert!(frequent < HISTORY_LIMIT, "{frequent}");
+        assert!(frequent > 2 * rare && rare > 0, "{frequent} vs {rare}");
+    }
+}
@@ -0,0 +1,281 @@
+//! Depth-first mate solver, independent of the evaluation-driven searches.
+//!
+//! It answers a narrower question than alpha-beta — "can the side to move
+//! force mate within N moves?" — which makes it both a stronger solver for
+//! mate puzzles and an oracle for checking the mate scores alpha-beta reports.
+
+use crate::engine::board::Board;
+use crate::engine::movegen::{
+    generate_captures, generate_evasions, generate_legal, generate_quiet_checks,
+};
+use crate::engine::search::score::Score;
+use crate::engine::search::signal::StopSignal;
+use crate::engine::search::MATE_SCORE;
+use crate::engine::types::Move;
+use std::collections::HashMap;
+
+// Poll the stop signal once every 1024 nodes.
+const STOP_CHECK_MASK: u64 = 1023;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MateProof {
+    /// Mate distance in moves of the side to move (1 = mate in one).
+    pub moves: u32,
+    pub best_move: Move,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MateSearch {
+    /// The shortest forced mate within the limit, if one was proven.
+    pub proof: Option<MateProof>,
+    pub nodes: u64,
+    /// False when the node budget ran out or the search was stopped, so a
+    /// missing proof is not a proof that no mate exists.
+    pub complete: bool,
+}
+
+/// Finds the shortest forced mate of at most `max_moves` moves for the side
+/// to move, visiting at most `node_limit` positions.
+pub fn solve_mate(board: &mut Board, max_moves: u32, node_limit: u64) -> MateSearch {
+    solve_mate_until(board, max_moves, node_limit, &StopSignal::new())
+}
+
+/// Like `solve_mate`, but also gives up once `stop` is raised.
+pub fn solve_mate_until(
+    board: &mut Board,
+    max_moves: u32,
+    node_limit: u64,
+    stop: &StopSignal,
+) -> MateSearch {
+    let mut solver = MateSolver {
+        nodes: 0,
+        node_limit,
+        stop: stop.clone(),
+        proven: HashMap::new(),
+    };
+
+    for moves in 1..=max_moves {
+        for mv in solver.attacker_moves(board, moves) {
+            match solver.move_mates(board, mv, moves) {
+                Some(true) => {
+                    return MateSearch {
+                        proof: Some(MateProof {
+                            moves,
+                            best_move: mv,
+                        }),
+                        nodes: solver.nodes,
+                        complete: true,
+                    };
+                }
+                Some(false) => {}
+                None => {
+                    return MateSearch {
+                        proof: None,
+                        nodes: solver.nodes,
+                        complete: false,
+                    };
+                }
+            }
+        }
+    }
+
+    MateSearch {
+        proof: None,
+        nodes: solver.nodes,
+        complete: true,
+    }
+}
+
+/// Converts a search score into a mate distance in moves for the side to
+/// move, given the depth it was searched to. Negative when being mated.
+pub fn mate_moves_from_score(score: i32, depth: u32) -> Option<i32> {
+    // Mate scores are MATE_SCORE plus the depth left when mate was found.
+    let depth = depth as i32;
+    let score = Score::new(score);
+    if score.is_mated() {
+        let plies = depth - (-score.get() - MATE_SCORE);
+        Some(-(plies / 2))
+    } else if score.is_mate() {
+        let plies = depth - (score.get() - MATE_SCORE);
+        Some((plies + 1) / 2)
+    } else {
+        None
+    }
+}
+
+struct MateSolver {
+    nodes: u64,
+    node_limit: u64,
+    stop: StopSignal,
+    // Keyed by (hash, moves left): whether the side to move mates in time.
+    proven: HashMap<(u64, u32), bool>,
+}
+
+impl MateSolver {
+    // Counts a node; false once the budget is spent or the search stopped.
+    fn visit(&mut self) -> bool {
+        self.nodes += 1;
+        self.nodes <= self.node_limit
+            && ((self.nodes - 1) & STOP_CHECK_MASK != 0 || !self.stop.is_stopped())
+    }
+
+    /// `None` means the node budget ran out.
+    fn attacker_wins(&mut self, board: &mut Board, moves: u32) -> Option<bool> {
+        let key = (board.hash(), moves);
+        if let Some(&wins) = self.proven.get(&key) {
+            return Some(wins);
+        }
+
+        let mut wins = false;
+        for mv in self.attacker_moves(board, moves) {
+            if self.move_mates(board, mv, moves)? {
+                wins = true;
+                break;
+            }
+        }
+        self.proven.insert(key, wins);
+        Some(wins)
+    }
+
+    // Whether `mv` forces mate within `moves` moves, counting `mv` itself.
+    fn move_mates(&mut self, board: &mut Board, mv: Move, moves: u32) -> Option<bool> {
+        if !self.visit() {
+            return None;
+        }
+        let undo = board.make_move(mv).ok()?;
+        let replies = if board.in_check() {
+            generate_evasions(board)
+        } else {
+            generate_legal(board)
+        };
+
+        let result = if replies.is_empty() {
+            Some(board.in_check())
+        } else if moves == 1 {
+            Some(false)
+        } else {
+            self.every_reply_loses(board, &replies, moves - 1)
+        };
+        board.unmake_move(mv, undo);
+        result
+    }
+
+    fn every_reply_loses(
+        &mut self,
+        board: &mut Board,
+        replies: &[Move],
+        moves: u32,
+    ) -> Option<bool> {
+        for reply in replies {
+            if !self.visit() {
+                return None;
+            }
+            let undo = board.make_move(*reply).ok()?;
+            let wins = self.attacker_wins(board, moves);
+            board.unmake_move(*reply, undo);
+            if !wins? {
+                return Some(false);
+            }
+        }
+        Some(true)
+    }
+
+    // Checks first, since forced mates mostly run through them. With one
+    // move left only checks can mate.
+    fn attacker_moves(&mut self, board: &mut Board, moves: u32) -> Vec<Move> {
+        if moves == 1 {
+            let mut checks = generate_captures(board);
+            checks.retain(|mv| gives_check(board, *mv));
+            checks.extend(generate_quiet_checks(board));
+            return checks;
+        }
+
+        let mut checks = Vec::new();
+        let mut quiet = Vec::new();
+        for mv in generate_legal(board) {
+            if gives_check(board, mv) {
+                checks.push(mv);
+            } else {
+                quiet.push(mv);
+            }
+        }
+        checks.extend(quiet);
+        checks
+    }
+}
+
+fn gives_check(board: &mut Board, mv: Move) -> bool {
+    let Ok(undo) = board.make_move(mv) else {
+        return false;
+    };
+    let gives_check = board.in_check();
+    board.unmake_move(mv, undo);
+    gives_check
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::eval::MaterialEvaluator;
+    use crate::engine::search::{AlphaBetaSearch, SearchAlgorithm};
+    use crate::engine::types::move_from_uci;
+
+    fn board(fen: &str) -> Board {
+        let mut board = Board::new();
+        board.set_fen(fen).expect("fen");
+        board
+    }
+
+    #[test]
+    fn proves_back_rank_mate_in_one() {
+        let mut board = board("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        let search = solve_mate(&mut board, 3, 100_000);
+        assert!(search.complete);
+        assert_eq!(
+            search.proof,
+            Some(MateProof {
+                moves: 1,
+                best_move: move_from_uci("a1a8").unwrap(),
+            })
+        );
+    }
+
+    #[test]
+    fn proves_mate_in_two_with_quiet_first_move() {
+        // 1. Kb6 (quiet) then Rh8# or Rg8# whatever Black plays.
+        let mut board = board("k7/8/2K5/8/8/8/8/7R w - - 0 1");
+        let search = solve_mate(&mut board, 2, 1_000_000);
+        let proof = search.proof.expect("mate");
+        assert_eq!(proof.moves, 2);
+    }
+
+    #[test]
+    fn stopped_search_is_incomplete() {
+        let mut board = board("k7/8/2K5/8/8/8/8/7R w - - 0 1");
+        let stop = StopSignal::new();
+        stop.stop();
+        let search = solve_mate_until(&mut board, 5, u64::MAX, &stop);
+        assert!(!search.complete);
+        assert_eq!(search.proof, None);
+    }
+
+    #[test]
+    fn reports_no_mate_when_none_exists() {
+        let mut board = board("4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+        let search = solve_mate(&mut board, 2, 1_000_000);
+        assert_eq!(search.proof, None);
+        assert!(search.complete);
+    }
+
+    #[test]
+    fn agrees_with_alphabeta_mate_score() {
+        let mut board = board("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        let depth = 3;
+        let result = AlphaBetaSearch::new().search(&mut board, &MaterialEvaluator, depth);
+        let proof = solve_mate(&mut board, 3, 100_000).proof.expect("mate");
+        assert_eq!(
+            mate_moves_from_score(result.score, depth),
+            Some(proof.moves as i32)
+        );
+    }
+}
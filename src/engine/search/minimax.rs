@@ -1,70 +1,83 @@
 use crate::engine::board::Board;
 use crate::engine::eval::Evaluator;
-use crate::engine::movegen::{generate_legal, is_king_in_check};
+use crate::engine::movegen::generate_legal;
 #[cfg(feature = "qsearch")]
 use crate::engine::search::quiescence::quiesce_mm;
+use crate::engine::search::score::{evaluate, Score};
+use crate::engine::search::signal::StopSignal;
 use crate::engine::search::traits::{SearchAlgorithm, SearchResult};
+use std::time::Instant;
 
-const MATE_SCORE: i32 = 30_000;
+#[cfg(feature = "qsearch")]
 const QUIESCE_DEPTH: u32 = 4;
 
-pub struct MinimaxSearch;
+// Poll the stop signal once every 1024 nodes.
+const STOP_CHECK_MASK: u64 = 1023;
+
+/// Plain negamax over every move, for checking the faster searches against.
+pub struct MinimaxSearch {
+    stop: StopSignal,
+    node_limit: Option<u64>,
+    deadline: Option<Instant>,
+}
+
+impl Default for MinimaxSearch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MinimaxSearch {
+    pub fn new() -> Self {
+        Self {
+            stop: StopSignal::new(),
+            node_limit: None,
+            deadline: None,
+        }
+    }
+}
 
 impl SearchAlgorithm for MinimaxSearch {
+    fn set_stop_signal(&mut self, signal: StopSignal) {
+        self.stop = signal;
+    }
+
+    fn set_node_limit(&mut self, limit: Option<u64>) {
+        self.node_limit = limit;
+    }
+
+    fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = deadline;
+    }
+
     fn search(
         &mut self,
         board: &mut Board,
         evaluator: &impl Evaluator,
         depth: u32,
     ) -> SearchResult {
-        let mut nodes = 0;
-        let mut best_moves = Vec::new();
-        let mut best_score = i32::MIN;
-
-        let moves = generate_legal(board);
-        if moves.is_empty() {
-            return SearchResult {
-                best_moves: Vec::new(),
-                score: evaluator.evaluate(board),
-                nodes,
-            };
-        }
-
-        for mv in moves {
-            let undo = match board.make_move(mv) {
-                Ok(undo) => undo,
-                Err(_) => continue,
-            };
-            let score = -negamax(board, evaluator, depth.saturating_sub(1), &mut nodes);
-            board.unmake_move(mv, undo);
-            if score > best_score {
-                best_score = score;
-                best_moves.clear();
-                best_moves.push(mv);
-            } else if score == best_score {
-                best_moves.push(mv);
-            }
-        }
-
-        SearchResult {
-            best_moves,
-            score: best_score,
-            nodes,
-        }
+        self.search_with_root_moves(board, evaluator, depth, None, None)
     }
 
-    fn search_with_root_ordering(
+    fn search_with_root_moves(
         &mut self,
         board: &mut Board,
         evaluator: &impl Evaluator,
         depth: u32,
         preferred_root: Option<&[crate::engine::types::Move]>,
+        root_moves: Option<&[crate::engine::types::Move]>,
     ) -> SearchResult {
-        let mut nodes = 0;
+        // The root is a visited position too.
+        let mut nodes = 1;
+        let root_ply = board.history.len();
+        let mut deepest = root_ply;
         let mut best_moves = Vec::new();
-        let mut best_score = i32::MIN;
+        let mut best_score = -Score::INFINITE;
 
         let mut moves = generate_legal(board);
+        if let Some(allowed) = root_moves {
+            moves.retain(|mv| allowed.contains(mv));
+        }
         if let Some(preferred) = preferred_root {
             moves = reorder_root_moves(&moves, preferred);
         }
@@ -72,8 +85,9 @@ impl SearchAlgorithm for MinimaxSearch {
         if moves.is_empty() {
             return SearchResult {
                 best_moves: Vec::new(),
-                score: evaluator.evaluate(board),
+                score: evaluate(evaluator, board).get(),
                 nodes,
+                seldepth: 0,
             };
         }
 
@@ -82,8 +96,18 @@ impl SearchAlgorithm for MinimaxSearch {
                 Ok(undo) => undo,
                 Err(_) => continue,
             };
-            let score = -negamax(board, evaluator, depth.saturating_sub(1), &mut nodes);
+            let score = -negamax(
+                self,
+                board,
+                evaluator,
+                depth.saturating_sub(1),
+                &mut nodes,
+                &mut deepest,
+            );
             board.unmake_move(mv, undo);
+            if self.stop.is_stopped() {
+                break;
+            }
             if score > best_score {
                 best_score = score;
                 best_moves.clear();
@@ -95,8 +119,9 @@ impl SearchAlgorithm for MinimaxSearch {
 
         SearchResult {
             best_moves,
-            score: best_score,
+            score: best_score.get(),
             nodes,
+            seldepth: (deepest - root_ply) as u32,
         }
     }
 }
@@ -124,52 +149,75 @@ fn reorder_root_moves(
 // When we make a move, the side to move flips, so a good score for them is a bad
 // score for us. That’s why we negate the child score: it “re-centers” the value
 // to the current player. This collapses max/min into a single loop.
-fn negamax(board: &mut Board, evaluator: &impl Evaluator, depth: u32, nodes: &mut u64) -> i32 {
+fn negamax(
+    search: &mut MinimaxSearch,
+    board: &mut Board,
+    evaluator: &impl Evaluator,
+    depth: u32,
+    nodes: &mut u64,
+    deepest: &mut usize,
+) -> Score {
     *nodes += 1;
+    *deepest = (*deepest).max(board.history.len());
+    if search.node_limit.is_some_and(|limit| *nodes > limit) {
+        search.stop.stop();
+    }
+    if *nodes & STOP_CHECK_MASK == 0 {
+        if search
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            search.stop.stop();
+        }
+        if search.stop.is_stopped() {
+            return Score::DRAW;
+        }
+    }
     if depth == 0 {
-        if !is_king_in_check(board, board.side_to_move) {
+        if !board.in_check() {
             #[cfg(feature = "qsearch")]
             {
-                return quiesce_mm(board, evaluator, nodes, QUIESCE_DEPTH);
+                return quiesce_mm(board, evaluator, nodes, deepest, QUIESCE_DEPTH);
             }
             #[cfg(not(feature = "qsearch"))]
             {
-                return evaluator.evaluate(board);
+                return evaluate(evaluator, board);
             }
         }
 
         let moves = generate_legal(board);
         if moves.is_empty() {
-            // Subtract depth so faster mates score higher and slower losses are preferred.
-            return -MATE_SCORE - depth as i32;
+            return Score::mated_in(depth);
         }
         #[cfg(feature = "qsearch")]
         {
-            return quiesce_mm(board, evaluator, nodes, QUIESCE_DEPTH);
+            return quiesce_mm(board, evaluator, nodes, deepest, QUIESCE_DEPTH);
         }
         #[cfg(not(feature = "qsearch"))]
         {
-            return evaluator.evaluate(board);
+            return evaluate(evaluator, board);
         }
     }
 
     let moves = generate_legal(board);
     if moves.is_empty() {
-        if is_king_in_check(board, board.side_to_move) {
-            // Subtract depth so faster mates score higher and slower losses are preferred.
-            return -MATE_SCORE - depth as i32;
+        if board.in_check() {
+            return Score::mated_in(depth);
         }
-        return 0;
+        return Score::DRAW;
     }
 
-    let mut best = i32::MIN;
+    let mut best = -Score::INFINITE;
     for mv in moves {
         let undo = match board.make_move(mv) {
             Ok(undo) => undo,
             Err(_) => continue,
         };
-        let score = -negamax(board, evaluator, depth - 1, nodes);
+        let score = -negamax(search, board, evaluator, depth - 1, nodes, deepest);
         board.unmake_move(mv, undo);
+        if search.stop.is_stopped() {
+            return Score::DRAW;
+        }
         if score > best {
             best = score;
         }
@@ -1,13 +1,31 @@
 pub mod alphabeta;
+pub mod easy_move;
+pub mod extensions;
+pub mod history;
+pub mod mate;
 pub mod minimax;
-#[cfg(feature = "qsearch")]
+pub mod node;
+pub mod params;
 pub mod quiescence;
+pub(crate) mod score;
+pub mod signal;
+pub mod threads;
+pub mod threat;
 pub mod traits;
 pub mod tt;
 
 pub use alphabeta::AlphaBetaSearch;
 pub use minimax::MinimaxSearch;
-pub use traits::{SearchAlgorithm, SearchResult};
+pub use params::SearchParams;
+pub use signal::StopSignal;
+pub use threat::{detect_threat, Threat};
+pub use traits::{
+    HashUsage, RootBound, RootBoundObserver, RootMoveObserver, SearchAlgorithm, SearchResult,
+    SearchStats,
+};
+
+/// Score for delivering mate; searches adjust it by depth so shorter mates win.
+pub(crate) const MATE_SCORE: i32 = 30_000;
 
 #[cfg(test)]
 mod tests;
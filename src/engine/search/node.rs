@@ -0,0 +1,46 @@
+//! Expected node types, after Knuth and Moore. In a well-ordered tree a PV
+//! node scores inside its window, a cut node fails high on its first move
+//! and an all node searches every move and fails low. The search predicts a
+//! node's type from its parent's before searching it, so pruning can stay
+//! careful where the principal variation runs and be bolder elsewhere.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeType {
+    Pv,
+    Cut,
+    All,
+}
+
+impl NodeType {
+    /// The expected type of the child searched `index`th (from 0): the
+    /// first child of a PV node continues the PV and its later children
+    /// should fail to improve on it, so they are cut nodes; a cut node's
+    /// first child should refute nothing, and once it does, the next child
+    /// is expected to cut instead; an all node's children are cut nodes.
+    pub fn child(self, index: usize) -> Self {
+        match (self, index) {
+            (NodeType::Pv, 0) => NodeType::Pv,
+            (NodeType::Cut, 0) => NodeType::All,
+            _ => NodeType::Cut,
+        }
+    }
+
+    pub fn is_pv(self) -> bool {
+        self == NodeType::Pv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn children_follow_the_minimal_tree() {
+        assert_eq!(NodeType::Pv.child(0), NodeType::Pv);
+        assert_eq!(NodeType::Pv.child(1), NodeType::Cut);
+        assert_eq!(NodeType::Cut.child(0), NodeType::All);
+        assert_eq!(NodeType::Cut.child(2), NodeType::Cut);
+        assert_eq!(NodeType::All.child(0), NodeType::Cut);
+        assert_eq!(NodeType::All.child(5), NodeType::Cut);
+    }
+}
@@ -0,0 +1,158 @@
+//! Tunable search parameters, loadable from a small TOML-style file so the
+//! bench can compare configurations without rebuilding.
+
+use std::fs;
+
+/// Contempt worth one quiet move more or less before late move pruning.
+const CONTEMPT_PER_QUIET: i32 = 25;
+/// Most quiet moves contempt adds to or takes from the pruning limit.
+const MAX_RISK: i32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchParams {
+    /// See `SearchAlgorithm::set_contempt`.
+    pub contempt: i32,
+    /// Maximum quiescence depth below the main search; only used with the
+    /// `qsearch` feature.
+    pub quiesce_depth: u32,
+    /// Most extensions along one line of the main search.
+    pub max_extensions: u32,
+    /// Extend pushes of passed pawns to the sixth and seventh rank.
+    pub passed_pawn_extension: bool,
+    /// Extend recaptures on the square the previous move captured on that
+    /// do not lose material. Off until the bench shows it pays.
+    pub recapture_extension: bool,
+    /// Skip late quiet moves near the leaves; see `lmp_depth`.
+    pub late_move_pruning: bool,
+    /// Late move pruning applies with at most this much depth left, when
+    /// not in check and not improving.
+    pub lmp_depth: u32,
+    /// Quiet moves searched before pruning starts, plus depth squared; see
+    /// `lmp_quiet_limit`.
+    pub lmp_base: u32,
+}
+
+impl Default for SearchParams {
+    fn default() -> Self {
+        Self {
+            contempt: 0,
+            quiesce_depth: 4,
+            max_extensions: 2,
+            passed_pawn_extension: true,
+            recapture_extension: false,
+            late_move_pruning: true,
+            lmp_depth: 3,
+            lmp_base: 3,
+        }
+    }
+}
+
+impl SearchParams {
+    /// Parses `key = value` lines; `#` starts a comment and keys not given
+    /// keep their defaults.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut params = Self::default();
+        parse_key_values(text, |key, value| params.set(key, value))?;
+        Ok(params)
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let text =
+            fs::read_to_string(path).map_err(|err| format!("failed to read {path}: {err}"))?;
+        Self::parse(&text).map_err(|err| format!("{path}: {err}"))
+    }
+
+    /// Quiet moves searched at `depth` before late move pruning starts.
+    /// Contempt makes the search take risks: against weaker opponents it
+    /// prunes sooner and reaches deeper, against stronger ones it looks at
+    /// more moves before giving up on them.
+    pub fn lmp_quiet_limit(&self, depth: u32) -> usize {
+        let risk = (self.contempt / CONTEMPT_PER_QUIET).clamp(-MAX_RISK, MAX_RISK);
+        let limit = (self.lmp_base + depth * depth) as i32 - risk;
+        limit.max(1) as usize
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "contempt" => self.contempt = parse_value(key, value)?,
+            "quiesce_depth" => self.quiesce_depth = parse_value(key, value)?,
+            "max_extensions" => self.max_extensions = parse_value(key, value)?,
+            "passed_pawn_extension" => self.passed_pawn_extension = parse_value(key, value)?,
+            "recapture_extension" => self.recapture_extension = parse_value(key, value)?,
+            "late_move_pruning" => self.late_move_pruning = parse_value(key, value)?,
+            "lmp_depth" => self.lmp_depth = parse_value(key, value)?,
+            "lmp_base" => self.lmp_base = parse_value(key, value)?,
+            _ => return Err(format!("unknown parameter: {key}")),
+        }
+        Ok(())
+    }
+}
+
+/// Calls `set` for each `key = value` line of `text`, skipping blank lines
+/// and `#` comments; errors are prefixed with the line number.
+pub(crate) fn parse_key_values(
+    text: &str,
+    mut set: impl FnMut(&str, &str) -> Result<(), String>,
+) -> Result<(), String> {
+    for (index, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("line {}: expected key = value", index + 1))?;
+        set(key.trim(), value.trim()).map_err(|err| format!("line {}: {err}", index + 1))?;
+    }
+    Ok(())
+}
+
+pub(crate) fn parse_value<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {key}: {value}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_overrides_and_keeps_defaults() {
+        let params = SearchParams::parse("# tuning run\ncontempt = 15  # cp\n\n").expect("parse");
+        assert_eq!(
+            params,
+            SearchParams {
+                contempt: 15,
+                ..SearchParams::default()
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_bad_values() {
+        let err = SearchParams::parse("contempt = 1\nnull_move = true").unwrap_err();
+        assert_eq!(err, "line 2: unknown parameter: null_move");
+        assert!(SearchParams::parse("quiesce_depth = -1").is_err());
+        assert!(SearchParams::parse("contempt").is_err());
+    }
+
+    #[test]
+    fn contempt_moves_the_pruning_limit_within_bounds() {
+        let limit = |contempt, lmp_base| {
+            SearchParams {
+                contempt,
+                lmp_base,
+                ..SearchParams::default()
+            }
+            .lmp_quiet_limit(2)
+        };
+        assert_eq!(limit(0, 3), 7);
+        assert_eq!(limit(30, 3), 6);
+        assert_eq!(limit(-30, 3), 8);
+        assert_eq!(limit(500, 3), 5);
+        assert_eq!(limit(-500, 3), 9);
+        assert_eq!(limit(50, 0), 2);
+        assert_eq!(limit(i32::MAX, 0), 2);
+    }
+}
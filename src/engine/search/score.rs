@@ -0,0 +1,130 @@
+//! Scores the searches pass around. A `Score` is from the side to move's
+//! view and is negated at every ply, so everything a search handles, window
+//! bounds and the "nothing searched yet" sentinel included, stays within
+//! `±INFINITE`: arithmetic on it saturates there instead of overflowing, and
+//! `i32::MIN`, which has no negation, is never produced.
+
+use crate::engine::board::Board;
+use crate::engine::eval::Evaluator;
+use crate::engine::search::MATE_SCORE;
+use crate::engine::trace::span;
+use std::ops::Neg;
+
+/// Beyond every score a search returns, mates included: the full window is
+/// `(-INFINITE, INFINITE)`.
+pub(crate) const INFINITE: i32 = 1_000_000;
+
+/// The largest static evaluation, well short of the mate scores so no
+/// evaluation passes for a mate.
+pub(crate) const MAX_EVAL: i32 = MATE_SCORE - 1_000;
+
+/// A search score in centipawns, or a mate. Mates are `MATE_SCORE` plus the
+/// depth left when the mate was found, so faster mates score higher and
+/// slower losses are preferred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) struct Score(i32);
+
+impl Score {
+    pub(crate) const INFINITE: Score = Score(INFINITE);
+    pub(crate) const DRAW: Score = Score(0);
+
+    /// `centipawns` clamped to `±INFINITE`.
+    #[inline]
+    pub(crate) const fn new(centipawns: i32) -> Score {
+        Score(if centipawns > INFINITE {
+            INFINITE
+        } else if centipawns < -INFINITE {
+            -INFINITE
+        } else {
+            centipawns
+        })
+    }
+
+    #[inline]
+    pub(crate) const fn get(self) -> i32 {
+        self.0
+    }
+
+    /// The score of giving mate with `depth` plies of search left.
+    #[inline]
+    pub(crate) fn mate_in(depth: u32) -> Score {
+        Score::new(MATE_SCORE.saturating_add_unsigned(depth))
+    }
+
+    /// The score of being mated with `depth` plies of search left.
+    #[inline]
+    pub(crate) fn mated_in(depth: u32) -> Score {
+        -Score::mate_in(depth)
+    }
+
+    /// Whether the score is a forced mate for either side rather than an
+    /// evaluation.
+    #[inline]
+    pub(crate) fn is_mate(self) -> bool {
+        self.0.abs() > MAX_EVAL
+    }
+
+    /// Whether the side to move is being mated.
+    #[inline]
+    pub(crate) fn is_mated(self) -> bool {
+        self.0 < -MAX_EVAL
+    }
+
+    /// `self + centipawns`, stopping at `±INFINITE`.
+    #[inline]
+    pub(crate) fn saturating_add(self, centipawns: i32) -> Score {
+        Score::new(self.0.saturating_add(centipawns))
+    }
+
+    /// `self - centipawns`, stopping at `±INFINITE`.
+    #[inline]
+    pub(crate) fn saturating_sub(self, centipawns: i32) -> Score {
+        Score::new(self.0.saturating_sub(centipawns))
+    }
+}
+
+impl Neg for Score {
+    type Output = Score;
+
+    #[inline]
+    fn neg(self) -> Score {
+        Score(-self.0)
+    }
+}
+
+/// `evaluator`'s score for the side to move, clamped to `±MAX_EVAL`, so an
+/// evaluator returning extreme values cannot overflow the search.
+#[inline]
+pub(crate) fn evaluate(evaluator: &impl Evaluator, board: &Board) -> Score {
+    span!(TRACE, "eval");
+    Score(evaluator.evaluate(board).clamp(-MAX_EVAL, MAX_EVAL))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic_saturates_at_infinite() {
+        assert_eq!(Score::new(i32::MAX), Score::INFINITE);
+        assert_eq!(Score::new(i32::MIN), -Score::INFINITE);
+        assert_eq!(Score::INFINITE.saturating_add(1), Score::INFINITE);
+        assert_eq!(
+            (-Score::INFINITE).saturating_sub(i32::MAX),
+            -Score::INFINITE
+        );
+        assert_eq!(Score::new(5).saturating_add(i32::MIN), -Score::INFINITE);
+        assert_eq!(-(-Score::INFINITE), Score::INFINITE);
+    }
+
+    #[test]
+    fn mates_rank_by_depth_and_never_pass_for_evaluations() {
+        assert!(Score::mate_in(3) > Score::mate_in(1));
+        assert!(Score::mated_in(3) < Score::mated_in(1));
+        assert!(Score::mate_in(0).is_mate() && Score::mated_in(0).is_mated());
+        assert!(!Score::mate_in(0).is_mated());
+        assert!(!Score::new(MAX_EVAL).is_mate() && !Score::new(-MAX_EVAL).is_mated());
+        assert_eq!(Score::mate_in(u32::MAX), Score::INFINITE);
+        assert_eq!(Score::mated_in(u32::MAX), -Score::INFINITE);
+    }
+}
@@ -0,0 +1,51 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+
+const RUNNING: u8 = 0;
+const STOPPED: u8 = 1;
+const CANCELLED: u8 = 2;
+
+/// Shared flag used to abort a running search from another thread.
+///
+/// Clones share the same flag, so a handle can be kept by whoever needs to
+/// cancel the search while the search itself polls it.
+#[derive(Debug, Clone, Default)]
+pub struct StopSignal(Arc<AtomicU8>);
+
+impl StopSignal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn stop(&self) {
+        self.0.store(STOPPED, Ordering::Relaxed);
+    }
+
+    /// Like `stop`, but only for one search: the engine lowers the signal
+    /// again once the search running now, or else the next one, has ended.
+    pub fn cancel(&self) {
+        let _ = self
+            .0
+            .compare_exchange(RUNNING, CANCELLED, Ordering::Relaxed, Ordering::Relaxed);
+    }
+
+    pub fn reset(&self) {
+        self.0.store(RUNNING, Ordering::Relaxed);
+    }
+
+    /// Lowers the signal if it was raised by `cancel`, as a search ends.
+    pub fn end_search(&self) {
+        let _ = self
+            .0
+            .compare_exchange(CANCELLED, RUNNING, Ordering::Relaxed, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.0.load(Ordering::Relaxed) != RUNNING
+    }
+
+    /// Whether both handles refer to the same underlying flag.
+    pub fn same_signal(&self, other: &StopSignal) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
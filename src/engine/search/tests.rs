@@ -1,23 +1,86 @@
+use crate::engine::audit::{verify_determinism, AUDIT_FENS};
 use crate::engine::board::Board;
-use crate::engine::eval::MaterialEvaluator;
-use crate::engine::search::{AlphaBetaSearch, MinimaxSearch, SearchAlgorithm};
-use crate::engine::types::uci_from_move;
+use crate::engine::book::Repertoire;
+use crate::engine::eval::{Evaluator, MaterialEvaluator};
+use crate::engine::search::score::MAX_EVAL;
+use crate::engine::search::tt::TranspositionTable;
+use crate::engine::search::{
+    AlphaBetaSearch, MinimaxSearch, SearchAlgorithm, SearchResult, StopSignal,
+};
+use crate::engine::types::{move_from_uci, uci_from_move, Move};
 use crate::engine::Engine;
 
 fn tactical_capture_fen() -> &'static str {
     "3rk3/8/8/8/8/8/8/3QK3 w - - 0 1"
 }
 
+#[cfg(feature = "qsearch")]
 fn quiescence_recapture_fen() -> &'static str {
     "4k3/8/8/8/8/4p3/3p4/3Q2K1 w - - 0 1"
 }
 
+// Scores every position as won or lost outright for the side to move, with
+// the most extreme values an `i32` holds.
+struct ExtremeEvaluator;
+
+impl Evaluator for ExtremeEvaluator {
+    fn evaluate(&self, board: &Board) -> i32 {
+        if board.hash() & 1 == 0 {
+            i32::MAX
+        } else {
+            i32::MIN
+        }
+    }
+}
+
+#[test]
+fn extreme_evaluations_cannot_overflow_the_search() {
+    let mut board = Board::new();
+    board.set_startpos();
+    for depth in 1..=3 {
+        let alphabeta = AlphaBetaSearch::new().search(&mut board, &ExtremeEvaluator, depth);
+        let minimax = MinimaxSearch::new().search(&mut board, &ExtremeEvaluator, depth);
+        for result in [alphabeta, minimax] {
+            assert!(!result.best_moves.is_empty());
+            assert!(result.score.abs() <= MAX_EVAL, "{}", result.score);
+        }
+    }
+
+    // Skill noise on top of an extreme evaluation saturates too.
+    let mut engine = Engine::with_components(ExtremeEvaluator, AlphaBetaSearch::new());
+    engine.set_position_startpos();
+    engine.set_skill(Some(crate::engine::skill::Skill::new(800)));
+    let (_, per_depth) = engine.search_iterative_results(2);
+    assert!(per_depth
+        .iter()
+        .all(|result| result.score.abs() <= MAX_EVAL));
+}
+
+#[test]
+fn mates_outscore_extreme_evaluations() {
+    let mut board = Board::new();
+    board
+        .set_fen("6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1")
+        .expect("fen");
+    let mut search = AlphaBetaSearch::new();
+    // The second search meets the first one's mate scores and bounds in
+    // the transposition table.
+    for _ in 0..2 {
+        let result = search.search(&mut board, &ExtremeEvaluator, 3);
+        assert_eq!(
+            result.best_moves,
+            vec![move_from_uci("d1d8").expect("move")]
+        );
+        assert!(result.score > MAX_EVAL + 1_000, "{}", result.score);
+    }
+}
+
 #[test]
 fn alphabeta_matches_minimax_depth1() {
     let mut board = Board::new();
     board.set_fen(tactical_capture_fen()).expect("fen");
 
-    let mut minimax = MinimaxSearch;
+    let mut minimax = MinimaxSearch::new();
     let mut alphabeta = AlphaBetaSearch::new();
 
     let mut mini_best: Vec<String> = minimax
@@ -44,11 +107,11 @@ fn alphabeta_matches_minimax_depth1() {
 
 #[test]
 fn seeded_search_depth_is_deterministic() {
-    let mut engine_a = Engine::with_components(MaterialEvaluator, MinimaxSearch);
+    let mut engine_a = Engine::with_components(MaterialEvaluator, MinimaxSearch::new());
     engine_a.set_rng_seed(7);
     engine_a.set_position_startpos();
 
-    let mut engine_b = Engine::with_components(MaterialEvaluator, MinimaxSearch);
+    let mut engine_b = Engine::with_components(MaterialEvaluator, MinimaxSearch::new());
     engine_b.set_rng_seed(7);
     engine_b.set_position_startpos();
 
@@ -76,6 +139,38 @@ fn iterative_deepening_best_move_matches_depth_result() {
     assert!(direct_best.iter().any(|mv| mv == &best_move));
 }
 
+#[test]
+fn pv_for_reads_lines_of_searched_positions_from_the_table() {
+    let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+    engine
+        .set_position_fen(tactical_capture_fen())
+        .expect("fen");
+    let mut pv = Vec::new();
+    engine.search_iterative_with_info(4, |info| pv = info.pv.clone());
+    assert!(pv.len() >= 2);
+
+    let root = engine.board().clone();
+    assert_eq!(engine.pv_for(&root, 16), pv);
+    assert_eq!(engine.pv_for(&root, 1), pv[..1]);
+    assert_eq!(engine.board().to_fen(), root.to_fen());
+
+    // A position inside the line has the rest of it.
+    let mut child = root.clone();
+    child.apply_move(pv[0]).expect("move");
+    assert_eq!(engine.pv_for(&child, pv.len() - 1), pv[1..]);
+    assert_eq!(
+        engine.pv_for_fen(&child.to_fen(), pv.len() - 1),
+        Ok(pv[1..].to_vec())
+    );
+
+    // Positions never searched have no line, and nothing is searched.
+    assert!(engine
+        .pv_for_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", 16)
+        .expect("fen")
+        .is_empty());
+    assert!(engine.pv_for_fen("not a fen", 16).is_err());
+}
+
 #[test]
 fn iterative_deepening_nodes_accounting() {
     let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
@@ -119,13 +214,72 @@ fn alphabeta_tt_keeps_best_moves_stable() {
     assert_eq!(first_best, second_best);
 }
 
+#[test]
+fn repetition_on_the_search_path_scores_a_perpetual_as_a_draw() {
+    // Black is two rooks for a queen down but checks forever with
+    // Qe1+ Kh2 Qh4+ Kg1; grabbing a pawn instead loses.
+    let mut board = Board::new();
+    board
+        .set_fen("7k/RR3ppp/8/PPP1q3/8/8/6P1/6K1 b - - 0 1")
+        .expect("fen");
+    let mut search = AlphaBetaSearch::new();
+    let result = search.search(&mut board, &MaterialEvaluator, 6);
+    assert_eq!(result.score, 0);
+    assert_eq!(result.best_moves, vec![move_from_uci("e5e1").unwrap()]);
+    assert!(search.stats().is_some_and(|stats| stats.repetitions > 0));
+}
+
+#[test]
+fn node_types_are_predicted_and_mostly_confirmed() {
+    let mut board = Board::new();
+    board.set_startpos();
+
+    let mut search = AlphaBetaSearch::new();
+    search.search(&mut board, &MaterialEvaluator, 4);
+    let stats = search.stats().expect("stats");
+    // One PV node per ply below the root, at least.
+    assert!(stats.pv_nodes >= 3, "{stats:?}");
+    assert!(stats.cut_nodes > stats.pv_nodes, "{stats:?}");
+    assert!(stats.cut_nodes_confirmed <= stats.cut_nodes);
+    assert!(stats.cut_nodes_confirmed * 2 > stats.cut_nodes, "{stats:?}");
+}
+
+#[test]
+fn clear_hash_forgets_earlier_searches() {
+    let mut board = Board::new();
+    board.set_startpos();
+
+    let mut search = AlphaBetaSearch::new();
+    search.search(&mut board, &MaterialEvaluator, 2);
+    assert!(search.hash_move(&board).is_some());
+
+    search.clear_hash();
+    assert_eq!(search.hash_move(&board), None);
+    assert_eq!(search.hashfull(), Some(0));
+}
+
+#[test]
+fn new_game_clears_the_hash() {
+    let mut board = Board::new();
+    board.set_startpos();
+
+    let mut search = AlphaBetaSearch::new();
+    search.set_threads(2);
+    search.search(&mut board, &MaterialEvaluator, 3);
+    assert!(search.hash_move(&board).is_some());
+
+    search.new_game();
+    assert_eq!(search.hash_move(&board), None);
+    assert_eq!(search.hashfull(), Some(0));
+}
+
 #[cfg(feature = "qsearch")]
 #[test]
 fn minimax_avoids_losing_queen_in_quiescence() {
     let mut board = Board::new();
     board.set_fen(quiescence_recapture_fen()).expect("fen");
 
-    let mut search = MinimaxSearch;
+    let mut search = MinimaxSearch::new();
     let result = search.search(&mut board, &MaterialEvaluator, 1);
     let best_moves: Vec<String> = result
         .best_moves
@@ -176,7 +330,7 @@ fn alphabeta_best_moves_subset_of_minimax_depth2_startpos() {
     let mut board = Board::new();
     board.set_startpos();
 
-    let mut minimax = MinimaxSearch;
+    let mut minimax = MinimaxSearch::new();
     let mut alphabeta = AlphaBetaSearch::new();
 
     let mini_best: Vec<String> = minimax
@@ -205,7 +359,7 @@ fn prefers_mate_over_material_capture() {
         .set_fen("1k6/8/8/8/7Q/8/PPP5/1K1Bq3 b - - 0 1")
         .expect("fen");
 
-    let mut search = MinimaxSearch;
+    let mut search = MinimaxSearch::new();
     let result = search.search(&mut board, &MaterialEvaluator, 1);
     let best_moves: Vec<String> = result
         .best_moves
@@ -215,3 +369,795 @@ fn prefers_mate_over_material_capture() {
 
     assert_eq!(best_moves, vec!["e1d1".to_string()]);
 }
+
+#[test]
+fn engine_stays_in_repertoire_until_out_of_book() {
+    let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+    engine.set_rng_seed(7);
+    let repertoire = Repertoire::from_lines("e2e4 e7e5\nd2d4 d7d5").expect("repertoire");
+    engine.set_repertoire(Some(repertoire));
+    engine.set_position_startpos();
+
+    for _ in 0..8 {
+        let best_move = engine.search_depth(2);
+        assert!(best_move == "e2e4" || best_move == "d2d4");
+    }
+}
+
+#[test]
+fn analysis_searches_every_move_of_book_positions() {
+    let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+    let repertoire = Repertoire::from_lines("e2e4 e7e5 g1f3 d8g5 b1c3").expect("repertoire");
+    engine.set_repertoire(Some(repertoire));
+    let position = ["e2e4", "e7e5", "g1f3", "d8g5"].map(String::from);
+    engine.set_position_startpos();
+    engine.try_apply_move_list(&position).expect("moves");
+    assert_eq!(engine.search_depth(1), "b1c3");
+
+    let infinite = crate::engine::limits::SearchLimits {
+        infinite: true,
+        ..crate::engine::limits::SearchLimits::depth(1)
+    };
+    assert_eq!(engine.search(&infinite, |_| {}).0, "f3g5");
+
+    engine.set_analysing(true);
+    assert_eq!(engine.search_depth(1), "f3g5");
+}
+
+#[test]
+fn search_respects_root_moves() {
+    let mut board = Board::new();
+    board.set_fen(tactical_capture_fen()).expect("fen");
+    let allowed = [move_from_uci("e1e2").unwrap()];
+
+    let mut search = AlphaBetaSearch::new();
+    let result =
+        search.search_with_root_moves(&mut board, &MaterialEvaluator, 2, None, Some(&allowed));
+    assert_eq!(result.best_moves, allowed.to_vec());
+
+    let mut search = MinimaxSearch::new();
+    let result =
+        search.search_with_root_moves(&mut board, &MaterialEvaluator, 2, None, Some(&allowed));
+    assert_eq!(result.best_moves, allowed.to_vec());
+}
+
+#[test]
+fn searches_are_deterministic() {
+    for fen in AUDIT_FENS {
+        verify_determinism(
+            || Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new()),
+            fen,
+            3,
+        )
+        .expect("alphabeta determinism");
+        verify_determinism(
+            || Engine::with_components(MaterialEvaluator, MinimaxSearch::new()),
+            fen,
+            2,
+        )
+        .expect("minimax determinism");
+    }
+}
+
+#[test]
+fn try_apply_move_list_rejects_illegal_moves() {
+    let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+    engine.set_position_startpos();
+    engine
+        .try_apply_move_list(&["e2e4".to_string(), "e7e5".to_string()])
+        .expect("legal moves");
+    let err = engine
+        .try_apply_move_list(&["e4e5".to_string()])
+        .unwrap_err();
+    assert_eq!(err, "illegal move e4e5 at index 0");
+}
+
+// Completes depth 1 with `e2e4`, then raises the stop signal during depth 2 and
+// returns `partial` as the aborted iteration's result.
+struct AbortingSearch {
+    stop: StopSignal,
+    partial: Vec<&'static str>,
+}
+
+impl SearchAlgorithm for AbortingSearch {
+    fn set_stop_signal(&mut self, signal: StopSignal) {
+        self.stop = signal;
+    }
+
+    fn search(
+        &mut self,
+        board: &mut Board,
+        evaluator: &impl Evaluator,
+        depth: u32,
+    ) -> SearchResult {
+        self.search_with_root_moves(board, evaluator, depth, None, None)
+    }
+
+    fn search_with_root_moves(
+        &mut self,
+        _board: &mut Board,
+        _evaluator: &impl Evaluator,
+        depth: u32,
+        _preferred_root: Option<&[Move]>,
+        _root_moves: Option<&[Move]>,
+    ) -> SearchResult {
+        let moves = if depth == 1 {
+            vec!["e2e4"]
+        } else {
+            self.stop.stop();
+            self.partial.clone()
+        };
+        SearchResult {
+            best_moves: moves.into_iter().filter_map(move_from_uci).collect(),
+            score: 0,
+            nodes: 1,
+            seldepth: 0,
+        }
+    }
+}
+
+fn best_after_abort(partial: Vec<&'static str>) -> String {
+    let search = AbortingSearch {
+        stop: StopSignal::new(),
+        partial,
+    };
+    let mut engine = Engine::with_components(MaterialEvaluator, search);
+    engine.set_position_startpos();
+    engine.search_depth(4)
+}
+
+#[test]
+fn aborted_iteration_keeps_root_move_that_beat_previous_best() {
+    assert_eq!(best_after_abort(vec!["d2d4"]), "d2d4");
+    assert_eq!(best_after_abort(vec!["e2e4", "d2d4"]), "e2e4");
+    assert_eq!(best_after_abort(Vec::new()), "e2e4");
+}
+
+#[test]
+fn aborted_alphabeta_iteration_only_trusts_moves_that_beat_the_previous_best() {
+    let mut board = Board::new();
+    board
+        .set_fen("4k3/8/8/8/3q4/8/8/3QK3 w - - 0 1")
+        .expect("fen");
+    let mut search = AlphaBetaSearch::new();
+    // The table's root move is now d1d4, which beats the previous best.
+    search.search(&mut board, &MaterialEvaluator, 3);
+    let previous = [move_from_uci("e1e2").expect("move")];
+
+    // Stop as the second root move starts, so only one is searched.
+    let stop = StopSignal::new();
+    search.set_stop_signal(stop.clone());
+    search.set_root_move_observer(Some(std::sync::Arc::new(move |_, number| {
+        if number == 2 {
+            stop.stop();
+        }
+    })));
+    let partial =
+        search.search_with_root_moves(&mut board, &MaterialEvaluator, 3, Some(&previous), None);
+
+    assert_eq!(partial.best_moves, previous);
+    assert!(!crate::engine::trust_partial_result(
+        &partial,
+        Some(&previous)
+    ));
+}
+
+#[test]
+fn null_move_search_detects_hanging_queen_threat() {
+    let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+    engine
+        .set_position_fen("k7/8/8/3p4/4Q3/8/8/4K3 w - - 0 1")
+        .expect("fen");
+    let result = engine.search_depth_result(2, None);
+
+    let threat = engine.root_threat(2, result.score).expect("threat");
+    assert_eq!(uci_from_move(threat.reply).as_deref(), Some("d5e4"));
+    assert!(threat.swing >= 900);
+}
+
+#[test]
+fn quiet_position_has_no_threat() {
+    let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+    engine
+        .set_position_fen("k7/8/8/3p4/8/8/4Q3/4K3 w - - 0 1")
+        .expect("fen");
+    let result = engine.search_depth_result(2, None);
+
+    assert_eq!(engine.root_threat(2, result.score), None);
+}
+
+#[test]
+fn contempt_steers_away_from_stalemate() {
+    // Kc7 stalemates Black, which is ahead by a bishop.
+    let fen = "k6b/3K2p1/1P4P1/8/8/8/8/8 w - - 0 1";
+    let mut board = Board::new();
+    board.set_fen(fen).expect("fen");
+
+    let mut search = AlphaBetaSearch::new();
+    let result = search.search(&mut board, &MaterialEvaluator, 2);
+    assert_eq!(result.score, 0);
+    assert_eq!(uci_from_move(result.best_moves[0]).as_deref(), Some("d7c7"));
+
+    let mut search = AlphaBetaSearch::new();
+    search.set_contempt(300);
+    let result = search.search(&mut board, &MaterialEvaluator, 2);
+    assert_eq!(result.score, -230);
+    assert!(!result
+        .best_moves
+        .iter()
+        .any(|mv| uci_from_move(*mv).as_deref() == Some("d7c7")));
+}
+
+#[test]
+fn node_limit_ends_search_with_last_completed_iteration() {
+    let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+    engine.set_position_startpos();
+    engine.set_node_limit(Some(5_000));
+
+    let (total_nodes, per_depth) = engine.search_iterative_results(30);
+    assert!(total_nodes > 5_000 && total_nodes < 7_000, "{total_nodes}");
+    assert!(per_depth.len() < 30);
+    assert!(!per_depth.last().expect("iteration").best_moves.is_empty());
+    assert!(!engine.stop_handle().is_stopped());
+}
+
+#[test]
+fn minimax_obeys_the_node_limit() {
+    let mut engine = Engine::with_components(MaterialEvaluator, MinimaxSearch::new());
+    engine.set_position_startpos();
+    engine.set_node_limit(Some(5_000));
+
+    let (total_nodes, per_depth) = engine.search_iterative_results(30);
+    assert!(total_nodes > 5_000 && total_nodes < 7_000, "{total_nodes}");
+    assert_eq!(per_depth.len(), 2);
+    assert!(!per_depth.last().expect("iteration").best_moves.is_empty());
+    assert!(!engine.stop_handle().is_stopped());
+}
+
+#[test]
+fn search_limits_apply_to_one_search() {
+    let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+    engine.set_position_startpos();
+    let limits = crate::engine::limits::SearchLimits {
+        nodes: Some(5_000),
+        ..Default::default()
+    };
+
+    let mut depths = 0;
+    let (bestmove, total_nodes) = engine.search(&limits, |_| depths += 1);
+    assert!(total_nodes > 5_000 && total_nodes < 7_000, "{total_nodes}");
+    assert!(depths > 0 && depths < 30);
+    assert!(crate::engine::types::move_from_uci(&bestmove).is_some());
+
+    // Without a node limit only the depth ends the next search.
+    let mut depths = 0;
+    engine.search(&crate::engine::limits::SearchLimits::depth(3), |_| {
+        depths += 1
+    });
+    assert_eq!(depths, 3);
+}
+
+#[test]
+fn move_time_ends_search_on_the_deadline() {
+    let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+    engine.set_position_startpos();
+    engine.set_move_time(Some(std::time::Duration::from_millis(100)));
+
+    let started = std::time::Instant::now();
+    let (_, per_depth) = engine.search_iterative_results(30);
+    assert!(started.elapsed() < std::time::Duration::from_secs(2));
+    assert!(!per_depth.is_empty() && per_depth.len() < 30);
+    assert!(!engine.stop_handle().is_stopped());
+}
+
+#[test]
+fn minimax_obeys_the_deadline() {
+    let mut board = Board::new();
+    board.set_startpos();
+    let stop = StopSignal::new();
+    let mut search = MinimaxSearch::new();
+    search.set_stop_signal(stop.clone());
+    search.set_deadline(Some(
+        std::time::Instant::now() + std::time::Duration::from_millis(50),
+    ));
+
+    // Depth 8 would take hours; the deadline ends it instead.
+    let started = std::time::Instant::now();
+    search.search(&mut board, &MaterialEvaluator, 8);
+    assert!(started.elapsed() < std::time::Duration::from_secs(2));
+    assert!(stop.is_stopped());
+}
+
+#[test]
+fn time_budget_stops_deepening_after_the_soft_limit() {
+    let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+    engine.set_position_startpos();
+    engine.set_time_budget(Some(crate::engine::clock::TimeBudget {
+        soft: std::time::Duration::from_millis(50),
+        hard: std::time::Duration::from_millis(200),
+        min: std::time::Duration::ZERO,
+    }));
+
+    let started = std::time::Instant::now();
+    let (_, per_depth) = engine.search_iterative_results(30);
+    assert!(started.elapsed() < std::time::Duration::from_secs(2));
+    assert!(!per_depth.is_empty() && per_depth.len() < 30);
+    assert!(!engine.stop_handle().is_stopped());
+}
+
+#[test]
+fn nodes_time_spends_the_time_budget_in_nodes() {
+    let search = |per_ms| {
+        let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+        engine.set_position_startpos();
+        engine.set_nodes_time(per_ms);
+        engine.set_time_budget(Some(crate::engine::clock::TimeBudget {
+            soft: std::time::Duration::from_millis(20),
+            hard: std::time::Duration::from_millis(50),
+            min: std::time::Duration::ZERO,
+        }));
+        let (total_nodes, per_depth) = engine.search_iterative_results(30);
+        assert!(!engine.stop_handle().is_stopped());
+        (total_nodes, per_depth.len())
+    };
+
+    let (total_nodes, depths) = search(Some(100));
+    assert!(total_nodes < 7_000, "{total_nodes}");
+    assert!(depths > 0 && depths < 30);
+    assert_eq!(search(Some(100)), (total_nodes, depths));
+    assert!(search(Some(300)).0 > total_nodes);
+}
+
+#[test]
+fn iterations_predicted_to_overrun_the_budget_are_not_started() {
+    let search = |move_time: Option<u64>, depth| {
+        let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+        engine.set_position_startpos();
+        // Counting a node as a millisecond makes the budget exact.
+        engine.set_nodes_time(Some(1));
+        engine.set_move_time(move_time.map(std::time::Duration::from_millis));
+        let mut infos = Vec::new();
+        let (_, total_nodes) =
+            engine.search_iterative_with_info(depth, |info| infos.push(info.clone()));
+        (total_nodes, infos)
+    };
+
+    let (through_four, infos) = search(None, 4);
+    assert_eq!(infos[0].ebf, None);
+    assert!(infos[1..]
+        .iter()
+        .all(|info| info.ebf.is_some_and(|ebf| ebf > 1.0)));
+
+    // A budget just past depth 4 cannot fit depth 5, which is not started
+    // only to be aborted: every node searched belongs to a reported
+    // iteration.
+    let (total_nodes, infos) = search(Some(through_four + 100), 30);
+    assert!(total_nodes <= through_four, "{total_nodes}");
+    assert_eq!(infos.last().map(|info| info.nodes), Some(total_nodes));
+}
+
+#[test]
+fn best_move_changes_log_each_new_best_move() {
+    let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+    engine
+        .set_position_fen(tactical_capture_fen())
+        .expect("fen");
+
+    let mut reported = Vec::new();
+    let (bestmove, _) = engine.search_iterative_with_info(3, |info| {
+        if info.best_move_changed {
+            reported.push(info.depth);
+        }
+    });
+
+    let changes = engine.best_move_changes();
+    assert_eq!(changes.first().map(|change| change.depth), Some(1));
+    assert_eq!(
+        changes
+            .iter()
+            .map(|change| change.depth)
+            .collect::<Vec<_>>(),
+        reported
+    );
+    assert!(changes
+        .windows(2)
+        .all(|pair| pair[0].best_move != pair[1].best_move && pair[0].depth < pair[1].depth));
+    assert_eq!(
+        uci_from_move(changes.last().expect("change").best_move).as_deref(),
+        Some(bestmove.as_str())
+    );
+}
+
+#[test]
+fn search_moves_restrict_the_root() {
+    let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+    engine
+        .set_position_fen("r7/8/8/8/8/4k3/8/Q3K3 w - - 0 1")
+        .expect("fen");
+    assert_eq!(engine.search_depth(1), "a1a8");
+
+    let quiet = move_from_uci("e1d1").expect("move");
+    let illegal = move_from_uci("e1e3").expect("move");
+    engine.set_search_moves(Some(vec![quiet, illegal]));
+    assert_eq!(engine.search_depth(1), "e1d1");
+
+    // With no legal search move the whole position is searched.
+    engine.set_search_moves(Some(vec![illegal]));
+    assert_eq!(engine.search_depth(1), "a1a8");
+}
+
+#[test]
+fn info_reports_a_legal_principal_variation() {
+    let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+    engine.set_position_startpos();
+
+    let mut infos = Vec::new();
+    let (bestmove, _) = engine.search_iterative_with_info(4, |info| infos.push(info.clone()));
+
+    let last = infos.last().expect("info");
+    assert_eq!(last.pv.len(), 4);
+    assert_eq!(
+        uci_from_move(last.pv[0]).as_deref(),
+        Some(bestmove.as_str())
+    );
+    let mut board = Board::new();
+    board.set_startpos();
+    for mv in &last.pv {
+        assert!(crate::engine::movegen::generate_legal(&mut board).contains(mv));
+        board.make_move(*mv).expect("make");
+    }
+}
+
+#[test]
+fn node_counts_visit_each_position_once() {
+    // No captures anywhere, so quiescence adds no positions of its own.
+    let fen = "4k3/8/8/8/8/8/8/4K3 w - - 0 1";
+    let mut board = Board::new();
+    board.set_fen(fen).expect("fen");
+    let legal = crate::engine::movegen::generate_legal(&mut board).len() as u64;
+    assert_eq!(legal, 5);
+
+    let minimax = MinimaxSearch::new().search(&mut board, &MaterialEvaluator, 1);
+    assert_eq!(minimax.nodes, 1 + legal);
+    // The black king has five replies to each of the white king's moves.
+    let minimax = MinimaxSearch::new().search(&mut board, &MaterialEvaluator, 2);
+    assert_eq!(minimax.nodes, 1 + legal + legal * 5);
+
+    // Alpha-beta visits the same positions at depth 1.
+    let alphabeta = AlphaBetaSearch::new().search(&mut board, &MaterialEvaluator, 1);
+    assert_eq!(alphabeta.nodes, 1 + legal);
+    // At depth 2 it searches every reply to the first move, then the null
+    // window of each later move is refuted by its first reply.
+    let alphabeta = AlphaBetaSearch::new().search(&mut board, &MaterialEvaluator, 2);
+    assert_eq!(alphabeta.nodes, 1 + legal + 5 + (legal - 1));
+}
+
+#[test]
+fn root_move_observer_sees_every_root_move_in_order() {
+    use std::sync::{Arc, Mutex};
+
+    let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+    engine.set_position_startpos();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&seen);
+    engine.set_root_move_observer(Some(Arc::new(move |mv, number| {
+        sink.lock().expect("lock").push((mv, number));
+    })));
+    engine.search_depth(2);
+
+    let seen = seen.lock().expect("lock");
+    // Two iterations over the 20 opening moves.
+    assert_eq!(seen.len(), 40);
+    assert!(seen[..20]
+        .iter()
+        .enumerate()
+        .all(|(index, (_, number))| *number == index + 1));
+}
+
+#[test]
+fn root_fail_highs_are_reported_as_lower_bounds() {
+    use crate::engine::search::tt::Bound;
+    use std::sync::{Arc, Mutex};
+
+    let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+    engine
+        .set_position_fen("4k3/8/8/3r4/8/8/8/3QK3 w - - 0 1")
+        .expect("fen");
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&seen);
+    engine.set_root_bound_observer(Some(Arc::new(move |bound| {
+        sink.lock().expect("lock").push(*bound);
+    })));
+    let best = engine.search_depth(3);
+
+    // The capture is not generated first, so the first iteration only
+    // finds it by a fail-high; later iterations search it first.
+    let seen = seen.lock().expect("lock");
+    let capture = move_from_uci("d1d5").expect("move");
+    assert_eq!(best, "d1d5");
+    assert!(seen.iter().all(|bound| bound.bound == Bound::Lower));
+    assert!(seen
+        .iter()
+        .any(|bound| bound.depth == 1 && bound.best_move == capture && bound.score > 0));
+    assert!(seen.iter().all(|bound| bound.depth == 1));
+}
+
+#[test]
+fn seldepth_covers_the_nominal_depth() {
+    let mut board = Board::new();
+    board.set_fen(tactical_capture_fen()).expect("fen");
+
+    let alphabeta = AlphaBetaSearch::new().search(&mut board, &MaterialEvaluator, 3);
+    let minimax = MinimaxSearch::new().search(&mut board, &MaterialEvaluator, 3);
+    for seldepth in [alphabeta.seldepth, minimax.seldepth] {
+        if cfg!(feature = "qsearch") {
+            assert!(seldepth >= 3);
+        } else {
+            assert_eq!(seldepth, 3);
+        }
+    }
+}
+
+#[test]
+fn searches_without_a_table_allocate_none_and_find_the_same_moves() {
+    let mut search = AlphaBetaSearch::without_table();
+    search.set_hash_size(64);
+    search.set_threads(2);
+    let usage = search.hash_usage().expect("usage");
+    assert_eq!((usage.entries, usage.bytes), (0, 0));
+    assert_eq!(search.hashfull(), Some(0));
+    search.set_threads(1);
+
+    for fen in [
+        "4k3/8/8/4N3/3q4/8/8/4K3 b - - 0 1",
+        "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+        "6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1",
+    ] {
+        let mut board = Board::new();
+        board.set_fen(fen).expect("fen");
+        let with_table = AlphaBetaSearch::new().search(&mut board, &MaterialEvaluator, 4);
+        let without = search.search(&mut board, &MaterialEvaluator, 4);
+        assert_eq!(without.best_moves, with_table.best_moves, "{fen}");
+        assert_eq!(without.score, with_table.score, "{fen}");
+        assert!(search.hash_move(&board).is_none());
+    }
+}
+
+#[test]
+fn symmetric_hashing_reuses_the_mirror_positions_entries() {
+    let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+    let mut board = Board::new();
+    board.set_fen(fen).expect("fen");
+    let mut mirror = board.flipped();
+    let fresh = AlphaBetaSearch::new().search(&mut mirror, &MaterialEvaluator, 4);
+
+    let mut search = AlphaBetaSearch::new();
+    search.set_symmetric_hash(true);
+    let original = search.search(&mut board, &MaterialEvaluator, 4);
+    let folded = search.search(&mut mirror, &MaterialEvaluator, 4);
+    assert_eq!(folded.score, original.score);
+    assert_eq!(folded.score, fresh.score);
+    let mirrored = |mv: Move| Move {
+        from: crate::engine::types::Square(mv.from.index() ^ 0x70),
+        to: crate::engine::types::Square(mv.to.index() ^ 0x70),
+        ..mv
+    };
+    assert_eq!(folded.best_moves[0], mirrored(original.best_moves[0]));
+    assert!(
+        folded.nodes * 2 < fresh.nodes,
+        "folded {} nodes, fresh {}",
+        folded.nodes,
+        fresh.nodes
+    );
+
+    // Contempt makes the mirror worth something else, so nothing is shared.
+    let run = |symmetric_hash| {
+        let mut search = AlphaBetaSearch::new();
+        search.set_symmetric_hash(symmetric_hash);
+        search.set_contempt(25);
+        search.search(&mut board.clone(), &MaterialEvaluator, 4);
+        search.search(&mut mirror.clone(), &MaterialEvaluator, 4)
+    };
+    let (on, off) = (run(true), run(false));
+    assert_eq!(
+        (on.best_moves, on.score, on.nodes),
+        (off.best_moves, off.score, off.nodes)
+    );
+
+    // So does an evaluator that does not promise symmetry.
+    struct Undeclared;
+    impl Evaluator for Undeclared {
+        fn evaluate(&self, board: &Board) -> i32 {
+            MaterialEvaluator.evaluate(board)
+        }
+    }
+    let run = |symmetric_hash| {
+        let mut search = AlphaBetaSearch::new();
+        search.set_symmetric_hash(symmetric_hash);
+        search.search(&mut board.clone(), &Undeclared, 4);
+        search.search(&mut mirror.clone(), &Undeclared, 4)
+    };
+    assert_eq!(run(true).nodes, run(false).nodes);
+}
+
+#[test]
+fn helper_threads_keep_the_main_result_and_add_their_nodes() {
+    let fen = "r7/8/8/8/8/4k3/8/Q3K3 w - - 0 1";
+    let mut board = Board::new();
+    board.set_fen(fen).expect("fen");
+    let single = AlphaBetaSearch::new().search(&mut board, &MaterialEvaluator, 3);
+
+    let mut search = AlphaBetaSearch::new();
+    search.set_hash_size(3);
+    search.set_threads(3);
+    // The three threads split the 3 MiB.
+    let usage = search.hash_usage().expect("usage");
+    let table = TranspositionTable::with_megabytes(1);
+    assert_eq!(
+        (usage.tables, usage.entries, usage.bytes),
+        (3, 3 * table.len(), 3 * table.memory_bytes())
+    );
+    let threaded = search.search(&mut board, &MaterialEvaluator, 3);
+    assert!(search.hashfull().expect("hashfull") > 0);
+    assert_eq!(threaded.best_moves, single.best_moves);
+    assert_eq!(threaded.score, single.score);
+    assert!(threaded.nodes >= single.nodes);
+    assert_eq!(board.to_fen(), fen);
+
+    search.set_threads(1);
+    let again = search.search(&mut board, &MaterialEvaluator, 3);
+    assert_eq!(again.best_moves, single.best_moves);
+}
+
+#[test]
+fn skill_caps_depth_and_nodes() {
+    use crate::engine::skill::Skill;
+
+    let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+    engine.set_rng_seed(3);
+    engine.set_position_startpos();
+    engine.set_skill(Some(Skill::new(800)));
+    let mut depths = Vec::new();
+    let (best, nodes) = engine.search_iterative_with_info(8, |info| depths.push(info.depth));
+    assert_eq!(depths, vec![1]);
+    assert!(nodes <= Skill::new(800).max_nodes());
+    assert_ne!(best, "0000");
+
+    // Depth 3 from the start position needs more than 800 nodes, so the
+    // node cap ends the search first.
+    engine.set_skill(Some(Skill::new(1300)));
+    depths.clear();
+    engine.search_iterative_with_info(8, |info| depths.push(info.depth));
+    assert_eq!(depths, vec![1, 2]);
+
+    engine.set_skill(None);
+    depths.clear();
+    engine.search_iterative_with_info(3, |info| depths.push(info.depth));
+    assert_eq!(depths, vec![1, 2, 3]);
+}
+
+#[test]
+fn passed_pawn_pushes_are_searched_a_ply_deeper() {
+    use crate::engine::search::SearchParams;
+
+    // No captures, so quiescence adds no plies of its own.
+    let mut board = Board::new();
+    board
+        .set_fen("4k3/8/8/4P3/8/8/8/K7 w - - 0 1")
+        .expect("fen");
+
+    let extended = AlphaBetaSearch::new().search(&mut board, &MaterialEvaluator, 1);
+    assert_eq!(extended.seldepth, 2);
+
+    let params = SearchParams {
+        passed_pawn_extension: false,
+        ..SearchParams::default()
+    };
+    let plain = AlphaBetaSearch::with_params(params).search(&mut board, &MaterialEvaluator, 1);
+    assert_eq!(plain.seldepth, 1);
+}
+
+#[test]
+fn late_move_pruning_saves_nodes_and_keeps_the_result() {
+    use crate::engine::search::SearchParams;
+
+    let mut board = Board::new();
+    board
+        .set_fen("r7/8/8/8/8/4k3/8/Q3K3 w - - 0 1")
+        .expect("fen");
+
+    let pruned = AlphaBetaSearch::new().search(&mut board, &MaterialEvaluator, 4);
+    let params = SearchParams {
+        late_move_pruning: false,
+        ..SearchParams::default()
+    };
+    let full = AlphaBetaSearch::with_params(params).search(&mut board, &MaterialEvaluator, 4);
+
+    let best = |result: &SearchResult| -> Vec<String> {
+        result
+            .best_moves
+            .iter()
+            .filter_map(|mv| uci_from_move(*mv))
+            .collect()
+    };
+    assert_eq!(best(&pruned), best(&full));
+    assert_eq!(pruned.score, full.score);
+    assert!(
+        pruned.nodes < full.nodes,
+        "{} >= {}",
+        pruned.nodes,
+        full.nodes
+    );
+}
+
+#[test]
+fn nodes_see_the_evaluations_of_nodes_too_deep_to_prune() {
+    use crate::engine::search::SearchParams;
+
+    let mut board = Board::new();
+    board
+        .set_fen("r7/8/8/8/8/4k3/8/Q3K3 w - - 0 1")
+        .expect("fen");
+
+    // Only depth 1 nodes may prune, so a node is only found not to be
+    // improving through the evaluation of its depth 3 grandparent.
+    let params = SearchParams {
+        lmp_depth: 1,
+        ..SearchParams::default()
+    };
+    let mut search = AlphaBetaSearch::with_params(params);
+    search.search(&mut board, &MaterialEvaluator, 4);
+    let stats = search.stats().expect("stats");
+    assert!(stats.lmp_pruned > 0, "{stats:?}");
+}
+
+#[test]
+fn contempt_scales_late_move_pruning() {
+    let pruned = |contempt| {
+        let mut board = Board::new();
+        board.set_startpos();
+        let mut search = AlphaBetaSearch::new();
+        search.set_contempt(contempt);
+        search.search(&mut board, &MaterialEvaluator, 4);
+        search.stats().expect("stats").lmp_pruned
+    };
+    let (bold, neutral, careful) = (pruned(50), pruned(0), pruned(-50));
+    assert!(
+        bold > neutral && neutral > careful,
+        "{bold} {neutral} {careful}"
+    );
+}
+
+#[test]
+fn easy_moves_answer_well_before_the_soft_limit() {
+    // Time is counted in nodes, so the iterations searched do not depend on
+    // the machine.
+    let search_for = |easy_move: bool| {
+        let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+        engine
+            .set_position_fen("4k3/8/8/8/3q4/8/8/3QK3 w - - 0 1")
+            .expect("fen");
+        engine.set_easy_move(easy_move);
+        engine.set_nodes_time(Some(100));
+        engine.set_time_budget(Some(crate::engine::clock::TimeBudget {
+            soft: std::time::Duration::from_millis(400),
+            hard: std::time::Duration::from_millis(800),
+            min: std::time::Duration::ZERO,
+        }));
+        let (nodes, per_depth) = engine.search_iterative_results(64);
+        let result = per_depth.last().expect("an iteration");
+        assert_eq!(
+            result.best_moves,
+            vec![move_from_uci("d1d4").expect("move")]
+        );
+        (nodes, per_depth.len())
+    };
+
+    // The soft limit is 40,000 nodes; an easy move stops past a sixteenth
+    // of it.
+    let (easy_nodes, easy_depth) = search_for(true);
+    let (full_nodes, full_depth) = search_for(false);
+    assert!(easy_nodes < 5_000, "{easy_nodes}");
+    assert!(full_nodes >= 40_000, "{full_nodes}");
+    assert!(full_depth > easy_depth);
+}
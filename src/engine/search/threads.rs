@@ -0,0 +1,86 @@
+//! Plumbing for multi-threaded search: helper threads search the same root
+//! next to the main thread, each with its own state, and their results are
+//! folded into the main thread's.
+//!
+//! Helpers do not share a transposition table with the main thread yet, so
+//! they add nodes but not strength; an SMP scheme plugs in here.
+
+use crate::engine::search::signal::StopSignal;
+use crate::engine::search::traits::SearchResult;
+use std::thread;
+
+/// Runs `main` on the calling thread and `helper` on one spawned thread per
+/// helper state. Once `main` returns, `stop` is raised so the helpers unwind,
+/// and they are joined before returning.
+pub fn run_with_helpers<T: Send, R: Send>(
+    helpers: &mut [T],
+    stop: &StopSignal,
+    main: impl FnOnce() -> R,
+    helper: impl Fn(usize, &mut T) -> R + Sync,
+) -> (R, Vec<R>) {
+    stop.reset();
+    thread::scope(|scope| {
+        let helper = &helper;
+        let handles: Vec<_> = helpers
+            .iter_mut()
+            .enumerate()
+            .map(|(id, state)| scope.spawn(move || helper(id, state)))
+            .collect();
+        let main_result = main();
+        stop.stop();
+        let helper_results = handles
+            .into_iter()
+            .map(|handle| handle.join().expect("helper search thread panicked"))
+            .collect();
+        (main_result, helper_results)
+    })
+}
+
+/// The main thread's result, with the work of the helpers added: nodes are
+/// summed and seldepth is the deepest any thread reached.
+pub fn aggregate(main: SearchResult, helpers: &[SearchResult]) -> SearchResult {
+    helpers.iter().fold(main, |mut total, helper| {
+        total.nodes = total.nodes.saturating_add(helper.nodes);
+        total.seldepth = total.seldepth.max(helper.seldepth);
+        total
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn helpers_run_until_main_finishes() {
+        let stop = StopSignal::new();
+        let mut states = vec![0u32; 3];
+        let (main, helpers) = run_with_helpers(
+            &mut states,
+            &stop,
+            || 7,
+            |id, spins: &mut u32| {
+                while !stop.is_stopped() {
+                    *spins += 1;
+                    thread::yield_now();
+                }
+                id
+            },
+        );
+        assert_eq!(main, 7);
+        assert_eq!(helpers, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn aggregate_keeps_main_moves_and_adds_helper_work() {
+        let result = |nodes, seldepth, score| SearchResult {
+            best_moves: Vec::new(),
+            score,
+            nodes,
+            seldepth,
+        };
+        let total = aggregate(result(100, 4, 50), &[result(80, 6, -20), result(30, 3, 0)]);
+        assert_eq!(total.nodes, 210);
+        assert_eq!(total.seldepth, 6);
+        assert_eq!(total.score, 50);
+    }
+}
@@ -0,0 +1,48 @@
+//! Root threat detection: search the position as if the side to move passed.
+//!
+//! When passing scores far below the real best score, the opponent has a
+//! serious threat (a hanging piece, a mating attack) that the chosen move is
+//! parrying, and the move deserves more thinking time than usual.
+
+use crate::engine::board::Board;
+use crate::engine::eval::Evaluator;
+use crate::engine::search::traits::SearchAlgorithm;
+use crate::engine::types::Move;
+
+/// How far below the best score passing must fall to count as a threat.
+pub const THREAT_MARGIN: i32 = 150;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Threat {
+    /// The opponent's best reply to a pass.
+    pub reply: Move,
+    /// Best score minus the score after passing, from the side to move.
+    pub swing: i32,
+}
+
+/// Searches a null move at `depth - 1` and reports a threat when it fails low
+/// by more than `THREAT_MARGIN` against `best_score`. Never reports one in
+/// check, where passing is illegal.
+pub fn detect_threat(
+    search: &mut impl SearchAlgorithm,
+    board: &mut Board,
+    evaluator: &impl Evaluator,
+    depth: u32,
+    best_score: i32,
+) -> Option<Threat> {
+    if board.in_check() {
+        return None;
+    }
+    let undo = board.make_null_move();
+    let result = search.search(board, evaluator, depth.saturating_sub(1).max(1));
+    board.unmake_null_move(undo);
+
+    let swing = best_score.saturating_add(result.score);
+    let reply = *result.best_moves.first()?;
+    (swing > THREAT_MARGIN).then_some(Threat { reply, swing })
+}
+
+/// The time budget for a move once a threat has been detected.
+pub fn extended_budget_ms(budget_ms: u64) -> u64 {
+    budget_ms.saturating_mul(3) / 2
+}
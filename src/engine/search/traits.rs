@@ -1,18 +1,169 @@
 use crate::engine::board::Board;
 use crate::engine::eval::Evaluator;
+use crate::engine::search::signal::StopSignal;
+use crate::engine::search::tt::Bound;
 use crate::engine::types::Move;
+use std::sync::Arc;
+use std::time::Instant;
 
 #[derive(Clone)]
 pub struct SearchResult {
     pub best_moves: Vec<Move>,
     pub score: i32,
+    /// Positions visited, counted once each: the root, every interior node
+    /// and leaf (including transposition-table cutoffs), and every position
+    /// reached inside quiescence. All searches follow this, so node counts
+    /// are comparable between algorithms.
     pub nodes: u64,
+    /// Deepest ply below the root reached, quiescence included.
+    pub seldepth: u32,
 }
 
+/// Counters a search keeps for diagnostics, over its last search call.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    pub tt_probes: u64,
+    pub tt_hits: u64,
+    /// Probes whose entry was deep enough to return its score directly.
+    pub tt_cutoffs: u64,
+    /// Quiet moves skipped by late move pruning.
+    pub lmp_pruned: u64,
+    /// Nodes scored as draws for repeating a position on the search path.
+    pub repetitions: u64,
+    pub beta_cutoffs: u64,
+    /// Beta cutoffs by the first move searched; the higher their share,
+    /// the better the move ordering.
+    pub first_move_cutoffs: u64,
+    /// Interior nodes searched in full, by expected type.
+    pub pv_nodes: u64,
+    pub cut_nodes: u64,
+    /// Expected cut nodes that did fail high; the rest were all nodes.
+    pub cut_nodes_confirmed: u64,
+}
+
+/// Memory held by a search's transposition tables, helper threads'
+/// included.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HashUsage {
+    pub tables: usize,
+    pub entries: usize,
+    pub bytes: usize,
+}
+
+/// Called with each root move as the search starts on it, and the move's
+/// 1-based position in the root move order.
+pub type RootMoveObserver = Arc<dyn Fn(Move, usize) + Send + Sync>;
+
+/// A root score known only as a bound, as when a root move fails high on
+/// its null-window scout and is about to be searched again in full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RootBound {
+    pub depth: u32,
+    pub best_move: Move,
+    /// Centipawns for the side to move at the root.
+    pub score: i32,
+    pub bound: Bound,
+}
+
+/// Called with each root bound before the re-search that resolves it.
+pub type RootBoundObserver = Arc<dyn Fn(&RootBound) + Send + Sync>;
+
 pub trait SearchAlgorithm {
+    /// Hands the search a signal to poll; once it is raised the search unwinds
+    /// and its result is incomplete. An aborted result must only list root
+    /// moves whose scores were fully resolved, so callers can still trust a
+    /// move that beat the first root move before the abort.
+    fn set_stop_signal(&mut self, signal: StopSignal) {
+        let _ = signal;
+    }
+
+    /// Caps the nodes each following search call may visit; past the limit
+    /// the search raises its stop signal and unwinds as if stopped.
+    fn set_node_limit(&mut self, limit: Option<u64>) {
+        let _ = limit;
+    }
+
+    /// Like `set_node_limit`, but for wall-clock time: the search stops itself
+    /// once `deadline` has passed.
+    fn set_deadline(&mut self, deadline: Option<Instant>) {
+        let _ = deadline;
+    }
+
+    /// How many centipawns the side to move at the root gives up to avoid a
+    /// draw; negative values make it seek draws.
+    fn set_contempt(&mut self, centipawns: i32) {
+        let _ = centipawns;
+    }
+
+    /// Lets a position and its color-flipped mirror share a transposition
+    /// table entry, for analysing symmetric positions. Searches only fold
+    /// when it cannot change their results.
+    fn set_symmetric_hash(&mut self, enabled: bool) {
+        let _ = enabled;
+    }
+
+    /// Installs an observer for root move progress (UCI `currmove`).
+    fn set_root_move_observer(&mut self, observer: Option<RootMoveObserver>) {
+        let _ = observer;
+    }
+
+    /// Installs an observer for root fail-highs (UCI `lowerbound`).
+    fn set_root_bound_observer(&mut self, observer: Option<RootBoundObserver>) {
+        let _ = observer;
+    }
+
+    /// Resizes the transposition table, for searches that have one. The
+    /// table's contents are lost.
+    fn set_hash_size(&mut self, megabytes: usize) {
+        let _ = megabytes;
+    }
+
+    /// Empties the transposition table, for searches that have one, so no
+    /// earlier search influences the next.
+    fn clear_hash(&mut self) {}
+
+    /// Forgets everything learned from earlier searches, for a new game:
+    /// the transposition table and any move-ordering memory.
+    fn new_game(&mut self) {
+        self.clear_hash();
+    }
+
+    /// How many threads following searches use, the calling one included.
+    /// Searches without multi-threading ignore it.
+    fn set_threads(&mut self, threads: usize) {
+        let _ = threads;
+    }
+
+    /// Transposition table fill rate in permille, for searches that have one.
+    fn hashfull(&self) -> Option<u32> {
+        None
+    }
+
+    /// Size of the transposition tables, for searches that have them.
+    fn hash_usage(&self) -> Option<HashUsage> {
+        None
+    }
+
+    /// Diagnostic counters of the last search call, for searches that keep
+    /// them.
+    fn stats(&self) -> Option<SearchStats> {
+        None
+    }
+
+    /// The move the search remembers as best in `board`'s position, used to
+    /// reconstruct the principal variation after a search.
+    fn hash_move(&self, board: &Board) -> Option<Move> {
+        let _ = board;
+        None
+    }
+
     fn search(&mut self, board: &mut Board, evaluator: &impl Evaluator, depth: u32)
         -> SearchResult;
 
+    /// Searches like `search`, trying the `preferred_root` moves first, in
+    /// order and ahead of any move the search would otherwise start with.
+    /// A stopped search therefore only names another move when it scored
+    /// above them.
     fn search_with_root_ordering(
         &mut self,
         board: &mut Board,
@@ -20,7 +171,17 @@ pub trait SearchAlgorithm {
         depth: u32,
         preferred_root: Option<&[Move]>,
     ) -> SearchResult {
-        let _ = preferred_root;
-        self.search(board, evaluator, depth)
+        self.search_with_root_moves(board, evaluator, depth, preferred_root, None)
     }
+
+    /// Searches like `search_with_root_ordering`, but only considers the root moves in
+    /// `root_moves` when it is provided.
+    fn search_with_root_moves(
+        &mut self,
+        board: &mut Board,
+        evaluator: &impl Evaluator,
+        depth: u32,
+        preferred_root: Option<&[Move]>,
+        root_moves: Option<&[Move]>,
+    ) -> SearchResult;
 }
@@ -1,4 +1,9 @@
+use crate::engine::trace::span;
 use crate::engine::types::Move;
+use std::mem::size_of;
+
+/// Table size used when none is configured, in megabytes.
+pub const DEFAULT_HASH_MB: usize = 32;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Bound {
@@ -16,6 +21,37 @@ pub struct TTEntry {
     pub best_move: Option<Move>,
 }
 
+/// Where a search keeps the positions it has seen. Searches are generic over
+/// it, so with `NoTable` probes and stores compile to nothing instead of being
+/// checked for at every node.
+pub trait TableBackend: Send {
+    /// The table for a `Hash` of `megabytes` MiB.
+    fn with_megabytes(megabytes: usize) -> Self;
+
+    /// Reallocates the table for `megabytes` MiB. All entries are dropped.
+    fn resize(&mut self, megabytes: usize);
+
+    /// Drops all entries, keeping the size.
+    fn clear(&mut self);
+
+    /// Number of slots.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Bytes held by the slots, used or not.
+    fn memory_bytes(&self) -> usize;
+
+    fn probe(&self, key: u64) -> Option<TTEntry>;
+
+    fn store(&mut self, entry: TTEntry);
+
+    /// Estimated fill rate in permille.
+    fn hashfull(&self) -> u32;
+}
+
 pub struct TranspositionTable {
     entries: Vec<Option<TTEntry>>,
     mask: usize,
@@ -30,7 +66,38 @@ impl TranspositionTable {
         }
     }
 
+    /// The largest power-of-two table that fits in `megabytes` MiB, with at
+    /// least one entry.
+    pub fn with_megabytes(megabytes: usize) -> Self {
+        let slots = megabytes.saturating_mul(1 << 20) / size_of::<Option<TTEntry>>();
+        Self::new(slots.checked_ilog2().map_or(1, |bits| 1 << bits))
+    }
+
+    /// Reallocates the table for `megabytes` MiB. All entries are dropped.
+    pub fn resize(&mut self, megabytes: usize) {
+        *self = Self::with_megabytes(megabytes);
+    }
+
+    /// Drops all entries, keeping the size.
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Bytes held by the slots, used or not.
+    pub fn memory_bytes(&self) -> usize {
+        self.entries.len() * size_of::<Option<TTEntry>>()
+    }
+
     pub fn probe(&self, key: u64) -> Option<TTEntry> {
+        span!(TRACE, "tt_probe");
         let index = self.index(key);
         match self.entries[index] {
             Some(entry) if entry.key == key => Some(entry),
@@ -39,6 +106,7 @@ impl TranspositionTable {
     }
 
     pub fn store(&mut self, entry: TTEntry) {
+        span!(TRACE, "tt_store");
         let index = self.index(entry.key);
         match self.entries[index] {
             None => self.entries[index] = Some(entry),
@@ -50,7 +118,144 @@ impl TranspositionTable {
         }
     }
 
+    /// Estimated fill rate in permille, from the first (up to) 1000 slots.
+    pub fn hashfull(&self) -> u32 {
+        let sample = &self.entries[..self.entries.len().min(1000)];
+        let used = sample.iter().filter(|entry| entry.is_some()).count();
+        (used * 1000 / sample.len()) as u32
+    }
+
     fn index(&self, key: u64) -> usize {
         (key as usize) & self.mask
     }
 }
+
+// The table keeps its inherent methods, so callers that name it directly
+// need no trait import; the backend forwards to them.
+impl TableBackend for TranspositionTable {
+    #[inline]
+    fn with_megabytes(megabytes: usize) -> Self {
+        TranspositionTable::with_megabytes(megabytes)
+    }
+
+    #[inline]
+    fn resize(&mut self, megabytes: usize) {
+        TranspositionTable::resize(self, megabytes);
+    }
+
+    #[inline]
+    fn clear(&mut self) {
+        TranspositionTable::clear(self);
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        TranspositionTable::len(self)
+    }
+
+    #[inline]
+    fn memory_bytes(&self) -> usize {
+        TranspositionTable::memory_bytes(self)
+    }
+
+    #[inline]
+    fn probe(&self, key: u64) -> Option<TTEntry> {
+        TranspositionTable::probe(self, key)
+    }
+
+    #[inline]
+    fn store(&mut self, entry: TTEntry) {
+        TranspositionTable::store(self, entry);
+    }
+
+    #[inline]
+    fn hashfull(&self) -> u32 {
+        TranspositionTable::hashfull(self)
+    }
+}
+
+/// No table at all, for targets with memory to spare for nothing but the
+/// board: every probe misses, nothing is stored and `Hash` is ignored. The
+/// search still finds the same moves, only with more nodes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoTable;
+
+impl TableBackend for NoTable {
+    #[inline]
+    fn with_megabytes(_: usize) -> Self {
+        NoTable
+    }
+
+    #[inline]
+    fn resize(&mut self, _: usize) {}
+
+    #[inline]
+    fn clear(&mut self) {}
+
+    #[inline]
+    fn len(&self) -> usize {
+        0
+    }
+
+    #[inline]
+    fn memory_bytes(&self) -> usize {
+        0
+    }
+
+    #[inline]
+    fn probe(&self, _: u64) -> Option<TTEntry> {
+        None
+    }
+
+    #[inline]
+    fn store(&mut self, _: TTEntry) {}
+
+    #[inline]
+    fn hashfull(&self) -> u32 {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashfull_samples_occupied_slots() {
+        let mut tt = TranspositionTable::new(2048);
+        assert_eq!(tt.hashfull(), 0);
+        for key in 0..250u64 {
+            tt.store(TTEntry {
+                key,
+                depth: 1,
+                score: 0,
+                bound: Bound::Exact,
+                best_move: None,
+            });
+        }
+        assert_eq!(tt.hashfull(), 250);
+    }
+
+    #[test]
+    fn megabyte_sizes_round_down_to_a_power_of_two() {
+        let mut tt = TranspositionTable::with_megabytes(DEFAULT_HASH_MB);
+        assert_eq!(tt.len(), 1 << 20);
+        assert!(tt.memory_bytes() <= DEFAULT_HASH_MB << 20);
+        assert!(tt.memory_bytes() > DEFAULT_HASH_MB << 19);
+
+        tt.store(TTEntry {
+            key: 7,
+            depth: 1,
+            score: 0,
+            bound: Bound::Exact,
+            best_move: None,
+        });
+        tt.clear();
+        assert!(tt.probe(7).is_none());
+        assert_eq!(tt.len(), 1 << 20);
+        tt.resize(1);
+        assert_eq!(tt.len(), 1 << 15);
+        assert!(tt.probe(7).is_none());
+        assert_eq!(TranspositionTable::with_megabytes(0).len(), 1);
+    }
+}
@@ -0,0 +1,176 @@
+use crate::engine::attacks::{king_attacks, knight_attacks, pawn_attacks};
+use crate::engine::board::Board;
+use crate::engine::eval::piece_value;
+use crate::engine::movegen::{offset_square, BISHOP_OFFSETS, ROOK_OFFSETS};
+use crate::engine::types::{Color, Move, Piece, PieceKind, Square};
+
+// Large enough that a king "capture" is never profitable to give up.
+const KING_VALUE: i32 = 20_000;
+
+/// Static exchange evaluation: the material balance for the side making `mv`
+/// once both sides have made every profitable recapture on the target square.
+/// Quiet moves score zero unless the moved piece can be won. `mv` should be
+/// legal in `board`; checks and pins are not considered.
+pub fn see(board: &Board, mv: Move) -> i32 {
+    let Some(mover) = board.squares[mv.from.index() as usize] else {
+        return 0;
+    };
+    let mut squares = board.squares;
+    let target = mv.to;
+
+    let captured = match squares[target.index() as usize] {
+        // Chess960 castling lands the king on its own rook; it wins nothing.
+        Some(piece) if piece.color == mover.color => return 0,
+        Some(piece) => see_value(piece.kind),
+        None if mover.kind == PieceKind::Pawn && board.en_passant == Some(target) => {
+            // Remove the pawn captured en passant so it cannot recapture.
+            let behind = match mover.color {
+                Color::White => offset_square(target, -16),
+                Color::Black => offset_square(target, 16),
+            };
+            if let Some(behind) = behind {
+                squares[behind.index() as usize] = None;
+            }
+            see_value(PieceKind::Pawn)
+        }
+        None => 0,
+    };
+    let placed = match mv.promotion {
+        Some(kind) => Piece {
+            color: mover.color,
+            kind,
+        },
+        None => mover,
+    };
+
+    let mut gains = vec![captured];
+    squares[mv.from.index() as usize] = None;
+    squares[target.index() as usize] = Some(placed);
+    let mut on_target = see_value(placed.kind);
+    let mut side = opposite(mover.color);
+
+    while let Some((from, kind)) = least_valuable_attacker(&squares, target, side) {
+        let previous = *gains.last().expect("gain");
+        gains.push(on_target - previous);
+        squares[from.index() as usize] = None;
+        squares[target.index() as usize] = Some(Piece { color: side, kind });
+        on_target = see_value(kind);
+        side = opposite(side);
+    }
+
+    // Each side may stop capturing when continuing would lose material.
+    while gains.len() > 1 {
+        let last = gains.pop().expect("gain");
+        let previous = gains.last_mut().expect("gain");
+        *previous = -(-*previous).max(last);
+    }
+    gains[0]
+}
+
+fn see_value(kind: PieceKind) -> i32 {
+    match kind {
+        PieceKind::King => KING_VALUE,
+        _ => piece_value(kind),
+    }
+}
+
+fn opposite(color: Color) -> Color {
+    match color {
+        Color::White => Color::Black,
+        Color::Black => Color::White,
+    }
+}
+
+// Rescanning after every capture picks up x-ray attackers behind the piece
+// that just moved.
+fn least_valuable_attacker(
+    squares: &[Option<Piece>; 128],
+    target: Square,
+    color: Color,
+) -> Option<(Square, PieceKind)> {
+    let holds = |square: Square, kind: PieceKind| {
+        squares[square.index() as usize] == Some(Piece { color, kind })
+    };
+
+    if let Some(from) = pawn_attacks(target, opposite(color))
+        .iter()
+        .find(|square| holds(**square, PieceKind::Pawn))
+    {
+        return Some((*from, PieceKind::Pawn));
+    }
+    if let Some(from) = knight_attacks(target)
+        .iter()
+        .find(|square| holds(**square, PieceKind::Knight))
+    {
+        return Some((*from, PieceKind::Knight));
+    }
+    let bishop = slider_attacker(squares, target, &BISHOP_OFFSETS);
+    let rook = slider_attacker(squares, target, &ROOK_OFFSETS);
+    for (kind, candidates) in [
+        (PieceKind::Bishop, &bishop),
+        (PieceKind::Rook, &rook),
+        (PieceKind::Queen, &bishop),
+        (PieceKind::Queen, &rook),
+    ] {
+        if let Some(from) = candidates.iter().find(|square| holds(**square, kind)) {
+            return Some((*from, kind));
+        }
+    }
+    king_attacks(target)
+        .iter()
+        .find(|square| holds(**square, PieceKind::King))
+        .map(|from| (*from, PieceKind::King))
+}
+
+// The first occupied square along each ray from `target`.
+fn slider_attacker(squares: &[Option<Piece>; 128], target: Square, offsets: &[i8]) -> Vec<Square> {
+    let mut found = Vec::new();
+    for offset in offsets {
+        let mut current = target;
+        while let Some(next) = offset_square(current, *offset) {
+            if squares[next.index() as usize].is_some() {
+                found.push(next);
+                break;
+            }
+            current = next;
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::types::move_from_uci;
+
+    fn see_of(fen: &str, uci: &str) -> i32 {
+        let mut board = Board::new();
+        board.set_fen(fen).expect("fen");
+        see(&board, move_from_uci(uci).expect("move"))
+    }
+
+    #[test]
+    fn undefended_capture_wins_the_piece() {
+        assert_eq!(see_of("4k3/8/8/3p4/8/8/8/3RK3 w - - 0 1", "d1d5"), 100);
+    }
+
+    #[test]
+    fn defended_capture_loses_the_attacker() {
+        assert_eq!(
+            see_of("4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1", "d1d5"),
+            100 - 900
+        );
+    }
+
+    #[test]
+    fn xray_support_counts() {
+        // Rooks doubled on the d-file win the pawn despite the defender.
+        assert_eq!(see_of("3rk3/8/8/3p4/8/8/3R4/3RK3 w - - 0 1", "d2d5"), 100);
+    }
+
+    #[test]
+    fn quiet_move_to_attacked_square_loses_the_piece() {
+        assert_eq!(see_of("4k3/8/8/8/2p5/8/8/2N1K3 w - - 0 1", "c1e2"), 0);
+        assert_eq!(see_of("4k3/8/8/8/2p5/8/8/2N1K3 w - - 0 1", "c1d3"), -320);
+    }
+}
@@ -0,0 +1,298 @@
+//! Strength limiting for casual play (UCI `UCI_LimitStrength` / `UCI_Elo`).
+//!
+//! A target rating maps to a depth cap, a node cap, an amount of noise
+//! added to every evaluation and a chance of blundering; the noise makes
+//! weaker settings misjudge positions the way weaker players do rather than
+//! just see less, and the blunders add the occasional outright oversight.
+//!
+//! The mapping is a rough calibration: self-play between levels (see the
+//! ignored `stronger_levels_win_self_play` test) should favour the higher
+//! rating, but the numbers are not measured Elo.
+
+use crate::engine::board::Board;
+use crate::engine::eval::{white_view, EvalTerm, EvalTrace, Evaluator};
+use crate::engine::movegen::generate_legal;
+use crate::engine::types::Move;
+
+pub const MIN_ELO: u32 = 800;
+pub const MAX_ELO: u32 = 2800;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Skill {
+    elo: u32,
+}
+
+impl Skill {
+    /// A skill level for `elo`, clamped to `MIN_ELO..=MAX_ELO`.
+    pub fn new(elo: u32) -> Self {
+        Self {
+            elo: elo.clamp(MIN_ELO, MAX_ELO),
+        }
+    }
+
+    pub fn elo(&self) -> u32 {
+        self.elo
+    }
+
+    /// Deepest iteration searched: 1 at `MIN_ELO`, 9 at `MAX_ELO`.
+    pub fn max_depth(&self) -> u32 {
+        1 + (self.elo - MIN_ELO) / 250
+    }
+
+    /// Node cap per move, doubling every 200 points from 200 at `MIN_ELO`.
+    pub fn max_nodes(&self) -> u64 {
+        200 << ((self.elo - MIN_ELO) / 200)
+    }
+
+    /// Largest noise added to an evaluation, in centipawns: 400 at `MIN_ELO`,
+    /// none at `MAX_ELO`.
+    pub fn eval_noise(&self) -> i32 {
+        ((MAX_ELO - self.elo) / 5) as i32
+    }
+
+    /// Chance in permille that a move is replaced by a blunder: 200 at
+    /// `MIN_ELO`, none at `MAX_ELO`.
+    pub fn blunder_chance(&self) -> u32 {
+        (MAX_ELO - self.elo) / 10
+    }
+
+    /// How much worse than the best move a blunder may look one ply deep,
+    /// in centipawns: 300 at `MIN_ELO`, shrinking to 100.
+    pub fn blunder_margin(&self) -> i32 {
+        100 + ((MAX_ELO - self.elo) / 10) as i32
+    }
+}
+
+/// A move to play instead of `chosen`: any other move whose one-ply score is
+/// within `margin` of the best one-ply score, picked by `pick` from their
+/// count. `None` when there is no such move.
+pub fn blunder(
+    board: &mut Board,
+    evaluator: &impl Evaluator,
+    chosen: Move,
+    margin: i32,
+    pick: impl FnOnce(usize) -> usize,
+) -> Option<Move> {
+    let mut scored = Vec::new();
+    for mv in generate_legal(board) {
+        let Ok(undo) = board.make_move(mv) else {
+            continue;
+        };
+        scored.push((mv, -evaluator.evaluate(board)));
+        board.unmake_move(mv, undo);
+    }
+    let best = scored.iter().map(|(_, score)| *score).max()?;
+    let candidates: Vec<Move> = scored
+        .into_iter()
+        .filter(|(mv, score)| *mv != chosen && *score >= best - margin)
+        .map(|(mv, _)| mv)
+        .collect();
+    if candidates.is_empty() {
+        return None;
+    }
+    Some(candidates[pick(candidates.len())])
+}
+
+/// Adds up to `amplitude` centipawns of noise either way to `inner`'s
+/// evaluations. The noise is a hash of the position and `seed`, so a position
+/// scores the same throughout one search and transpositions stay consistent.
+/// Besides weakening play, it diversifies self-play games (`datagen
+/// --eval-noise`).
+pub struct NoisyEvaluator<'a, E: Evaluator> {
+    inner: &'a E,
+    amplitude: i32,
+    seed: u64,
+}
+
+impl<'a, E: Evaluator> NoisyEvaluator<'a, E> {
+    pub fn new(inner: &'a E, amplitude: i32, seed: u64) -> Self {
+        Self {
+            inner,
+            amplitude,
+            seed,
+        }
+    }
+}
+
+impl<E: Evaluator> NoisyEvaluator<'_, E> {
+    fn noise(&self, board: &Board) -> i32 {
+        if self.amplitude <= 0 {
+            return 0;
+        }
+        let span = 2 * self.amplitude as u64 + 1;
+        (mix(board.hash() ^ self.seed) % span) as i32 - self.amplitude
+    }
+}
+
+impl<E: Evaluator> Evaluator for NoisyEvaluator<'_, E> {
+    fn evaluate(&self, board: &Board) -> i32 {
+        self.inner.evaluate(board).saturating_add(self.noise(board))
+    }
+
+    fn trace(&self, board: &Board) -> EvalTrace {
+        let mut trace = self.inner.trace(board);
+        if self.amplitude > 0 {
+            trace.terms.push(EvalTerm {
+                name: "Noise",
+                sides: None,
+                total: white_view(board, self.noise(board)),
+            });
+        }
+        trace
+    }
+}
+
+// SplitMix64 finalizer: spreads nearby hashes over the whole range.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::eval::MaterialEvaluator;
+
+    #[test]
+    fn limits_grow_with_rating() {
+        let weakest = Skill::new(0);
+        assert_eq!(weakest.elo(), MIN_ELO);
+        assert_eq!(
+            (
+                weakest.max_depth(),
+                weakest.max_nodes(),
+                weakest.eval_noise()
+            ),
+            (1, 200, 400)
+        );
+        assert_eq!(
+            (weakest.blunder_chance(), weakest.blunder_margin()),
+            (200, 300)
+        );
+        let strongest = Skill::new(5000);
+        assert_eq!(
+            (
+                strongest.max_depth(),
+                strongest.max_nodes(),
+                strongest.eval_noise()
+            ),
+            (9, 200 << 10, 0)
+        );
+        assert_eq!(strongest.blunder_chance(), 0);
+        assert!(Skill::new(1500).max_depth() < strongest.max_depth());
+    }
+
+    #[test]
+    fn noise_is_bounded_and_stable_per_position() {
+        let mut board = Board::new();
+        board.set_startpos();
+        let noisy = NoisyEvaluator::new(&MaterialEvaluator, 50, 7);
+        let score = noisy.evaluate(&board);
+        assert!(score.abs() <= 50);
+        assert_eq!(noisy.evaluate(&board), score);
+        assert_eq!(
+            NoisyEvaluator::new(&MaterialEvaluator, 0, 7).evaluate(&board),
+            0
+        );
+
+        let seeds: Vec<i32> = (0..16)
+            .map(|seed| NoisyEvaluator::new(&MaterialEvaluator, 50, seed).evaluate(&board))
+            .collect();
+        assert!(seeds.iter().any(|noise| *noise != seeds[0]));
+    }
+
+    #[test]
+    fn noisy_self_play_repeats_by_seed() {
+        use crate::engine::game::{play_game, GameConfig};
+        use crate::engine::search::AlphaBetaSearch;
+        use crate::engine::Engine;
+
+        // Seeded like datagen's players: one seed per side.
+        let game = |noise| {
+            let player = |seed| {
+                let mut engine = Engine::with_components(
+                    NoisyEvaluator::new(&MaterialEvaluator, noise, seed),
+                    AlphaBetaSearch::new(),
+                );
+                engine.set_rng_seed(seed);
+                engine
+            };
+            let config = GameConfig {
+                white_depth: 2,
+                black_depth: 2,
+                max_plies: 24,
+                ..GameConfig::default()
+            };
+            play_game(&mut player(2), &mut player(3), &config)
+                .expect("game")
+                .moves
+        };
+        let noisy = game(50);
+        assert_eq!(game(50), noisy);
+        assert_ne!(game(0), noisy);
+    }
+
+    #[test]
+    fn blunders_stay_within_the_margin() {
+        let mut board = Board::new();
+        // Taking the queen is best; a blunder may give it up, but only
+        // within the margin.
+        board
+            .set_fen("4k3/8/8/8/3q4/8/8/3QK3 w - - 0 1")
+            .expect("fen");
+        let take = crate::engine::types::move_from_uci("d1d4").unwrap();
+        let choose = |margin| blunder(&mut board.clone(), &MaterialEvaluator, take, margin, |_| 0);
+        assert_eq!(choose(300), None);
+        let blunder = choose(900).expect("a blunder");
+        assert_ne!(blunder, take);
+    }
+
+    // A rough calibration check: run with `cargo test --release -- --ignored`.
+    #[test]
+    #[ignore]
+    fn stronger_levels_win_self_play() {
+        use crate::engine::elo::{Outcome, Pentanomial, Sprt, SprtResult};
+        use crate::engine::game::GameConfig;
+        use crate::engine::search::AlphaBetaSearch;
+        use crate::engine::types::Color;
+        use crate::engine::Engine;
+
+        // The skills cap the depth below this.
+        let config = GameConfig {
+            white_depth: 9,
+            black_depth: 9,
+            max_plies: 200,
+            ..GameConfig::default()
+        };
+        // Both games of a pair share a seed, with colors swapped. Pairs are
+        // played until the SPRT tells whether the gap is 200 Elo or more.
+        let sprt = Sprt::new(0.0, 200.0);
+        let mut stats = Pentanomial::default();
+        for pair in 0..16u64 {
+            let engine = |elo| {
+                let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+                engine.set_rng_seed(pair);
+                engine.set_skill(Some(Skill::new(elo)));
+                engine
+            };
+            let (mut strong, mut weak) = (engine(2000), engine(MIN_ELO));
+            let white = strong.play_game(&mut weak, &config).expect("game");
+            let (mut strong, mut weak) = (engine(2000), engine(MIN_ELO));
+            let black = weak.play_game(&mut strong, &config).expect("game");
+            stats.add_pair(
+                Outcome::for_player(white.result, Color::White),
+                Outcome::for_player(black.result, Color::Black),
+            );
+            if sprt.test(&stats) != SprtResult::Continue {
+                break;
+            }
+        }
+        let report = format!("{stats}, {}", stats.elo().expect("pairs"));
+        assert_eq!(
+            sprt.test(&stats),
+            SprtResult::AcceptH1,
+            "2000 against {MIN_ELO}: {report}"
+        );
+    }
+}
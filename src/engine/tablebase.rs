@@ -0,0 +1,199 @@
+//! Syzygy endgame tablebase files: finding them and knowing which positions
+//! they cover. This is the setup half of a prober; reading results out of
+//! the compressed tables comes on top of it.
+//!
+//! Tables are named by material, strongest side first (`KQvK`, `KRPvKR`),
+//! with `.rtbw` holding win/draw/loss and `.rtbz` distance to zeroing.
+
+use crate::engine::board::Board;
+use crate::engine::types::{Color, PieceKind};
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+const WDL_EXTENSION: &str = "rtbw";
+const DTZ_EXTENSION: &str = "rtbz";
+
+/// The first bytes of every win/draw/loss and DTZ table.
+const WDL_MAGIC: [u8; 4] = [0x71, 0xE8, 0x23, 0x5D];
+const DTZ_MAGIC: [u8; 4] = [0xD7, 0x66, 0x0C, 0xA5];
+
+/// Piece letters in the order Syzygy names list them.
+const PIECE_ORDER: [(PieceKind, char); 6] = [
+    (PieceKind::King, 'K'),
+    (PieceKind::Queen, 'Q'),
+    (PieceKind::Rook, 'R'),
+    (PieceKind::Bishop, 'B'),
+    (PieceKind::Knight, 'N'),
+    (PieceKind::Pawn, 'P'),
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Tablebases {
+    wdl: BTreeSet<String>,
+    dtz: BTreeSet<String>,
+}
+
+impl Tablebases {
+    /// Indexes the tables in `paths`, a list of directories separated like
+    /// `PATH` (`:` on Unix, `;` on Windows). Files that are not Syzygy
+    /// tables, including empty or truncated downloads, are ignored; a
+    /// directory that cannot be read is an error.
+    pub fn open(paths: &str) -> Result<Self, String> {
+        let mut tablebases = Self::default();
+        for dir in env::split_paths(paths).filter(|dir| !dir.as_os_str().is_empty()) {
+            let entries = fs::read_dir(&dir)
+                .map_err(|err| format!("failed to read {}: {err}", dir.display()))?;
+            for entry in entries.flatten() {
+                tablebases.add_file(&entry.path());
+            }
+        }
+        Ok(tablebases)
+    }
+
+    fn add_file(&mut self, path: &Path) {
+        let (Some(stem), Some(extension)) = (
+            path.file_stem().and_then(|stem| stem.to_str()),
+            path.extension().and_then(|extension| extension.to_str()),
+        ) else {
+            return;
+        };
+        if !is_table_name(stem) {
+            return;
+        }
+        match extension {
+            WDL_EXTENSION if has_table_header(path, WDL_MAGIC) => self.wdl.insert(stem.to_string()),
+            DTZ_EXTENSION if has_table_header(path, DTZ_MAGIC) => self.dtz.insert(stem.to_string()),
+            _ => false,
+        };
+    }
+
+    /// How many table files were found, win/draw/loss and DTZ together.
+    pub fn file_count(&self) -> usize {
+        self.wdl.len() + self.dtz.len()
+    }
+
+    pub fn wdl_count(&self) -> usize {
+        self.wdl.len()
+    }
+
+    pub fn dtz_count(&self) -> usize {
+        self.dtz.len()
+    }
+
+    /// The most pieces, kings included, of any win/draw/loss table.
+    pub fn max_pieces(&self) -> usize {
+        self.wdl
+            .iter()
+            .map(|name| name.len() - 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Whether a win/draw/loss table covers `board`'s material. Castling
+    /// rights put a position outside every table.
+    pub fn covers(&self, board: &Board) -> bool {
+        if board.castling_rights != 0 {
+            return false;
+        }
+        let white = material_key(board, Color::White);
+        let black = material_key(board, Color::Black);
+        self.wdl.contains(&format!("{white}v{black}"))
+            || self.wdl.contains(&format!("{black}v{white}"))
+    }
+}
+
+// `K`, then the other pieces in table order, once per piece.
+fn material_key(board: &Board, color: Color) -> String {
+    let mut key = String::new();
+    for (kind, letter) in PIECE_ORDER {
+        let count = board
+            .squares
+            .iter()
+            .flatten()
+            .filter(|piece| piece.color == color && piece.kind == kind)
+            .count();
+        key.extend(std::iter::repeat_n(letter, count));
+    }
+    key
+}
+
+// Whether `path` starts with `magic` and has the size of a whole table:
+// complete files are 16 bytes past a multiple of 64.
+fn has_table_header(path: &Path, magic: [u8; 4]) -> bool {
+    let Ok(mut file) = fs::File::open(path) else {
+        return false;
+    };
+    let mut header = [0u8; 4];
+    file.read_exact(&mut header).is_ok()
+        && header == magic
+        && file.metadata().is_ok_and(|meta| meta.len() % 64 == 16)
+}
+
+// `K[QRBNP]*vK[QRBNP]*`.
+fn is_table_name(name: &str) -> bool {
+    let side = |side: &str| {
+        side.strip_prefix('K')
+            .is_some_and(|rest| rest.chars().all(|ch| "QRBNP".contains(ch)))
+    };
+    name.split_once('v')
+        .is_some_and(|(white, black)| side(white) && side(black))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The header of a table file, padded to the smallest complete size.
+    fn table(magic: [u8; 4]) -> Vec<u8> {
+        let mut bytes = magic.to_vec();
+        bytes.resize(80, 0);
+        bytes
+    }
+
+    #[test]
+    fn indexes_tables_and_finds_covered_positions() {
+        let dir = env::temp_dir().join(format!("prune-syzygy-{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("dir");
+        for (name, bytes) in [
+            ("KQvK.rtbw", table(WDL_MAGIC)),
+            ("KQvK.rtbz", table(DTZ_MAGIC)),
+            ("KRPvKR.rtbw", table(WDL_MAGIC)),
+            ("README.txt", table(WDL_MAGIC)),
+            ("KXvK.rtbw", table(WDL_MAGIC)),
+            // Empty, truncated and mislabelled files are not tables.
+            ("KRvK.rtbw", Vec::new()),
+            ("KBvK.rtbw", table(WDL_MAGIC)[..40].to_vec()),
+            ("KNvK.rtbw", table(DTZ_MAGIC)),
+        ] {
+            fs::write(dir.join(name), bytes).expect("table");
+        }
+        let tablebases = Tablebases::open(dir.to_str().expect("path"));
+        fs::remove_dir_all(&dir).ok();
+        let tablebases = tablebases.expect("tablebases");
+
+        assert_eq!(tablebases.file_count(), 3);
+        assert_eq!(tablebases.wdl_count(), 2);
+        assert_eq!(tablebases.dtz_count(), 1);
+        assert_eq!(tablebases.max_pieces(), 5);
+
+        let covered = |fen: &str| {
+            let mut board = Board::new();
+            board.set_fen(fen).expect("fen");
+            tablebases.covers(&board)
+        };
+        assert!(covered("4k3/8/8/8/8/8/8/3QK3 w - - 0 1"));
+        // Black's queen is the same table seen from the other side.
+        assert!(covered("3qk3/8/8/8/8/8/8/4K3 w - - 0 1"));
+        assert!(covered("4k3/r7/8/8/8/4P3/8/R3K3 b - - 0 1"));
+        assert!(!covered("4k3/8/8/8/8/8/8/3RK3 w - - 0 1"));
+    }
+
+    #[test]
+    fn missing_directories_are_errors() {
+        assert!(Tablebases::open("/nonexistent/prune/syzygy").is_err());
+        assert_eq!(Tablebases::open("").map(|tb| tb.file_count()), Ok(0));
+    }
+}
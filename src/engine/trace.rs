@@ -0,0 +1,79 @@
+//! Profiling spans, enabled by the `tracing` feature.
+//!
+//! `span!(LEVEL, "name", fields...)` enters a `tracing` span until the end of
+//! the enclosing block, so a subscriber such as `tracing-flame` can build a
+//! flamegraph of the search without the code being instrumented by hand.
+//! Search iterations are `debug` spans; move generation, evaluation and
+//! transposition-table operations are `trace` spans, as they run at every
+//! node. Without the feature the macro expands to nothing.
+
+macro_rules! span {
+    ($level:ident, $name:literal $($fields:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::span!(tracing::Level::$level, $name $($fields)*).entered();
+    };
+}
+
+pub(crate) use span;
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use crate::engine::eval::MaterialEvaluator;
+    use crate::engine::search::AlphaBetaSearch;
+    use crate::engine::Engine;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    // Counts the spans created under each name.
+    #[derive(Default)]
+    struct SpanCounter {
+        next_id: AtomicU64,
+        counts: Arc<Mutex<HashMap<&'static str, u64>>>,
+    }
+
+    impl Subscriber for SpanCounter {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            *self
+                .counts
+                .lock()
+                .expect("counts")
+                .entry(span.metadata().name())
+                .or_default() += 1;
+            Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+        }
+
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, _: &Event<'_>) {}
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[test]
+    fn searches_open_spans_for_each_instrumented_stage() {
+        let counter = SpanCounter::default();
+        let counts = Arc::clone(&counter.counts);
+        tracing::subscriber::with_default(counter, || {
+            let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+            engine.set_position_startpos();
+            engine.search_iterative_results(3);
+        });
+
+        let counts = counts.lock().expect("counts");
+        assert_eq!(counts.get("iteration"), Some(&3));
+        for name in ["movegen", "eval", "tt_probe", "tt_store"] {
+            assert!(counts.get(name).is_some_and(|count| *count > 0), "{name}");
+        }
+    }
+}
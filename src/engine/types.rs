@@ -5,6 +5,7 @@ pub enum Color {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum GameStatus {
     Ongoing,
     Checkmate,
@@ -0,0 +1,91 @@
+//! Win/draw/loss estimates from centipawn scores, for GUIs that show
+//! outcome probabilities instead of scores.
+//!
+//! The model is logistic: the chance of winning is `1 / (1 + e^((a - s) / b))`
+//! for score `s`. With more material left on the board a pawn advantage is
+//! less decisive, so `a` and `b` grow with the material.
+
+use crate::engine::board::Board;
+use crate::engine::eval::piece_value;
+use std::fmt;
+
+/// Material of both sides in the start position, kings excluded.
+const START_MATERIAL: i32 = 8000;
+
+/// Expected outcome for the side to move, in permille; the three always sum
+/// to 1000.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Wdl {
+    pub win: u32,
+    pub draw: u32,
+    pub loss: u32,
+}
+
+impl Wdl {
+    /// Estimates the outcome of `score` (centipawns for the side to move)
+    /// with `material` centipawns of material on the board.
+    pub fn from_score(score: i32, material: i32) -> Self {
+        let phase = f64::from(material.clamp(0, START_MATERIAL)) / f64::from(START_MATERIAL);
+        let a = 150.0 + 200.0 * phase;
+        let b = 60.0 + 60.0 * phase;
+        let score = f64::from(score);
+        let probability = |x: f64| (1000.0 / (1.0 + ((a - x) / b).exp())).round() as u32;
+        let win = probability(score);
+        let loss = probability(-score).min(1000 - win);
+        Self {
+            win,
+            draw: 1000 - win - loss,
+            loss,
+        }
+    }
+}
+
+impl fmt::Display for Wdl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.win, self.draw, self.loss)
+    }
+}
+
+/// Material of both sides, kings excluded.
+pub fn total_material(board: &Board) -> i32 {
+    board
+        .squares
+        .iter()
+        .flatten()
+        .map(|piece| piece_value(piece.kind))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equal_scores_are_mostly_drawn_and_symmetric() {
+        let level = Wdl::from_score(0, START_MATERIAL);
+        assert_eq!(level.win, level.loss);
+        assert!(level.draw > 850);
+
+        let ahead = Wdl::from_score(300, START_MATERIAL);
+        let behind = Wdl::from_score(-300, START_MATERIAL);
+        assert_eq!(
+            (ahead.win, ahead.draw, ahead.loss),
+            (behind.loss, behind.draw, behind.win)
+        );
+        assert_eq!(ahead.win + ahead.draw + ahead.loss, 1000);
+    }
+
+    #[test]
+    fn advantages_count_more_with_less_material() {
+        let middlegame = Wdl::from_score(200, START_MATERIAL);
+        let endgame = Wdl::from_score(200, 1000);
+        assert!(endgame.win > middlegame.win);
+        assert_eq!(Wdl::from_score(30_000, 0).to_string(), "1000 0 0");
+
+        let mut board = Board::new();
+        board
+            .set_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")
+            .expect("fen");
+        assert_eq!(total_material(&board), START_MATERIAL);
+    }
+}
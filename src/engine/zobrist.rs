@@ -14,36 +14,41 @@ struct ZobristKeys {
 }
 
 pub fn compute_hash(board: &Board) -> u64 {
+    hash_position(board, false)
+}
+
+/// The hash of `board.flipped()`, the color-flipped mirror of the position,
+/// computed without building it.
+pub fn compute_mirror_hash(board: &Board) -> u64 {
+    hash_position(board, true)
+}
+
+fn hash_position(board: &Board, mirror: bool) -> u64 {
     let keys = keys();
     let mut hash = 0u64;
 
     for (index, square) in board.squares.iter().enumerate() {
-        if let Some(piece) = square {
-            let square_index = square_index_from_0x88(index as u8);
-            if let Some(sq) = square_index {
-                let piece_index = piece_index(*piece);
-                hash ^= keys.piece_square[piece_index][sq];
-            }
+        if let Some(piece) = square
+            && let Some(sq) = square_index_from_0x88(index as u8)
+        {
+            hash ^= piece_key(keys, *piece, sq, mirror);
         }
     }
 
-    if board.side_to_move == Color::Black {
+    if (board.side_to_move == Color::Black) != mirror {
         hash ^= keys.side_to_move;
     }
 
-    let castling_index = board.castling_rights as usize & 0x0f;
-    hash ^= keys.castling_rights[castling_index];
+    hash ^= castling_key(keys, board.castling_rights, mirror);
 
     if let Some(ep) = board.en_passant {
-        let file = ep.index() & 0x0f;
-        if file < 8 {
-            hash ^= keys.en_passant_file[file as usize];
-        }
+        hash ^= en_passant_key(keys, ep);
     }
 
     hash
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn update_hash_for_move(
     board: &Board,
     mv: Move,
@@ -54,39 +59,89 @@ pub fn update_hash_for_move(
     rook_move: Option<(Square, Square)>,
     previous_castling: u8,
     previous_en_passant: Option<Square>,
+) -> u64 {
+    board.hash
+        ^ move_delta(
+            board,
+            mv,
+            original_piece,
+            moved_piece,
+            captured,
+            captured_square,
+            rook_move,
+            previous_castling,
+            previous_en_passant,
+            false,
+        )
+}
+
+/// `update_hash_for_move` for the hash of the color-flipped mirror.
+#[allow(clippy::too_many_arguments)]
+pub fn update_mirror_hash_for_move(
+    board: &Board,
+    mv: Move,
+    original_piece: Piece,
+    moved_piece: Piece,
+    captured: Option<Piece>,
+    captured_square: Option<Square>,
+    rook_move: Option<(Square, Square)>,
+    previous_castling: u8,
+    previous_en_passant: Option<Square>,
+) -> u64 {
+    board.mirror_hash
+        ^ move_delta(
+            board,
+            mv,
+            original_piece,
+            moved_piece,
+            captured,
+            captured_square,
+            rook_move,
+            previous_castling,
+            previous_en_passant,
+            true,
+        )
+}
+
+// The keys a move toggles, in the position's own hash or in its mirror's.
+#[allow(clippy::too_many_arguments)]
+fn move_delta(
+    board: &Board,
+    mv: Move,
+    original_piece: Piece,
+    moved_piece: Piece,
+    captured: Option<Piece>,
+    captured_square: Option<Square>,
+    rook_move: Option<(Square, Square)>,
+    previous_castling: u8,
+    previous_en_passant: Option<Square>,
+    mirror: bool,
 ) -> u64 {
     let keys = keys();
-    let mut hash = board.hash;
+    let mut delta = 0u64;
 
     if previous_castling <= 0x0f {
-        hash ^= keys.castling_rights[previous_castling as usize];
+        delta ^= castling_key(keys, previous_castling, mirror);
     }
     if let Some(ep) = previous_en_passant {
-        let file = ep.index() & 0x0f;
-        if file < 8 {
-            hash ^= keys.en_passant_file[file as usize];
-        }
+        delta ^= en_passant_key(keys, ep);
     }
 
-    hash ^= keys.side_to_move;
+    delta ^= keys.side_to_move;
 
     if let Some(from_sq) = square_index(mv.from) {
-        let piece_idx = piece_index(original_piece);
-        hash ^= keys.piece_square[piece_idx][from_sq];
+        delta ^= piece_key(keys, original_piece, from_sq, mirror);
     }
 
-    if let Some(capture_sq) = captured_square {
-        if let Some(captured_piece) = captured {
-            if let Some(capture_index) = square_index(capture_sq) {
-                let captured_idx = piece_index(captured_piece);
-                hash ^= keys.piece_square[captured_idx][capture_index];
-            }
-        }
+    if let Some(capture_sq) = captured_square
+        && let Some(captured_piece) = captured
+        && let Some(capture_index) = square_index(capture_sq)
+    {
+        delta ^= piece_key(keys, captured_piece, capture_index, mirror);
     }
 
     if let Some(to_sq) = square_index(mv.to) {
-        let moved_idx = piece_index(moved_piece);
-        hash ^= keys.piece_square[moved_idx][to_sq];
+        delta ^= piece_key(keys, moved_piece, to_sq, mirror);
     }
 
     if let Some((rook_from, rook_to)) = rook_move {
@@ -95,26 +150,56 @@ pub fn update_hash_for_move(
             kind: PieceKind::Rook,
         };
         if let Some(rook_from_idx) = square_index(rook_from) {
-            let rook_index = piece_index(rook_piece);
-            hash ^= keys.piece_square[rook_index][rook_from_idx];
+            delta ^= piece_key(keys, rook_piece, rook_from_idx, mirror);
         }
         if let Some(rook_to_idx) = square_index(rook_to) {
-            let rook_index = piece_index(rook_piece);
-            hash ^= keys.piece_square[rook_index][rook_to_idx];
+            delta ^= piece_key(keys, rook_piece, rook_to_idx, mirror);
         }
     }
 
-    let new_castling = board.castling_rights as usize & 0x0f;
-    hash ^= keys.castling_rights[new_castling];
+    delta ^= castling_key(keys, board.castling_rights, mirror);
 
     if let Some(ep) = board.en_passant {
-        let file = ep.index() & 0x0f;
-        if file < 8 {
-            hash ^= keys.en_passant_file[file as usize];
-        }
+        delta ^= en_passant_key(keys, ep);
     }
 
-    hash
+    delta
+}
+
+// The mirror sees each piece on the other side of the board in the other
+// color; mirroring the ranks of a 0..64 index flips its top three bits.
+fn piece_key(keys: &ZobristKeys, piece: Piece, sq: usize, mirror: bool) -> u64 {
+    if mirror {
+        let piece = Piece {
+            color: match piece.color {
+                Color::White => Color::Black,
+                Color::Black => Color::White,
+            },
+            kind: piece.kind,
+        };
+        keys.piece_square[piece_index(piece)][sq ^ 56]
+    } else {
+        keys.piece_square[piece_index(piece)][sq]
+    }
+}
+
+fn castling_key(keys: &ZobristKeys, rights: u8, mirror: bool) -> u64 {
+    let rights = rights as usize & 0x0f;
+    let rights = if mirror {
+        ((rights & 0b0011) << 2) | ((rights & 0b1100) >> 2)
+    } else {
+        rights
+    };
+    keys.castling_rights[rights]
+}
+
+fn en_passant_key(keys: &ZobristKeys, ep: Square) -> u64 {
+    let file = ep.index() & 0x0f;
+    if file < 8 {
+        keys.en_passant_file[file as usize]
+    } else {
+        0
+    }
 }
 
 fn square_index(square: Square) -> Option<usize> {
@@ -153,9 +238,9 @@ fn keys() -> &'static ZobristKeys {
     KEYS.get_or_init(|| {
         let mut rng = SplitMix64::new(0x9e37_79b9_7f4a_7c15);
         let mut piece_square = [[0u64; SQUARES]; PIECE_TYPES];
-        for piece in 0..PIECE_TYPES {
-            for square in 0..SQUARES {
-                piece_square[piece][square] = rng.next_u64();
+        for piece in piece_square.iter_mut() {
+            for value in piece.iter_mut() {
+                *value = rng.next_u64();
             }
         }
 
@@ -0,0 +1,292 @@
+//! Just enough JSON for the engine's tools: reading Lichess events, server
+//! requests and bench history (objects, arrays, strings, numbers, booleans
+//! and null), and writing strings.
+
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(BTreeMap<String, Json>),
+}
+
+impl Json {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            chars: text.chars().collect(),
+            pos: 0,
+        };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.pos != parser.chars.len() {
+            return Err(format!("trailing characters in JSON: {text}"));
+        }
+        Ok(value)
+    }
+
+    /// The field `key` of an object; `None` for a missing field or a value
+    /// that is not an object.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.get(key),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(number) => Some(*number),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Json::Number(number) if *number >= 0.0 => Some(*number as u64),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&BTreeMap<String, Json>> {
+        match self {
+            Json::Object(fields) => Some(fields),
+            _ => None,
+        }
+    }
+
+    /// The string at `path`, following nested objects.
+    pub fn str_at(&self, path: &[&str]) -> Option<&str> {
+        path.iter()
+            .try_fold(self, |value, key| value.get(key))?
+            .as_str()
+    }
+}
+
+/// `value` as a JSON string literal, quotes included. Control characters
+/// are escaped, as JSON does not allow them raw.
+pub fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            '\u{0}'..='\u{1f}' => quoted.push_str(&format!("\\u{:04x}", ch as u32)),
+            _ => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some('"') => self.string().map(Json::String),
+            Some('t') => self.literal("true", Json::Bool(true)),
+            Some('f') => self.literal("false", Json::Bool(false)),
+            Some('n') => self.literal("null", Json::Null),
+            Some(ch) if ch == '-' || ch.is_ascii_digit() => self.number(),
+            Some(ch) => Err(format!("unexpected '{ch}' in JSON")),
+            None => Err("unexpected end of JSON".to_string()),
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.expect('{')?;
+        let mut fields = BTreeMap::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(Json::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            fields.insert(key, self.value()?);
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(Json::Object(fields)),
+                _ => return Err("expected ',' or '}' in JSON object".to_string()),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.pos += 1;
+            return Ok(Json::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(Json::Array(items)),
+                _ => return Err("expected ',' or ']' in JSON array".to_string()),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut text = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(text),
+                Some('\\') => match self.next() {
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some('r') => text.push('\r'),
+                    Some('b') => text.push('\u{8}'),
+                    Some('f') => text.push('\u{c}'),
+                    Some('u') => {
+                        let hex: String = (0..4).filter_map(|_| self.next()).collect();
+                        let code = u32::from_str_radix(&hex, 16)
+                            .map_err(|_| format!("invalid escape \\u{hex} in JSON"))?;
+                        // Surrogate pairs only appear in text we never read.
+                        text.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+                    }
+                    Some(ch) => text.push(ch),
+                    None => return Err("unterminated JSON string".to_string()),
+                },
+                Some(ch) => text.push(ch),
+                None => return Err("unterminated JSON string".to_string()),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|ch| ch.is_ascii_digit() || "+-.eE".contains(ch))
+        {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        text.parse()
+            .map(Json::Number)
+            .map_err(|_| format!("invalid number in JSON: {text}"))
+    }
+
+    fn literal(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        for expected in word.chars() {
+            if self.next() != Some(expected) {
+                return Err(format!("expected {word} in JSON"));
+            }
+        }
+        Ok(value)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.next() {
+            Some(ch) if ch == expected => Ok(()),
+            _ => Err(format!("expected '{expected}' in JSON")),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.pos += 1;
+        Some(ch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_values() {
+        let json = Json::parse(
+            r#"{"type": "gameFull", "white": {"id": "bot", "rating": 1500},
+                "moves": [], "rated": false, "clock": null, "note": "a \"b\"\n"}"#,
+        )
+        .expect("json");
+        assert_eq!(json.str_at(&["type"]), Some("gameFull"));
+        assert_eq!(json.str_at(&["white", "id"]), Some("bot"));
+        assert_eq!(
+            json.get("white").and_then(|w| w.get("rating")?.as_u64()),
+            Some(1500)
+        );
+        assert_eq!(json.get("moves"), Some(&Json::Array(Vec::new())));
+        assert_eq!(json.get("rated").and_then(Json::as_bool), Some(false));
+        assert_eq!(json.get("clock"), Some(&Json::Null));
+        assert_eq!(json.str_at(&["note"]), Some("a \"b\"\n"));
+    }
+
+    #[test]
+    fn quoted_strings_read_back() {
+        assert_eq!(quote("a \"b\"\\"), r#""a \"b\"\\""#);
+        assert_eq!(
+            quote("line\nnul\u{0}bell\u{7}"),
+            r#""line\nnul\u0000bell\u0007""#
+        );
+        for text in ["plain", "tab\tand\r\n", "\u{1f}\u{1}", "é ♞"] {
+            assert_eq!(
+                Json::parse(&quote(text)),
+                Ok(Json::String(text.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_text() {
+        for text in ["", "{", r#"{"a" 1}"#, "[1,]", "tru", r#""open"#, "{} x"] {
+            assert!(Json::parse(text).is_err(), "{text}");
+        }
+    }
+}
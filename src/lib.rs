@@ -1,2 +1,8 @@
+#[cfg(feature = "async")]
+pub mod async_api;
 pub mod engine;
+pub mod json;
+#[cfg(feature = "lichess")]
+pub mod lichess;
+pub mod prelude;
 pub mod uci;
@@ -0,0 +1,131 @@
+//! Blocking calls to the Lichess bot API. Streams are read line by line on
+//! the calling thread; `super::run` moves them onto threads of their own.
+
+use crate::json::Json;
+use std::io::{BufRead, BufReader, Read};
+
+const LICHESS_URL: &str = "https://lichess.org";
+
+pub struct Client {
+    agent: ureq::Agent,
+    base_url: String,
+    token: String,
+}
+
+impl Client {
+    /// A client authenticated with a bot account's API token.
+    pub fn new(token: &str) -> Self {
+        Self::with_base_url(LICHESS_URL, token)
+    }
+
+    /// A client for another server speaking the same API, such as a local
+    /// Lichess instance for testing.
+    pub fn with_base_url(base_url: &str, token: &str) -> Self {
+        Self {
+            agent: ureq::Agent::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+        }
+    }
+
+    /// The id (lowercase user name) of the account the token belongs to.
+    pub fn account_id(&self) -> Result<String, String> {
+        let text = self
+            .send(self.request("GET", "/api/account"))?
+            .into_string()
+            .map_err(|err| format!("failed to read account: {err}"))?;
+        Json::parse(&text)?
+            .str_at(&["id"])
+            .map(str::to_string)
+            .ok_or_else(|| "account has no id".to_string())
+    }
+
+    /// Incoming challenges and started games, one JSON value per event.
+    pub fn stream_events(&self) -> Result<NdJson, String> {
+        self.stream("/api/stream/event")
+    }
+
+    /// The full game, then a state update after every move.
+    pub fn stream_game(&self, game_id: &str) -> Result<NdJson, String> {
+        self.stream(&format!("/api/bot/game/stream/{game_id}"))
+    }
+
+    pub fn accept_challenge(&self, challenge_id: &str) -> Result<(), String> {
+        let path = format!("/api/challenge/{challenge_id}/accept");
+        self.send(self.request("POST", &path)).map(drop)
+    }
+
+    /// Declines with one of Lichess' reason keys, such as `variant` or `later`.
+    pub fn decline_challenge(&self, challenge_id: &str, reason: &str) -> Result<(), String> {
+        let path = format!("/api/challenge/{challenge_id}/decline");
+        let request = self.request("POST", &path);
+        let target = describe(&request);
+        check(target, request.send_form(&[("reason", reason)])).map(drop)
+    }
+
+    /// Plays `uci_move` in `game_id`.
+    pub fn make_move(&self, game_id: &str, uci_move: &str) -> Result<(), String> {
+        let path = format!("/api/bot/game/{game_id}/move/{uci_move}");
+        self.send(self.request("POST", &path)).map(drop)
+    }
+
+    fn stream(&self, path: &str) -> Result<NdJson, String> {
+        let response = self.send(self.request("GET", path))?;
+        Ok(NdJson {
+            lines: BufReader::new(response.into_reader()),
+        })
+    }
+
+    fn request(&self, method: &str, path: &str) -> ureq::Request {
+        self.agent
+            .request(method, &format!("{}{path}", self.base_url))
+            .set("Authorization", &format!("Bearer {}", self.token))
+    }
+
+    fn send(&self, request: ureq::Request) -> Result<ureq::Response, String> {
+        let target = describe(&request);
+        check(target, request.call())
+    }
+}
+
+fn describe(request: &ureq::Request) -> String {
+    format!("{} {}", request.method(), request.url())
+}
+
+// Turns error statuses into messages carrying Lichess' explanation.
+fn check(
+    target: String,
+    result: Result<ureq::Response, ureq::Error>,
+) -> Result<ureq::Response, String> {
+    match result {
+        Ok(response) => Ok(response),
+        Err(ureq::Error::Status(code, response)) => {
+            let body = response.into_string().unwrap_or_default();
+            Err(format!("{target} failed with {code}: {}", body.trim()))
+        }
+        Err(err) => Err(format!("{target} failed: {err}")),
+    }
+}
+
+/// A newline-delimited JSON stream. Lichess sends empty lines to keep idle
+/// connections open; they are skipped. Ends when the server closes it.
+pub struct NdJson {
+    lines: BufReader<Box<dyn Read + Send + Sync>>,
+}
+
+impl Iterator for NdJson {
+    type Item = Result<Json, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match self.lines.read_line(&mut line) {
+                Ok(0) => return None,
+                Ok(_) if line.trim().is_empty() => continue,
+                Ok(_) => return Some(Json::parse(line.trim())),
+                Err(err) => return Some(Err(format!("stream failed: {err}"))),
+            }
+        }
+    }
+}
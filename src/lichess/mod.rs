@@ -0,0 +1,549 @@
+//! Plays on Lichess as a bot account: accepts challenges, follows each game's
+//! state stream and answers with the engine's moves.
+//!
+//! HTTP calls block, so they run on blocking threads and the streams are
+//! read on threads of their own; searches go through `async_api::analyse`.
+//! One engine is created per game, and games run concurrently up to
+//! `BotConfig::max_games`.
+
+mod client;
+
+pub use crate::json::Json;
+pub use client::{Client, NdJson};
+
+use crate::async_api::analyse;
+use crate::engine::clock::Clocks;
+use crate::engine::eval::Evaluator;
+use crate::engine::search::SearchAlgorithm;
+use crate::engine::types::Color;
+use crate::engine::Engine;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Variants the engine can play: the standard start position or any other.
+const PLAYABLE_VARIANTS: [&str; 2] = ["standard", "fromPosition"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BotConfig {
+    /// The deepest iteration searched; the clock usually stops it first.
+    pub max_depth: u32,
+    /// Thinking time per move in games without a clock.
+    pub move_time: Duration,
+    /// Challenges are declined while this many games are running.
+    pub max_games: usize,
+}
+
+impl Default for BotConfig {
+    fn default() -> Self {
+        Self {
+            max_depth: 64,
+            move_time: Duration::from_secs(10),
+            max_games: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Challenge {
+    pub id: String,
+    /// The challenger's account id.
+    pub challenger: String,
+    /// A Lichess variant key such as `standard` or `chess960`.
+    pub variant: String,
+    pub rated: bool,
+}
+
+/// An event from the account's event stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    Challenge(Challenge),
+    GameStart {
+        game_id: String,
+    },
+    /// Events the bot does not act on, such as finished games.
+    Other,
+}
+
+impl Event {
+    pub fn from_json(json: &Json) -> Result<Self, String> {
+        match json.str_at(&["type"]) {
+            Some("challenge") => {
+                let field = |path: &[&str]| {
+                    json.str_at(path)
+                        .map(str::to_string)
+                        .ok_or_else(|| format!("challenge without {}", path.join(".")))
+                };
+                Ok(Event::Challenge(Challenge {
+                    id: field(&["challenge", "id"])?,
+                    challenger: field(&["challenge", "challenger", "id"])?,
+                    variant: field(&["challenge", "variant", "key"])?,
+                    rated: json
+                        .get("challenge")
+                        .and_then(|challenge| challenge.get("rated")?.as_bool())
+                        .unwrap_or(false),
+                }))
+            }
+            Some("gameStart") => json
+                .str_at(&["game", "gameId"])
+                .or_else(|| json.str_at(&["game", "id"]))
+                .map(|game_id| Event::GameStart {
+                    game_id: game_id.to_string(),
+                })
+                .ok_or_else(|| "gameStart without a game id".to_string()),
+            Some(_) => Ok(Event::Other),
+            None => Err("event without a type".to_string()),
+        }
+    }
+}
+
+/// The moves and clocks of a game after its latest move.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameState {
+    /// The moves since the initial position, in UCI notation.
+    pub moves: Vec<String>,
+    pub clocks: Clocks,
+    /// `started` while the game is on; `mate`, `resign`, `draw` and others
+    /// once it is over.
+    pub status: String,
+}
+
+impl GameState {
+    fn from_json(json: &Json) -> Self {
+        let millis = |key: &str| json.get(key).and_then(Json::as_u64);
+        Self {
+            moves: json
+                .str_at(&["moves"])
+                .unwrap_or("")
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
+            clocks: Clocks {
+                white_ms: millis("wtime"),
+                black_ms: millis("btime"),
+                white_inc_ms: millis("winc").unwrap_or(0),
+                black_inc_ms: millis("binc").unwrap_or(0),
+            },
+            status: json.str_at(&["status"]).unwrap_or("started").to_string(),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        matches!(self.status.as_str(), "created" | "started")
+    }
+}
+
+/// An update from a game's stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameEvent {
+    /// The first update: who plays which side and where the game started.
+    Full {
+        /// Account ids; `None` for the Lichess AI.
+        white: Option<String>,
+        black: Option<String>,
+        /// `startpos` or a FEN.
+        initial_fen: String,
+        state: GameState,
+    },
+    State(GameState),
+    /// Chat lines and opponent-gone notices.
+    Other,
+}
+
+impl GameEvent {
+    pub fn from_json(json: &Json) -> Result<Self, String> {
+        match json.str_at(&["type"]) {
+            Some("gameFull") => Ok(GameEvent::Full {
+                white: json.str_at(&["white", "id"]).map(str::to_string),
+                black: json.str_at(&["black", "id"]).map(str::to_string),
+                initial_fen: json
+                    .str_at(&["initialFen"])
+                    .unwrap_or("startpos")
+                    .to_string(),
+                state: GameState::from_json(json.get("state").unwrap_or(&Json::Null)),
+            }),
+            Some("gameState") => Ok(GameEvent::State(GameState::from_json(json))),
+            Some(_) => Ok(GameEvent::Other),
+            None => Err("game event without a type".to_string()),
+        }
+    }
+}
+
+/// Why a challenge is declined, as a Lichess reason key, or `None` to
+/// accept it.
+pub fn decline_reason(
+    challenge: &Challenge,
+    running_games: usize,
+    config: &BotConfig,
+) -> Option<&'static str> {
+    if !PLAYABLE_VARIANTS.contains(&challenge.variant.as_str()) {
+        Some("variant")
+    } else if running_games >= config.max_games {
+        Some("later")
+    } else {
+        None
+    }
+}
+
+/// Runs the bot until the event stream closes. `new_engine` makes the
+/// engine for each game.
+pub async fn run<E, S, F>(client: Client, config: BotConfig, new_engine: F) -> Result<(), String>
+where
+    E: Evaluator + Send + 'static,
+    S: SearchAlgorithm + Send + 'static,
+    F: Fn() -> Engine<E, S>,
+{
+    let client = Arc::new(client);
+    let account = blocking(&client, Client::account_id).await?;
+    let running = Arc::new(AtomicUsize::new(0));
+    // Accepted challenges whose games have not started. Their slots are
+    // already taken, and a game keeps the id of the challenge it came from.
+    let mut accepted = HashSet::new();
+    let mut events = stream(&client, Client::stream_events);
+
+    while let Some(event) = events.recv().await {
+        let event = match Event::from_json(&event?) {
+            Ok(event) => event,
+            Err(err) => {
+                eprintln!("ignoring event: {err}");
+                continue;
+            }
+        };
+        match event {
+            // Our own challenges to others show up here too.
+            Event::Challenge(challenge) if challenge.challenger != account => {
+                // Take the slot before deciding, so two challenges can never
+                // both be accepted for the last one.
+                let taken = running.fetch_add(1, Ordering::SeqCst);
+                let reason = decline_reason(&challenge, taken, &config);
+                let id = challenge.id.clone();
+                let result = blocking(&client, move |client| match reason {
+                    Some(reason) => client.decline_challenge(&challenge.id, reason),
+                    None => client.accept_challenge(&challenge.id),
+                })
+                .await;
+                if reason.is_none() && result.is_ok() {
+                    accepted.insert(id);
+                } else {
+                    running.fetch_sub(1, Ordering::SeqCst);
+                }
+                if let Err(err) = result {
+                    eprintln!("{err}");
+                }
+            }
+            Event::GameStart { game_id } => {
+                // Games from the bot's own challenges have no slot yet.
+                if !accepted.remove(&game_id) {
+                    running.fetch_add(1, Ordering::SeqCst);
+                }
+                let game = play_game(
+                    Arc::clone(&client),
+                    account.clone(),
+                    game_id.clone(),
+                    new_engine(),
+                    config,
+                );
+                let running = Arc::clone(&running);
+                tokio::spawn(async move {
+                    if let Err(err) = game.await {
+                        eprintln!("game {game_id}: {err}");
+                    }
+                    running.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+            Event::Challenge(_) | Event::Other => {}
+        }
+    }
+    Ok(())
+}
+
+async fn play_game<E, S>(
+    client: Arc<Client>,
+    account: String,
+    game_id: String,
+    mut engine: Engine<E, S>,
+    config: BotConfig,
+) -> Result<(), String>
+where
+    E: Evaluator + Send + 'static,
+    S: SearchAlgorithm + Send + 'static,
+{
+    let mut updates = {
+        let game_id = game_id.clone();
+        stream(&client, move |client| client.stream_game(&game_id))
+    };
+    let mut color = None;
+    let mut initial_fen = "startpos".to_string();
+    // Draw offers and the like repeat a state; each position is answered once.
+    let mut answered_ply = None;
+
+    while let Some(update) = updates.recv().await {
+        let state = match GameEvent::from_json(&update?)? {
+            GameEvent::Full {
+                white,
+                black,
+                initial_fen: fen,
+                state,
+            } => {
+                color = if white.as_deref() == Some(account.as_str()) {
+                    Some(Color::White)
+                } else if black.as_deref() == Some(account.as_str()) {
+                    Some(Color::Black)
+                } else {
+                    return Err("the bot is not playing in this game".to_string());
+                };
+                initial_fen = fen;
+                state
+            }
+            GameEvent::State(state) => state,
+            GameEvent::Other => continue,
+        };
+        if !state.is_running() {
+            break;
+        }
+        let Some(color) = color else {
+            return Err("game state before the full game".to_string());
+        };
+        if answered_ply == Some(state.moves.len()) {
+            continue;
+        }
+        if initial_fen == "startpos" {
+            engine.set_position_startpos();
+        } else {
+            engine.set_position_fen(&initial_fen)?;
+        }
+        engine.try_apply_move_list(&state.moves)?;
+        if engine.side_to_move() != color {
+            continue;
+        }
+
+        match state.clocks.budget(color, None) {
+            Some(budget) => engine.set_time_budget(Some(budget)),
+            None => engine.set_move_time(Some(config.move_time)),
+        }
+        let (bestmove, returned) = analyse(engine, config.max_depth)
+            .bestmove
+            .await
+            .ok_or("the search thread panicked")?;
+        engine = returned;
+        answered_ply = Some(state.moves.len());
+        let game_id = game_id.clone();
+        blocking(&client, move |client| client.make_move(&game_id, &bestmove)).await?;
+    }
+    Ok(())
+}
+
+// Runs a blocking API call without holding up the runtime.
+async fn blocking<T, F>(client: &Arc<Client>, call: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce(&Client) -> Result<T, String> + Send + 'static,
+{
+    let client = Arc::clone(client);
+    tokio::task::spawn_blocking(move || call(&client))
+        .await
+        .map_err(|err| format!("API call failed: {err}"))?
+}
+
+// Reads a stream on its own thread. A failure to open or read it is the
+// last item; the thread ends once the receiver is dropped and another line
+// arrives.
+fn stream<F>(client: &Arc<Client>, open: F) -> mpsc::UnboundedReceiver<Result<Json, String>>
+where
+    F: FnOnce(&Client) -> Result<NdJson, String> + Send + 'static,
+{
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let client = Arc::clone(client);
+    thread::spawn(move || match open(&client) {
+        Ok(lines) => {
+            for line in lines {
+                let failed = line.is_err();
+                if sender.send(line).is_err() || failed {
+                    break;
+                }
+            }
+        }
+        Err(err) => {
+            let _ = sender.send(Err(err));
+        }
+    });
+    receiver
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::eval::MaterialEvaluator;
+    use crate::engine::search::AlphaBetaSearch;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    fn parse(text: &str) -> Json {
+        Json::parse(text).expect("json")
+    }
+
+    // A back-rank mate for the bot as white, then the state after it.
+    const GAME_STREAM: &str = concat!(
+        r#"{"type":"gameFull","id":"g1","white":{"id":"prunebot"},"black":{"id":"lovlas"},"#,
+        r#""initialFen":"6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1","#,
+        r#""state":{"type":"gameState","moves":"","status":"started"}}"#,
+        "\n",
+        r#"{"type":"gameState","moves":"a1a8","status":"mate"}"#,
+        "\n",
+    );
+
+    // Answers `connections` requests like Lichess would, returning the
+    // request lines of the moves played.
+    fn serve(listener: TcpListener, connections: usize) -> thread::JoinHandle<Vec<String>> {
+        thread::spawn(move || {
+            let mut moves = Vec::new();
+            for stream in listener.incoming().take(connections) {
+                let mut stream = stream.expect("connection");
+                let mut reader = BufReader::new(stream.try_clone().expect("clone"));
+                let mut request = String::new();
+                reader.read_line(&mut request).expect("request line");
+                let mut header = String::new();
+                while reader.read_line(&mut header).expect("header") > 0 && header.trim() != "" {
+                    header.clear();
+                }
+                let body = if request.starts_with("GET /api/bot/game/stream/") {
+                    GAME_STREAM
+                } else {
+                    moves.push(request.trim().to_string());
+                    r#"{"ok":true}"#
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+                .expect("response");
+            }
+            moves
+        })
+    }
+
+    #[test]
+    fn plays_the_engine_move_until_the_game_ends() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).expect("bind");
+        let url = format!("http://{}", listener.local_addr().expect("addr"));
+        let server = serve(listener, 2);
+        let client = Arc::new(Client::with_base_url(&url, "token"));
+        let engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+        let config = BotConfig {
+            max_depth: 3,
+            ..BotConfig::default()
+        };
+
+        let game = play_game(
+            client,
+            "prunebot".to_string(),
+            "g1".to_string(),
+            engine,
+            config,
+        );
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .expect("runtime")
+            .block_on(game)
+            .expect("game");
+        assert_eq!(
+            server.join().expect("server"),
+            ["POST /api/bot/game/g1/move/a1a8 HTTP/1.1"]
+        );
+    }
+
+    #[test]
+    fn reads_challenges_and_game_starts() {
+        let challenge = parse(
+            r#"{"type":"challenge","challenge":{"id":"7pGLxJ4F","status":"created",
+                "challenger":{"id":"lovlas","name":"Lovlas","rating":1500},
+                "variant":{"key":"standard","name":"Standard"},"rated":true,"speed":"rapid"}}"#,
+        );
+        assert_eq!(
+            Event::from_json(&challenge),
+            Ok(Event::Challenge(Challenge {
+                id: "7pGLxJ4F".to_string(),
+                challenger: "lovlas".to_string(),
+                variant: "standard".to_string(),
+                rated: true,
+            }))
+        );
+
+        let start = parse(r#"{"type":"gameStart","game":{"gameId":"5IrD6Gzz","color":"white"}}"#);
+        assert_eq!(
+            Event::from_json(&start),
+            Ok(Event::GameStart {
+                game_id: "5IrD6Gzz".to_string()
+            })
+        );
+        let finish = parse(r#"{"type":"gameFinish","game":{"gameId":"5IrD6Gzz"}}"#);
+        assert_eq!(Event::from_json(&finish), Ok(Event::Other));
+        assert!(Event::from_json(&parse(r#"{"type":"challenge"}"#)).is_err());
+    }
+
+    #[test]
+    fn reads_the_full_game_and_state_updates() {
+        let full = parse(
+            r#"{"id":"5IrD6Gzz","type":"gameFull","variant":{"key":"standard"},
+                "white":{"id":"prunebot","name":"PruneBot","title":"BOT"},"black":{"aiLevel":3},
+                "initialFen":"startpos",
+                "state":{"type":"gameState","moves":"e2e4 c7c5","wtime":7598040,
+                    "btime":8395220,"winc":10000,"binc":10000,"status":"started"}}"#,
+        );
+        let GameEvent::Full {
+            white,
+            black,
+            initial_fen,
+            state,
+        } = GameEvent::from_json(&full).expect("event")
+        else {
+            panic!("expected the full game");
+        };
+        assert_eq!(white.as_deref(), Some("prunebot"));
+        assert_eq!(black, None);
+        assert_eq!(initial_fen, "startpos");
+        assert_eq!(state.moves, ["e2e4", "c7c5"]);
+        assert_eq!(state.clocks.white_ms, Some(7598040));
+        assert_eq!(state.clocks.black_inc_ms, 10000);
+        assert!(state.is_running());
+
+        let over = parse(r#"{"type":"gameState","moves":"f2f3 e7e5 g2g4 d8h4","status":"mate"}"#);
+        let GameEvent::State(state) = GameEvent::from_json(&over).expect("event") else {
+            panic!("expected a state update");
+        };
+        assert_eq!(state.moves.len(), 4);
+        assert_eq!(state.clocks.white_ms, None);
+        assert!(!state.is_running());
+
+        let chat = parse(r#"{"type":"chatLine","username":"lovlas","text":"hi","room":"player"}"#);
+        assert_eq!(GameEvent::from_json(&chat), Ok(GameEvent::Other));
+    }
+
+    #[test]
+    fn declines_unplayable_variants_and_challenges_beyond_the_game_limit() {
+        let config = BotConfig {
+            max_games: 2,
+            ..BotConfig::default()
+        };
+        let challenge = |variant: &str| Challenge {
+            id: "id".to_string(),
+            challenger: "lovlas".to_string(),
+            variant: variant.to_string(),
+            rated: false,
+        };
+        assert_eq!(decline_reason(&challenge("standard"), 1, &config), None);
+        assert_eq!(decline_reason(&challenge("fromPosition"), 0, &config), None);
+        assert_eq!(
+            decline_reason(&challenge("crazyhouse"), 0, &config),
+            Some("variant")
+        );
+        assert_eq!(
+            decline_reason(&challenge("standard"), 2, &config),
+            Some("later")
+        );
+    }
+}
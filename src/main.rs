@@ -1,43 +1,323 @@
-use chess_engine::engine::eval::MaterialEvaluator;
-use chess_engine::engine::search::{AlphaBetaSearch, MinimaxSearch};
-use chess_engine::engine::Engine;
-use chess_engine::uci;
+use prune::engine::audit::{verify_determinism, AUDIT_FENS};
+use prune::engine::book::Repertoire;
+use prune::engine::epd::EpdRecord;
+use prune::engine::eval::MaterialEvaluator;
+use prune::engine::search::AlphaBetaSearch;
+use prune::engine::Engine;
+use prune::uci::{self, UciClient};
 use std::env;
+use std::fs;
+use std::process;
+
+struct Args {
+    default_depth: u32,
+    seed: Option<u64>,
+    repertoire: Option<String>,
+    verify_determinism: bool,
+    analyse_epd: Option<String>,
+    compare_epd: Option<String>,
+    reference: Option<String>,
+    reference_depth: Option<u32>,
+    threshold: i32,
+    log: Option<String>,
+    record: Option<String>,
+    replay: Option<String>,
+}
 
 fn main() {
-    let (default_depth, seed) = parse_args();
-    // let mut engine = Engine::with_components(MaterialEvaluator, MinimaxSearch);
+    let args = parse_args();
+    if args.verify_determinism {
+        process::exit(run_determinism_audit(args.default_depth));
+    }
+    if let Some(path) = &args.analyse_epd {
+        process::exit(run_epd_analysis(path, args.default_depth));
+    }
+    if let Some(path) = &args.compare_epd {
+        process::exit(run_reference_comparison(path, &args));
+    }
+
+    if let Some(path) = &args.log
+        && let Err(err) = uci::set_log_file(path)
+    {
+        eprintln!("invalid --log: {err}");
+    }
+
+    // let mut engine = Engine::with_components(MaterialEvaluator, MinimaxSearch::new());
+    let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+    if let Some(seed) = args.seed {
+        engine.set_rng_seed(seed);
+    }
+    if let Some(path) = args.repertoire {
+        match Repertoire::load(&path) {
+            Ok(repertoire) => engine.set_repertoire(Some(repertoire)),
+            Err(err) => eprintln!("invalid --repertoire: {err}"),
+        }
+    }
+
+    let mut input: Box<dyn Iterator<Item = String>> = match &args.replay {
+        Some(path) => match uci::load_session(path) {
+            Ok(commands) => Box::new(uci::replay(commands)),
+            Err(err) => {
+                eprintln!("invalid --replay: {err}");
+                process::exit(1);
+            }
+        },
+        None => Box::new(uci::stdin_lines()),
+    };
+    if let Some(path) = &args.record {
+        match uci::Recorder::create(path) {
+            Ok(mut recorder) => {
+                input = Box::new(input.inspect(move |line| recorder.record(line)));
+            }
+            Err(err) => eprintln!("invalid --record: {err}"),
+        }
+    }
+    uci::run_loop_with_input(&mut engine, args.default_depth, input);
+}
+
+fn run_determinism_audit(depth: u32) -> i32 {
+    let mut failures = 0;
+    for fen in AUDIT_FENS {
+        let make_engine = || Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+        match verify_determinism(make_engine, fen, depth) {
+            Ok(()) => println!("ok {fen}"),
+            Err(err) => {
+                println!("nondeterministic {fen}: {err}");
+                failures += 1;
+            }
+        }
+    }
+    if failures == 0 {
+        0
+    } else {
+        1
+    }
+}
+
+// Prints each position of an EPD file with the engine's analysis opcodes
+// added to its own.
+fn run_epd_analysis(path: &str, depth: u32) -> i32 {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) => {
+            eprintln!("failed to read {path}: {err}");
+            return 1;
+        }
+    };
+    let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+    let mut failures = 0;
+    for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+        let record = EpdRecord::parse(line).and_then(|record| {
+            let board = record.board()?;
+            engine.set_position_fen(&board.to_fen())?;
+            Ok(record)
+        });
+        match record {
+            Ok(mut record) => {
+                let analysis = engine.analyse_epd(depth);
+                for (opcode, operands) in analysis.operations {
+                    record.set_operation(&opcode, operands);
+                }
+                println!("{record}");
+            }
+            Err(err) => {
+                eprintln!("invalid EPD: {err}");
+                failures += 1;
+            }
+        }
+    }
+    if failures == 0 {
+        0
+    } else {
+        1
+    }
+}
+
+struct Divergence {
+    fen: String,
+    gap: i32,
+    ours: (String, Option<i32>),
+    reference: (String, Option<i32>),
+}
+
+// Analyses every position of an EPD file with prune and with the reference
+// engine and prints those where the best moves differ or the scores are more
+// than `--threshold` apart, largest score gap first, as the likeliest bugs.
+fn run_reference_comparison(path: &str, args: &Args) -> i32 {
+    let Some(command) = args.reference.as_deref() else {
+        eprintln!("missing --reference <engine>");
+        return 1;
+    };
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) => {
+            eprintln!("failed to read {path}: {err}");
+            return 1;
+        }
+    };
+    let mut reference = match UciClient::spawn(command) {
+        Ok(reference) => reference,
+        Err(err) => {
+            eprintln!("invalid --reference: {err}");
+            return 1;
+        }
+    };
+    let depth = args.default_depth;
+    let reference_depth = args.reference_depth.unwrap_or(depth);
     let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
-    if let Some(seed) = seed {
+    if let Some(seed) = args.seed {
         engine.set_rng_seed(seed);
     }
-    uci::run_loop(&mut engine, default_depth);
+
+    let mut compared = 0;
+    let mut failures = 0;
+    let mut divergences = Vec::new();
+    for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+        let fen = match EpdRecord::parse(line).and_then(|record| record.board()) {
+            Ok(board) => board.to_fen(),
+            Err(err) => {
+                eprintln!("invalid EPD: {err}");
+                failures += 1;
+                continue;
+            }
+        };
+        let theirs = match reference.analyse(&fen, reference_depth) {
+            Ok(theirs) => theirs,
+            Err(err) => {
+                eprintln!("reference failed on {fen}: {err}");
+                return 1;
+            }
+        };
+        // Like the reference, each position starts a new game.
+        engine.reset_state();
+        if let Err(err) = engine.set_position_fen(&fen) {
+            eprintln!("invalid EPD: {err}");
+            failures += 1;
+            continue;
+        }
+        let mut score = None;
+        let (bestmove, _) =
+            engine.search_iterative_with_info(depth, |info| score = Some(info.score));
+        let ours = (bestmove, score);
+
+        compared += 1;
+        let gap = match (ours.1, theirs.score) {
+            (Some(ours), Some(theirs)) => ours.abs_diff(theirs) as i32,
+            _ => 0,
+        };
+        if ours.0 != theirs.bestmove || gap > args.threshold {
+            divergences.push(Divergence {
+                fen,
+                gap,
+                ours,
+                reference: (theirs.bestmove, theirs.score),
+            });
+        }
+    }
+
+    divergences.sort_by_key(|divergence| std::cmp::Reverse(divergence.gap));
+    let score = |score: Option<i32>| score.map_or("?".to_string(), |score| score.to_string());
+    for divergence in &divergences {
+        println!(
+            "gap {} fen {} prune {} {} reference {} {}",
+            divergence.gap,
+            divergence.fen,
+            divergence.ours.0,
+            score(divergence.ours.1),
+            divergence.reference.0,
+            score(divergence.reference.1)
+        );
+    }
+    eprintln!(
+        "compared {compared} positions, {} diverge",
+        divergences.len()
+    );
+    if failures == 0 {
+        0
+    } else {
+        1
+    }
 }
 
-fn parse_args() -> (u32, Option<u64>) {
-    let mut default_depth = 6u32;
-    let mut seed = None;
+fn parse_args() -> Args {
+    let mut parsed = Args {
+        default_depth: 6,
+        seed: None,
+        repertoire: None,
+        verify_determinism: false,
+        analyse_epd: None,
+        compare_epd: None,
+        reference: None,
+        reference_depth: None,
+        threshold: 50,
+        log: None,
+        record: None,
+        replay: None,
+    };
     let mut args = env::args().skip(1);
 
     while let Some(arg) = args.next() {
         match arg.as_str() {
             "--depth" => match args.next() {
                 Some(value) => match value.parse::<u32>() {
-                    Ok(parsed) => default_depth = parsed,
+                    Ok(value) => parsed.default_depth = value,
                     Err(_) => eprintln!("invalid --depth: {value}"),
                 },
                 None => eprintln!("missing value for --depth"),
             },
             "--seed" => match args.next() {
                 Some(value) => match value.parse::<u64>() {
-                    Ok(parsed) => seed = Some(parsed),
+                    Ok(value) => parsed.seed = Some(value),
                     Err(_) => eprintln!("invalid --seed: {value}"),
                 },
                 None => eprintln!("missing value for --seed"),
             },
+            "--repertoire" => match args.next() {
+                Some(value) => parsed.repertoire = Some(value),
+                None => eprintln!("missing value for --repertoire"),
+            },
+            "--verify-determinism" => parsed.verify_determinism = true,
+            "--analyse-epd" => match args.next() {
+                Some(value) => parsed.analyse_epd = Some(value),
+                None => eprintln!("missing value for --analyse-epd"),
+            },
+            "--compare-epd" => match args.next() {
+                Some(value) => parsed.compare_epd = Some(value),
+                None => eprintln!("missing value for --compare-epd"),
+            },
+            "--reference" => match args.next() {
+                Some(value) => parsed.reference = Some(value),
+                None => eprintln!("missing value for --reference"),
+            },
+            "--reference-depth" => match args.next() {
+                Some(value) => match value.parse::<u32>() {
+                    Ok(value) => parsed.reference_depth = Some(value),
+                    Err(_) => eprintln!("invalid --reference-depth: {value}"),
+                },
+                None => eprintln!("missing value for --reference-depth"),
+            },
+            "--threshold" => match args.next() {
+                Some(value) => match value.parse::<i32>() {
+                    Ok(value) => parsed.threshold = value,
+                    Err(_) => eprintln!("invalid --threshold: {value}"),
+                },
+                None => eprintln!("missing value for --threshold"),
+            },
+            "--log" => match args.next() {
+                Some(value) => parsed.log = Some(value),
+                None => eprintln!("missing value for --log"),
+            },
+            "--record" => match args.next() {
+                Some(value) => parsed.record = Some(value),
+                None => eprintln!("missing value for --record"),
+            },
+            "--replay" => match args.next() {
+                Some(value) => parsed.replay = Some(value),
+                None => eprintln!("missing value for --replay"),
+            },
             _ => eprintln!("unknown argument: {arg}"),
         }
     }
 
-    (default_depth, seed)
+    parsed
 }
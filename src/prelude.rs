@@ -0,0 +1,23 @@
+//! The types most programs using the engine need, in one import:
+//!
+//! ```
+//! use prune::prelude::*;
+//!
+//! let mut engine = Engine::with_components(MaterialEvaluator, AlphaBetaSearch::new());
+//! engine.set_position_startpos();
+//! assert_eq!(engine.side_to_move(), Color::White);
+//! ```
+
+pub use crate::engine::annotate::AnnotatedMove;
+pub use crate::engine::board::Board;
+pub use crate::engine::composite::{
+    CompositeEvaluator, ImbalanceTerm, MaterialTerm, Phases, TempoTerm, Term,
+};
+pub use crate::engine::eval::{Evaluator, MaterialEvaluator};
+pub use crate::engine::imbalance::{ImbalanceEvaluator, ImbalanceParams};
+pub use crate::engine::limits::SearchLimits;
+pub use crate::engine::search::{
+    AlphaBetaSearch, MinimaxSearch, SearchAlgorithm, SearchParams, SearchResult, StopSignal,
+};
+pub use crate::engine::types::{Color, GameStatus, Move, Piece, PieceKind};
+pub use crate::engine::{Engine, SearchInfo};
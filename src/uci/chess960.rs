@@ -0,0 +1,110 @@
+//! Chess960 notation for the UCI layer (`UCI_Chess960`).
+//!
+//! Chess960 GUIs send every castling move as the king capturing its own rook
+//! (`e1h1`). The board writes castling from the standard setup as the king
+//! moving two files (`e1g1`) and every other castling move already as the
+//! king taking its rook, so only the standard setups need rewriting.
+
+use crate::engine::board::Board;
+use crate::engine::castling::{castling_move, castling_wing, CastlingFiles, Wing};
+use crate::engine::types::{move_from_uci, uci_from_move, Color, Move, PieceKind, Square};
+
+/// Converts a king-takes-rook castling move to the board's castling move;
+/// any other move is returned unchanged.
+pub fn move_from_chess960(board: &Board, text: &str) -> String {
+    let Some(mv) = move_from_uci(text) else {
+        return text.to_string();
+    };
+    let Some(king) = board.squares[mv.from.index() as usize] else {
+        return text.to_string();
+    };
+    let files = &board.castling_files;
+    let castles = Wing::BOTH.into_iter().find(|&wing| {
+        king.kind == PieceKind::King
+            && files.is_standard(king.color, wing)
+            && mv == king_takes_rook(files, king.color, wing)
+    });
+    match castles {
+        Some(wing) => uci_from_move(castling_move(files, king.color, wing))
+            .unwrap_or_else(|| text.to_string()),
+        None => text.to_string(),
+    }
+}
+
+/// Writes `mv` for a Chess960 GUI: castling becomes the king taking its own
+/// rook.
+pub fn move_to_chess960(board: &Board, mv: Move) -> Option<String> {
+    match (
+        castling_wing(board, mv),
+        board.squares[mv.from.index() as usize],
+    ) {
+        (Some(wing), Some(king)) => {
+            uci_from_move(king_takes_rook(&board.castling_files, king.color, wing))
+        }
+        _ => uci_from_move(mv),
+    }
+}
+
+fn king_takes_rook(files: &CastlingFiles, color: Color, wing: Wing) -> Move {
+    let castle = castling_move(files, color, wing);
+    Move {
+        to: Square((castle.from.index() & 0x70) | files.rook(color, wing)),
+        ..castle
+    }
+}
+
+/// Writes a line of moves played from `board` for a Chess960 GUI.
+pub fn line_to_chess960(board: &Board, moves: &[Move]) -> Vec<String> {
+    let mut board = board.clone();
+    let mut line = Vec::new();
+    for mv in moves {
+        let Some(text) = move_to_chess960(&board, *mv) else {
+            break;
+        };
+        line.push(text);
+        if board.apply_move(*mv).is_err() {
+            break;
+        }
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board_from(fen: &str) -> Board {
+        let mut board = Board::new();
+        board.set_fen(fen).expect("fen");
+        board
+    }
+
+    #[test]
+    fn castling_round_trips_through_king_takes_rook() {
+        let board = board_from("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        assert_eq!(move_from_chess960(&board, "e1h1"), "e1g1");
+        assert_eq!(move_from_chess960(&board, "e1a1"), "e1c1");
+        assert_eq!(move_from_chess960(&board, "e1f1"), "e1f1");
+        assert_eq!(move_from_chess960(&board, "a1a8"), "a1a8");
+
+        let short = move_from_uci("e1g1").expect("move");
+        assert_eq!(move_to_chess960(&board, short).as_deref(), Some("e1h1"));
+        let rook_move = move_from_uci("h1h8").expect("move");
+        assert_eq!(move_to_chess960(&board, rook_move).as_deref(), Some("h1h8"));
+
+        let line = [short, move_from_uci("e8c8").expect("move")];
+        assert_eq!(line_to_chess960(&board, &line), vec!["e1h1", "e8a8"]);
+    }
+
+    #[test]
+    fn chess960_castling_is_already_king_takes_rook() {
+        // King on b1 between rooks on a1 and h1: both castling moves are the
+        // king onto its rook, read and written unchanged.
+        let board = board_from("rk5r/pppppppp/8/8/8/8/PPPPPPPP/RK5R w HAha - 0 1");
+        assert_eq!(move_from_chess960(&board, "b1a1"), "b1a1");
+        let long = move_from_uci("b1a1").expect("move");
+        let short = move_from_uci("b1h1").expect("move");
+        assert_eq!(move_to_chess960(&board, long).as_deref(), Some("b1a1"));
+        assert_eq!(move_to_chess960(&board, short).as_deref(), Some("b1h1"));
+    }
+}
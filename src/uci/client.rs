@@ -0,0 +1,191 @@
+//! Driving another UCI engine, e.g. a reference engine whose analysis prune's
+//! is compared with.
+
+use crate::engine::search::MATE_SCORE;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long `UciClient::spawn` gives the engine to answer each command.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// An engine running as a child process. Its output is read on a thread of
+/// its own, so an engine that hangs costs a timeout rather than the caller.
+pub struct UciClient {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<Result<String, String>>,
+    timeout: Duration,
+}
+
+/// What an engine found in a position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientAnalysis {
+    pub bestmove: String,
+    /// Centipawns for the side to move from the last exact score reported;
+    /// mates are scaled like prune's own scores, `MATE_SCORE` less the plies.
+    pub score: Option<i32>,
+}
+
+impl UciClient {
+    /// Starts `command`, a program followed by its arguments separated by
+    /// whitespace, and waits until it has answered `uci`.
+    pub fn spawn(command: &str) -> Result<Self, String> {
+        Self::with_timeout(command, DEFAULT_TIMEOUT)
+    }
+
+    /// Like `spawn`, but the engine fails once it takes longer than
+    /// `timeout` to answer a command, a search included.
+    pub fn with_timeout(command: &str, timeout: Duration) -> Result<Self, String> {
+        let mut words = command.split_whitespace();
+        let program = words.next().ok_or("empty engine command")?;
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|err| format!("failed to start {program}: {err}"))?;
+        let stdin = child.stdin.take().ok_or("engine has no stdin")?;
+        let stdout = BufReader::new(child.stdout.take().ok_or("engine has no stdout")?);
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in stdout.lines() {
+                let line = line.map_err(|err| format!("failed to read from engine: {err}"));
+                let failed = line.is_err();
+                if sender.send(line).is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        let mut client = Self {
+            child,
+            stdin,
+            lines,
+            timeout,
+        };
+        client.send("uci")?;
+        client.read_until(|line| line == "uciok")?;
+        Ok(client)
+    }
+
+    /// Searches `fen` to `depth` from a fresh game.
+    pub fn analyse(&mut self, fen: &str, depth: u32) -> Result<ClientAnalysis, String> {
+        self.send("ucinewgame")?;
+        self.send(&format!("position fen {fen}"))?;
+        self.send("isready")?;
+        self.read_until(|line| line == "readyok")?;
+        self.send(&format!("go depth {depth}"))?;
+
+        let mut score = None;
+        let last = self.read_until(|line| {
+            if let Some(reported) = parse_info_score(line) {
+                score = Some(reported);
+            }
+            line.starts_with("bestmove")
+        })?;
+        let bestmove = last
+            .split_whitespace()
+            .nth(1)
+            .ok_or("bestmove without a move")?
+            .to_string();
+        Ok(ClientAnalysis { bestmove, score })
+    }
+
+    fn send(&mut self, line: &str) -> Result<(), String> {
+        writeln!(self.stdin, "{line}")
+            .and_then(|()| self.stdin.flush())
+            .map_err(|err| format!("engine closed its input: {err}"))
+    }
+
+    // Reads lines until one satisfies `done` and returns it, giving up once
+    // the timeout has passed.
+    fn read_until(&mut self, mut done: impl FnMut(&str) -> bool) -> Result<String, String> {
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            let line = match self.lines.recv_timeout(left) {
+                Ok(line) => line?,
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(format!("engine did not answer within {:?}", self.timeout));
+                }
+                Err(RecvTimeoutError::Disconnected) => return Err("engine exited".to_string()),
+            };
+            if done(line.trim()) {
+                return Ok(line.trim().to_string());
+            }
+        }
+    }
+}
+
+impl Drop for UciClient {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// The score of an `info` line, unless it is missing or only a bound.
+pub fn parse_info_score(line: &str) -> Option<i32> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    if tokens.first() != Some(&"info")
+        || tokens.get(1) == Some(&"string")
+        || tokens.contains(&"lowerbound")
+        || tokens.contains(&"upperbound")
+    {
+        return None;
+    }
+    let at = tokens.iter().position(|token| *token == "score")?;
+    let value: i32 = tokens.get(at + 2)?.parse().ok()?;
+    match *tokens.get(at + 1)? {
+        "cp" => Some(value),
+        // Mate in `value` moves is `2 * value - 1` plies away for the side
+        // delivering it, and `-2 * value` plies for the side being mated.
+        "mate" if value > 0 => Some(MATE_SCORE - (2 * value - 1)),
+        "mate" => Some(-MATE_SCORE - 2 * value),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_exact_scores_and_mates() {
+        assert_eq!(
+            parse_info_score("info depth 5 seldepth 7 score cp -34 nodes 100 pv e2e4"),
+            Some(-34)
+        );
+        assert_eq!(
+            parse_info_score("info depth 3 score mate 2 pv a1a8"),
+            Some(MATE_SCORE - 3)
+        );
+        assert_eq!(
+            parse_info_score("info depth 4 score mate -1 pv e8d8"),
+            Some(-MATE_SCORE + 2)
+        );
+        assert_eq!(
+            parse_info_score("info depth 5 score cp 20 lowerbound"),
+            None
+        );
+        assert_eq!(parse_info_score("info string score cp 5"), None);
+        assert_eq!(parse_info_score("bestmove e2e4"), None);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn gives_up_on_engines_that_never_answer() {
+        // `cat` echoes `uci` back but never says `uciok`.
+        let started = Instant::now();
+        let err = UciClient::with_timeout("cat", Duration::from_millis(100))
+            .err()
+            .expect("timeout");
+        assert!(err.contains("did not answer"), "{err}");
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
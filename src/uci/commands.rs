@@ -1,16 +1,43 @@
+use crate::engine::clock::Clocks;
+use crate::engine::limits::SearchLimits;
+use crate::engine::types::{Color, Piece, Square};
+use std::time::Duration;
+
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Command {
     Uci,
+    /// `debug on|off`: whether to send diagnostic `info string` lines.
+    Debug(bool),
     IsReady,
     UciNewGame,
     Position(PositionCommand),
     Go(GoCommand),
+    /// `analysebatch [go parameters]`: analyse the FENs on the following
+    /// lines, up to `end`, one after another with the same limits.
+    AnalyseBatch(GoCommand),
+    Edit(EditCommand),
+    Flip,
+    /// `d`: print the current position.
+    Display,
+    /// `eval`: print the static evaluation term by term.
+    Eval,
+    /// `moves [to] <square>`: list the legal moves from, or onto, a square.
+    Moves {
+        square: Square,
+        to: bool,
+    },
+    /// `setoption name <name> [value <value>]`; names may contain spaces.
+    SetOption {
+        name: String,
+        value: Option<String>,
+    },
     Stop,
     Quit,
     Unknown(String),
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct PositionCommand {
     pub fen: Option<String>,
     pub moves: Vec<String>,
@@ -24,4 +51,51 @@ pub struct GoCommand {
     pub btime: Option<u64>,
     pub winc: Option<u64>,
     pub binc: Option<u64>,
+    pub movestogo: Option<u32>,
+    /// `go mate N`: look for a mate in at most N moves.
+    pub mate: Option<u32>,
+    /// `go nodes N`: stop once N nodes have been searched.
+    pub nodes: Option<u64>,
+    /// `go searchmoves m1 m2 ...`: only consider these root moves.
+    pub searchmoves: Vec<String>,
+    /// `go perft N`: count leaf positions to depth N instead of searching.
+    pub perft: Option<u32>,
+    /// `go infinite`: search until `stop`, however deep that gets.
+    pub infinite: bool,
+}
+
+impl GoCommand {
+    /// The limits this command puts on the search; `searchmoves`, `mate` and
+    /// `perft` are handled by the loop.
+    pub fn limits(&self) -> SearchLimits {
+        SearchLimits {
+            depth: self.depth,
+            nodes: self.nodes,
+            movetime: self.movetime.map(Duration::from_millis),
+            clocks: self.clocks(),
+            movestogo: self.movestogo,
+            infinite: self.infinite,
+        }
+    }
+
+    pub fn clocks(&self) -> Clocks {
+        Clocks {
+            white_ms: self.wtime,
+            black_ms: self.btime,
+            white_inc_ms: self.winc.unwrap_or(0),
+            black_inc_ms: self.binc.unwrap_or(0),
+        }
+    }
+}
+
+/// Non-standard commands that edit the current position in place.
+#[derive(Debug, PartialEq)]
+#[non_exhaustive]
+pub enum EditCommand {
+    Put(Square, Piece),
+    Remove(Square),
+    /// Sets the side to move, or toggles it when no color is given.
+    Turn(Option<Color>),
+    Castling(u8),
+    EnPassant(Option<Square>),
 }
@@ -0,0 +1,119 @@
+//! Diagnostics for `debug on`: extra `info string debug ...` lines that stay
+//! silent, and cost nothing to format, while debug mode is off.
+
+use super::write_line;
+use crate::engine::book::Repertoire;
+use crate::engine::search::{HashUsage, SearchStats};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DebugLog {
+    enabled: bool,
+}
+
+impl DebugLog {
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Sends `info string debug <message>`; `message` only runs when debug
+    /// mode is on.
+    pub fn log(&self, message: impl FnOnce() -> String) {
+        if self.enabled {
+            write_line(&format!("info string debug {}", message()));
+        }
+    }
+
+    /// Reports an iteration's transposition table, pruning, move ordering
+    /// and node type counters.
+    pub fn log_stats(&self, stats: &SearchStats) {
+        self.log(|| {
+            format!(
+                "tt probes {} hits {} ({}%) cutoffs {}",
+                stats.tt_probes,
+                stats.tt_hits,
+                percent(stats.tt_hits, stats.tt_probes),
+                stats.tt_cutoffs
+            )
+        });
+        self.log(|| {
+            format!(
+                "pruning lmp {} repetitions {}",
+                stats.lmp_pruned, stats.repetitions
+            )
+        });
+        self.log(|| {
+            format!(
+                "ordering cutoffs {} first move {} ({}%)",
+                stats.beta_cutoffs,
+                stats.first_move_cutoffs,
+                percent(stats.first_move_cutoffs, stats.beta_cutoffs)
+            )
+        });
+        self.log(|| {
+            format!(
+                "nodes pv {} cut {} confirmed {} ({}%)",
+                stats.pv_nodes,
+                stats.cut_nodes,
+                stats.cut_nodes_confirmed,
+                percent(stats.cut_nodes_confirmed, stats.cut_nodes)
+            )
+        });
+    }
+
+    /// Reports the memory the transposition tables and the opening book
+    /// hold, to help size `Hash`.
+    pub fn log_memory(&self, hash: Option<HashUsage>, book: Option<&Repertoire>) {
+        if let Some(hash) = hash {
+            self.log(|| {
+                format!(
+                    "memory tt {} in {} tables, {} entries",
+                    format_bytes(hash.bytes),
+                    hash.tables,
+                    hash.entries
+                )
+            });
+        }
+        if let Some(book) = book {
+            self.log(|| {
+                format!(
+                    "memory book {} in {} positions",
+                    format_bytes(book.memory_bytes()),
+                    book.len()
+                )
+            });
+        }
+    }
+}
+
+fn format_bytes(bytes: usize) -> String {
+    if bytes >= 1 << 20 {
+        format!("{:.1} MiB", bytes as f64 / f64::from(1 << 20))
+    } else {
+        format!("{:.1} KiB", bytes as f64 / f64::from(1 << 10))
+    }
+}
+
+fn percent(part: u64, whole: u64) -> u64 {
+    (part * 100).checked_div(whole).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_are_only_built_when_enabled() {
+        let mut log = DebugLog::default();
+        log.log(|| panic!("formatted while debug is off"));
+        log.set_enabled(true);
+        assert!(log.is_enabled());
+        assert_eq!(percent(3, 4), 75);
+        assert_eq!(percent(0, 0), 0);
+        assert_eq!(format_bytes(32 << 20), "32.0 MiB");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+    }
+}
@@ -0,0 +1,71 @@
+//! `Debug Log File`: a copy of the UCI conversation, for debugging how a GUI
+//! and the engine talk to each other. Every line read is logged as
+//! `HH:MM:SS.mmm >> line` and every line sent as `HH:MM:SS.mmm << line`,
+//! with UTC wall-clock times.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Lines are sent from the search threads too, so the file is shared.
+static LOG: Mutex<Option<File>> = Mutex::new(None);
+
+/// Starts appending the conversation to `path`, or stops logging when it is
+/// empty.
+pub fn set_log_file(path: &str) -> Result<(), String> {
+    let file = if path.is_empty() {
+        None
+    } else {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|err| format!("failed to open log file {path}: {err}"))?;
+        Some(file)
+    };
+    *LOG.lock().unwrap_or_else(|err| err.into_inner()) = file;
+    Ok(())
+}
+
+pub(super) fn log_input(line: &str) {
+    log_line(">>", line);
+}
+
+pub(super) fn log_output(line: &str) {
+    log_line("<<", line);
+}
+
+fn log_line(direction: &str, line: &str) {
+    let mut log = LOG.lock().unwrap_or_else(|err| err.into_inner());
+    if let Some(file) = log.as_mut() {
+        // A failed write must not take the engine down mid-game.
+        let _ = writeln!(file, "{} {direction} {line}", timestamp(SystemTime::now()));
+    }
+}
+
+fn timestamp(time: SystemTime) -> String {
+    let millis = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis());
+    let seconds = millis / 1000 % 86_400;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        millis % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn timestamps_are_utc_time_of_day() {
+        let time = UNIX_EPOCH + Duration::from_millis(3 * 86_400_000 + 45_296_007);
+        assert_eq!(timestamp(time), "12:34:56.007");
+    }
+}
@@ -1,122 +1,885 @@
+use crate::engine::board::Board;
+use crate::engine::book::Repertoire;
+use crate::engine::clock::Pacing;
+use crate::engine::cpu::cpu_features;
 use crate::engine::eval::Evaluator;
+use crate::engine::fen::{parse_castling_rights, parse_en_passant, piece_from_fen};
+use crate::engine::limits::SearchLimits;
+use crate::engine::movegen::{generate_legal, moves_from, moves_to, perft};
+use crate::engine::search::tt::Bound;
 use crate::engine::search::SearchAlgorithm;
+use crate::engine::skill::Skill;
+use crate::engine::tablebase::Tablebases;
+use crate::engine::types::{
+    algebraic_from_square, move_from_uci, square_from_algebraic, uci_from_move, Color, GameStatus,
+    Move, Square,
+};
+use crate::engine::wdl::Wdl;
 use crate::engine::Engine;
 use std::io::{self, Write};
-use std::time::Instant;
+use std::sync::Arc;
+use std::thread::{self, ScopedJoinHandle};
+use std::time::{Duration, Instant};
 
+mod chess960;
+mod client;
 mod commands;
+mod debug;
+mod iolog;
+mod opponent;
+mod options;
+mod session;
 
-pub use commands::{Command, GoCommand, PositionCommand};
+pub use client::{parse_info_score, ClientAnalysis, UciClient};
+pub use commands::{Command, EditCommand, GoCommand, PositionCommand};
+pub use debug::DebugLog;
+pub use iolog::set_log_file;
+pub use opponent::{parse_opponent, Opponent};
+pub use options::{find_option, OptionKind, OptionValue, UciOption, OPTIONS};
+pub use session::{load_session, parse_session, replay, Recorder, SessionCommand};
 
-pub fn run_loop<E: Evaluator, S: SearchAlgorithm>(engine: &mut Engine<E, S>, default_depth: u32) {
-    let stdin = io::stdin();
+/// How long a search runs before it reports `currmove` progress.
+const CURRMOVE_AFTER: Duration = Duration::from_secs(1);
 
-    loop {
-        let mut line = String::new();
-        if stdin.read_line(&mut line).is_err() {
-            break;
-        }
+/// How often a finished `go infinite` search checks for `stop`.
+const INFINITE_POLL: Duration = Duration::from_millis(5);
 
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
-        }
+/// Per-session values set through `setoption` that apply to each `go`.
+#[derive(Debug, Clone, Copy)]
+struct Settings {
+    default_depth: u32,
+    /// From `UCI_Opponent`.
+    contempt: i32,
+    /// Added to the contempt while the opponent is short of time, to keep
+    /// the game going rather than simplify.
+    time_trouble_contempt: i32,
+    /// From `UCI_ShowWDL`: append `wdl W D L` to info lines.
+    show_wdl: bool,
+    /// From `UCI_LimitStrength` and `UCI_Elo`; the rating only applies
+    /// while strength is limited.
+    limit_strength: bool,
+    elo: u32,
+    /// From `UCI_Chess960`: castling is written as the king taking its rook.
+    chess960: bool,
+    /// From `EasyMove`; `UCI_AnalyseMode` turns easy moves off regardless.
+    easy_move: bool,
+    /// From `UCI_AnalyseMode`: every `go` searches, with no book moves, easy
+    /// moves or contempt, since the GUI wants the position's true score.
+    analyse_mode: bool,
+    /// From `MinimumThinkingTime` and `TimeVariance`.
+    pacing: Pacing,
+    /// From `SymmetricHash`; only applies in `UCI_AnalyseMode`.
+    symmetric_hash: bool,
+    /// From `OwnBook`: answer `go` with a book move while in book.
+    own_book: bool,
+    /// From `debug on|off`.
+    debug: DebugLog,
+}
+
+/// Reads UCI commands from stdin until `quit` or the end of input.
+///
+/// `go` runs on a worker thread so `stop` can interrupt it and `isready` is
+/// answered mid-search; any other command waits for the running search to
+/// finish first. `quit` and the end of input also let it finish (a `go
+/// infinite` is stopped), so piped scripts get their `bestmove`; send `stop`
+/// first to abort.
+pub fn run_loop<E, S>(engine: &mut Engine<E, S>, default_depth: u32)
+where
+    E: Evaluator + Send,
+    S: SearchAlgorithm + Send,
+{
+    run_loop_with_input(engine, default_depth, stdin_lines());
+}
+
+/// The lines of stdin, up to the end of input or the first read error.
+pub fn stdin_lines() -> impl Iterator<Item = String> {
+    io::stdin().lines().map_while(Result::ok)
+}
+
+/// Like `run_loop`, but reads the commands from `input`, e.g. a `replay`
+/// of a recorded session.
+pub fn run_loop_with_input<E, S>(
+    engine: &mut Engine<E, S>,
+    default_depth: u32,
+    input: impl IntoIterator<Item = String>,
+) where
+    E: Evaluator + Send,
+    S: SearchAlgorithm + Send,
+{
+    let mut settings = Settings {
+        default_depth,
+        contempt: 0,
+        time_trouble_contempt: 20,
+        show_wdl: false,
+        limit_strength: false,
+        elo: 1500,
+        chess960: false,
+        easy_move: true,
+        own_book: false,
+        analyse_mode: false,
+        symmetric_hash: false,
+        pacing: Pacing::default(),
+        debug: DebugLog::default(),
+    };
+    let mut position = PositionState::default();
+    let mut input = input.into_iter();
+    let stop = engine.stop_handle();
 
-        match parse_line(line) {
-            Command::Uci => {
-                write_line("id name prune");
-                write_line("id author madab");
-                write_line("uciok");
+    thread::scope(|scope| {
+        let mut idle = Some(engine);
+        let mut worker: Option<ScopedJoinHandle<'_, &mut Engine<E, S>>> = None;
+        // Whether the running search is `go infinite`, which only ends on
+        // `stop`.
+        let mut infinite = false;
+
+        while let Some(line) = read_line(&mut input) {
+            if line.is_empty() {
+                continue;
+            }
+
+            let command = parse_line(&line);
+            if matches!(command, Command::Stop) {
+                stop.stop();
             }
-            Command::IsReady => {
+            // The protocol wants `isready` answered at once, search or not.
+            if matches!(command, Command::IsReady) && worker.is_some() {
                 write_line("readyok");
+                continue;
             }
-            Command::UciNewGame => {
-                engine.reset_state();
+            if let Some(running) = worker.take() {
+                // The GUI should send `stop` first; any command that needs
+                // the engine implies it rather than waiting forever.
+                if infinite {
+                    stop.stop();
+                }
+                idle = Some(running.join().expect("search thread panicked"));
             }
-            Command::Position(cmd) => {
-                let set_result = match cmd.fen.as_deref() {
-                    Some(fen) => engine.set_position_fen(fen),
-                    None => {
-                        engine.set_position_startpos();
-                        Ok(())
+            let engine = idle
+                .take()
+                .expect("engine is idle once the worker is joined");
+
+            match command {
+                Command::Go(_) if let Some(err) = &position.error => {
+                    write_line(&format!(
+                        "info string not searching, the last position command failed: {err}"
+                    ));
+                    write_line("bestmove 0000");
+                    idle = Some(engine);
+                }
+                Command::Go(cmd) => {
+                    // Cleared here rather than on the worker so a `stop` read
+                    // right after `go` cannot be lost.
+                    engine.clear_stop();
+                    infinite = cmd.infinite;
+                    worker = Some(scope.spawn(move || {
+                        run_go(engine, &cmd, settings);
+                        engine
+                    }));
+                }
+                Command::AnalyseBatch(cmd) => {
+                    let (fens, stopped) = match read_batch(&mut input) {
+                        BatchInput::Complete(fens) => (fens, false),
+                        BatchInput::Stopped(fens) => (fens, true),
+                        BatchInput::Quit => break,
+                    };
+                    position = PositionState::default();
+                    engine.clear_stop();
+                    // Stopped while reading, the batch reports that it
+                    // analysed none of its positions.
+                    if stopped {
+                        stop.stop();
                     }
-                };
-                match set_result {
-                    Ok(()) => engine.apply_move_list(&cmd.moves),
-                    Err(err) => write_line(&format!("info string invalid FEN: {err}")),
+                    infinite = false;
+                    worker = Some(scope.spawn(move || {
+                        run_batch(engine, &fens, &cmd, settings);
+                        engine
+                    }));
+                }
+                Command::Quit => break,
+                command => {
+                    run_command(engine, command, &mut settings, &mut position);
+                    idle = Some(engine);
                 }
             }
-            Command::Go(cmd) => {
-                let depth = cmd.depth.unwrap_or(default_depth);
-                let status = engine.game_status();
-                match status {
-                    crate::engine::types::GameStatus::Ongoing => {
-                        let mut preferred_root = None;
-                        let mut last_result = None;
-
-                        if depth == 0 {
-                            let started = Instant::now();
-                            let result = engine.search_depth_result(0, preferred_root.as_deref());
-                            let elapsed = started.elapsed();
-                            let elapsed_ms = elapsed.as_millis();
-                            let nps = if elapsed.as_secs_f64() <= 0.0 {
-                                0.0
-                            } else {
-                                (result.nodes as f64) / elapsed.as_secs_f64()
-                            };
-                            write_line(&format!(
-                                "info depth 0 score cp {} nodes {} nps {} time {}",
-                                result.score, result.nodes, nps as u64, elapsed_ms
-                            ));
-                            last_result = Some(result);
-                        } else {
-                            for current_depth in 1..=depth {
-                                let started = Instant::now();
-                                let result = engine
-                                    .search_depth_result(current_depth, preferred_root.as_deref());
-                                let elapsed = started.elapsed();
-                                let elapsed_ms = elapsed.as_millis();
-                                let nps = if elapsed.as_secs_f64() <= 0.0 {
-                                    0.0
-                                } else {
-                                    (result.nodes as f64) / elapsed.as_secs_f64()
-                                };
-                                write_line(&format!(
-                                    "info depth {} score cp {} nodes {} nps {} time {}",
-                                    current_depth,
-                                    result.score,
-                                    result.nodes,
-                                    nps as u64,
-                                    elapsed_ms
-                                ));
-                                preferred_root = Some(result.best_moves.clone());
-                                last_result = Some(result);
-                            }
-                        }
-
-                        let bestmove = if let Some(result) = last_result {
-                            engine.pick_best_move(&result.best_moves)
-                        } else {
-                            "0000".to_string()
-                        };
-                        write_line(&format!("bestmove {bestmove}"));
-                    }
-                    crate::engine::types::GameStatus::Checkmate
-                    | crate::engine::types::GameStatus::Stalemate => {
-                        write_line("bestmove 0000");
+        }
+
+        // The end of input counts as `quit`, so a search still running
+        // answers before the loop returns.
+        if let Some(running) = worker.take() {
+            if infinite {
+                stop.stop();
+            }
+            running.join().expect("search thread panicked");
+        }
+    });
+}
+
+fn run_command<E: Evaluator, S: SearchAlgorithm>(
+    engine: &mut Engine<E, S>,
+    command: Command,
+    settings: &mut Settings,
+    position: &mut PositionState,
+) {
+    // Setting up the board by hand replaces a failed position. Options such
+    // as `UCI_Chess960` change how the next move list reads, but leave a
+    // failed position as unsearchable as it was.
+    match command {
+        Command::UciNewGame | Command::Edit(_) | Command::Flip => {
+            *position = PositionState::default();
+        }
+        Command::SetOption { .. } => position.last = None,
+        _ => {}
+    }
+    match command {
+        Command::Uci => {
+            write_line("id name prune");
+            write_line("id author madab");
+            write_line(&format!("info string cpu features: {}", cpu_features()));
+            for option in advertised_options(settings) {
+                write_line(&option.declaration());
+            }
+            write_line("uciok");
+        }
+        Command::Debug(enabled) => settings.debug.set_enabled(enabled),
+        Command::IsReady => {
+            write_line("readyok");
+        }
+        Command::UciNewGame => {
+            engine.reset_state();
+        }
+        Command::Position(cmd) => {
+            let result = set_position(engine, &cmd, settings, position.last.as_ref());
+            *position = match result {
+                Ok(()) => PositionState {
+                    last: Some(cmd),
+                    error: None,
+                },
+                Err(err) => {
+                    write_line(&format!("info string {err}"));
+                    PositionState {
+                        last: None,
+                        error: Some(err),
                     }
                 }
+            };
+        }
+        Command::Edit(edit) => {
+            apply_edit(engine, edit);
+            if let Err(err) = engine.validate_position() {
+                write_line(&format!("info string position invalid: {err}"));
+            }
+        }
+        Command::Flip => engine.flip_position(),
+        Command::Display => display_board(engine.board()),
+        Command::Moves { square, to } => list_moves(engine.board(), square, to, settings.chess960),
+        Command::Eval => {
+            let trace = engine.eval_trace();
+            for line in trace.to_string().lines() {
+                write_line(line);
+            }
+            write_line("");
+            write_line(&format!(
+                "Evaluation: {} cp (White's view), {} cp for the side to move",
+                trace.total(),
+                trace.for_side_to_move(engine.board())
+            ));
+        }
+        Command::SetOption { name, value } => {
+            if let Err(err) = apply_option(engine, settings, &name, value.as_deref()) {
+                write_line(&format!("info string {err}"));
+            }
+        }
+        Command::Unknown(line) => settings.debug.log(|| format!("unknown command: {line}")),
+        // `run_loop` handles these; a stopped search has already answered.
+        Command::Go(_) | Command::AnalyseBatch(_) | Command::Stop | Command::Quit => {}
+    }
+}
+
+/// What the loop remembers between `position` commands.
+#[derive(Debug, Default)]
+struct PositionState {
+    /// The last command, while it is fully applied and the board has not
+    /// been touched since.
+    last: Option<PositionCommand>,
+    /// Why the last command failed; `go` refuses to search the position it
+    /// left until the board is set up again.
+    error: Option<String>,
+}
+
+// Sets up the board for `position`. GUIs resend the whole game every move,
+// so a command that extends `previous`'s move list only plays the new
+// moves. An invalid FEN leaves the board as it was, and an illegal move
+// leaves the position after the moves before it.
+fn set_position<E: Evaluator, S: SearchAlgorithm>(
+    engine: &mut Engine<E, S>,
+    cmd: &PositionCommand,
+    settings: &Settings,
+    previous: Option<&PositionCommand>,
+) -> Result<(), String> {
+    let played = match previous {
+        Some(previous)
+            if previous.fen == cmd.fen
+                && cmd.moves.len() > previous.moves.len()
+                && cmd.moves.starts_with(&previous.moves) =>
+        {
+            settings.debug.log(|| {
+                format!(
+                    "position extends the previous one by {} moves",
+                    cmd.moves.len() - previous.moves.len()
+                )
+            });
+            previous.moves.len()
+        }
+        _ => {
+            match cmd.fen.as_deref() {
+                Some(fen) => engine.set_position_fen(fen),
+                None => {
+                    engine.set_position_startpos();
+                    Ok(())
+                }
+            }
+            .map_err(|err| format!("invalid FEN: {err}"))?;
+            0
+        }
+    };
+
+    for (index, text) in cmd.moves.iter().enumerate().skip(played) {
+        // In Chess960 each castling move is recognised in the position it
+        // is played from.
+        let mv = if settings.chess960 {
+            chess960::move_from_chess960(engine.board(), text)
+        } else {
+            text.clone()
+        };
+        engine
+            .try_apply_move_list(&[mv])
+            .map_err(|_| format!("illegal move {text} at index {index}"))?;
+    }
+    if cmd.fen.is_none() && !settings.chess960 {
+        report_repertoire_deviation(engine, &cmd.moves);
+    }
+    Ok(())
+}
+
+// The limits of `go`. A node or time budget bounds the search on its own,
+// so only an explicit depth limits it further; without either the search
+// stops at the `Depth` option.
+fn search_limits(cmd: &GoCommand, settings: &Settings, side: Color) -> SearchLimits {
+    let mut limits = cmd.limits();
+    if limits.depth.is_none() && !limits.is_bounded(side) {
+        limits.depth = Some(settings.default_depth);
+    }
+    limits
+}
+
+/// Searches the current position and answers with `bestmove`, including when
+/// the search is stopped early.
+fn run_go<E: Evaluator, S: SearchAlgorithm>(
+    engine: &mut Engine<E, S>,
+    cmd: &GoCommand,
+    settings: Settings,
+) {
+    if let Err(err) = engine.validate_position() {
+        write_line(&format!("info string position invalid: {err}"));
+        write_line("bestmove 0000");
+        return;
+    }
+    if let Some(depth) = cmd.perft {
+        run_perft(engine.board(), depth, settings.chess960);
+        return;
+    }
+    if engine.game_status() != GameStatus::Ongoing {
+        write_line("bestmove 0000");
+        return;
+    }
+
+    if let Some(moves) = cmd.mate
+        && let Some(bestmove) = run_mate_search(engine, moves)
+    {
+        write_line(&format!("bestmove {bestmove}"));
+        return;
+    }
+
+    // `searchmoves` asks for a search among particular moves, which the
+    // book may not cover, and `infinite` for analysis.
+    if settings.own_book
+        && !settings.analyse_mode
+        && !cmd.infinite
+        && cmd.searchmoves.is_empty()
+        && let Some(mv) = engine.book_move()
+    {
+        let bestmove = if settings.chess960 {
+            chess960::move_to_chess960(engine.board(), mv)
+        } else {
+            uci_from_move(mv)
+        };
+        if let Some(bestmove) = bestmove {
+            write_line("info string book move");
+            write_line(&format!("bestmove {bestmove}"));
+            return;
+        }
+    }
+
+    engine.set_contempt(contempt(cmd, &settings, engine.side_to_move()));
+
+    let limits = search_limits(cmd, &settings, engine.side_to_move());
+    let root = engine.board().clone();
+    let chess960 = settings.chess960;
+    // Moves from the root in the GUI's notation.
+    let name = |mv: Move| {
+        if chess960 {
+            chess960::move_to_chess960(&root, mv)
+        } else {
+            uci_from_move(mv)
+        }
+    };
+    let search_moves: Vec<_> = cmd
+        .searchmoves
+        .iter()
+        .filter_map(|mv| {
+            if chess960 {
+                move_from_uci(&chess960::move_from_chess960(&root, mv))
+            } else {
+                move_from_uci(mv)
             }
-            Command::Stop => {
-                engine.stop_search();
-                write_line("bestmove 0000");
+        })
+        .collect();
+    engine.set_search_moves((!search_moves.is_empty()).then_some(search_moves));
+    // Root move progress is only worth reporting once a search runs long.
+    let started = Instant::now();
+    let material = engine.material();
+    // The observer runs on search threads, so it gets the root moves' names
+    // up front rather than the board.
+    let root_names: Vec<(Move, String)> = generate_legal(&mut root.clone())
+        .into_iter()
+        .filter_map(|mv| Some((mv, name(mv)?)))
+        .collect();
+    let root_names = Arc::new(root_names);
+    let names = Arc::clone(&root_names);
+    engine.set_root_move_observer(Some(Arc::new(move |mv, number| {
+        if started.elapsed() >= CURRMOVE_AFTER
+            && let Some((_, mv)) = names.iter().find(|(root_move, _)| *root_move == mv)
+        {
+            write_line(&format!("info currmove {mv} currmovenumber {number}"));
+        }
+    })));
+    // A fail-high is shown at once, so the GUI sees the new move and its
+    // bound while the re-search runs. The root searches a full window, so
+    // only the scouts of later moves report bounds, and those fail high.
+    engine.set_root_bound_observer(Some(Arc::new(move |root_bound| {
+        if root_bound.bound != Bound::Lower {
+            return;
+        }
+        if let Some((_, mv)) = root_names
+            .iter()
+            .find(|(root_move, _)| *root_move == root_bound.best_move)
+        {
+            write_line(&format!(
+                "info depth {} score cp {} lowerbound time {} pv {mv}",
+                root_bound.depth,
+                root_bound.score,
+                started.elapsed().as_millis()
+            ));
+        }
+    })));
+    let (bestmove, _) = engine.search(&limits, |info| {
+        let nps = (u128::from(info.nodes) * 1000)
+            .checked_div(info.time_ms)
+            .unwrap_or(0);
+        let pv: Vec<String> = if chess960 {
+            chess960::line_to_chess960(&root, &info.pv)
+        } else {
+            info.pv.iter().filter_map(|mv| uci_from_move(*mv)).collect()
+        };
+        let hashfull = info
+            .hashfull
+            .map(|permille| format!(" hashfull {permille}"))
+            .unwrap_or_default();
+        let wdl = if settings.show_wdl {
+            format!(" wdl {}", Wdl::from_score(info.score, material))
+        } else {
+            String::new()
+        };
+        write_line(&format!(
+            "info depth {} seldepth {} score cp {}{wdl} nodes {} nps {} time {}{hashfull} pv {}",
+            info.depth,
+            info.seldepth,
+            info.score,
+            info.nodes,
+            nps,
+            info.time_ms,
+            pv.join(" ")
+        ));
+        if let Some(stats) = &info.stats {
+            settings.debug.log_stats(stats);
+        }
+        if let Some(ebf) = info.ebf {
+            settings.debug.log(|| format!("ebf {ebf:.2}"));
+        }
+        if info.best_move_changed
+            && let Some(best) = info.best_moves.first().and_then(|mv| name(*mv))
+        {
+            write_line(&format!(
+                "info string bestmove change depth {} time {} move {best}",
+                info.depth, info.time_ms
+            ));
+        }
+    });
+    engine.set_root_move_observer(None);
+    engine.set_root_bound_observer(None);
+    // An infinite search that runs out of depth still holds its answer
+    // until the GUI asks for it.
+    if cmd.infinite {
+        let stop = engine.stop_handle();
+        while !stop.is_stopped() {
+            thread::sleep(INFINITE_POLL);
+        }
+    }
+    settings
+        .debug
+        .log_memory(engine.hash_usage(), engine.repertoire());
+    let bestmove = move_from_uci(&bestmove).and_then(name).unwrap_or(bestmove);
+    write_line(&format!("bestmove {bestmove}"));
+}
+
+// The contempt for a search by `side`: none in analysis mode, and more
+// when the opponent is short of time.
+fn contempt(cmd: &GoCommand, settings: &Settings, side: Color) -> i32 {
+    if settings.analyse_mode {
+        return 0;
+    }
+    let mut contempt = settings.contempt;
+    if cmd.clocks().opponent_in_time_trouble(side) {
+        contempt += settings.time_trouble_contempt;
+    }
+    contempt
+}
+
+/// How the FEN list of an `analysebatch` ended.
+enum BatchInput {
+    /// At `end` or the end of input.
+    Complete(Vec<String>),
+    /// At `stop`: none of the positions are analysed.
+    Stopped(Vec<String>),
+    /// At `quit`.
+    Quit,
+}
+
+// Reads the FENs of an `analysebatch`, one per line, up to `end`. `stop`
+// and `quit` are still obeyed while reading.
+fn read_batch(input: &mut impl Iterator<Item = String>) -> BatchInput {
+    let mut fens = Vec::new();
+    while let Some(line) = read_line(input) {
+        match line.as_str() {
+            "end" => break,
+            "" => {}
+            "stop" => return BatchInput::Stopped(fens),
+            "quit" => return BatchInput::Quit,
+            fen => fens.push(fen.to_string()),
+        }
+    }
+    BatchInput::Complete(fens)
+}
+
+// Analyses each FEN with the limits of `cmd`, one after another without
+// clearing the hash table, and answers with a line tagged by the FEN's
+// 1-based index instead of `bestmove`. `stop` skips the positions left.
+fn run_batch<E: Evaluator, S: SearchAlgorithm>(
+    engine: &mut Engine<E, S>,
+    fens: &[String],
+    cmd: &GoCommand,
+    settings: Settings,
+) {
+    let started = Instant::now();
+    let stop = engine.stop_handle();
+    engine.set_search_moves(None);
+
+    let mut analysed = 0;
+    for (index, fen) in fens.iter().enumerate() {
+        if stop.is_stopped() {
+            break;
+        }
+        let tag = format!("info string batch {}", index + 1);
+        if let Err(err) = engine.set_position_fen(fen) {
+            write_line(&format!("{tag} invalid FEN: {err}"));
+            continue;
+        }
+        if let Err(err) = engine.validate_position() {
+            write_line(&format!("{tag} position invalid: {err}"));
+            continue;
+        }
+        analysed += 1;
+        if engine.game_status() != GameStatus::Ongoing {
+            write_line(&format!("{tag} bestmove 0000"));
+            continue;
+        }
+
+        engine.set_contempt(contempt(cmd, &settings, engine.side_to_move()));
+        let limits = search_limits(cmd, &settings, engine.side_to_move());
+        let mut last = (0, 0);
+        let (bestmove, nodes) = engine.search(&limits, |info| last = (info.depth, info.score));
+        let bestmove = move_from_uci(&bestmove)
+            .and_then(|mv| {
+                if settings.chess960 {
+                    chess960::move_to_chess960(engine.board(), mv)
+                } else {
+                    uci_from_move(mv)
+                }
+            })
+            .unwrap_or(bestmove);
+        let (depth, score) = last;
+        write_line(&format!(
+            "{tag} bestmove {bestmove} score cp {score} depth {depth} nodes {nodes}"
+        ));
+    }
+    write_line(&format!(
+        "info string batch done {analysed} of {} positions time {}",
+        fens.len(),
+        started.elapsed().as_millis()
+    ));
+}
+
+// Prints the position for a human: a diagram, then the FEN fields spelled
+// out, the FEN itself and the zobrist key.
+fn display_board(board: &Board) {
+    for line in board.diagram().lines() {
+        write_line(line);
+    }
+    let fen = board.to_fen();
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+    let side = match board.side_to_move {
+        Color::White => "white",
+        Color::Black => "black",
+    };
+    write_line("");
+    write_line(&format!("Side to move: {side}"));
+    write_line(&format!("Castling: {}", fields[2]));
+    write_line(&format!("En passant: {}", fields[3]));
+    write_line(&format!("Halfmove clock: {}", board.halfmove_clock));
+    write_line(&format!("Fen: {fen}"));
+    write_line(&format!("Key: {:016X}", board.hash()));
+}
+
+// Prints the legal moves of the piece on `square`, or those landing on it,
+// for GUIs that highlight where a clicked piece can go.
+fn list_moves(root: &Board, square: Square, to: bool, chess960: bool) {
+    let mut board = root.clone();
+    let moves = if to {
+        moves_to(&mut board, square)
+    } else {
+        moves_from(&mut board, square)
+    };
+    let names: Vec<String> = moves
+        .into_iter()
+        .filter_map(|mv| {
+            if chess960 {
+                chess960::move_to_chess960(root, mv)
+            } else {
+                uci_from_move(mv)
             }
-            Command::Quit => {
-                break;
+        })
+        .collect();
+    let line = format!(
+        "info string moves {} {}: {}",
+        if to { "to" } else { "from" },
+        algebraic_from_square(square).unwrap_or_default(),
+        names.join(" ")
+    );
+    write_line(line.trim_end());
+}
+
+// Prints the leaf count below each root move, then the total, in the format
+// other engines use so the output can be diffed against theirs.
+fn run_perft(root: &Board, depth: u32, chess960: bool) {
+    let started = Instant::now();
+    let mut board = root.clone();
+    let mut total = 0;
+    if depth == 0 {
+        total = 1;
+    } else {
+        for mv in generate_legal(&mut board) {
+            let name = if chess960 {
+                chess960::move_to_chess960(root, mv)
+            } else {
+                uci_from_move(mv)
+            };
+            let Ok(undo) = board.make_move(mv) else {
+                continue;
+            };
+            let nodes = perft(&mut board, depth - 1);
+            board.unmake_move(mv, undo);
+            total += nodes;
+            write_line(&format!("{}: {nodes}", name.unwrap_or_default()));
+        }
+    }
+    write_line("");
+    write_line(&format!("Nodes searched: {total}"));
+    write_line(&format!(
+        "info string perft depth {depth} time {}",
+        started.elapsed().as_millis()
+    ));
+}
+
+// Reports a proven mate like a completed iteration and returns its move.
+// Without a proof the caller falls back to a normal search.
+fn run_mate_search<E: Evaluator, S: SearchAlgorithm>(
+    engine: &mut Engine<E, S>,
+    moves: u32,
+) -> Option<String> {
+    let started = Instant::now();
+    let search = engine.search_mate(moves);
+    let proof = search.proof?;
+    let bestmove = uci_from_move(proof.best_move)?;
+    let time_ms = started.elapsed().as_millis();
+    let nps = (u128::from(search.nodes) * 1000)
+        .checked_div(time_ms)
+        .unwrap_or(0);
+    write_line(&format!(
+        "info depth {} score mate {} nodes {} nps {} time {} pv {}",
+        proof.moves * 2 - 1,
+        proof.moves,
+        search.nodes,
+        nps,
+        time_ms,
+        bestmove
+    ));
+    Some(bestmove)
+}
+
+// Spin defaults reflect the session, e.g. the depth passed to `run_loop`.
+fn advertised_options(settings: &Settings) -> Vec<UciOption> {
+    OPTIONS
+        .iter()
+        .map(|option| match option.kind {
+            OptionKind::Spin { min, max, .. } => {
+                let default = match option.name {
+                    "Depth" => i64::from(settings.default_depth),
+                    "TimeTroubleContempt" => i64::from(settings.time_trouble_contempt),
+                    _ => return *option,
+                };
+                UciOption {
+                    kind: OptionKind::Spin { default, min, max },
+                    ..*option
+                }
             }
-            Command::Unknown(_) => {}
+            _ => *option,
+        })
+        .collect()
+}
+
+fn apply_option<E: Evaluator, S: SearchAlgorithm>(
+    engine: &mut Engine<E, S>,
+    settings: &mut Settings,
+    name: &str,
+    value: Option<&str>,
+) -> Result<(), String> {
+    let option = find_option(name).ok_or_else(|| format!("unknown option: {name}"))?;
+    match (option.name, option.parse_value(value)?) {
+        ("Depth", OptionValue::Spin(depth)) => settings.default_depth = depth as u32,
+        ("TimeTroubleContempt", OptionValue::Spin(contempt)) => {
+            settings.time_trouble_contempt = contempt as i32;
+        }
+        ("Hash", OptionValue::Spin(megabytes)) => engine.set_hash_size(megabytes as usize),
+        ("Clear Hash", OptionValue::Button) => engine.clear_hash(),
+        ("Threads", OptionValue::Spin(threads)) => engine.set_threads(threads as usize),
+        ("nodestime", OptionValue::Spin(per_ms)) => engine.set_nodes_time(Some(per_ms as u64)),
+        ("MinimumThinkingTime", OptionValue::Spin(ms)) => {
+            settings.pacing.min_think = Duration::from_millis(ms as u64);
+            engine.set_pacing(settings.pacing);
+        }
+        ("TimeVariance", OptionValue::Spin(percent)) => {
+            settings.pacing.variance_percent = percent as u32;
+            engine.set_pacing(settings.pacing);
+        }
+        ("UCI_ShowWDL", OptionValue::Check(show)) => settings.show_wdl = show,
+        ("OwnBook", OptionValue::Check(own_book)) => settings.own_book = own_book,
+        ("UCI_Chess960", OptionValue::Check(chess960)) => settings.chess960 = chess960,
+        ("EasyMove", OptionValue::Check(easy_move)) => {
+            settings.easy_move = easy_move;
+            engine.set_easy_move(settings.easy_move && !settings.analyse_mode);
+        }
+        ("SymmetricHash", OptionValue::Check(symmetric_hash)) => {
+            settings.symmetric_hash = symmetric_hash;
+            engine.set_symmetric_hash(settings.symmetric_hash && settings.analyse_mode);
+        }
+        ("UCI_AnalyseMode", OptionValue::Check(analyse_mode)) => {
+            settings.analyse_mode = analyse_mode;
+            engine.set_analysing(analyse_mode);
+            engine.set_easy_move(settings.easy_move && !settings.analyse_mode);
+            engine.set_symmetric_hash(settings.symmetric_hash && settings.analyse_mode);
+        }
+        ("UCI_LimitStrength", OptionValue::Check(limit)) => {
+            settings.limit_strength = limit;
+            engine.set_skill(limit.then(|| Skill::new(settings.elo)));
+        }
+        ("UCI_Elo", OptionValue::Spin(elo)) => {
+            settings.elo = elo as u32;
+            engine.set_skill(settings.limit_strength.then(|| Skill::new(settings.elo)));
+        }
+        ("BookFile", OptionValue::String(path)) if path.is_empty() => engine.set_repertoire(None),
+        ("BookFile", OptionValue::String(path)) => {
+            engine.set_repertoire(Some(Repertoire::load(&path)?));
+        }
+        ("SyzygyPath", OptionValue::String(path)) if path.is_empty() => {
+            engine.set_tablebases(None);
+        }
+        ("SyzygyPath", OptionValue::String(path)) => {
+            let tablebases = Tablebases::open(&path)?;
+            write_line(&format!(
+                "info string found {} tablebase files ({} WDL, {} DTZ, up to {} pieces)",
+                tablebases.file_count(),
+                tablebases.wdl_count(),
+                tablebases.dtz_count(),
+                tablebases.max_pieces()
+            ));
+            engine.set_tablebases(Some(tablebases));
+        }
+        ("Debug Log File", OptionValue::String(path)) => set_log_file(&path)?,
+        ("UCI_Opponent", OptionValue::String(value)) if value.is_empty() => settings.contempt = 0,
+        ("UCI_Opponent", OptionValue::String(value)) => {
+            settings.contempt = parse_opponent(&value)?.contempt();
         }
+        _ => return Err(format!("option {} is not handled", option.name)),
+    }
+    Ok(())
+}
+
+fn apply_edit<E: Evaluator, S: SearchAlgorithm>(engine: &mut Engine<E, S>, edit: EditCommand) {
+    match edit {
+        EditCommand::Put(square, piece) => engine.set_piece(square, Some(piece)),
+        EditCommand::Remove(square) => engine.set_piece(square, None),
+        EditCommand::Turn(color) => {
+            let color = color.unwrap_or(match engine.side_to_move() {
+                Color::White => Color::Black,
+                Color::Black => Color::White,
+            });
+            engine.set_side_to_move(color);
+        }
+        EditCommand::Castling(rights) => engine.set_castling_rights(rights),
+        EditCommand::EnPassant(square) => engine.set_en_passant(square),
+    }
+}
+
+fn report_repertoire_deviation<E: Evaluator, S: SearchAlgorithm>(
+    engine: &Engine<E, S>,
+    moves: &[String],
+) {
+    let Some(repertoire) = engine.repertoire() else {
+        return;
+    };
+    if let Ok(Some(deviation)) = repertoire.first_deviation(moves) {
+        let played = uci_from_move(deviation.played).unwrap_or_default();
+        let expected: Vec<String> = deviation
+            .expected
+            .iter()
+            .filter_map(|mv| uci_from_move(*mv))
+            .collect();
+        write_line(&format!(
+            "info string out of repertoire at ply {}: played {} expected {}",
+            deviation.ply + 1,
+            played,
+            expected.join(" ")
+        ));
     }
 }
 
@@ -128,10 +891,33 @@ pub fn parse_line(line: &str) -> Command {
 
     match *first {
         "uci" => Command::Uci,
+        "debug" => match rest {
+            ["on"] => Command::Debug(true),
+            ["off"] => Command::Debug(false),
+            _ => Command::Unknown(line.to_string()),
+        },
         "isready" => Command::IsReady,
         "ucinewgame" => Command::UciNewGame,
         "position" => parse_position(rest).unwrap_or_else(|| Command::Unknown(line.to_string())),
-        "go" => parse_go(rest).unwrap_or_else(|| Command::Unknown(line.to_string())),
+        "go" => Command::Go(parse_go(rest)),
+        "analysebatch" => Command::AnalyseBatch(parse_go(rest)),
+        "put" | "remove" | "turn" | "castling" | "ep" => {
+            parse_edit(first, rest).unwrap_or_else(|| Command::Unknown(line.to_string()))
+        }
+        "flip" => Command::Flip,
+        "d" => Command::Display,
+        "eval" => Command::Eval,
+        "moves" => match rest {
+            [square] => {
+                square_from_algebraic(square).map(|square| Command::Moves { square, to: false })
+            }
+            ["to", square] => {
+                square_from_algebraic(square).map(|square| Command::Moves { square, to: true })
+            }
+            _ => None,
+        }
+        .unwrap_or_else(|| Command::Unknown(line.to_string())),
+        "setoption" => parse_setoption(rest).unwrap_or_else(|| Command::Unknown(line.to_string())),
         "stop" => Command::Stop,
         "quit" => Command::Quit,
         _ => Command::Unknown(line.to_string()),
@@ -147,13 +933,13 @@ fn parse_position(tokens: &[&str]) -> Option<Command> {
 
     match tokens[0] {
         "startpos" => {
-            if let Some(moves_index) = tokens.iter().position(|&t| t == "moves") {
-                if moves_index + 1 < tokens.len() {
-                    cmd.moves = tokens[moves_index + 1..]
-                        .iter()
-                        .map(|m| (*m).to_string())
-                        .collect();
-                }
+            if let Some(moves_index) = tokens.iter().position(|&t| t == "moves")
+                && moves_index + 1 < tokens.len()
+            {
+                cmd.moves = tokens[moves_index + 1..]
+                    .iter()
+                    .map(|m| (*m).to_string())
+                    .collect();
             }
         }
         "fen" => {
@@ -164,10 +950,8 @@ fn parse_position(tokens: &[&str]) -> Option<Command> {
             let fen_fields = &tokens[1..7];
             cmd.fen = Some(fen_fields.join(" "));
 
-            if tokens.len() > 7 {
-                if tokens[7] == "moves" && tokens.len() > 8 {
-                    cmd.moves = tokens[8..].iter().map(|m| (*m).to_string()).collect();
-                }
+            if tokens.len() > 7 && tokens[7] == "moves" && tokens.len() > 8 {
+                cmd.moves = tokens[8..].iter().map(|m| (*m).to_string()).collect();
             }
         }
         _ => return None,
@@ -176,45 +960,107 @@ fn parse_position(tokens: &[&str]) -> Option<Command> {
     Some(Command::Position(cmd))
 }
 
-fn parse_go(tokens: &[&str]) -> Option<Command> {
+fn parse_edit(command: &str, tokens: &[&str]) -> Option<Command> {
+    let edit = match (command, tokens) {
+        ("put", [piece, square]) => {
+            let mut chars = piece.chars();
+            let piece = piece_from_fen(chars.next()?).filter(|_| chars.next().is_none())?;
+            EditCommand::Put(square_from_algebraic(square)?, piece)
+        }
+        ("remove", [square]) => EditCommand::Remove(square_from_algebraic(square)?),
+        ("turn", []) => EditCommand::Turn(None),
+        ("turn", ["w"]) => EditCommand::Turn(Some(Color::White)),
+        ("turn", ["b"]) => EditCommand::Turn(Some(Color::Black)),
+        ("castling", [rights]) => EditCommand::Castling(parse_castling_rights(rights).ok()?),
+        ("ep", [square]) => EditCommand::EnPassant(parse_en_passant(square).ok()?),
+        _ => return None,
+    };
+    Some(Command::Edit(edit))
+}
+
+fn parse_setoption(tokens: &[&str]) -> Option<Command> {
+    let ("name", rest) = tokens.split_first().map(|(first, rest)| (*first, rest))? else {
+        return None;
+    };
+    let value_index = rest.iter().position(|&t| t == "value");
+    let name_tokens = &rest[..value_index.unwrap_or(rest.len())];
+    if name_tokens.is_empty() {
+        return None;
+    }
+    let value = value_index.map(|index| rest[index + 1..].join(" "));
+    Some(Command::SetOption {
+        name: name_tokens.join(" "),
+        value,
+    })
+}
+
+const GO_KEYWORDS: &[&str] = &[
+    "searchmoves",
+    "ponder",
+    "wtime",
+    "btime",
+    "winc",
+    "binc",
+    "movestogo",
+    "depth",
+    "nodes",
+    "mate",
+    "movetime",
+    "infinite",
+    "perft",
+];
+
+fn parse_go(tokens: &[&str]) -> GoCommand {
     let mut cmd = GoCommand::default();
     let mut i = 0;
 
     while i < tokens.len() {
         match tokens[i] {
-            "depth" => {
-                if i + 1 < tokens.len() {
-                    cmd.depth = tokens[i + 1].parse().ok();
-                    i += 1;
-                }
+            "depth" if i + 1 < tokens.len() => {
+                cmd.depth = tokens[i + 1].parse().ok();
+                i += 1;
             }
-            "movetime" => {
-                if i + 1 < tokens.len() {
-                    cmd.movetime = tokens[i + 1].parse().ok();
-                    i += 1;
-                }
+            "movetime" if i + 1 < tokens.len() => {
+                cmd.movetime = tokens[i + 1].parse().ok();
+                i += 1;
             }
-            "wtime" => {
-                if i + 1 < tokens.len() {
-                    cmd.wtime = tokens[i + 1].parse().ok();
-                    i += 1;
-                }
+            "wtime" if i + 1 < tokens.len() => {
+                cmd.wtime = tokens[i + 1].parse().ok();
+                i += 1;
             }
-            "btime" => {
-                if i + 1 < tokens.len() {
-                    cmd.btime = tokens[i + 1].parse().ok();
-                    i += 1;
-                }
+            "btime" if i + 1 < tokens.len() => {
+                cmd.btime = tokens[i + 1].parse().ok();
+                i += 1;
             }
-            "winc" => {
-                if i + 1 < tokens.len() {
-                    cmd.winc = tokens[i + 1].parse().ok();
-                    i += 1;
-                }
+            "winc" if i + 1 < tokens.len() => {
+                cmd.winc = tokens[i + 1].parse().ok();
+                i += 1;
+            }
+            "binc" if i + 1 < tokens.len() => {
+                cmd.binc = tokens[i + 1].parse().ok();
+                i += 1;
+            }
+            "nodes" if i + 1 < tokens.len() => {
+                cmd.nodes = tokens[i + 1].parse().ok();
+                i += 1;
+            }
+            "movestogo" if i + 1 < tokens.len() => {
+                cmd.movestogo = tokens[i + 1].parse().ok();
+                i += 1;
             }
-            "binc" => {
-                if i + 1 < tokens.len() {
-                    cmd.binc = tokens[i + 1].parse().ok();
+            "mate" if i + 1 < tokens.len() => {
+                cmd.mate = tokens[i + 1].parse().ok();
+                i += 1;
+            }
+            "perft" if i + 1 < tokens.len() => {
+                cmd.perft = tokens[i + 1].parse().ok();
+                i += 1;
+            }
+            "infinite" => cmd.infinite = true,
+            "searchmoves" => {
+                // The move list runs until the next go parameter.
+                while i + 1 < tokens.len() && !GO_KEYWORDS.contains(&tokens[i + 1]) {
+                    cmd.searchmoves.push(tokens[i + 1].to_string());
                     i += 1;
                 }
             }
@@ -224,10 +1070,24 @@ fn parse_go(tokens: &[&str]) -> Option<Command> {
         i += 1;
     }
 
-    Some(Command::Go(cmd))
+    cmd
+}
+
+// Reads the next line, trimmed, or `None` at the end of input.
+fn read_line(input: &mut impl Iterator<Item = String>) -> Option<String> {
+    let line = input.next()?;
+    let line = line.trim();
+    if !line.is_empty() {
+        iolog::log_input(line);
+    }
+    Some(line.to_string())
 }
 
+// Write errors are ignored: once the GUI has closed its end of the pipe
+// there is no one left to answer, and the search still has to wind down.
 fn write_line(line: &str) {
-    println!("{line}");
-    let _ = io::stdout().flush();
+    iolog::log_output(line);
+    let mut stdout = io::stdout().lock();
+    let _ = writeln!(stdout, "{line}");
+    let _ = stdout.flush();
 }
@@ -0,0 +1,98 @@
+//! The `UCI_Opponent` option: `<title> <elo> <computer|human> <name>`, where
+//! the title and rating may be `none`.
+
+/// The rating contempt is measured against; opponents rated above it make the
+/// engine welcome draws, weaker ones make it play on.
+const ENGINE_ELO: i32 = 2000;
+const MAX_CONTEMPT: i32 = 50;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Opponent {
+    pub title: Option<String>,
+    pub elo: Option<u32>,
+    pub computer: bool,
+    pub name: String,
+}
+
+pub fn parse_opponent(value: &str) -> Result<Opponent, String> {
+    let mut tokens = value.split_whitespace();
+    let (Some(title), Some(elo), Some(kind)) = (tokens.next(), tokens.next(), tokens.next()) else {
+        return Err(format!("invalid UCI_Opponent: {value}"));
+    };
+    let title = (title != "none").then(|| title.to_string());
+    let elo = match elo {
+        "none" => None,
+        elo => Some(
+            elo.parse()
+                .map_err(|_| format!("invalid UCI_Opponent rating: {elo}"))?,
+        ),
+    };
+    let computer = match kind {
+        "computer" => true,
+        "human" => false,
+        _ => return Err(format!("invalid UCI_Opponent kind: {kind}")),
+    };
+    Ok(Opponent {
+        title,
+        elo,
+        computer,
+        name: tokens.collect::<Vec<_>>().join(" "),
+    })
+}
+
+impl Opponent {
+    /// Contempt in centipawns: 1 per 10 rating points below the engine,
+    /// capped either way. A title stands in for a missing rating.
+    pub fn contempt(&self) -> i32 {
+        let rating = self
+            .elo
+            .map(|elo| elo as i32)
+            .or_else(|| self.title.as_deref().and_then(title_rating));
+        match rating {
+            Some(rating) => ((ENGINE_ELO - rating) / 10).clamp(-MAX_CONTEMPT, MAX_CONTEMPT),
+            None => 0,
+        }
+    }
+}
+
+fn title_rating(title: &str) -> Option<i32> {
+    match title {
+        "GM" => Some(2500),
+        "IM" => Some(2400),
+        "FM" | "WGM" => Some(2300),
+        "CM" | "WIM" => Some(2200),
+        "WFM" => Some(2100),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_uci_opponent_values() {
+        let opponent = parse_opponent("GM 2750 human Magnus Carlsen").expect("opponent");
+        assert_eq!(opponent.title.as_deref(), Some("GM"));
+        assert_eq!(opponent.elo, Some(2750));
+        assert!(!opponent.computer);
+        assert_eq!(opponent.name, "Magnus Carlsen");
+
+        let opponent = parse_opponent("none none computer Stockfish").expect("opponent");
+        assert_eq!((opponent.title, opponent.elo), (None, None));
+        assert!(opponent.computer);
+
+        assert!(parse_opponent("none 1500").is_err());
+        assert!(parse_opponent("none strong human Bob").is_err());
+    }
+
+    #[test]
+    fn contempt_scales_with_rating() {
+        let contempt = |value: &str| parse_opponent(value).expect("opponent").contempt();
+        assert_eq!(contempt("none 1700 human Club Player"), 30);
+        assert_eq!(contempt("none 2100 human Expert"), -10);
+        assert_eq!(contempt("none 900 human Beginner"), MAX_CONTEMPT);
+        assert_eq!(contempt("GM none human Anonymous"), -MAX_CONTEMPT);
+        assert_eq!(contempt("none none human Unknown"), 0);
+    }
+}
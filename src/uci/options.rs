@@ -0,0 +1,254 @@
+use crate::engine::search::tt::DEFAULT_HASH_MB;
+use crate::engine::skill::{MAX_ELO, MIN_ELO};
+
+/// The options the engine advertises after `uci` and accepts via `setoption`.
+pub const OPTIONS: &[UciOption] = &[
+    UciOption {
+        name: "Depth",
+        kind: OptionKind::Spin {
+            default: 6,
+            min: 0,
+            max: 64,
+        },
+    },
+    UciOption {
+        name: "Hash",
+        kind: OptionKind::Spin {
+            default: DEFAULT_HASH_MB as i64,
+            min: 1,
+            max: 4096,
+        },
+    },
+    UciOption {
+        name: "Clear Hash",
+        kind: OptionKind::Button,
+    },
+    UciOption {
+        name: "Threads",
+        kind: OptionKind::Spin {
+            default: 1,
+            min: 1,
+            max: 128,
+        },
+    },
+    UciOption {
+        name: "EasyMove",
+        kind: OptionKind::Check { default: true },
+    },
+    UciOption {
+        name: "SymmetricHash",
+        kind: OptionKind::Check { default: false },
+    },
+    UciOption {
+        name: "OwnBook",
+        kind: OptionKind::Check { default: false },
+    },
+    UciOption {
+        name: "BookFile",
+        kind: OptionKind::String { default: "" },
+    },
+    UciOption {
+        name: "SyzygyPath",
+        kind: OptionKind::String { default: "" },
+    },
+    UciOption {
+        name: "TimeTroubleContempt",
+        kind: OptionKind::Spin {
+            default: 20,
+            min: 0,
+            max: 100,
+        },
+    },
+    UciOption {
+        name: "Debug Log File",
+        kind: OptionKind::String { default: "" },
+    },
+    UciOption {
+        name: "MinimumThinkingTime",
+        kind: OptionKind::Spin {
+            default: 0,
+            min: 0,
+            max: 60_000,
+        },
+    },
+    UciOption {
+        name: "TimeVariance",
+        kind: OptionKind::Spin {
+            default: 0,
+            min: 0,
+            max: 100,
+        },
+    },
+    UciOption {
+        name: "nodestime",
+        kind: OptionKind::Spin {
+            default: 0,
+            min: 0,
+            max: 10_000,
+        },
+    },
+    UciOption {
+        name: "UCI_LimitStrength",
+        kind: OptionKind::Check { default: false },
+    },
+    UciOption {
+        name: "UCI_Elo",
+        kind: OptionKind::Spin {
+            default: 1500,
+            min: MIN_ELO as i64,
+            max: MAX_ELO as i64,
+        },
+    },
+    UciOption {
+        name: "UCI_Chess960",
+        kind: OptionKind::Check { default: false },
+    },
+    UciOption {
+        name: "UCI_AnalyseMode",
+        kind: OptionKind::Check { default: false },
+    },
+    UciOption {
+        name: "UCI_ShowWDL",
+        kind: OptionKind::Check { default: false },
+    },
+    UciOption {
+        name: "UCI_Opponent",
+        kind: OptionKind::String { default: "" },
+    },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OptionKind {
+    Spin {
+        default: i64,
+        min: i64,
+        max: i64,
+    },
+    Check {
+        default: bool,
+    },
+    /// An empty default is advertised as `<empty>`, as GUIs expect.
+    String {
+        default: &'static str,
+    },
+    /// Triggers an action; `setoption` sends no value.
+    Button,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UciOption {
+    pub name: &'static str,
+    pub kind: OptionKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OptionValue {
+    Spin(i64),
+    Check(bool),
+    String(String),
+    Button,
+}
+
+impl UciOption {
+    /// The `option name ...` line sent in reply to `uci`.
+    pub fn declaration(&self) -> String {
+        match self.kind {
+            OptionKind::Spin { default, min, max } => format!(
+                "option name {} type spin default {default} min {min} max {max}",
+                self.name
+            ),
+            OptionKind::Check { default } => {
+                format!("option name {} type check default {default}", self.name)
+            }
+            OptionKind::String { default } => {
+                let default = if default.is_empty() {
+                    "<empty>"
+                } else {
+                    default
+                };
+                format!("option name {} type string default {default}", self.name)
+            }
+            OptionKind::Button => format!("option name {} type button", self.name),
+        }
+    }
+
+    pub fn parse_value(&self, value: Option<&str>) -> Result<OptionValue, String> {
+        match (self.kind, value) {
+            (OptionKind::Spin { min, max, .. }, Some(value)) => {
+                let parsed: i64 = value
+                    .parse()
+                    .map_err(|_| format!("invalid value for {}: {value}", self.name))?;
+                if parsed < min || parsed > max {
+                    return Err(format!(
+                        "{} must be between {min} and {max}, got {parsed}",
+                        self.name
+                    ));
+                }
+                Ok(OptionValue::Spin(parsed))
+            }
+            (OptionKind::Check { .. }, Some("true")) => Ok(OptionValue::Check(true)),
+            (OptionKind::Check { .. }, Some("false")) => Ok(OptionValue::Check(false)),
+            (OptionKind::Check { .. }, value) => Err(format!(
+                "invalid value for {}: {}",
+                self.name,
+                value.unwrap_or_default()
+            )),
+            (OptionKind::String { .. }, value) => {
+                let value = value.unwrap_or_default();
+                let value = if value == "<empty>" { "" } else { value };
+                Ok(OptionValue::String(value.to_string()))
+            }
+            (OptionKind::Button, _) => Ok(OptionValue::Button),
+            (OptionKind::Spin { .. }, None) => Err(format!("missing value for {}", self.name)),
+        }
+    }
+}
+
+/// Looks up an option by name; UCI option names are case-insensitive.
+pub fn find_option(name: &str) -> Option<&'static UciOption> {
+    OPTIONS
+        .iter()
+        .find(|option| option.name.eq_ignore_ascii_case(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn declarations_follow_uci_syntax() {
+        let lines: Vec<String> = OPTIONS.iter().map(UciOption::declaration).collect();
+        assert!(lines.contains(&"option name Depth type spin default 6 min 0 max 64".to_string()));
+        assert!(lines.contains(&"option name BookFile type string default <empty>".to_string()));
+    }
+
+    #[test]
+    fn values_are_checked_against_the_option_type() {
+        let depth = find_option("depth").expect("depth option");
+        assert_eq!(depth.parse_value(Some("12")), Ok(OptionValue::Spin(12)));
+        assert!(depth.parse_value(Some("65")).is_err());
+        assert!(depth.parse_value(Some("deep")).is_err());
+        assert!(depth.parse_value(None).is_err());
+
+        let book = find_option("BookFile").expect("book option");
+        assert_eq!(
+            book.parse_value(Some("<empty>")),
+            Ok(OptionValue::String(String::new()))
+        );
+        assert!(find_option("Ponder").is_none());
+
+        let wdl = find_option("UCI_ShowWDL").expect("wdl option");
+        assert_eq!(
+            wdl.declaration(),
+            "option name UCI_ShowWDL type check default false"
+        );
+        assert_eq!(wdl.parse_value(Some("true")), Ok(OptionValue::Check(true)));
+        assert!(wdl.parse_value(Some("yes")).is_err());
+
+        let clear = find_option("clear hash").expect("clear hash option");
+        assert_eq!(clear.declaration(), "option name Clear Hash type button");
+        assert_eq!(clear.parse_value(None), Ok(OptionValue::Button));
+    }
+}
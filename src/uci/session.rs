@@ -0,0 +1,137 @@
+//! Recording and replaying UCI sessions. `--record FILE` saves every command
+//! read as `<ms> <command>`, the milliseconds since the session started, and
+//! `--replay FILE` sends the commands again at the same times, so a crash or
+//! a time loss a user reports can be reproduced from their recording.
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Writes the commands of a session to a file as they are read.
+#[derive(Debug)]
+pub struct Recorder {
+    file: File,
+    started: Instant,
+}
+
+impl Recorder {
+    /// Starts a new recording at `path`, replacing any file there.
+    pub fn create(path: &str) -> Result<Self, String> {
+        let file = File::create(path)
+            .map_err(|err| format!("failed to create session file {path}: {err}"))?;
+        Ok(Self {
+            file,
+            started: Instant::now(),
+        })
+    }
+
+    /// Records `line`, unless it is blank. Each line is written at once so
+    /// a recording survives a crash.
+    pub fn record(&mut self, line: &str) {
+        let line = line.trim();
+        if line.is_empty() {
+            return;
+        }
+        let millis = self.started.elapsed().as_millis();
+        // A failed write must not take the engine down mid-game.
+        let _ = writeln!(self.file, "{millis} {line}");
+    }
+}
+
+/// One command of a recorded session and when it was read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionCommand {
+    pub at: Duration,
+    pub line: String,
+}
+
+/// Parses a recording; blank lines are skipped.
+pub fn parse_session(contents: &str) -> Result<Vec<SessionCommand>, String> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            let (millis, command) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+            let millis: u64 = millis
+                .parse()
+                .map_err(|_| format!("line {}: invalid time {millis}", index + 1))?;
+            Ok(SessionCommand {
+                at: Duration::from_millis(millis),
+                line: command.to_string(),
+            })
+        })
+        .collect()
+}
+
+pub fn load_session(path: &str) -> Result<Vec<SessionCommand>, String> {
+    let contents =
+        fs::read_to_string(path).map_err(|err| format!("failed to read {path}: {err}"))?;
+    parse_session(&contents)
+}
+
+/// Yields the commands of a recording, each no earlier than it was read in
+/// the original session, counting from the first call to `next`.
+pub fn replay(commands: Vec<SessionCommand>) -> impl Iterator<Item = String> {
+    let mut started = None;
+    commands.into_iter().map(move |command| {
+        let started = *started.get_or_insert_with(Instant::now);
+        if let Some(wait) = (started + command.at).checked_duration_since(Instant::now()) {
+            thread::sleep(wait);
+        }
+        command.line
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn recordings_replay_with_their_timing() {
+        let path = env::temp_dir().join(format!("prune-recorder-{}.log", std::process::id()));
+        let path = path.to_str().expect("utf-8 path");
+        let mut recorder = Recorder::create(path).expect("create");
+        recorder.record("uci\n");
+        recorder.record("  ");
+        thread::sleep(Duration::from_millis(20));
+        recorder.record("go depth 2");
+        let commands = load_session(path).expect("load");
+        fs::remove_file(path).ok();
+
+        let lines: Vec<&str> = commands
+            .iter()
+            .map(|command| command.line.as_str())
+            .collect();
+        assert_eq!(lines, ["uci", "go depth 2"]);
+        assert!(commands[1].at >= commands[0].at + Duration::from_millis(20));
+
+        let started = Instant::now();
+        let replayed: Vec<String> = replay(commands.clone()).collect();
+        assert_eq!(replayed, lines);
+        assert!(started.elapsed() >= commands[1].at - commands[0].at);
+    }
+
+    #[test]
+    fn rejects_lines_without_a_time() {
+        assert_eq!(
+            parse_session("0 uci\n\n15 isready\n"),
+            Ok(vec![
+                SessionCommand {
+                    at: Duration::ZERO,
+                    line: "uci".to_string(),
+                },
+                SessionCommand {
+                    at: Duration::from_millis(15),
+                    line: "isready".to_string(),
+                },
+            ])
+        );
+        assert_eq!(
+            parse_session("0 uci\nisready\n"),
+            Err("line 2: invalid time isready".to_string())
+        );
+    }
+}
@@ -25,6 +25,7 @@ fn uci_handshake_works() {
 
     assert!(stdout.contains("id name prune"));
     assert!(stdout.contains("id author madab"));
+    assert!(stdout.contains("info string cpu features: "));
     assert!(stdout.contains("uciok"));
     assert!(stdout.contains("readyok"));
 }
@@ -54,12 +55,696 @@ fn uci_reports_invalid_fen() {
     assert!(stdout.contains("missing white king"));
 }
 
+#[test]
+fn uci_searches_after_a_double_pawn_push() {
+    let stdout = run_engine("position startpos moves e2e4\ngo depth 1\nquit\n");
+
+    assert!(!stdout.contains("position invalid"));
+    let bestmove = stdout
+        .lines()
+        .find(|line| line.starts_with("bestmove"))
+        .expect("bestmove");
+    assert_ne!(bestmove, "bestmove 0000");
+}
+
+#[test]
+fn uci_edits_position_and_revalidates() {
+    let stdout = run_engine(
+        "position startpos\nremove e1\ngo depth 1\nput K d1\nput q h4\nturn b\ngo depth 1\nquit\n",
+    );
+
+    assert!(stdout.contains("info string position invalid: missing white king"));
+    let bestmoves: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.starts_with("bestmove"))
+        .collect();
+    assert_eq!(bestmoves.len(), 2);
+    assert_eq!(bestmoves[0], "bestmove 0000");
+    assert_ne!(bestmoves[1], "bestmove 0000");
+}
+
+#[test]
+fn uci_advertises_and_applies_options() {
+    let stdout = run_engine(
+        "uci\nsetoption name Depth value 1\nsetoption name Depth value 99\nsetoption name Hash value 1\nsetoption name Clear Hash\nsetoption name Threads value 2\nsetoption name EasyMove value false\nsetoption name UCI_AnalyseMode value true\nsetoption name SymmetricHash value true\nsetoption name Ponder value true\nposition startpos\ngo\nquit\n",
+    );
+
+    assert!(stdout.contains("option name Depth type spin default 6 min 0 max 64"));
+    assert!(stdout.contains("option name BookFile type string default <empty>"));
+    assert!(stdout.contains("option name Hash type spin default 32 min 1 max 4096"));
+    assert!(stdout.contains("option name Threads type spin default 1 min 1 max 128"));
+    assert!(stdout.contains("option name Clear Hash type button"));
+    assert!(stdout.contains("option name EasyMove type check default true"));
+    assert!(stdout.contains("option name UCI_AnalyseMode type check default false"));
+    assert!(stdout.contains("option name SymmetricHash type check default false"));
+    assert!(!stdout.contains("info string option"));
+    assert!(stdout.contains("info string Depth must be between 0 and 64, got 99"));
+    assert!(stdout.contains("info string unknown option: Ponder"));
+    let depths: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.starts_with("info depth"))
+        .collect();
+    assert_eq!(depths.len(), 1);
+    assert!(depths[0].starts_with("info depth 1 "));
+}
+
+#[test]
+fn uci_show_wdl_appends_outcome_estimates() {
+    let stdout = run_engine(
+        "uci\nposition startpos\ngo depth 1\nsetoption name UCI_ShowWDL value true\ngo depth 1\nquit\n",
+    );
+
+    assert!(stdout.contains("option name UCI_ShowWDL type check default false"));
+    let infos: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.starts_with("info depth"))
+        .collect();
+    assert_eq!(infos.len(), 2);
+    assert!(!infos[0].contains(" wdl "));
+    let wdl: Vec<u32> = infos[1]
+        .split(" wdl ")
+        .nth(1)
+        .expect("wdl in info line")
+        .split_whitespace()
+        .take(3)
+        .map(|value| value.parse().expect("permille"))
+        .collect();
+    assert_eq!(wdl.iter().sum::<u32>(), 1000);
+}
+
+#[test]
+fn uci_debug_mode_adds_diagnostics() {
+    let stdout = run_engine(
+        "position startpos\ngo depth 2\ndebug on\nbogus\ngo depth 2\ndebug off\ngo depth 1\nquit\n",
+    );
+
+    let debug: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.starts_with("info string debug "))
+        .collect();
+    assert!(debug.contains(&"info string debug unknown command: bogus"));
+    assert_eq!(
+        debug
+            .iter()
+            .filter(|line| line.starts_with("info string debug tt probes "))
+            .count(),
+        2
+    );
+    assert!(debug
+        .iter()
+        .any(|line| line.starts_with("info string debug pruning lmp ")));
+    assert!(debug
+        .iter()
+        .any(|line| line.starts_with("info string debug ordering cutoffs ")));
+    assert!(debug
+        .iter()
+        .any(|line| line.starts_with("info string debug nodes pv ")));
+    assert_eq!(
+        debug
+            .iter()
+            .filter(|line| line.starts_with("info string debug memory tt "))
+            .copied()
+            .collect::<Vec<_>>(),
+        ["info string debug memory tt 24.0 MiB in 1 tables, 1048576 entries"]
+    );
+}
+
+#[test]
+fn uci_go_perft_divides_by_root_move() {
+    let stdout = run_engine("position startpos moves e2e4\ngo perft 3\ngo depth 1\nquit\n");
+
+    let divide: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.contains(": ") && !line.starts_with("Nodes"))
+        .collect();
+    assert_eq!(divide.len(), 20);
+    let sum: u64 = divide
+        .iter()
+        .map(|line| line.split(": ").nth(1).unwrap().parse::<u64>().unwrap())
+        .sum();
+    assert_eq!(sum, 13160);
+    assert!(stdout.contains("\nNodes searched: 13160\n"));
+    // A double push leaves an en passant square behind, which must not make
+    // the position look invalid.
+    assert!(!stdout.contains("position invalid"));
+    assert_eq!(
+        stdout
+            .lines()
+            .filter(|line| line.starts_with("bestmove"))
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn uci_d_prints_the_position() {
+    let stdout = run_engine("position startpos moves e2e4 e7e5 g1f3\nd\nquit\n");
+
+    assert!(stdout.contains(" | r | n | b | q | k | b | n | r | 8\n"));
+    assert!(stdout.contains(" |   |   |   |   |   | N |   |   | 3\n"));
+    assert!(stdout.contains("Side to move: black\n"));
+    assert!(stdout.contains("Castling: KQkq\n"));
+    assert!(stdout.contains("En passant: -\n"));
+    assert!(stdout.contains("Halfmove clock: 1\n"));
+    assert!(
+        stdout.contains("Fen: rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2\n")
+    );
+    let key = stdout
+        .lines()
+        .find_map(|line| line.strip_prefix("Key: "))
+        .expect("zobrist key");
+    assert_eq!(key.len(), 16);
+    assert!(u64::from_str_radix(key, 16).is_ok());
+}
+
+#[test]
+fn uci_eval_prints_the_terms() {
+    let stdout = run_engine("position fen 4k3/8/8/8/8/8/Pp6/Q3K3 b - - 0 1\neval\nquit\n");
+
+    let material = stdout
+        .lines()
+        .find(|line| line.starts_with("Material"))
+        .expect("material term");
+    let columns: Vec<&str> = material.split_whitespace().collect();
+    assert_eq!(columns, ["Material", "1000", "100", "900"]);
+    assert!(stdout.contains("Evaluation: 900 cp (White's view), -900 cp for the side to move\n"));
+}
+
+#[test]
+fn uci_debug_log_file_records_both_directions() {
+    let log = std::env::temp_dir().join(format!("prune-io-{}.log", std::process::id()));
+    std::fs::remove_file(&log).ok();
+    let stdout = run_engine(&format!(
+        "uci\nsetoption name Debug Log File value {}\nisready\nsetoption name Debug Log File value <empty>\nisready\nquit\n",
+        log.display()
+    ));
+    let contents = std::fs::read_to_string(&log).expect("read log");
+    std::fs::remove_file(&log).ok();
+
+    assert!(stdout.contains("option name Debug Log File type string default <empty>"));
+    let lines: Vec<&str> = contents.lines().collect();
+    assert_eq!(lines.len(), 3, "{contents}");
+    assert!(lines[0].ends_with(" >> isready"), "{contents}");
+    assert!(lines[1].ends_with(" << readyok"), "{contents}");
+    assert!(lines[2].ends_with(" >> setoption name Debug Log File value <empty>"));
+    let (time, _) = lines[0].split_once(' ').expect("timestamp");
+    assert_eq!(time.len(), "12:34:56.789".len(), "{contents}");
+}
+
+#[test]
+fn uci_sessions_replay_as_recorded() {
+    let session = std::env::temp_dir().join(format!("prune-session-{}.log", std::process::id()));
+    let path = session.to_str().expect("utf-8 path");
+    let recorded = run_engine_with_args(
+        &["--record", path],
+        "uci\nposition startpos moves e2e4\ngo depth 2\nquit\n",
+    );
+    let contents = std::fs::read_to_string(&session).expect("read session");
+    let commands: Vec<&str> = contents
+        .lines()
+        .map(|line| line.split_once(' ').expect("time and command").1)
+        .collect();
+    assert_eq!(
+        commands,
+        ["uci", "position startpos moves e2e4", "go depth 2", "quit"]
+    );
+
+    // Replay ignores stdin and answers the recorded commands the same way.
+    let replayed = run_engine_with_args(&["--replay", path], "");
+    std::fs::remove_file(&session).ok();
+    let bestmove = |stdout: &str| {
+        stdout
+            .lines()
+            .find(|line| line.starts_with("bestmove "))
+            .map(str::to_string)
+    };
+    assert!(bestmove(&recorded).is_some(), "{recorded}");
+    assert_eq!(bestmove(&replayed), bestmove(&recorded));
+    assert!(replayed.contains("uciok"));
+}
+
+#[test]
+fn compare_epd_lists_where_the_reference_engine_disagrees() {
+    let epd = std::env::temp_dir().join(format!("prune-compare-{}.epd", std::process::id()));
+    std::fs::write(
+        &epd,
+        "k7/8/2K5/8/8/8/8/1R6 w - -\nrnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -\n",
+    )
+    .expect("write epd");
+    let path = epd.to_str().expect("utf-8 path");
+    let reference = format!("{} --seed 1", resolve_engine_exe().display());
+    let compare = |depth: &str, reference_depth: &str| {
+        run_engine_with_args(
+            &[
+                "--compare-epd",
+                path,
+                "--reference",
+                &reference,
+                "--seed",
+                "1",
+                "--depth",
+                depth,
+                "--reference-depth",
+                reference_depth,
+            ],
+            "",
+        )
+    };
+
+    // Only the deeper reference finds 1. Kc7 Ka7 2. Ra1#.
+    let shallow = compare("1", "3");
+    let lines: Vec<&str> = shallow.lines().collect();
+    assert_eq!(lines.len(), 1, "{shallow}");
+    assert!(
+        lines[0].starts_with("gap 29500 fen k7/8/2K5/8/8/8/8/1R6 w - - 0 1 prune ")
+            && lines[0].ends_with(" 500 reference c6c7 30000"),
+        "{shallow}"
+    );
+    // The same engine at the same depth agrees with itself.
+    assert_eq!(compare("3", "3"), "");
+    std::fs::remove_file(&epd).ok();
+}
+
+#[test]
+fn uci_own_book_without_a_book_file_uses_the_builtin_lines() {
+    let stdout = run_engine(
+        "setoption name OwnBook value true\nposition startpos\ngo depth 3\nposition startpos moves a2a3\ngo depth 1\nquit\n",
+    );
+
+    let bestmoves: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.starts_with("bestmove "))
+        .collect();
+    assert_eq!(bestmoves.len(), 2);
+    assert!(
+        ["e2e4", "d2d4", "c2c4", "g1f3"]
+            .iter()
+            .any(|mv| bestmoves[0] == format!("bestmove {mv}")),
+        "{}",
+        bestmoves[0]
+    );
+    // 1.a3 is not in the built-in lines, so that position is searched.
+    let depths: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.starts_with("info depth"))
+        .collect();
+    assert_eq!(depths.len(), 1);
+}
+
+#[test]
+fn uci_own_book_plays_book_moves_without_searching() {
+    let book = std::env::temp_dir().join(format!("prune-own-book-{}.txt", std::process::id()));
+    std::fs::write(&book, "e2e4 e7e5 g1f3\n").expect("write book");
+    let stdout = run_engine(&format!(
+        "uci\nsetoption name BookFile value {}\nsetoption name OwnBook value true\nposition startpos moves e2e4 e7e5\ngo depth 3\nposition startpos moves d2d4\ngo depth 1\nquit\n",
+        book.display()
+    ));
+    std::fs::remove_file(&book).ok();
+
+    assert!(stdout.contains("option name OwnBook type check default false"));
+    let bestmoves: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.starts_with("bestmove "))
+        .collect();
+    assert_eq!(bestmoves.len(), 2);
+    assert_eq!(bestmoves[0], "bestmove g1f3");
+    assert!(stdout.contains("info string book move"));
+    // Only the position out of book is searched.
+    let depths: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.starts_with("info depth"))
+        .collect();
+    assert_eq!(depths.len(), 1);
+    assert!(depths[0].starts_with("info depth 1 "));
+}
+
+#[test]
+fn uci_analyse_mode_searches_book_positions() {
+    let book = std::env::temp_dir().join(format!("prune-analyse-book-{}.txt", std::process::id()));
+    std::fs::write(&book, "e2e4 e7e5 g1f3\n").expect("write book");
+    let stdout = run_engine(&format!(
+        "uci\nsetoption name BookFile value {}\nsetoption name OwnBook value true\nsetoption name UCI_AnalyseMode value true\nsetoption name UCI_Opponent value none 1000 human Beginner\nposition startpos moves e2e4 e7e5\ngo depth 2\nquit\n",
+        book.display()
+    ));
+    std::fs::remove_file(&book).ok();
+
+    assert!(!stdout.contains("info string book move"));
+    let depths: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.starts_with("info depth") && !line.contains(" lowerbound "))
+        .collect();
+    assert_eq!(depths.len(), 2);
+    assert!(depths.iter().all(|line| line.contains(" pv ")));
+    assert!(stdout.contains("bestmove "));
+}
+
+#[test]
+fn uci_syzygy_path_reports_tablebase_files() {
+    let dir = std::env::temp_dir().join(format!("prune-uci-syzygy-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("create dir");
+    // Table headers padded to the smallest complete size; the empty file
+    // is skipped.
+    let table = |magic: [u8; 4]| {
+        let mut bytes = magic.to_vec();
+        bytes.resize(80, 0);
+        bytes
+    };
+    let (wdl, dtz) = ([0x71, 0xE8, 0x23, 0x5D], [0xD7, 0x66, 0x0C, 0xA5]);
+    for (name, bytes) in [
+        ("KQvK.rtbw", table(wdl)),
+        ("KQvK.rtbz", table(dtz)),
+        ("KRvK.rtbw", table(wdl)),
+        ("KBvK.rtbw", Vec::new()),
+    ] {
+        std::fs::write(dir.join(name), bytes).expect("write table");
+    }
+    let stdout = run_engine(&format!(
+        "uci\nsetoption name SyzygyPath value {}\nsetoption name SyzygyPath value /nonexistent/prune\nisready\nquit\n",
+        dir.display()
+    ));
+    std::fs::remove_dir_all(&dir).ok();
+
+    assert!(stdout.contains("option name SyzygyPath type string default <empty>"));
+    assert!(stdout.contains("info string found 3 tablebase files (2 WDL, 1 DTZ, up to 3 pieces)"));
+    assert!(stdout.contains("info string failed to read /nonexistent/prune"));
+    assert!(stdout.contains("readyok"));
+}
+
+#[test]
+fn uci_nodestime_makes_timed_searches_repeatable() {
+    let last_nodes = || {
+        let stdout = run_engine(
+            "uci\nsetoption name nodestime value 20\nposition startpos\ngo wtime 10000 btime 10000\nquit\n",
+        );
+        assert!(stdout.contains("option name nodestime type spin default 0 min 0 max 10000"));
+        let info = stdout
+            .lines()
+            .rfind(|line| line.starts_with("info depth") && !line.contains(" lowerbound "))
+            .expect("an info line")
+            .to_string();
+        let nodes: u64 = info
+            .split_whitespace()
+            .skip_while(|token| *token != "nodes")
+            .nth(1)
+            .and_then(|nodes| nodes.parse().ok())
+            .expect("nodes in the info line");
+        (info.split(" nps ").next().map(str::to_string), nodes)
+    };
+
+    let (first, nodes) = last_nodes();
+    assert_eq!(last_nodes(), (first, nodes));
+    // An even share of 10 seconds is a few hundred milliseconds, so a few
+    // thousand nodes at 20 per millisecond.
+    assert!(nodes < 20_000, "{nodes}");
+}
+
+#[test]
+fn uci_limit_strength_caps_the_search() {
+    let stdout = run_engine(
+        "uci\nsetoption name UCI_Elo value 800\nsetoption name UCI_LimitStrength value true\nposition startpos\ngo depth 5\nquit\n",
+    );
+
+    assert!(stdout.contains("option name UCI_LimitStrength type check default false"));
+    assert!(stdout.contains("option name UCI_Elo type spin default 1500 min 800 max 2800"));
+    let depths: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.starts_with("info depth") && !line.contains(" lowerbound "))
+        .collect();
+    assert_eq!(depths.len(), 1);
+    assert!(depths[0].starts_with("info depth 1 "));
+    assert!(stdout
+        .lines()
+        .any(|line| line.starts_with("bestmove ") && line != "bestmove 0000"));
+}
+
+#[test]
+fn uci_chess960_castles_king_takes_rook() {
+    let stdout = run_engine(
+        "uci\nsetoption name UCI_Chess960 value true\nposition fen r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w HAha - 0 1 moves e1h1\ngo depth 1 searchmoves e8h8\nposition fen 1r2k1r1/pppppppp/8/8/8/8/PPPPPPPP/1R2K1R1 w GBgb - 0 1 moves e1g1\ngo depth 1 searchmoves e8b8\nquit\n",
+    );
+
+    assert!(stdout.contains("option name UCI_Chess960 type check default false"));
+    assert!(stdout.lines().any(|line| line == "bestmove e8h8"));
+    // Rooks on the b- and g-files castle too.
+    assert!(stdout.lines().any(|line| line == "bestmove e8b8"));
+    assert!(!stdout.contains("illegal move"));
+}
+
+#[test]
+fn uci_stop_interrupts_search_with_a_real_bestmove() {
+    let started = std::time::Instant::now();
+    let stdout = run_engine("position startpos\ngo depth 30\nstop\nquit\n");
+
+    let bestmoves: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.starts_with("bestmove"))
+        .collect();
+    assert_eq!(bestmoves.len(), 1);
+    assert_ne!(bestmoves[0], "bestmove 0000");
+    assert!(started.elapsed().as_secs() < 30);
+}
+
+#[test]
+fn uci_isready_is_answered_during_a_search() {
+    let stdout = run_engine("position startpos\ngo depth 30\nisready\nstop\nquit\n");
+
+    let lines: Vec<&str> = stdout.lines().collect();
+    let readyok = lines.iter().position(|line| *line == "readyok");
+    let bestmove = lines.iter().position(|line| line.starts_with("bestmove"));
+    assert!(
+        readyok.is_some_and(|readyok| bestmove.is_some_and(|bestmove| readyok < bestmove)),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn uci_position_reports_illegal_moves() {
+    let stdout = run_engine(
+        "position startpos moves e2e4 e7e5 e4e5 g1f3\nd\nsetoption name UCI_Chess960 value true\nposition startpos moves e2e4 e1g1\nquit\n",
+    );
+
+    assert!(
+        stdout.contains("info string illegal move e4e5 at index 2\n"),
+        "{stdout}"
+    );
+    // The moves before the illegal one stay applied.
+    assert!(stdout.contains("Fen: rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2\n"));
+    assert!(
+        stdout.contains("info string illegal move e1g1 at index 1\n"),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn uci_position_plays_only_the_moves_added_since_the_last_command() {
+    let stdout = run_engine(
+        "debug on\nposition startpos moves e2e4\nposition startpos moves e2e4 e7e5 g1f3\nd\nposition startpos moves e2e4 e7e5\nd\nposition startpos moves e2e4 e7e5 g1f3 g8f6\nposition startpos moves e2e4 e7e5 g1f3 g8f6 f3e5 e8e6\nposition startpos moves e2e4 e7e5 g1f3 g8f6 f3e5 d7d6\nd\nquit\n",
+    );
+
+    let extended: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.starts_with("info string debug position extends"))
+        .collect();
+    // The takeback and the command after the illegal move start over.
+    assert_eq!(
+        extended, ["info string debug position extends the previous one by 2 moves"; 3],
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("Fen: rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2\n")
+    );
+    assert!(stdout.contains("Fen: rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2\n"));
+    assert!(stdout.contains("info string illegal move e8e6 at index 5\n"));
+    assert!(
+        stdout.contains("Fen: rnbqkb1r/ppp2ppp/3p1n2/4N3/4P3/8/PPPP1PPP/RNBQKB1R w KQkq - 0 4\n"),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn uci_go_refuses_positions_left_by_a_failed_position_command() {
+    let stdout = run_engine(
+        "position startpos moves e2e4 e7e5 e4e5\ngo depth 1\nposition fen 8/8/8/8/8/8/8/8 w - - 0 1\ngo depth 1\nposition startpos moves e2e4\ngo depth 1\nquit\n",
+    );
+
+    let refusals = stdout
+        .lines()
+        .filter(|line| {
+            line.starts_with("info string not searching, the last position command failed: ")
+        })
+        .count();
+    assert_eq!(refusals, 2, "{stdout}");
+    assert!(stdout.contains("failed: illegal move e4e5 at index 2\n"));
+    assert!(stdout.contains("failed: invalid FEN: "));
+    let bestmoves: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.starts_with("bestmove"))
+        .collect();
+    assert_eq!(bestmoves.len(), 3);
+    assert_eq!(&bestmoves[..2], ["bestmove 0000", "bestmove 0000"]);
+    assert_ne!(bestmoves[2], "bestmove 0000");
+}
+
+#[test]
+fn uci_setoption_keeps_a_failed_position_unsearchable() {
+    let stdout = run_engine(
+        "position startpos moves e2e4 e7e5 e4e5\nsetoption name Hash value 2\ngo depth 1\nquit\n",
+    );
+
+    assert!(
+        stdout.contains(
+            "info string not searching, the last position command failed: illegal move e4e5 at index 2\n"
+        ),
+        "{stdout}"
+    );
+    assert!(stdout.contains("bestmove 0000"));
+}
+
+#[test]
+fn uci_go_mate_reports_mate_score() {
+    let stdout = run_engine("position fen k7/8/2K5/8/8/8/8/7R w - - 0 1\ngo mate 2\nquit\n");
+
+    assert!(stdout.contains("score mate 2"));
+    let bestmove = stdout
+        .lines()
+        .find(|line| line.starts_with("bestmove"))
+        .expect("bestmove");
+    assert_ne!(bestmove, "bestmove 0000");
+}
+
+#[test]
+fn uci_go_nodes_bounds_the_search() {
+    let stdout = run_engine("position startpos\ngo nodes 3000\nquit\n");
+
+    let bestmove = stdout
+        .lines()
+        .find(|line| line.starts_with("bestmove"))
+        .expect("bestmove");
+    assert_ne!(bestmove, "bestmove 0000");
+    assert!(!stdout.contains("info depth 64"));
+}
+
+#[test]
+fn uci_go_movetime_answers_in_time() {
+    let started = std::time::Instant::now();
+    let stdout = run_engine("position startpos\ngo movetime 200\nquit\n");
+
+    assert!(started.elapsed().as_secs() < 5);
+    let bestmove = stdout
+        .lines()
+        .find(|line| line.starts_with("bestmove"))
+        .expect("bestmove");
+    assert_ne!(bestmove, "bestmove 0000");
+}
+
+#[test]
+fn uci_go_with_clocks_budgets_its_time() {
+    let started = std::time::Instant::now();
+    let stdout = run_engine("position startpos\ngo wtime 3000 btime 3000 winc 0 binc 0\nquit\n");
+
+    assert!(started.elapsed().as_secs() < 5);
+    let bestmove = stdout
+        .lines()
+        .find(|line| line.starts_with("bestmove"))
+        .expect("bestmove");
+    assert_ne!(bestmove, "bestmove 0000");
+}
+
+#[test]
+fn uci_minimum_thinking_time_paces_moves_but_stop_still_answers() {
+    let started = std::time::Instant::now();
+    let stdout = run_engine(
+        "setoption name MinimumThinkingTime value 600\nsetoption name TimeVariance value 20\nposition startpos\ngo wtime 10000 btime 10000\nquit\n",
+    );
+    // The clock alone plans about a third of a second; the search keeps
+    // deepening to the minimum.
+    assert!(started.elapsed().as_millis() >= 600);
+    assert!(stdout.lines().any(|line| line.starts_with("bestmove")));
+
+    let started = std::time::Instant::now();
+    let stdout = run_engine(
+        "setoption name MinimumThinkingTime value 60000\nposition startpos\ngo wtime 600000 btime 600000\nstop\nquit\n",
+    );
+    assert!(started.elapsed().as_secs() < 30);
+    assert!(stdout.lines().any(|line| line.starts_with("bestmove")));
+}
+
+#[test]
+fn uci_reports_best_move_changes() {
+    let stdout = run_engine("position fen 3rk3/8/8/8/8/8/8/3QK3 w - - 0 1\ngo depth 3\nquit\n");
+
+    let first = stdout
+        .lines()
+        .find(|line| line.starts_with("info string bestmove change"))
+        .expect("best move change");
+    assert!(first.starts_with("info string bestmove change depth 1 time "));
+}
+
+#[test]
+fn uci_go_searchmoves_limits_the_root() {
+    let stdout = run_engine(
+        "position fen 3rk3/8/8/8/8/8/8/3QK3 w - - 0 1\ngo searchmoves e1e2 e1f2 depth 2\nquit\n",
+    );
+
+    let bestmove = stdout
+        .lines()
+        .find(|line| line.starts_with("bestmove"))
+        .expect("bestmove");
+    assert!(bestmove == "bestmove e1e2" || bestmove == "bestmove e1f2");
+}
+
+#[test]
+fn uci_info_lines_carry_the_principal_variation() {
+    let stdout = run_engine("position startpos\ngo depth 3\nquit\n");
+
+    let last_info = stdout
+        .lines()
+        .find(|line| line.starts_with("info depth 3 "))
+        .expect("info depth 3");
+    let pv: Vec<&str> = last_info
+        .split(" pv ")
+        .nth(1)
+        .expect("pv")
+        .split_whitespace()
+        .collect();
+    assert_eq!(pv.len(), 3);
+    assert!(last_info.contains(" hashfull "));
+    let bestmove = stdout
+        .lines()
+        .find(|line| line.starts_with("bestmove"))
+        .expect("bestmove");
+    assert_eq!(bestmove, format!("bestmove {}", pv[0]));
+}
+
+fn run_engine(input: &str) -> String {
+    run_engine_with_args(&[], input)
+}
+
+fn run_engine_with_args(args: &[&str], input: &str) -> String {
+    let mut child = Command::new(resolve_engine_exe())
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to spawn engine binary");
+    child
+        .stdin
+        .as_mut()
+        .expect("failed to open stdin")
+        .write_all(input.as_bytes())
+        .expect("failed to write to stdin");
+    let output = child
+        .wait_with_output()
+        .expect("failed to read engine output");
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
 fn resolve_engine_exe() -> PathBuf {
-    if let Some(exe) = option_env!("CARGO_BIN_EXE_chess_engine") {
+    if let Some(exe) = option_env!("CARGO_BIN_EXE_prune") {
         return PathBuf::from(exe);
     }
 
-    let exe_name = format!("chess-engine{}", std::env::consts::EXE_SUFFIX);
+    let exe_name = format!("prune{}", std::env::consts::EXE_SUFFIX);
     let current = std::env::current_exe().expect("failed to get current exe");
     let exe_dir = current
         .parent()
@@ -73,3 +758,164 @@ fn resolve_engine_exe() -> PathBuf {
         panic!("engine binary not found at {}", candidate.display());
     }
 }
+
+#[test]
+fn uci_lists_moves_from_and_to_a_square() {
+    let stdout = run_engine(
+        "position fen 4k3/8/8/8/8/8/4P3/R3K1NR w KQ - 0 1\nmoves e1\nmoves to f3\nmoves e3\nmoves e8\nmoves z9\nquit\n",
+    );
+    assert!(stdout.contains("info string moves from e1: e1d1 e1f1 e1d2 e1f2 e1c1\n"));
+    assert!(stdout.contains("info string moves to f3: g1f3\n"));
+    assert!(stdout.contains("info string moves from e3:\n"));
+    assert!(stdout.contains("info string moves from e8:\n"));
+    assert!(!stdout.contains("moves from z9"));
+}
+
+#[test]
+fn uci_analysebatch_tags_a_result_for_each_position() {
+    let stdout = run_engine(
+        "analysebatch depth 2\n\
+         4k3/8/8/3r4/8/8/8/3QK3 w - - 0 1\n\
+         not a fen\n\
+         7k/5Q2/6K1/8/8/8/8/8 b - - 0 1\n\
+         rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1\n\
+         end\n\
+         quit\n",
+    );
+
+    assert!(
+        stdout.contains("info string batch 1 bestmove d1d5 score cp "),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("info string batch 2 invalid FEN: "),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("info string batch 3 bestmove 0000"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("info string batch 4 bestmove "), "{stdout}");
+    assert!(
+        stdout.contains("info string batch done 3 of 4 positions time "),
+        "{stdout}"
+    );
+    assert!(!stdout.lines().any(|line| line.starts_with("bestmove")));
+}
+
+#[test]
+fn uci_analysebatch_obeys_stop_and_quit_while_reading() {
+    let stdout = run_engine(
+        "analysebatch depth 2\n\
+         4k3/8/8/3r4/8/8/8/3QK3 w - - 0 1\n\
+         stop\n\
+         isready\n\
+         analysebatch depth 2\n\
+         4k3/8/8/3r4/8/8/8/3QK3 w - - 0 1\n\
+         quit\n\
+         isready\n",
+    );
+
+    assert!(
+        stdout.contains("info string batch done 0 of 1 positions time "),
+        "{stdout}"
+    );
+    assert!(!stdout.contains("info string batch 1 "), "{stdout}");
+    // `quit` ended the engine before the second batch or `isready` ran.
+    assert_eq!(stdout.matches("readyok").count(), 1, "{stdout}");
+    assert_eq!(stdout.matches("batch done").count(), 1, "{stdout}");
+}
+
+#[test]
+fn uci_end_of_input_acts_as_quit() {
+    // No `stop` or `quit`: closing stdin ends even an infinite search with
+    // its `bestmove`.
+    let stdout = run_engine("position startpos\ngo infinite\n");
+    assert!(
+        stdout
+            .lines()
+            .last()
+            .is_some_and(|line| line.starts_with("bestmove ")),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn uci_closed_stdout_does_not_crash_the_engine() {
+    let mut child = Command::new(resolve_engine_exe())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to spawn engine binary");
+    drop(child.stdout.take());
+    child
+        .stdin
+        .take()
+        .expect("failed to open stdin")
+        .write_all(b"uci\nposition startpos\ngo depth 3\nquit\n")
+        .expect("failed to write to stdin");
+    let output = child.wait_with_output().expect("failed to wait for engine");
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn uci_go_infinite_holds_bestmove_until_stop() {
+    let mut child = Command::new(resolve_engine_exe())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to spawn engine binary");
+    let mut stdin = child.stdin.take().expect("failed to open stdin");
+    // Depth 1 finishes at once, so only `stop` can release the bestmove.
+    stdin
+        .write_all(b"position startpos\ngo infinite depth 1\n")
+        .expect("failed to write to stdin");
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    stdin
+        .write_all(b"isready\nstop\nquit\n")
+        .expect("failed to write to stdin");
+    drop(stdin);
+    let output = child
+        .wait_with_output()
+        .expect("failed to read engine output");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let lines: Vec<&str> = stdout.lines().collect();
+    let readyok = lines.iter().position(|line| *line == "readyok");
+    let bestmove = lines.iter().position(|line| line.starts_with("bestmove"));
+    assert!(
+        readyok.is_some_and(|readyok| bestmove.is_some_and(|bestmove| readyok < bestmove)),
+        "{stdout}"
+    );
+
+    // Without a `stop`, `quit` still ends the search.
+    let stdout = run_engine("position startpos\ngo infinite\nquit\n");
+    assert_eq!(
+        stdout
+            .lines()
+            .filter(|line| line.starts_with("bestmove "))
+            .count(),
+        1,
+        "{stdout}"
+    );
+}
+
+#[test]
+fn uci_reports_root_fail_highs_as_lower_bounds() {
+    let stdout = run_engine("position fen 4k3/8/8/3r4/8/8/8/3QK3 w - - 0 1\ngo depth 2\nquit\n");
+
+    let bounds: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.contains("bound"))
+        .collect();
+    assert_eq!(bounds.len(), 1, "{stdout}");
+    assert!(bounds[0].starts_with("info depth 1 score cp "), "{stdout}");
+    assert!(bounds[0].contains(" lowerbound time "), "{stdout}");
+    assert!(bounds[0].ends_with(" pv d1d5"), "{stdout}");
+}